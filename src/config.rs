@@ -33,6 +33,8 @@ impl Config {
 
     // 2MB
     pub const DEFAULT_WORKING_MEMORY_SIZE: usize = 2 * 1024 * 1024;
+
+    pub const DEFAULT_RECURSIVE_MAX_COUNT: usize = 100000;
 }
 
 impl Default for Config {
@@ -48,7 +50,11 @@ impl Default for Config {
 }
 
 pub fn load() -> Config {
+    // test时候的args是test harness的 例如 --test-threads 不能交给clap
+    #[cfg(not(test))]
     let commandLine = CommandLine::parse();
+    #[cfg(test)]
+    let commandLine = CommandLine::parse_from([env!("CARGO_PKG_NAME")]);
 
     if commandLine.configFilePath.is_none() {
         return Config::default();
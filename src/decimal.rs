@@ -0,0 +1,204 @@
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use crate::throwFormat;
+use crate::types::Byte;
+use anyhow::Result;
+
+// decimal(precision, scale) 是精确的定点数 底层是rust_decimal 96bit的mantissa 加上 scale
+
+/// rust_decimal 能精确表示的最多有效数字
+pub const MAX_PRECISION: u32 = 28;
+
+/// 1byte(符号和指数) + 16byte(左对齐的mantissa) + 1byte(scale)
+pub const ENCODED_LEN: usize = 1 + size_of::<u128>() + 1;
+
+/// mantissa最多有29位数字 都左对齐到29位
+const ALIGNED_DIGIT_COUNT: u32 = 29;
+
+/// 1位数字且scale是28的时候 指数最小
+const MIN_EXPONENT: i32 = 1 - MAX_PRECISION as i32;
+
+const ZERO_HEAD: Byte = 0x80;
+
+/// '1.5', '-2', '1e3'
+pub fn parse(text: &str) -> Result<Decimal> {
+    let text = text.trim();
+
+    if let Ok(decimal) = Decimal::from_str_exact(text) {
+        return Ok(decimal);
+    }
+
+    match Decimal::from_scientific(text) {
+        Ok(decimal) => Ok(decimal),
+        Err(_) => throwFormat!("invalid decimal:'{}'", text),
+    }
+}
+
+pub fn fromF64(float: f64) -> Result<Decimal> {
+    match Decimal::from_f64_retain(float) {
+        // 去掉二进制带来的尾巴 0.1 不会变为 0.1000000000000000055511151231
+        Some(decimal) => Ok(parse(&float.to_string()).unwrap_or(decimal)),
+        None => throwFormat!("can not transform {} to decimal", float),
+    }
+}
+
+pub fn toF64(decimal: &Decimal) -> f64 {
+    decimal.to_f64().unwrap()
+}
+
+/// 落地的时候 按照column的scale四舍五入 整数部分超出了precision的话报错 <br>
+/// 没有限定 precision scale 的话去掉末尾的0
+pub fn fit(decimal: Decimal, precisionScale: Option<(u32, u32)>) -> Result<Decimal> {
+    let (precision, scale) =
+        match precisionScale {
+            Some(precisionScale) => precisionScale,
+            None => return Ok(decimal.normalize()),
+        };
+
+    let mut fitted = decimal.round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+    fitted.rescale(scale);
+
+    if fitted.scale() != scale || fitted.mantissa().unsigned_abs() >= 10u128.pow(precision) {
+        throwFormat!("{} is out of range for decimal({}, {})", decimal, precision, scale);
+    }
+
+    if fitted.is_zero() {
+        fitted.set_sign_positive(true);
+    }
+
+    Ok(fitted)
+}
+
+/// index seek 的时候 value要和落地的有相同的scale 才能得到相同的binary <br>
+/// 不能四舍五入 scale不够的话原样返回 反正也不会和落地的相等 大小的顺序还是对的
+pub fn fitLossless(decimal: Decimal, precisionScale: Option<(u32, u32)>) -> Decimal {
+    let scale =
+        match precisionScale {
+            Some((_, scale)) => scale,
+            None => return decimal.normalize(),
+        };
+
+    if decimal.round_dp(scale) != decimal {
+        return decimal;
+    }
+
+    let mut fitted = decimal;
+    fitted.rescale(scale);
+
+    if fitted.scale() != scale {
+        return decimal;
+    }
+
+    if fitted.is_zero() {
+        fitted.set_sign_positive(true);
+    }
+
+    fitted
+}
+
+/// 和scale无关的order preserving <br>
+/// 1st byte: 0是0x80, 正数比它大 指数越大越大, 负数比它小 指数越大越小 <br>
+/// mantissa左对齐到29位数字 负数全部取反 <br>
+/// 末尾的scale只是为了decode后保留原来的小数位数 相同的数值scale不同的话binary不同
+pub fn encode(decimal: &Decimal) -> [Byte; ENCODED_LEN] {
+    let mut bytes = [0; ENCODED_LEN];
+    bytes[ENCODED_LEN - 1] = decimal.scale() as Byte;
+
+    if decimal.is_zero() {
+        bytes[0] = ZERO_HEAD;
+        return bytes;
+    }
+
+    let mantissa = decimal.mantissa().unsigned_abs();
+    let digitCount = mantissa.ilog10() + 1;
+    let exponent = digitCount as i32 - decimal.scale() as i32;
+    let aligned = mantissa * 10u128.pow(ALIGNED_DIGIT_COUNT - digitCount);
+
+    if decimal.is_sign_negative() {
+        bytes[0] = ZERO_HEAD - 1 - (exponent - MIN_EXPONENT) as Byte;
+        bytes[1..ENCODED_LEN - 1].copy_from_slice(&(!aligned).to_be_bytes());
+    } else {
+        bytes[0] = ZERO_HEAD + 1 + (exponent - MIN_EXPONENT) as Byte;
+        bytes[1..ENCODED_LEN - 1].copy_from_slice(&aligned.to_be_bytes());
+    }
+
+    bytes
+}
+
+pub fn decode(bytes: &[Byte]) -> Decimal {
+    let scale = bytes[ENCODED_LEN - 1] as u32;
+
+    let head = bytes[0];
+    if head == ZERO_HEAD {
+        return Decimal::from_i128_with_scale(0, scale);
+    }
+
+    let aligned = u128::from_be_bytes(bytes[1..ENCODED_LEN - 1].try_into().unwrap());
+
+    let (negative, aligned, exponent) =
+        if head > ZERO_HEAD {
+            (false, aligned, (head - ZERO_HEAD - 1) as i32 + MIN_EXPONENT)
+        } else {
+            (true, !aligned, (ZERO_HEAD - 1 - head) as i32 + MIN_EXPONENT)
+        };
+
+    let digitCount = (exponent + scale as i32) as u32;
+    let mantissa = (aligned / 10u128.pow(ALIGNED_DIGIT_COUNT - digitCount)) as i128;
+
+    Decimal::from_i128_with_scale(if negative { -mantissa } else { mantissa }, scale)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::decimal;
+
+    #[test]
+    pub fn testFit() {
+        let d = |text: &str| decimal::parse(text).unwrap();
+
+        assert_eq!(decimal::fit(d("1.555"), Some((10, 2))).unwrap().to_string(), "1.56");
+        assert_eq!(decimal::fit(d("-1.5"), Some((10, 2))).unwrap().to_string(), "-1.50");
+        assert_eq!(decimal::fit(d("-0.001"), Some((10, 2))).unwrap().to_string(), "0.00");
+        assert_eq!(decimal::fit(d("99999999.994"), Some((10, 2))).unwrap().to_string(), "99999999.99");
+        assert!(decimal::fit(d("99999999.995"), Some((10, 2))).is_err());
+        assert!(decimal::fit(d("100"), Some((2, 0))).is_err());
+        assert_eq!(decimal::fit(d("1.500"), None).unwrap().to_string(), "1.5");
+
+        assert_eq!(decimal::fitLossless(d("1.5"), Some((10, 2))).to_string(), "1.50");
+        assert_eq!(decimal::fitLossless(d("1.555"), Some((10, 2))).to_string(), "1.555");
+
+        assert_eq!(decimal::fromF64(0.1).unwrap().to_string(), "0.1");
+        assert_eq!(d("1e3"), d("1000"));
+        assert!(decimal::parse("1.2.3").is_err());
+    }
+
+    #[test]
+    pub fn testEncodeOrderPreserving() {
+        let values = [
+            "-79228162514264337593543950335", "-100", "-99.99", "-1.5", "-1.25", "-0.0000000000000000000000000001",
+            "0", "0.0000000000000000000000000001", "0.01", "0.1", "1", "1.2", "1.25", "10", "79228162514264337593543950335",
+        ];
+
+        let mut prevBinary = None;
+
+        for value in values {
+            let decimal = decimal::parse(value).unwrap();
+            let binary = decimal::encode(&decimal);
+
+            let decoded = decimal::decode(&binary);
+            assert_eq!(decoded, decimal);
+            assert_eq!(decoded.scale(), decimal.scale());
+
+            if let Some(prevBinary) = prevBinary {
+                assert!(prevBinary < binary, "{}", value);
+            }
+
+            prevBinary = Some(binary);
+        }
+
+        // 数值相同scale不同 只有末尾的scale不同
+        let binary = decimal::encode(&decimal::parse("1.5").unwrap());
+        let binary0 = decimal::encode(&decimal::parse("1.50").unwrap());
+        assert_eq!(binary[..decimal::ENCODED_LEN - 1], binary0[..decimal::ENCODED_LEN - 1]);
+    }
+}
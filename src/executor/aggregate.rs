@@ -0,0 +1,438 @@
+use std::cmp::Ordering;
+use std::sync::Mutex;
+use bytes::BytesMut;
+use hashbrown::{HashMap, HashSet};
+use crate::codec::BinaryCodec;
+use crate::executor::CommandExecutor;
+use crate::executor::sort;
+use crate::executor::store::{ScanHooks, ScanParams};
+use crate::graph_value::GraphValue;
+use crate::meta::{self, Table};
+use crate::parser::command::select::{Aggregate, AggregateFunction, OrderBy, SelectTable};
+use crate::parser::op::{MathCalcOp, Op};
+use crate::{throw, throwFormat};
+use crate::types::{ColumnFamily, DataKey, RowData, TableMutations};
+use crate::types::{CommittedPreProcessor, UncommittedPreProcessor};
+use anyhow::Result;
+use rust_decimal::Decimal;
+
+/// 单个聚合函数在1个分组上的中间状态
+#[derive(Default)]
+struct Accumulator {
+    count: i64,
+    /// sum avg 用到 是Integer Decimal Double
+    sum: Option<GraphValue>,
+    /// min max 用到
+    extreme: Option<GraphValue>,
+    /// distinct 用到 value encode后的binary
+    distinctValues: HashSet<Vec<u8>>,
+}
+
+impl Accumulator {
+    fn accumulate(&mut self, aggregate: &Aggregate, rowData: &RowData) -> Result<()> {
+        // count(*)
+        let columnName = match aggregate.columnName {
+            Some(ref columnName) => columnName,
+            None => {
+                self.count += 1;
+                return Ok(());
+            }
+        };
+
+        let value = rowData.get(columnName).unwrap_or(&GraphValue::Null);
+
+        // null 不参与聚合
+        if let GraphValue::Null = value {
+            return Ok(());
+        }
+
+        if aggregate.distinct {
+            let mut binary = BytesMut::new();
+            value.encode2ByteMut(&mut binary)?;
+
+            if self.distinctValues.insert(binary.to_vec()) == false {
+                return Ok(());
+            }
+        }
+
+        self.count += 1;
+
+        match aggregate.aggregateFunction {
+            AggregateFunction::Count => {}
+            AggregateFunction::Sum | AggregateFunction::Avg => {
+                self.sum = Some(
+                    match (self.sum.take(), value) {
+                        (None, GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Double(_)) => value.clone(),
+                        (Some(GraphValue::Integer(sum)), GraphValue::Integer(integer)) => {
+                            match sum.checked_add(*integer) {
+                                Some(sum) => GraphValue::Integer(sum),
+                                None => throwFormat!("{} overflow", aggregate.name()),
+                            }
+                        }
+                        // decimal 是精确的 溢出会报错
+                        (Some(sum @ (GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Double(_))),
+                            GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Double(_)) => {
+                            sum.calcOneToOne(Op::MathCalcOp(MathCalcOp::Plus), value)?
+                        }
+                        _ => throwFormat!("{} needs numeric column", aggregate.name()),
+                    }
+                );
+            }
+            AggregateFunction::Min | AggregateFunction::Max => {
+                let replace =
+                    match self.extreme {
+                        None => true,
+                        Some(ref extreme) => {
+                            match (value.partial_cmp(extreme), aggregate.aggregateFunction) {
+                                (Some(Ordering::Less), AggregateFunction::Min) => true,
+                                (Some(Ordering::Greater), AggregateFunction::Max) => true,
+                                (None, _) => throwFormat!("{} meets incomparable values", aggregate.name()),
+                                _ => false,
+                            }
+                        }
+                    };
+
+                if replace {
+                    self.extreme = Some(value.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self, aggregate: &Aggregate) -> GraphValue {
+        match aggregate.aggregateFunction {
+            AggregateFunction::Count => GraphValue::Integer(self.count),
+            AggregateFunction::Sum => self.sum.unwrap_or(GraphValue::Null),
+            AggregateFunction::Avg => {
+                match self.sum {
+                    Some(GraphValue::Integer(sum)) => GraphValue::Decimal(Decimal::from(sum) / Decimal::from(self.count)),
+                    Some(GraphValue::Decimal(sum)) => GraphValue::Decimal(sum / Decimal::from(self.count)),
+                    Some(GraphValue::Double(sum)) => GraphValue::Double(sum / self.count as f64),
+                    _ => GraphValue::Null,
+                }
+            }
+            AggregateFunction::Min | AggregateFunction::Max => self.extreme.unwrap_or(GraphValue::Null),
+        }
+    }
+}
+
+struct Group {
+    /// group by 的各column的值
+    groupRowData: RowData,
+    accumulators: Vec<Accumulator>,
+}
+
+/// 以group by的column的值encode后的binary分组 <br>
+/// 内存占用和分组的数量相关 和rowData的数量无关
+pub(super) struct Aggregator<'a> {
+    aggregates: Vec<&'a Aggregate>,
+    groupBys: &'a [String],
+    groupKey_groupIndex: HashMap<Vec<u8>, usize>,
+    /// 保持分组首次出现的顺序
+    groups: Vec<Group>,
+}
+
+impl<'a> Aggregator<'a> {
+    /// aggregates中同名的只会计算1次
+    pub(super) fn new(aggregates: impl Iterator<Item=&'a Aggregate>, groupBys: &'a [String]) -> Self {
+        let mut aggregatesDedup: Vec<&'a Aggregate> = Vec::new();
+
+        for aggregate in aggregates {
+            if aggregatesDedup.iter().any(|aggregate0| aggregate0.name() == aggregate.name()) == false {
+                aggregatesDedup.push(aggregate);
+            }
+        }
+
+        Aggregator {
+            aggregates: aggregatesDedup,
+            groupBys,
+            groupKey_groupIndex: HashMap::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    pub(super) fn add(&mut self, rowData: &RowData) -> Result<()> {
+        let mut groupKey = BytesMut::new();
+        for groupBy in self.groupBys {
+            rowData.get(groupBy).unwrap_or(&GraphValue::Null).encode2ByteMut(&mut groupKey)?;
+        }
+
+        let groupIndex =
+            match self.groupKey_groupIndex.get(groupKey.as_ref()) {
+                Some(groupIndex) => *groupIndex,
+                None => {
+                    self.groups.push(self.newGroup(Some(rowData)));
+                    self.groupKey_groupIndex.insert(groupKey.to_vec(), self.groups.len() - 1);
+                    self.groups.len() - 1
+                }
+            };
+
+        let group = &mut self.groups[groupIndex];
+        for (aggregate, accumulator) in self.aggregates.iter().zip(group.accumulators.iter_mut()) {
+            accumulator.accumulate(aggregate, rowData)?;
+        }
+
+        Ok(())
+    }
+
+    fn newGroup(&self, rowData: Option<&RowData>) -> Group {
+        let mut groupRowData = RowData::with_capacity(self.groupBys.len() + self.aggregates.len());
+
+        if let Some(rowData) = rowData {
+            for groupBy in self.groupBys {
+                groupRowData.insert(groupBy.clone(), rowData.get(groupBy).cloned().unwrap_or(GraphValue::Null));
+            }
+        }
+
+        Group {
+            groupRowData,
+            accumulators: self.aggregates.iter().map(|_| Accumulator::default()).collect(),
+        }
+    }
+
+    /// 各分组的rowData包含group by的column和全部的聚合的值(以聚合的name为key)
+    pub(super) fn finish(mut self) -> Vec<RowData> {
+        // 没有group by的时候 即使没有数据也要有1行 count(*) 是0
+        if self.groups.is_empty() && self.groupBys.is_empty() {
+            self.groups.push(self.newGroup(None));
+        }
+
+        let aggregates = self.aggregates;
+
+        self.groups.into_iter().map(
+            |group| {
+                let mut rowData = group.groupRowData;
+
+                for (aggregate, accumulator) in aggregates.iter().zip(group.accumulators) {
+                    rowData.insert(aggregate.name(), accumulator.finish(aggregate));
+                }
+
+                rowData
+            }
+        ).collect()
+    }
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// scan后分组聚合 然后是 having, order by, offset limit, 最后挑选要显示的
+    pub(super) fn selectTableAggregate(&self, selectTable: &SelectTable, table: &Table) -> Result<Vec<(DataKey, RowData)>> {
+        let groupBys: &[String] = selectTable.groupBys.as_deref().unwrap_or_default();
+        let selectAggregates: &[Aggregate] = selectTable.aggregates.as_deref().unwrap_or_default();
+        let havingAggregates: &[Aggregate] = selectTable.having.as_ref().map(|having| having.aggregates.as_slice()).unwrap_or_default();
+
+        let aggregateColumnNames =
+            selectAggregates.iter().chain(havingAggregates).filter_map(|aggregate| aggregate.columnName.as_ref());
+
+        for columnName in groupBys.iter().chain(aggregateColumnNames) {
+            if table.columns.iter().any(|column| &column.name == columnName) == false {
+                throwFormat!("table:{} has no column:{}", table.name, columnName);
+            }
+        }
+
+        let aggregator = Mutex::new(Aggregator::new(selectAggregates.iter().chain(havingAggregates), groupBys));
+
+        let scanParams = ScanParams {
+            table,
+            tableFilter: selectTable.tableFilterExpr.as_ref(),
+            ..Default::default()
+        };
+
+        // 和order by相同 通过hook喂给aggregator 返回false使得scanSatisfiedRows自身不去收集
+        let scanHooks = ScanHooks {
+            committedPreProcessor: Option::<Box<dyn CommittedPreProcessor>>::None,
+            committedPostProcessor: Some(
+                |_: &ColumnFamily, _: DataKey, rowData: &RowData| {
+                    aggregator.lock().unwrap().add(rowData)?;
+                    Result::<bool>::Ok(false)
+                }
+            ),
+            uncommittedPreProcessor: Option::<Box<dyn UncommittedPreProcessor>>::None,
+            uncommittedPostProcessor: Some(
+                |_: &TableMutations, _: DataKey, rowData: &RowData| {
+                    aggregator.lock().unwrap().add(rowData)?;
+                    Result::<bool>::Ok(false)
+                }
+            ),
+        };
+
+        self.scanSatisfiedRows(scanParams, true, scanHooks)?;
+
+        let mut rowDatas = aggregator.into_inner().unwrap().finish();
+
+        if let Some(ref having) = selectTable.having {
+            let mut rowDatasSatisfied = Vec::with_capacity(rowDatas.len());
+
+            for rowData in rowDatas {
+                match having.expr.calc(Some(&rowData))? {
+                    GraphValue::Boolean(true) => rowDatasSatisfied.push(rowData),
+                    GraphValue::Boolean(false) => {}
+                    _ => throw!("having should get a boolean"),
+                }
+            }
+
+            rowDatas = rowDatasSatisfied;
+        }
+
+        if let Some(ref orderBys) = selectTable.orderBys {
+            // order by 用的可能是聚合的alias 聚合后的rowData中的key是聚合的name
+            let orderBys: Vec<OrderBy> =
+                orderBys.iter().map(
+                    |orderBy| {
+                        let columnName =
+                            selectAggregates.iter()
+                                .find(|aggregate| aggregate.alias.as_ref() == Some(&orderBy.columnName))
+                                .map_or_else(|| orderBy.columnName.clone(), |aggregate| aggregate.name());
+
+                        OrderBy {
+                            columnName,
+                            asc: orderBy.asc,
+                            expr: None,
+                        }
+                    }
+                ).collect();
+
+            rowDatas.sort_by(|rowData, rowData0| sort::compareRowData(&orderBys, rowData, rowData0));
+        }
+
+        // 未显式指明column的话显示group by的column
+        let selectedColNames = selectTable.selectedColNames.as_deref().unwrap_or(groupBys);
+
+        let mut rowDatasDisplay = Vec::with_capacity(rowDatas.len());
+
+        for rowData in rowDatas.into_iter().skip(selectTable.offset.unwrap_or(0)).take(selectTable.limit.unwrap_or(usize::MAX)) {
+            let mut rowDataDisplay = RowData::with_capacity(selectedColNames.len() + selectAggregates.len());
+
+            for aggregate in selectAggregates {
+                rowDataDisplay.insert(aggregate.displayName(), rowData.get(&aggregate.name()).cloned().unwrap_or(GraphValue::Null));
+            }
+
+            if let Some(ref projections) = selectTable.projections {
+                for projection in projections {
+                    rowDataDisplay.insert(projection.alias.clone(), projection.expr.calc(Some(&rowData))?);
+                }
+            }
+
+            for selectedColName in selectedColNames {
+                rowDataDisplay.insert(selectedColName.clone(), rowData.get(selectedColName).cloned().unwrap_or(GraphValue::Null));
+            }
+
+            rowDatasDisplay.push((meta::DATA_KEY_INVALID, rowDataDisplay));
+        }
+
+        Ok(rowDatasDisplay)
+    }
+}
+
+/// relation select 的 dest 上的聚合 对1个src的多条dest data聚合成1行
+pub(super) fn aggregateRowDatas(aggregates: &[Aggregate], rowDatas: &[(DataKey, RowData)]) -> Result<RowData> {
+    let mut aggregator = Aggregator::new(aggregates.iter(), &[]);
+
+    for (_, rowData) in rowDatas {
+        aggregator.add(rowData)?;
+    }
+
+    let mut rowData = aggregator.finish().pop().unwrap();
+
+    // 以displayName为key
+    let mut rowDataDisplay = RowData::with_capacity(aggregates.len());
+    for aggregate in aggregates {
+        rowDataDisplay.insert(aggregate.displayName(), rowData.remove(&aggregate.name()).unwrap_or(GraphValue::Null));
+    }
+
+    Ok(rowDataDisplay)
+}
+
+#[cfg(test)]
+mod test {
+    use rust_decimal::Decimal;
+    use serde_json::{json, Value};
+    use crate::meta;
+    use crate::session::Session;
+
+    #[test]
+    pub fn testAggregate() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop relation aggUsage");
+        let _ = session.executeSql("drop table aggUser");
+        let _ = session.executeSql("drop table aggCar");
+        session.executeSql("create table aggUser (id integer, city string, age integer, salary decimal(10,2))").unwrap();
+
+        let rows = |session: &mut Session, sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        // 空表上没有group by的话也有1行
+        assert_eq!(rows(&mut session, "select aggUser[count(*)]"), vec![json!({"count(*)": 0})]);
+        assert_eq!(rows(&mut session, "select aggUser[count(*), sum(age), avg(age), max(age)]"), vec![json!({"count(*)": 0, "sum(age)": null, "avg(age)": null, "max(age)": null})]);
+        assert_eq!(rows(&mut session, "select aggUser[city, count(*)] group by city"), Vec::<Value>::new());
+
+        session.executeSql("insert into aggUser values (1, 'a', 20, 10.50)").unwrap();
+        session.executeSql("insert into aggUser values (2, 'a', 25, 20.25)").unwrap();
+        session.executeSql("insert into aggUser values (3, 'b', 30, 30.00)").unwrap();
+        session.executeSql("insert into aggUser values (4, null, 41, 1.10)").unwrap();
+        session.executeSql("insert into aggUser values (5, null, 50, null)").unwrap();
+
+        let decimal = |value: &Value| value.as_str().unwrap().parse::<Decimal>().unwrap();
+
+        let row = rows(&mut session, "select aggUser[count(*), count(distinct city), sum(age), avg(age), min(age), max(age)]").remove(0);
+        assert_eq!(row["count(*)"], json!(5));
+        assert_eq!(row["count(distinct city)"], json!(2));
+        assert_eq!(row["sum(age)"], json!(166));
+        assert_eq!(row["min(age)"], json!(20));
+        assert_eq!(row["max(age)"], json!(50));
+        // integer的avg是decimal
+        assert_eq!(decimal(&row["avg(age)"]), Decimal::new(332, 1));
+
+        // null不参与
+        let row = rows(&mut session, "select aggUser[count(salary), sum(salary), avg(salary)]").remove(0);
+        assert_eq!(row["count(salary)"], json!(4));
+        assert_eq!(decimal(&row["sum(salary)"]), Decimal::new(6185, 2));
+        assert_eq!(decimal(&row["avg(salary)"]), Decimal::new(154625, 4));
+
+        // null也是1个分组
+        assert_eq!(rows(&mut session, "select aggUser[city, count(*)] group by city order by city"),
+                   vec![json!({"city": "a", "count(*)": 2}), json!({"city": "b", "count(*)": 1}), json!({"city": null, "count(*)": 2})]);
+
+        let groups = rows(&mut session, "select aggUser[city, count(*) as total, avg(age)] group by city having count(*) > 1 order by total desc");
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group["total"] == json!(2) && group["city"] != json!("b")));
+
+        // having用到没有select的聚合
+        let groups = rows(&mut session, "select aggUser[city, avg(salary)] group by city having max(age) < 40 order by city");
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["city"], json!("a"));
+        assert_eq!(decimal(&groups[0]["avg(salary)"]), Decimal::new(15375, 3));
+        assert_eq!(groups[1]["city"], json!("b"));
+        assert_eq!(decimal(&groups[1]["avg(salary)"]), Decimal::new(30, 0));
+
+        // relation select 的dest端 各个src分别聚合
+        session.executeSql("create table aggCar (id integer, price integer)").unwrap();
+        session.executeSql("insert into aggCar values (7, 100)").unwrap();
+        session.executeSql("insert into aggCar values (8, 300)").unwrap();
+        session.executeSql("insert into aggCar values (9, 200)").unwrap();
+        session.executeSql("create relation aggUsage (number integer)").unwrap();
+        session.executeSql("link aggUser(id = 1) to aggCar(price > 150) by aggUsage(number = 1)").unwrap();
+        session.executeSql("link aggUser(id = 2) to aggCar(id = 7) by aggUsage(number = 1)").unwrap();
+
+        let mut relRows = rows(&mut session, "select aggUser[id] -aggUsage-> aggCar[count(*), min(price), max(price), sum(price), avg(price)]");
+        relRows.sort_by_key(|row| row["aggUser"][0]["id"].as_i64().unwrap());
+        assert_eq!(relRows.len(), 2);
+
+        let car = &relRows[0]["aggCar"][0];
+        assert_eq!((&car["count(*)"], &car["min(price)"], &car["max(price)"], &car["sum(price)"]), (&json!(2), &json!(200), &json!(300), &json!(500)));
+        assert_eq!(decimal(&car["avg(price)"]), Decimal::new(250, 0));
+
+        let car = &relRows[1]["aggCar"][0];
+        assert_eq!((&car["count(*)"], &car["sum(price)"]), (&json!(1), &json!(100)));
+
+        // 没有relation的src不会出现
+        assert_eq!(rows(&mut session, "select aggUser[id](id = 3) -aggUsage-> aggCar[count(*)]"), Vec::<Value>::new());
+
+        session.executeSql("drop relation aggUsage").unwrap();
+        session.executeSql("drop table aggUser").unwrap();
+        session.executeSql("drop table aggCar").unwrap();
+    }
+}
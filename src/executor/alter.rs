@@ -58,23 +58,33 @@ impl<'session> CommandExecutor<'session> {
             }
         }
 
-        // 如果drop掉的column涉及到索引如何应对 如果未写cascade那么报错失败 要写的话级联干掉
-        for indexName in &table.indexNames.clone() {
-            let mut dbObjectIndexRefMut = Session::getDBObjectMutByName(indexName)?;
-            let index = dbObjectIndexRefMut.asIndexMut()?;
+        // primary key 的column不能drop
+        let intersect = utils::intersection(&table.primaryKeyColumnNames, columnNames2Drop);
+        if intersect.is_empty() == false {
+            throwFormat!("table:{tableName}, primary key columns:{intersect:?} can not be dropped");
+        }
 
+        // 如果drop掉的column涉及到索引如何应对 如果未写cascade那么报错失败 要写的话级联干掉
+        let mut droppedIndexNames = Vec::new();
+        for index in self.getTableIndexesFromMetaStore(tableName, &table.indexNames)? {
             // 涉及到了现有的index, 要是cascade不存在的话报错失败
             let intersect = utils::intersection(&index.columnNames, columnNames2Drop);
             if intersect.is_empty() == false {
                 if cascade == false {
-                    throwFormat!(" table:{tableName}, index:{indexName}, columns:{intersect:?} will be dropped, try to use cascade");
+                    throwFormat!(" table:{tableName}, index:{}, columns:{intersect:?} will be dropped, try to use cascade", index.name);
                 }
 
                 // 级联drop掉涉及到的index
-                self.dropIndex(indexName, Some(table), Some(index))?;
+                self.dropIndexData(&index)?;
+                droppedIndexNames.push(index.name);
             }
         }
 
+        if droppedIndexNames.is_empty() == false {
+            table.indexNames.retain(|indexName| droppedIndexNames.contains(indexName) == false);
+            self.session.putUpdateMeta(table.id, &DBObject::Table(table.clone()))?;
+        }
+
         // 干掉column对应的数据部分的
         {
             let columnFamily = Session::getColumnFamily(table.id)?;
@@ -124,6 +134,9 @@ impl<'session> CommandExecutor<'session> {
         table.columns.retain(|column| columnNames2Drop.contains(&column.name) == false);
         self.session.putUpdateMeta(table.id, &DBObject::Table(table.clone()))?;
 
+        drop(dbObjectTableRefMut);
+        self.invalidateIndexes(&droppedIndexNames);
+
         Ok(())
     }
 
@@ -149,12 +162,15 @@ impl<'session> CommandExecutor<'session> {
             let mut newAddColumnValues = self.vecWithCapacityIn(columns2Add.len());
 
             for column in columns2Add {
-                let defaultValue = match (column.nullable, &column.defaultValue) {
+                let mut defaultValue = match (column.nullable, &column.defaultValue) {
                     (_, Some(default)) => GraphValue::try_from(default)?,
                     (false, None) => throwFormat!("column: {} is not nullable and there is no default value", column.name),
                     (true, None) => GraphValue::Null,
                 };
 
+                // json的default是string
+                column.type0.fitValue(&mut defaultValue)?;
+
                 newAddColumnValues.push(defaultValue);
             }
 
@@ -1,13 +1,14 @@
 use std::sync::atomic::Ordering;
-use crate::{getKeyIfSome, global, meta, throw, throwFormat, u64ToByteArrRef};
-use crate::executor::{CommandExecResult, CommandExecutor};
-use crate::meta::{DBObject, Index, Table};
+use hashbrown::HashSet;
+use crate::{byte_slice_to_u64, getKeyIfSome, global, meta, throw, throwFormat, u64ToByteArrRef};
+use crate::executor::{index, CommandExecResult, CommandExecutor};
+use crate::meta::{ColumnType, DBObject, Index, Table};
 use anyhow::Result;
 use bytes::{BufMut, BytesMut};
 use crate::codec::BinaryCodec;
 use crate::executor::store::ScanParams;
 use crate::session::Session;
-use crate::types::DBRawIterator;
+use crate::types::{Byte, DBRawIterator};
 use crate::utils::HashMapExt;
 
 impl<'session> CommandExecutor<'session> {
@@ -25,6 +26,12 @@ impl<'session> CommandExecutor<'session> {
         // 生成column family
         self.session.createColFamily(table.id)?;
 
+        // primary key 的 key->dataKey 单独的column family
+        if table.hasPrimaryKey() {
+            table.primaryKeyId = meta::nextDBObjectId();
+            self.session.createColFamily(table.primaryKeyId)?;
+        }
+
         // todo 使用 u64的tableId 为key 完成
         let tableId = table.id;
 
@@ -72,6 +79,52 @@ impl<'session> CommandExecutor<'session> {
             }
         }
 
+        // vector column 上的是ann index
+        let hasVectorColumn =
+            targetTable.columns.iter().any(|column| {
+                matches!(column.type0, ColumnType::Vector(_)) && index.columnNames.contains(&column.name)
+            });
+
+        if hasVectorColumn {
+            if index.columnNames.len() > 1 {
+                throw!("vector column can only be indexed alone, as ann index");
+            }
+
+            index.ivfCentroids = Some(vec![]);
+        }
+
+        // list column 上的是inverted index
+        let hasListColumn =
+            targetTable.columns.iter().any(|column| {
+                matches!(column.type0, ColumnType::List(_)) && index.columnNames.contains(&column.name)
+            });
+
+        if hasListColumn {
+            if index.columnNames.len() > 1 {
+                throw!("list column can only be indexed alone, as inverted index");
+            }
+
+            index.inverted = true;
+        }
+
+        // point column 上的是spatial index
+        let hasPointColumn =
+            targetTable.columns.iter().any(|column| {
+                matches!(column.type0, ColumnType::Point) && index.columnNames.contains(&column.name)
+            });
+
+        if hasPointColumn {
+            if index.columnNames.len() > 1 {
+                throw!("point column can only be indexed alone, as spatial index");
+            }
+
+            index.spatial = true;
+        }
+
+        if index.unique && (index.isAnn() || index.inverted || index.spatial) {
+            throw!("unique index can not be built on vector, list or point column");
+        }
+
         // 分配id
         index.id = meta::nextDBObjectId();
         index.trashId = meta::nextDBObjectId();
@@ -85,26 +138,42 @@ impl<'session> CommandExecutor<'session> {
         self.session.createColFamily(index.trashId)?;
 
         // 新建index的时候要是表上已经有数据需要当场生成index数据
-        self.generateIndexDataForExistingTableData(targetTable, &index)?;
+        let generateResult =
+            if index.isAnn() {
+                self.generateAnnIndexDataForExistingTableData(targetTable, &mut index)
+            } else {
+                self.generateIndexDataForExistingTableData(targetTable, &index)
+            };
+
+        // 例如unique index遇到已有的重复数据 要把建立的column family干掉
+        if let Err(e) = generateResult {
+            self.session.dropColFamily(index.id)?;
+            self.session.dropColFamily(index.trashId)?;
+            return Err(e);
+        }
 
         let indexId = index.id;
         let dbObjectIndex = DBObject::Index(index);
 
         // 落地 index
         self.session.putUpdateMeta(indexId, &dbObjectIndex)?;
-        // map 更新
-        meta::NAME_DB_OBJ.insert(dbObjectIndex.getName().to_string(), dbObjectIndex);
 
         // 回写更新后的表的信息落地
         targetTable.indexNames.push(indexName);
         self.session.putUpdateMeta(targetTable.id, &DBObject::Table(targetTable.clone()))?;
 
+        // map 更新
+        // 要先释放table的RefMut, index和table的name落在dashmap的同1个shard上的话insert会死锁
+        drop(dbObjectTargetTable);
+        meta::NAME_DB_OBJ.insert(dbObjectIndex.getName().to_string(), dbObjectIndex);
+
         Ok(CommandExecResult::DdlResult)
     }
 
     // todo 要是create table,insert,create index连在1起的话 该该函数不生效因为读取的是已经提交的 而这时insert的尚未提交
     /// 当创建index的时候,要是table上已经有数据了需要对这些数据创建索引 <br>
-    /// 直接对数据store本体上手
+    /// 直接对数据store本体上手 <br>
+    /// unique index 的话已有的未删掉的data不能有重复的值
     fn generateIndexDataForExistingTableData(&self, table: &Table, index: &Index) -> Result<()> {
         let mut dbRawIteratorTable: DBRawIterator = self.session.getDBRawIteratorWithoutSnapshot(&Session::getColumnFamily(table.id)?)?;
         dbRawIteratorTable.seek(meta::DATA_KEY_PATTERN);
@@ -113,6 +182,11 @@ impl<'session> CommandExecutor<'session> {
 
         let mut indexKeyBuffer = self.newIn();
 
+        // unique index 用来探寻data的mvcc xmax
+        let mut dbRawIteratorMvcc: DBRawIterator = self.session.getDBRawIteratorWithoutSnapshot(&Session::getColumnFamily(table.id)?)?;
+        let mut mvccKeyBuffer = self.withCapacityIn(meta::MVCC_KEY_BYTE_LEN);
+        let mut uniqueIndexValues: HashSet<Vec<Byte>> = HashSet::new();
+
         loop {
             let dataKey = getKeyIfSome!(dbRawIteratorTable);
 
@@ -130,17 +204,42 @@ impl<'session> CommandExecutor<'session> {
 
             let rowData = self.readRowDataBinary(rowDataBinary, &scanParams)?.unwrap();
 
-            indexKeyBuffer.clear();
+            if index.inverted {
+                // 每个不同的元素各有1条
+                for elementBinary in index::encodeInvertedIndexElements(rowData.get(&index.columnNames[0]).unwrap())? {
+                    indexKeyBuffer.clear();
+                    indexKeyBuffer.put_slice(&elementBinary);
+                    indexKeyBuffer.put_slice(dataKey);
 
-            for indexColumnName in &index.columnNames {
-                let columnValue = rowData.get(indexColumnName).unwrap();
-                columnValue.encode2ByteMut(&mut indexKeyBuffer)?;
+                    meta::STORE.put_cf(&indexColumnFamily, indexKeyBuffer.as_ref(), global::EMPTY_BINARY.as_slice())?;
+                }
+            } else {
+                indexKeyBuffer.clear();
+
+                if index.spatial {
+                    index::encodeSpatialIndexKey(rowData.get(&index.columnNames[0]).unwrap(), &mut indexKeyBuffer);
+                } else {
+                    for indexColumnName in &index.columnNames {
+                        let columnValue = rowData.get(indexColumnName).unwrap();
+                        columnValue.encode2ByteMut(&mut indexKeyBuffer)?;
+                    }
+
+                    if index.unique && index::uniqueCheckNeeded(index, &rowData) {
+                        let dataKey = byte_slice_to_u64!(dataKey);
+
+                        if index::committedDataDeleted(&mut mvccKeyBuffer, &mut dbRawIteratorMvcc, dataKey) == false {
+                            if uniqueIndexValues.insert(indexKeyBuffer.to_vec()) == false {
+                                throwFormat!("create unique index failed, table: {} has duplicate values on columns: {:?}", table.name, index.columnNames);
+                            }
+                        }
+                    }
+                }
+
+                indexKeyBuffer.put_slice(dataKey);
+
+                meta::STORE.put_cf(&indexColumnFamily, indexKeyBuffer.as_ref(), global::EMPTY_BINARY.as_slice())?;
             }
 
-            indexKeyBuffer.put_slice(dataKey);
-
-            meta::STORE.put_cf(&indexColumnFamily, indexKeyBuffer.as_ref(), global::EMPTY_BINARY.as_slice())?;
-
             dbRawIteratorTable.next();
         }
 
@@ -25,10 +25,13 @@ impl<'session> CommandExecutor<'session> {
         };
 
         let mut buffer = self.withCapacityIn(meta::MVCC_KEY_BYTE_LEN);
+        // indexKey的长度不定 不能复用上边定长的buffer 不然扩容时候会realloc bump上的内存
+        let mut indexKeyBuffer = self.newIn();
 
         // 遍历添加当前tx对应的xmax
         for (targetDataKey, targetRowData) in targetRowDatas {
-            self.generateIndexData(table, &mut buffer, targetDataKey, &targetRowData, true)?;
+            self.generateIndexData(table, &mut indexKeyBuffer, targetDataKey, &targetRowData, true)?;
+            self.generatePrimaryKeyData(table, targetDataKey, &targetRowData, true)?;
 
             let oldXmax = self.generateDeleteDataXmax(&mut buffer, targetDataKey)?;
             self.session.writeDeleteDataMutation(table.id, oldXmax);
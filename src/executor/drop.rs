@@ -1,7 +1,9 @@
+use std::mem;
 use std::mem::{forget, ManuallyDrop};
 use std::ptr;
 use crate::executor::{CommandExecResult, CommandExecutor};
 use anyhow::Result;
+use rocksdb::IteratorMode;
 use dashmap::mapref::one::RefMut;
 use bytes::BufMut;
 use crate::{extractDirectionKeyTagFromPointerKey, extractRowIdFromDataKey, extractRowIdFromKeySlice};
@@ -101,9 +103,14 @@ impl<'session> CommandExecutor<'session> {
             _ => throw!("not table nor relation")
         };
 
-        // 清理相应的index
-        for indexName in &table.indexNames.clone() {
-            self.dropIndex(indexName, Some(table), None)?;
+        // 清理相应的index 整个过程只持有table的lock
+        let indexNames = mem::take(&mut table.indexNames);
+        for index in self.getTableIndexesFromMetaStore(table.name.as_str(), &indexNames)? {
+            self.dropIndexData(&index)?;
+        }
+
+        if table.hasPrimaryKey() {
+            self.session.dropColFamily(table.primaryKeyId)?;
         }
 
         self.session.dropColFamily(table.id)?;
@@ -111,53 +118,126 @@ impl<'session> CommandExecutor<'session> {
 
         dbObjectTableRefMut.invalidate();
 
+        drop(dbObjectTableRefMut);
+        self.invalidateIndexes(&indexNames);
+
         Ok(CommandExecResult::DdlResult)
     }
 
-    /// drop table, alter table drop columns 都会调用到该函数
-    pub(super) fn dropIndex<>(&self,
-                              indexName: &str,
-                              table: Option<&mut Table>,
-                              index: Option<&mut Index>) -> Result<CommandExecResult> {
+    pub(super) fn dropIndex(&self, indexName: &str) -> Result<CommandExecResult> {
         log::info!("drop index: {}", indexName);
 
-        // 看似多余其实必要, 不然要是直接用函数参数会和外部引用的产生交互需要显式的生命周期标识
-        let mut index = index;
-        let mut indexLock = None;
+        // 只是读取index对应的table的名字 不持有index的lock
+        let tableName = {
+            let dbObjectIndex = Session::getDBObjectByName(indexName)?;
+            dbObjectIndex.asIndex()?.tableName.clone()
+        };
 
-        // 因为dashMap的RefMut不想是java那样是可重入的,要是同1个线程上重复去lock会死锁的
-        if index.is_none() {
-            // 因为生命周期标识是类型系统的1部分,如下的赋值调用需要函数签名用到显式的生命周期标识
-            indexLock = Some(Session::getDBObjectMutByName(indexName)?);
-            index = Some(indexLock.as_mut().unwrap().asIndexMut()?);
-        }
+        let mut dbObjectTableRefMut = Session::getDBObjectMutByName(tableName.as_str())?;
+
+        let table = match dbObjectTableRefMut.value_mut() {
+            DBObject::Table(table) | DBObject::Relation(table) => table,
+            _ => throwFormat!("{} is neither table nor relation", tableName)
+        };
+
+        let index =
+            match self.getTableIndexesFromMetaStore(table.name.as_str(), &table.indexNames)?.into_iter().find(|index| index.name == indexName) {
+                Some(index) => index,
+                None => throwFormat!("db object:{} not exist", indexName)
+            };
+
+        self.dropIndexData(&index)?;
+
+        //------------------------去掉table的meta信息上相应的index------------------
+        table.indexNames.retain(|indexNameExist| indexNameExist != indexName);
+        self.session.putUpdateMeta(dbObjectTableRefMut.getId(), dbObjectTableRefMut.value())?;
+
+        drop(dbObjectTableRefMut);
+        self.invalidateIndexes(&[indexName.to_string()]);
+
+        Ok(CommandExecResult::DdlResult)
+    }
 
-        let index = index.unwrap();
+    /// drop table, drop index, alter table drop columns 都会调用到该函数 不涉及NAME_DB_OBJ
+    pub(super) fn dropIndexData(&self, index: &Index) -> Result<()> {
         self.session.dropColFamily(index.id)?;
         // 莫忘了对应的trash
         self.session.dropColFamily(index.trashId)?;
-        self.session.deleteMeta(index.id)?;
-        index.invalidate();
+        self.session.deleteMeta(index.id)
+    }
 
-        //------------------------去掉table的meta信息上相应的index------------------
+    /// 因为dashMap的RefMut不像是java那样是可重入的,index和table落在同1个shard的话持有table的lock再去lock index会死锁 <br>
+    /// 持有table的lock的时候table上的index要到meta store读取
+    pub(super) fn getTableIndexesFromMetaStore(&self, tableName: &str, indexNames: &[String]) -> Result<Vec<Index>> {
+        let columnFamilyMeta = meta::STORE.cf_handle(meta::COLUMN_FAMILY_NAME_META).unwrap();
 
-        let mut table = table;
-        let mut tableLock = None;
+        let mut indexes = Vec::with_capacity(indexNames.len());
 
-        if table.is_none() {
-            tableLock = Some(Session::getDBObjectMutByName(&index.tableName)?);
-            table = Some(tableLock.as_mut().unwrap().asTableMut()?);
+        for iterResult in meta::STORE.iterator_cf(&columnFamilyMeta, IteratorMode::Start) {
+            let (_, value) = iterResult?;
+
+            if let DBObject::Index(index) = serde_json::from_slice::<DBObject>(&*value)? {
+                if index.tableName == tableName && indexNames.contains(&index.name) {
+                    indexes.push(index);
+                }
+            }
         }
 
-        let table = table.unwrap();
-        table.indexNames.retain(|indexNameExist| indexNameExist != indexName);
-        self.session.putUpdateMeta(table.id, &DBObject::Table(table.clone()))?;
+        Ok(indexes)
+    }
 
-        Ok(CommandExecResult::DdlResult)
+    /// table的lock放掉之后 再去使得NAME_DB_OBJ上的相应的index失效
+    pub(super) fn invalidateIndexes(&self, indexNames: &[String]) {
+        for indexName in indexNames {
+            if let Some(mut dbObjectIndex) = meta::NAME_DB_OBJ.get_mut(indexName.as_str()) {
+                dbObjectIndex.invalidate();
+            }
+        }
     }
 
     #[inline]
     pub(super) fn dropRelation(&self, relationName: &str) -> Result<CommandExecResult> {
         self.dropTable(relationName)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::meta;
+    use crate::session::Session;
+
+    /// index多了的话 总会有和table落在dashMap的同1个shard上的
+    #[test]
+    pub fn testDropTableWithIndexes() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        for _ in 0..2 {
+            let _ = session.executeSql("drop table dropCustomer");
+            session.executeSql("create table dropCustomer (id integer, a integer, b integer, c integer, d integer)").unwrap();
+
+            for column in ["a", "b", "c", "d"] {
+                for seq in 0..4 {
+                    session.executeSql(format!("create index dropCustomer{column}{seq} on dropCustomer[{column}]").as_str()).unwrap();
+                }
+            }
+
+            session.executeSql("insert into dropCustomer values (1, 1, 2, 3, 4)").unwrap();
+
+            // 干掉后同名的index可以再建
+            session.executeSql("drop index dropCustomera0").unwrap();
+            session.executeSql("create index dropCustomera0 on dropCustomer[a]").unwrap();
+
+            // column上的index级联干掉
+            assert!(session.executeSql("alter table dropCustomer drop columns (b)").is_err());
+            session.executeSql("alter table dropCustomer drop columns cascade (b)").unwrap();
+            session.executeSql("create index dropCustomerb0 on dropCustomer[c]").unwrap();
+
+            assert_eq!(session.executeSql("select dropCustomer[id,a,c](c = 3)").unwrap().remove(0), vec![json!({"id": 1, "a": 1, "c": 3})]);
+
+            session.executeSql("drop table dropCustomer").unwrap();
+        }
+    }
+}
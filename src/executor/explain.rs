@@ -0,0 +1,219 @@
+use std::time::{Duration, Instant};
+use serde_json::{json, Map, Value};
+use crate::executor::{CommandExecResult, CommandExecutor};
+use crate::executor::optimizer::filter::TableFilterProcResult;
+use crate::parser::command::explain::Explain;
+use crate::parser::command::select::Select;
+use crate::parser::op::Op;
+use crate::{global, JSON_ENUM_UNTAGGED};
+use anyhow::Result;
+
+/// filter必然不成立 不用读取
+pub(super) const ACCESS_PATH_NONE: &str = "none";
+pub(super) const ACCESS_PATH_INDEX: &str = "index";
+pub(super) const ACCESS_PATH_ANN_INDEX: &str = "annIndex";
+pub(super) const ACCESS_PATH_INVERTED_INDEX: &str = "invertedIndex";
+pub(super) const ACCESS_PATH_SPATIAL_INDEX: &str = "spatialIndex";
+pub(super) const ACCESS_PATH_PRIMARY_KEY: &str = "primaryKey";
+pub(super) const ACCESS_PATH_SCAN: &str = "scan";
+
+/// explain 过程中收集的
+pub(super) struct ExplainCollector {
+    pub analyze: bool,
+    /// 每趟scanSatisfiedRows对应1个
+    pub scanStages: Vec<ScanStage>,
+}
+
+/// 对某个table的1趟scan 它是怎么读取data的
+#[derive(Default)]
+pub(super) struct ScanStage {
+    pub tableName: String,
+    /// 是index还是scan 要是filter必然不成立的话不用读取是none
+    pub accessPath: &'static str,
+    /// 没有filter的时候是None
+    pub tableFilterProcResult: Option<Value>,
+    /// 能够使用的index 以挑选的先后排序
+    pub candidateIndexNames: Vec<String>,
+    pub chosenIndexName: Option<String>,
+    pub indexLocalSearch: bool,
+    pub concurrentScan: bool,
+    pub concurrency: Option<u64>,
+
+    // 以下是 explain analyze 使用的
+    /// 读取并对filter计算过的数量 使用index的话是index找到的数量
+    pub rowsScanned: usize,
+    /// 满足filter的数量
+    pub rowsReturned: usize,
+    pub elapsed: Duration,
+}
+
+impl ScanStage {
+    pub fn recordTableFilterProcResult(&mut self, tableFilterProcResult: &TableFilterProcResult) {
+        let value =
+            match tableFilterProcResult {
+                TableFilterProcResult::AllIndexableTableFilterColsAreNonsenseWhenIsPureAnd { hasExprAbandonedByIndex } => {
+                    json!({
+                        "variant": "AllIndexableTableFilterColsAreNonsenseWhenIsPureAnd",
+                        "hasExprAbandonedByIndex": hasExprAbandonedByIndex,
+                    })
+                }
+                TableFilterProcResult::IndexableTableFilterColHasConflictWhenIsPureAnd => json!({"variant": "IndexableTableFilterColHasConflictWhenIsPureAnd"}),
+                TableFilterProcResult::IndexableTableFilterColHasNonesenseWhenIsPureOr => json!({"variant": "IndexableTableFilterColHasNonesenseWhenIsPureOr"}),
+                TableFilterProcResult::NoColumnNameInTableFilter => json!({"variant": "NoColumnNameInTableFilter"}),
+                TableFilterProcResult::MaybeCanUseIndex {
+                    indexableTableFilterColName_opValueVecVec,
+                    isPureAnd,
+                    isPureOr,
+                    orHasNonsense
+                } => {
+                    // accumulateAnd accumulateOr 压缩后的 column -> 各条脉络(之间是or) -> opValue(之间是and)
+                    let mut mergedRanges = Map::new();
+
+                    for (columnName, opValueVecVec) in indexableTableFilterColName_opValueVecVec {
+                        let opValueVecVec: Vec<Vec<Value>> =
+                            opValueVecVec.iter().map(|opValueVec| {
+                                opValueVec.iter().map(|(op, value)| {
+                                    json!({
+                                        "op": opName(op),
+                                        "value": JSON_ENUM_UNTAGGED!(serde_json::to_value(value).unwrap_or(Value::Null)),
+                                    })
+                                }).collect()
+                            }).collect();
+
+                        mergedRanges.insert(columnName.clone(), json!(opValueVecVec));
+                    }
+
+                    json!({
+                        "variant": "MaybeCanUseIndex",
+                        "isPureAnd": isPureAnd,
+                        "isPureOr": isPureOr,
+                        "orHasNonsense": orHasNonsense,
+                        "mergedRanges": mergedRanges,
+                    })
+                }
+            };
+
+        self.tableFilterProcResult = Some(value);
+    }
+
+    fn toJson(&self, analyze: bool) -> Value {
+        let mut value = json!({
+            "table": self.tableName,
+            "accessPath": self.accessPath,
+            "tableFilterProcResult": self.tableFilterProcResult,
+            "candidateIndexes": self.candidateIndexNames,
+            "chosenIndex": self.chosenIndexName,
+            "indexLocalSearch": self.indexLocalSearch,
+            "concurrentScan": self.concurrentScan,
+            "concurrency": self.concurrency,
+        });
+
+        if analyze {
+            value["rowsScanned"] = json!(self.rowsScanned);
+            value["rowsReturned"] = json!(self.rowsReturned);
+            value["elapsedMs"] = json!(durationToMs(self.elapsed));
+        }
+
+        value
+    }
+}
+
+fn opName(op: &Op) -> String {
+    match op {
+        Op::MathCmpOp(mathCmpOp) => mathCmpOp.to_string(),
+        Op::LogicalOp(logicalOp) => logicalOp.to_string(),
+        Op::SqlOp(sqlOp) => sqlOp.to_string(),
+        Op::MathCalcOp(mathCalcOp) => mathCalcOp.to_string(),
+    }
+}
+
+fn durationToMs(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1000_000.0).round() / 1000.0
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// explain 不会实际读取data,只是记录各个scan选择的路径 <br>
+    /// explain analyze 实际执行 另外记录读取数量和耗时
+    pub(super) fn explain(&self, explain: &Explain) -> Result<CommandExecResult> {
+        self.explainCollector.replace(Some(ExplainCollector { analyze: explain.analyze, scanStages: Vec::new() }));
+
+        let start = Instant::now();
+
+        let selectResult =
+            match (&explain.select, explain.analyze) {
+                // 不实际读取的话上1个selectRel没有data会使得后边的提前结束 各个selectRel单独过1遍
+                (Select::SelectRels(selectRels), false) => {
+                    let mut selectResult = Ok(CommandExecResult::None);
+
+                    for selectRel in selectRels {
+                        selectResult = self.select(&Select::SelectRels(vec![selectRel.clone()]));
+                        if selectResult.is_err() {
+                            break;
+                        }
+                    }
+
+                    selectResult
+                }
+                (select, _) => self.select(select),
+            };
+
+        let elapsed = start.elapsed();
+
+        // 出错了也要清理掉
+        let explainCollector = self.explainCollector.take().unwrap();
+
+        let rowCount =
+            match selectResult? {
+                CommandExecResult::SelectResult(values) => values.len(),
+                _ => 0,
+            };
+
+        let scanStages: Vec<Value> =
+            explainCollector.scanStages.iter().map(|scanStage| scanStage.toJson(explain.analyze)).collect();
+
+        let mut plan = json!({
+            "analyze": explain.analyze,
+            "scans": scanStages,
+        });
+
+        if explain.analyze {
+            plan["rowsReturned"] = json!(rowCount);
+            plan["elapsedMs"] = json!(durationToMs(elapsed));
+        }
+
+        Ok(CommandExecResult::SelectResult(vec![plan]))
+    }
+
+    #[inline]
+    pub(super) fn explaining(&self) -> bool {
+        self.explainCollector.borrow().is_some()
+    }
+
+    /// explain 不带 analyze 的时候 scan确定了读取路径后不再实际读取
+    #[inline]
+    pub(super) fn explainWithoutAnalyze(&self) -> bool {
+        match self.explainCollector.borrow().as_ref() {
+            Some(explainCollector) => explainCollector.analyze == false,
+            None => false,
+        }
+    }
+
+    pub(super) fn beginScanStage(&self, tableName: &str) {
+        if let Some(explainCollector) = self.explainCollector.borrow_mut().as_mut() {
+            explainCollector.scanStages.push(ScanStage {
+                tableName: tableName.to_string(),
+                accessPath: ACCESS_PATH_NONE,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// 在explain的话 修改当前的scanStage 不在的话什么也不做
+    pub(super) fn recordScanStage(&self, record: impl FnOnce(&mut ScanStage)) {
+        if let Some(explainCollector) = self.explainCollector.borrow_mut().as_mut() {
+            if let Some(scanStage) = explainCollector.scanStages.last_mut() {
+                record(scanStage);
+            }
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use hashbrown::HashSet;
+use bytes::{BufMut, BytesMut};
+use dashmap::mapref::one::Ref;
+use crate::executor::{explain, sort, CommandExecutor};
+use crate::executor::store::{ScanHooks, ScanParams};
+use crate::expr::Expr;
+use crate::graph_value::GraphValue;
+use crate::meta::{DBObject, Index, Table};
+use crate::parser::command::select::OrderBy;
+use crate::parser::element::Element;
+use crate::session::Session;
+use crate::types::{DataKey, DBRawIterator, RowData};
+use crate::vector::DistanceMetric;
+use crate::{byte_slice_to_u64, getKeyIfSome, global, meta, throwFormat, vector};
+use anyhow::Result;
+
+/// vector是null的 不属于任何的list 不够limit的时候最后才看
+const NULL_LIST_NO: u32 = u32::MAX;
+
+/// ```order by cosine(embedding, $q) limit 10``` 使用ann index
+pub(in crate::executor) struct AnnSearch<'a> {
+    pub dbObjectIndex: Ref<'a, String, DBObject>,
+    pub distanceMetric: DistanceMetric,
+    pub query: Vec<f32>,
+}
+
+/// ann index 的key是 listNo(u32) + dataKey, 这里只写listNo
+pub(in crate::executor) fn encodeAnnIndexKey(index: &Index, columnValue: &GraphValue, indexKeyBuffer: &mut BytesMut) {
+    let listNo =
+        match columnValue {
+            GraphValue::Vector(vector) => vector::nearestList(index.ivfCentroids.as_ref().unwrap(), vector),
+            _ => NULL_LIST_NO,
+        };
+
+    indexKeyBuffer.put_u32(listNo);
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// 只有1个order by 且是asc 有limit, 是cosine l2 dot 函数 参数分别是有ann index的vector column和常量
+    pub(in crate::executor) fn getAnnSearch<'a>(&self,
+                                                table: &'a Table,
+                                                orderBys: &[OrderBy],
+                                                limit: Option<usize>) -> Result<Option<AnnSearch<'a>>> {
+        let orderBy =
+            match (orderBys, limit) {
+                ([orderBy], Some(_)) if orderBy.asc => orderBy,
+                _ => return Ok(None),
+            };
+
+        let (distanceMetric, args) =
+            match orderBy.expr {
+                Some(Expr::Function { ref functionName, ref args }) if args.len() == 2 => {
+                    match DistanceMetric::fromFunctionName(functionName) {
+                        Some(distanceMetric) => (distanceMetric, args),
+                        None => return Ok(None),
+                    }
+                }
+                _ => return Ok(None),
+            };
+
+        // cosine('[1, 2]', embedding) 也可以
+        let (columnName, queryExpr) =
+            match (&*args[0], &*args[1]) {
+                (Expr::Single(Element::TextLiteral(columnName)), queryExpr) if queryExpr.needAcutalRowData() == false => (columnName, queryExpr),
+                (queryExpr, Expr::Single(Element::TextLiteral(columnName))) if queryExpr.needAcutalRowData() == false => (columnName, queryExpr),
+                _ => return Ok(None),
+            };
+
+        for indexName in &table.indexNames {
+            let dbObjectIndex = Session::getDBObjectByName(indexName)?;
+            let index = dbObjectIndex.asIndex()?;
+
+            if index.isAnn() == false || &index.columnNames[0] != columnName {
+                continue;
+            }
+
+            let query = vector::fromGraphValue(&queryExpr.calc(None)?)?;
+
+            log::info!("order by use ann index: {}", indexName);
+
+            return Ok(Some(AnnSearch {
+                dbObjectIndex,
+                distanceMetric,
+                query,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// 以离query近的顺序读取list 读取了ivfProbeCount个list且满足了offset+limit后便可停下 <br>
+    /// 和scanByIndexInOrder相同 只应对committed的 调用方要确保当前tx在这个table上没有mutation
+    pub(in crate::executor) fn scanByAnnIndex(&self,
+                                              annSearch: AnnSearch,
+                                              scanParams: &ScanParams,
+                                              orderBys: &[OrderBy]) -> Result<Vec<(DataKey, RowData)>> {
+        let index = annSearch.dbObjectIndex.asIndex()?;
+        let centroids = index.ivfCentroids.as_ref().unwrap();
+
+        self.beginScanStage(scanParams.table.name.as_str());
+        self.recordScanStage(|scanStage| {
+            scanStage.accessPath = explain::ACCESS_PATH_ANN_INDEX;
+            scanStage.chosenIndexName = Some(index.name.clone());
+        });
+
+        if self.explainWithoutAnalyze() {
+            return Ok(vec![]);
+        }
+
+        let mut listNos = vector::rankLists(centroids, &annSearch.query, annSearch.distanceMetric)?;
+        listNos.push(NULL_LIST_NO);
+
+        let probeCount = vector::ivfProbeCount(centroids.len());
+
+        let offset = scanParams.offset.unwrap_or(0);
+        let limit = scanParams.limit.unwrap();
+
+        // 要用vector column算distance 不能提前prune
+        let scanParams = ScanParams {
+            table: scanParams.table,
+            tableFilter: scanParams.tableFilter,
+            ..Default::default()
+        };
+
+        let indexColumnFamily = Session::getColumnFamily(index.id)?;
+        let mut indexDBRawIterator: DBRawIterator = self.session.getDBRawIterator(&indexColumnFamily)?;
+
+        let mut visitedDataKeys = HashSet::new();
+        let mut candidates = Vec::new();
+
+        for (probedCount, listNo) in listNos.into_iter().enumerate() {
+            // 虽然已经看了probeCount个list 满足filter的不够的话还要继续
+            if probedCount >= probeCount && candidates.len() >= offset + limit {
+                break;
+            }
+
+            let listNoBinary = listNo.to_be_bytes();
+            indexDBRawIterator.seek(listNoBinary);
+
+            self.scanRowDatasByIndexInBatches(
+                &scanParams,
+                || {
+                    loop {
+                        let indexKey = indexDBRawIterator.key()?;
+
+                        if indexKey.starts_with(&listNoBinary) == false {
+                            return None;
+                        }
+
+                        let dataKey = extractDataKeyFromIndexKey!(indexKey);
+
+                        indexDBRawIterator.next();
+
+                        if visitedDataKeys.insert(dataKey) {
+                            return Some(dataKey);
+                        }
+                    }
+                },
+                |dataKey, mut rowData| {
+                    sort::calcOrderByExprs(orderBys, &mut rowData)?;
+                    candidates.push((dataKey, rowData));
+                    Ok(true)
+                },
+            )?;
+        }
+
+        candidates.sort_by(|(_, rowData), (_, rowData0)| sort::compareRowData(orderBys, rowData, rowData0));
+
+        let mut satisfiedRows: Vec<(DataKey, RowData)> = candidates.into_iter().skip(offset).take(limit).collect();
+
+        for (_, rowData) in &mut satisfiedRows {
+            sort::removeOrderByExprValues(orderBys, rowData);
+        }
+
+        self.recordScanStage(|scanStage| scanStage.rowsReturned += satisfiedRows.len());
+
+        Ok(satisfiedRows)
+    }
+
+    /// create index 的时候 以表上已有的vector训练得到各个list的中心 然后生成index数据 <br>
+    /// 中心只在这个时候训练 vector不够IVF_MIN_TRAINING_VECTOR_COUNT的话报错 要在导入数据后再建立 <br>
+    /// 和generateIndexDataForExistingTableData相同 直接对数据store本体上手
+    pub(in crate::executor) fn generateAnnIndexDataForExistingTableData(&self, table: &Table, index: &mut Index) -> Result<()> {
+        let mut dbRawIteratorTable: DBRawIterator = self.session.getDBRawIteratorWithoutSnapshot(&Session::getColumnFamily(table.id)?)?;
+        dbRawIteratorTable.seek(meta::DATA_KEY_PATTERN);
+
+        let scanParams = ScanParams {
+            table,
+            selectedColumnNames: Some(&index.columnNames),
+            ..Default::default()
+        };
+
+        let mut dataKey_values = Vec::new();
+
+        loop {
+            let dataKey = getKeyIfSome!(dbRawIteratorTable);
+
+            if dataKey.starts_with(&[meta::KEY_PREFIX_DATA]) == false {
+                break;
+            }
+
+            let rowDataBinary = dbRawIteratorTable.value().unwrap();
+            let mut rowData = self.readRowDataBinary(rowDataBinary, &scanParams)?.unwrap();
+
+            dataKey_values.push((dataKey.to_vec(), rowData.remove(&index.columnNames[0]).unwrap()));
+
+            dbRawIteratorTable.next();
+        }
+
+        // 均匀间隔的挑选训练用的vector
+        let vectors: Vec<&Vec<f32>> =
+            dataKey_values.iter().filter_map(|(_, value)| {
+                match value {
+                    GraphValue::Vector(vector) => Some(vector),
+                    _ => None,
+                }
+            }).collect();
+
+        if vectors.len() < vector::IVF_MIN_TRAINING_VECTOR_COUNT {
+            throwFormat!("ann index:{} needs at least {} vectors in table:{} to train lists, but get {}",
+                index.name, vector::IVF_MIN_TRAINING_VECTOR_COUNT, table.name, vectors.len());
+        }
+
+        let step = vectors.len().div_ceil(vector::IVF_TRAINING_SAMPLE_LIMIT).max(1);
+        let samples: Vec<Vec<f32>> = vectors.iter().step_by(step).map(|vector| (*vector).clone()).collect();
+
+        index.ivfCentroids = Some(vector::kmeans(&samples, vector::ivfListCount(vectors.len())));
+
+        log::info!("ann index:{} has {} lists", index.name, index.ivfCentroids.as_ref().unwrap().len());
+
+        let indexColumnFamily = Session::getColumnFamily(index.id)?;
+
+        let mut indexKeyBuffer = self.newIn();
+
+        for (dataKey, value) in &dataKey_values {
+            indexKeyBuffer.clear();
+
+            encodeAnnIndexKey(index, value, &mut indexKeyBuffer);
+            indexKeyBuffer.put_slice(dataKey);
+
+            meta::STORE.put_cf(&indexColumnFamily, indexKeyBuffer.as_ref(), global::EMPTY_BINARY.as_slice())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::{meta, vector};
+    use crate::session::Session;
+
+    #[test]
+    pub fn testAnnIndex() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table annItem");
+        session.executeSql("create table annItem (id integer, embedding vector(2))").unwrap();
+
+        // 没有vector 训练不出中心
+        assert!(session.executeSql("create index annItemEmbedding on annItem[embedding]").is_err());
+
+        for id in 0..vector::IVF_MIN_TRAINING_VECTOR_COUNT {
+            session.executeSql(format!("insert into annItem values ({id}, '[{id}, 0]')").as_str()).unwrap();
+        }
+
+        session.executeSql("create index annItemEmbedding on annItem[embedding]").unwrap();
+
+        let sql = "select annItem[id] order by l2(embedding, '[10.2, 0]') limit 3";
+
+        let explain = session.executeSql(format!("explain {sql}").as_str()).unwrap().remove(0);
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("annIndex"));
+
+        assert_eq!(session.executeSql(sql).unwrap().remove(0), vec![json!({"id": 10}), json!({"id": 11}), json!({"id": 9})]);
+
+        let explain = session.executeSql(format!("explain analyze {sql}").as_str()).unwrap().remove(0);
+        assert!(explain[0]["scans"][0]["elapsedMs"].as_f64().unwrap() > 0.0);
+
+        session.executeSql("drop table annItem").unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+use hashbrown::HashSet;
+use bytes::BytesMut;
+use dashmap::mapref::one::Ref;
+use crate::codec::BinaryCodec;
+use crate::executor::CommandExecutor;
+use crate::executor::store::{ScanHooks, ScanParams};
+use crate::expr::Expr;
+use crate::graph_value::GraphValue;
+use crate::meta::{ColumnType, DBObject, Table};
+use crate::parser::element::Element;
+use crate::parser::op::{LogicalOp, Op, SqlOp};
+use crate::session::Session;
+use crate::types::{Byte, CommittedPostProcessor, CommittedPreProcessor, DataKey, DBRawIterator, RowData, UncommittedPostProcessor, UncommittedPreProcessor};
+use crate::{byte_slice_to_u64, getKeyIfSome, list, meta};
+use anyhow::Result;
+
+/// ```tags contains 'rust'``` 和 ```tags any ['rust', 'db']``` 使用inverted index
+pub(in crate::executor) struct InvertedIndexSearch<'a> {
+    pub dbObjectIndex: Ref<'a, String, DBObject>,
+    /// 要找的各个元素的编码
+    pub elementBinaries: Vec<Vec<Byte>>,
+    /// contains 要全都有是交集, any 有其中之1是并集
+    pub matchAll: bool,
+}
+
+/// list的各个不同的元素的编码 inverted index 的key是 元素的编码 + dataKey <br>
+/// null 的list 没有key
+pub(in crate::executor) fn encodeInvertedIndexElements(columnValue: &GraphValue) -> Result<Vec<Vec<Byte>>> {
+    let list =
+        match columnValue {
+            GraphValue::List(list) => list,
+            _ => return Ok(Vec::new()),
+        };
+
+    let mut elementBinaries = Vec::with_capacity(list.len());
+
+    for element in list {
+        let mut elementBinary = BytesMut::new();
+        element.encode2ByteMut(&mut elementBinary)?;
+        elementBinaries.push(elementBinary.to_vec());
+    }
+
+    elementBinaries.sort();
+    elementBinaries.dedup();
+
+    Ok(elementBinaries)
+}
+
+/// tableFilter 最外层的and连接的各个部分
+pub(super) fn collectAndExprs<'a>(expr: &'a Expr, dest: &mut Vec<&'a Expr>) {
+    match expr {
+        Expr::BiDirection { leftExpr, op: Op::LogicalOp(LogicalOp::And), rightExprs } => {
+            collectAndExprs(leftExpr, dest);
+
+            for rightExpr in rightExprs {
+                collectAndExprs(rightExpr, dest);
+            }
+        }
+        _ => dest.push(expr),
+    }
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// tableFilter 最外层的and的某个部分是 list column contains|any 常量, 且这个column有inverted index <br>
+    /// 别的部分读取rowData后再计算 要找的元素是空的话用不了
+    pub(in crate::executor) fn getInvertedIndexSearch<'a>(&self,
+                                                          table: &'a Table,
+                                                          tableFilter: &Expr) -> Result<Option<InvertedIndexSearch<'a>>> {
+        if table.indexNames.is_empty() {
+            return Ok(None);
+        }
+
+        let mut andExprs = Vec::new();
+        collectAndExprs(tableFilter, &mut andExprs);
+
+        'loopAndExpr:
+        for andExpr in andExprs {
+            let (columnName, sqlOp, rightExpr) =
+                match andExpr {
+                    Expr::BiDirection {
+                        leftExpr,
+                        op: Op::SqlOp(sqlOp @ (SqlOp::Contain | SqlOp::Any)),
+                        rightExprs
+                    } if rightExprs.len() == 1 && rightExprs[0].needAcutalRowData() == false => {
+                        match &**leftExpr {
+                            Expr::Single(Element::TextLiteral(columnName)) => (columnName, sqlOp, &rightExprs[0]),
+                            _ => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+
+            let elementType =
+                match table.columns.iter().find(|column| &column.name == columnName) {
+                    Some(column) => {
+                        match column.type0 {
+                            ColumnType::List(ref elementType) => elementType,
+                            _ => continue,
+                        }
+                    }
+                    None => continue,
+                };
+
+            let mut elements =
+                match rightExpr.calc(None)? {
+                    GraphValue::List(list) => list,
+                    GraphValue::Json(json) if json.is_array() => list::fromJson(&json)?,
+                    GraphValue::Null => continue,
+                    value => vec![value],
+                };
+
+            if elements.is_empty() {
+                continue;
+            }
+
+            // 元素的type和column的不同的话 binary便对不上了
+            for element in &mut elements {
+                if elementType.compatibleWithValue(element) == false {
+                    continue 'loopAndExpr;
+                }
+
+                elementType.promoteValue(element);
+            }
+
+            for indexName in &table.indexNames {
+                let dbObjectIndex = Session::getDBObjectByName(indexName)?;
+                let index = dbObjectIndex.asIndex()?;
+
+                if index.inverted == false || &index.columnNames[0] != columnName {
+                    continue;
+                }
+
+                let elementBinaries = encodeInvertedIndexElements(&GraphValue::List(elements))?;
+
+                log::info!("use inverted index: {}", indexName);
+
+                return Ok(Some(InvertedIndexSearch {
+                    dbObjectIndex,
+                    elementBinaries,
+                    matchAll: matches!(sqlOp, SqlOp::Contain),
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 各个元素分别seek得到dataKey 然后交集或并集 <br>
+    /// 和searchByIndex相同 只应对committed的, 当前tx的mutation由scanSatisfiedRows应对
+    pub(in crate::executor) fn scanByInvertedIndex<A, B, C, D>(&self,
+                                                               invertedIndexSearch: InvertedIndexSearch,
+                                                               scanParams: &ScanParams,
+                                                               scanHooks: &mut ScanHooks<A, B, C, D>) -> Result<Vec<(DataKey, RowData)>>
+    where
+        A: CommittedPreProcessor,
+        B: CommittedPostProcessor,
+        C: UncommittedPreProcessor,
+        D: UncommittedPostProcessor,
+    {
+        let index = invertedIndexSearch.dbObjectIndex.asIndex()?;
+
+        let indexColumnFamily = Session::getColumnFamily(index.id)?;
+        let mut indexDBRawIterator: DBRawIterator = self.session.getDBRawIterator(&indexColumnFamily)?;
+
+        let mut dataKeys: Option<HashSet<DataKey>> = None;
+
+        for elementBinary in &invertedIndexSearch.elementBinaries {
+            let mut elementDataKeys = HashSet::new();
+
+            indexDBRawIterator.seek(elementBinary);
+
+            // 元素的编码是自带长度的 前缀相同便是这个元素
+            loop {
+                let indexKey = getKeyIfSome!(indexDBRawIterator);
+
+                if indexKey.starts_with(elementBinary) == false {
+                    break;
+                }
+
+                elementDataKeys.insert(extractDataKeyFromIndexKey!(indexKey));
+
+                indexDBRawIterator.next();
+            }
+
+            dataKeys =
+                match dataKeys {
+                    None => Some(elementDataKeys),
+                    Some(dataKeys) if invertedIndexSearch.matchAll => Some(dataKeys.intersection(&elementDataKeys).copied().collect()),
+                    Some(mut dataKeys) => {
+                        dataKeys.extend(elementDataKeys);
+                        Some(dataKeys)
+                    }
+                };
+
+            // 交集已经是空的了
+            if invertedIndexSearch.matchAll && dataKeys.as_ref().is_some_and(|dataKeys| dataKeys.is_empty()) {
+                break;
+            }
+        }
+
+        let mut dataKeys: Vec<DataKey> = dataKeys.unwrap_or_default().into_iter().collect();
+        dataKeys.sort();
+
+        self.recordScanStage(|scanStage| scanStage.rowsScanned += dataKeys.len());
+
+        let rowDatas = self.getRowDatasByDataKeys(dataKeys.as_slice(), scanParams, scanHooks)?;
+
+        Ok(rowDatas.into_iter().skip(scanParams.offset.unwrap_or(0)).take(scanParams.limit.unwrap_or(usize::MAX)).collect())
+    }
+}
@@ -2,26 +2,29 @@ use std::alloc::Layout;
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use hashbrown::{HashMap, HashSet};
+use std::mem;
 use std::ops::Deref;
 use std::rc::Rc;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use dashmap::mapref::one::Ref;
+use rocksdb::WriteBatchWithTransaction;
 use serde_json::Value;
 use crate::graph_value::GraphValue;
 use crate::parser::op::{LikePattern, MathCmpOp, Op, SqlOp};
-use crate::executor::{CommandExecutor, index, optimizer};
+use crate::executor::{explain, CommandExecutor, index, optimizer};
 use crate::expr::Expr;
 use crate::meta::{ColumnType, DBObject, Table};
 use crate::{byte_slice_to_u32, byte_slice_to_u64, global, meta, suffix_plus_plus, throwFormat, u64ToByteArrRef, utils};
 use crate::codec::{BinaryCodec, MyBytes, SliceWrapper};
 use crate::executor::store;
-use crate::session::Session;
+use crate::session::{CommitCheck, Session};
 use crate::types::{Byte, ColumnFamily, DataKey, DBRawIterator, Pointer, RowData, SessionHashMap, SessionHashSet, SessionVec, TableMutations};
 use anyhow::Result;
 use crate::executor::optimizer::merge;
 use crate::executor::optimizer::merge::AccumulateResult;
 use crate::executor::store::{ScanHooks, ScanParams};
 use crate::parser::op;
+use crate::parser::command::select::OrderBy;
 use crate::types::{CommittedPostProcessor, CommittedPreProcessor, UncommittedPostProcessor, UncommittedPreProcessor};
 use crate::utils::VirtualSlice;
 
@@ -40,6 +43,19 @@ macro_rules! extractIndexRowDataFromIndexKey {
     };
 }
 
+mod ann;
+mod inverted;
+mod primary_key;
+mod spatial;
+mod unique;
+
+pub(in crate::executor) use inverted::encodeInvertedIndexElements;
+pub(in crate::executor) use spatial::encodeSpatialIndexKey;
+pub(in crate::executor) use unique::{committedDataDeleted, uniqueCheckNeeded};
+
+/// scanRowDatasByIndexInBatches 每趟攒下这么多的dataKey后调用getRowDatasByDataKeys
+const DATA_KEY_BATCH_SIZE: usize = 128;
+
 pub(in crate::executor) struct IndexSearch<'a> {
     pub dbObjectIndex: Ref<'a, String, DBObject>,
 
@@ -144,6 +160,11 @@ impl<'session> CommandExecutor<'session> {
             let dbObjectIndex = Session::getDBObjectByName(indexName)?;
             let index = dbObjectIndex.asIndex()?;
 
+            // ann index 上的key是list的编号 inverted index 上的key是list的元素 spatial index 上的key是geohash 应对不了普通的filter
+            if index.isAnn() || index.inverted || index.spatial {
+                continue 'loopIndex;
+            }
+
             // filter能用到index的几个字段
             // tableFilter的字段和index的字段就算有交集,tableFilter的字段也兜底要包含index的第1个字段
             // 例如 (b=1 and c=3),虽然index含有字段a,b,c,然而tableFilter未包含打头的a字段 不能使用
@@ -304,11 +325,15 @@ impl<'session> CommandExecutor<'session> {
             return compareFilterdColCount;
         });
 
+        self.recordScanStage(|scanStage| {
+            scanStage.candidateIndexNames = candiateInices.iter().map(|(dbObjectIndex, _, _, _)| dbObjectIndex.getName().clone()).collect();
+        });
+
         // 目前的话实现的比较粗糙,排前头的几个要是 indexFilteredColNames 大小相同 选第1个
         let (dbObjectIndex,
             indexSelectedColCount,
             indexFilteredColNames,
-            opValueVecVecAcrossIndexFilteredCols) = candiateInices.remove(0);
+            mut opValueVecVecAcrossIndexFilteredCols) = candiateInices.remove(0);
 
         //  对拥有相同 indexFilteredColNames 的多个 index 的筛选
         // index字段要覆盖全部的过滤条件
@@ -330,9 +355,19 @@ impl<'session> CommandExecutor<'session> {
                     }
                 }
 
-                indexFilterColTypes.push(indexFilterColumn.type0);
+                indexFilterColTypes.push(indexFilterColumn.type0.clone());
 
-                let opValueVecVec = opValueVecVecAcrossIndexFilteredCols.get(index).unwrap();
+                let opValueVecVec = opValueVecVecAcrossIndexFilteredCols.get_mut(index).unwrap();
+
+                // bytes json的index只用来等值查找 范围的话scan
+                if let ColumnType::Bytes | ColumnType::Json = indexFilterColumn.type0 {
+                    let allEqual =
+                        opValueVecVec.iter().flatten().all(|(op, _)| matches!(op, Op::MathCmpOp(MathCmpOp::Equal)));
+
+                    if allEqual == false {
+                        return Ok(None);
+                    }
+                }
 
                 for opValueVec in opValueVecVec {
                     for (_, value) in opValueVec {
@@ -340,6 +375,9 @@ impl<'session> CommandExecutor<'session> {
                             throwFormat!("table: {}, column:{}, type:{} is not compatible with value:{}",
                                 scanParams.table.name, columnNameFromIndexUsed, indexFilterColumn.type0, value)
                         }
+
+                        // index里边的是decimal 用来seek的value的type也要相同
+                        indexFilterColumn.type0.promoteValue(value);
                     }
                 }
             }
@@ -432,6 +470,9 @@ impl<'session> CommandExecutor<'session> {
         let process =
             |rowDatas: SessionHashMap<DataKey, (DataKey, RowData)>,
              dataKeys: SessionHashSet<DataKey>| {
+                // index找到的数量
+                self.recordScanStage(|scanStage| scanStage.rowsScanned += rowDatas.len() + dataKeys.len());
+
                 if indexSearch.indexLocalSearch {
                     let rowDatas = rowDatas.into_values().collect::<Vec<(DataKey, RowData)>>();
                     return Result::<Vec<(DataKey, RowData)>>::Ok(rowDatas);
@@ -487,10 +528,15 @@ impl<'session> CommandExecutor<'session> {
         if beginPosition >= indexSearch.opValueVecVecAcrossIndexFilteredCols.len() {
             indexDBRawIterator.seek(prefixBuffer.as_ref());
 
-            if let Some(indexKey) = indexDBRawIterator.key() {
-                if extractIndexRowDataFromIndexKey!(indexKey).starts_with(prefixBuffer.as_ref()) {
-                    processWhenPrefixFollowing1stColSatisfied(indexKey, beginPosition)?;
+            // 相同value的data可能有多条
+            while let Some(indexKey) = indexDBRawIterator.key() {
+                if extractIndexRowDataFromIndexKey!(indexKey).starts_with(prefixBuffer.as_ref()) == false {
+                    break;
                 }
+
+                processWhenPrefixFollowing1stColSatisfied(indexKey, beginPosition)?;
+
+                indexDBRawIterator.next();
             }
 
             return process(rowDatas, dataKeys);
@@ -499,21 +545,23 @@ impl<'session> CommandExecutor<'session> {
         // seek那都是要以index的第1个column为切入的, 后边的column是在index数据基础上的筛选
         let opValueVecVecOnIndexFollowing1stColumn = &indexSearch.opValueVecVecAcrossIndexFilteredCols[beginPosition];
 
-        // 当前应对不了string 它不是固定长度的
-        // 先要看看后边的第1个是不是string
-        let following1stColumnType = indexSearch.indexFilterColTypes[beginPosition];
+        // 当前应对不了string bytes 它们不是固定长度的
+        // 先要看看后边的第1个是不是变长的
+        let following1stColumnType = &indexSearch.indexFilterColTypes[beginPosition];
 
         // 包含 prefix 和 后边第1列的value的buffer
         let mut lowerValueBuffer = self.withCapacityIn(prefixBuffer.len() + following1stColumnType.graphValueSize().unwrap_or_else(|| 0usize));
         lowerValueBuffer.put_slice(prefixBuffer.as_ref());
+        // 后边要在它上边打个小窗口(slice)写入value 要确保len还不止是capacity
+        lowerValueBuffer.resize(lowerValueBuffer.capacity(), 0);
         let mut upperValueBuffer = lowerValueBuffer.clone();
 
         // opValueVecOnIndex1stColumn 之间不管isAnd如何都是 or
         for opValueVecOnIndexFollowing1stColumn in opValueVecVecOnIndexFollowing1stColumn {
             // opValueVecOnIndex1stColumn 的各个元素(opValueVec)之间是不论是不是isAnd,都是or
             if indexSearch.isPureAnd {
-                // 不是用不用like的问题 是 column是不是string
-                if following1stColumnType == ColumnType::String {
+                // 不是用不用like的问题 是 column是不是变长的
+                if following1stColumnType.graphValueSize().is_none() {
                     let applyFiltersOnFollowing1stColValue = |indexKey: &[Byte]| {
                         // 对indexRowData来说只要第1列的value
                         let stringValue = {
@@ -527,12 +575,10 @@ impl<'session> CommandExecutor<'session> {
                             // 去掉了prefixBuffer内容的index剩下的内容
                             let indexRowData = &extractIndexRowDataFromIndexKey!(indexKey)[prefixBuffer.len()..];
 
-                            assert_eq!(indexRowData[0], GraphValue::STRING);
+                            // string bytes json 都是 type + len + content
+                            assert!(matches!(indexRowData[0], GraphValue::STRING | GraphValue::BYTES | GraphValue::JSON));
 
-                            let len = byte_slice_to_u32!(&indexRowData[GraphValue::TYPE_BYTE_LEN..GraphValue::STRING_CONTENT_OFFSET]) as usize;
-                            let string = String::from_utf8_lossy(&indexRowData[GraphValue::STRING_CONTENT_OFFSET..GraphValue::STRING_CONTENT_OFFSET + len]).to_string();
-
-                            GraphValue::String(string)
+                            GraphValue::decodeFromSliceWrapper(&mut SliceWrapper::new(indexRowData), None)?
                         };
 
                         for (op, value) in opValueVecOnIndexFollowing1stColumn {
@@ -546,8 +592,6 @@ impl<'session> CommandExecutor<'session> {
 
                     // 如何应对 like 'a%' and >'a'
                     for (op, value) in opValueVecOnIndexFollowing1stColumn {
-                        assert!(value.isString());
-
                         // 因为string是变长的,只能现用现生成,不像是int等固定长度可以提前分配空间,性能上会降低
                         let mut bufferString = {
                             let capacity = prefixBuffer.len() + value.size().unwrap();
@@ -1049,6 +1093,152 @@ impl<'session> CommandExecutor<'session> {
         Ok(Some(rowData))
     }
 
+    /// order by的column都是定长的数字 方向相同 且是某个index的打头的若干column 那么index上key的顺序便是order by的顺序 <br>
+    /// string是以长度打头的 binary顺序和字面的顺序不同 用不了
+    pub(in crate::executor) fn getIndexSuitableForOrderBy<'a>(&self,
+                                                              table: &'a Table,
+                                                              orderBys: &[OrderBy]) -> Result<Option<Ref<'a, String, DBObject>>> {
+        if orderBys.is_empty() || table.indexNames.is_empty() {
+            return Ok(None);
+        }
+
+        let asc = orderBys[0].asc;
+
+        for orderBy in orderBys {
+            if orderBy.asc != asc {
+                return Ok(None);
+            }
+
+            let column = table.columns.iter().find(|column| column.name == orderBy.columnName);
+
+            match column {
+                Some(column) => {
+                    match column.type0 {
+                        ColumnType::Integer | ColumnType::Double => {}
+                        _ => return Ok(None),
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+
+        for indexName in &table.indexNames {
+            let dbObjectIndex = Session::getDBObjectByName(indexName)?;
+            let index = dbObjectIndex.asIndex()?;
+
+            if index.columnNames.len() < orderBys.len() {
+                continue;
+            }
+
+            let matched =
+                index.columnNames.iter().zip(orderBys).all(
+                    |(indexColumnName, orderBy)| indexColumnName == &orderBy.columnName
+                );
+
+            if matched {
+                log::info!("order by use index: {}", indexName);
+                return Ok(Some(dbObjectIndex));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 以index上的key的顺序读取table的data 满足了offset limit后便可停下 <br>
+    /// 和searchByIndex相同 只应对committed的 调用方要确保当前tx在这个table上没有mutation
+    pub(in crate::executor) fn scanByIndexInOrder(&self,
+                                                  dbObjectIndex: &DBObject,
+                                                  scanParams: &ScanParams,
+                                                  asc: bool) -> Result<Vec<(DataKey, RowData)>> {
+        let index = dbObjectIndex.asIndex()?;
+
+        self.beginScanStage(scanParams.table.name.as_str());
+        self.recordScanStage(|scanStage| {
+            scanStage.accessPath = explain::ACCESS_PATH_INDEX;
+            scanStage.chosenIndexName = Some(index.name.clone());
+        });
+
+        if self.explainWithoutAnalyze() {
+            return Ok(vec![]);
+        }
+
+        let indexColumnFamily = Session::getColumnFamily(index.id)?;
+        let mut indexDBRawIterator = self.session.getDBRawIterator(&indexColumnFamily)?;
+
+        if asc {
+            indexDBRawIterator.seek_to_first();
+        } else {
+            indexDBRawIterator.seek_to_last();
+        }
+
+        let mut offsetRemaining = scanParams.offset.unwrap_or(0);
+        let limit = scanParams.limit.unwrap_or(usize::MAX);
+
+        let mut satisfiedRows = Vec::new();
+
+        self.scanRowDatasByIndexInBatches(
+            scanParams,
+            || {
+                let indexKey = indexDBRawIterator.key()?;
+                let dataKey = extractDataKeyFromIndexKey!(indexKey);
+
+                if asc {
+                    indexDBRawIterator.next();
+                } else {
+                    indexDBRawIterator.prev();
+                }
+
+                Some(dataKey)
+            },
+            |dataKey, rowData| {
+                if offsetRemaining > 0 {
+                    offsetRemaining -= 1;
+                    return Ok(true);
+                }
+
+                satisfiedRows.push((dataKey, rowData));
+
+                Ok(satisfiedRows.len() < limit)
+            },
+        )?;
+
+        self.recordScanStage(|scanStage| scanStage.rowsReturned += satisfiedRows.len());
+
+        Ok(satisfiedRows)
+    }
+
+    /// 沿着index每趟攒下DATA_KEY_BATCH_SIZE个dataKey 再调用getRowDatasByDataKeys 把得到的rowData交给rowDataConsumer <br>
+    /// nextDataKey返回None是index上没有了 rowDataConsumer返回false是不要再继续了
+    pub(in crate::executor) fn scanRowDatasByIndexInBatches(&self,
+                                                            scanParams: &ScanParams,
+                                                            mut nextDataKey: impl FnMut() -> Option<DataKey>,
+                                                            mut rowDataConsumer: impl FnMut(DataKey, RowData) -> Result<bool>) -> Result<()> {
+        let mut dataKeys = Vec::with_capacity(DATA_KEY_BATCH_SIZE);
+
+        loop {
+            dataKeys.clear();
+
+            while dataKeys.len() < DATA_KEY_BATCH_SIZE {
+                match nextDataKey() {
+                    Some(dataKey) => dataKeys.push(dataKey),
+                    None => break,
+                }
+            }
+
+            if dataKeys.is_empty() {
+                return Ok(());
+            }
+
+            self.recordScanStage(|scanStage| scanStage.rowsScanned += dataKeys.len());
+
+            for (dataKey, rowData) in self.getRowDatasByDataKeys(dataKeys.as_slice(), scanParams, &mut ScanHooks::default())? {
+                if rowDataConsumer(dataKey, rowData)? == false {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
     pub(in crate::executor) fn generateIndexData(&self,
                                                  table: &Table,
                                                  indexKeyBuffer: &mut BytesMut,
@@ -1064,6 +1254,28 @@ impl<'session> CommandExecutor<'session> {
 
             assert_eq!(table.name, index.tableName);
 
+            // 每个不同的元素各有1条
+            if index.inverted {
+                for elementBinary in inverted::encodeInvertedIndexElements(rowData.get(&index.columnNames[0]).unwrap())? {
+                    indexKeyBuffer.clear();
+
+                    if delete {
+                        indexKeyBuffer.put_u64(self.session.getTxId()?);
+                    }
+
+                    indexKeyBuffer.put_slice(&elementBinary);
+                    indexKeyBuffer.put_slice(dataKeyBinary);
+
+                    if delete {
+                        self.session.writeAddIndexMutation(index.trashId, (indexKeyBuffer.to_vec(), global::EMPTY_BINARY));
+                    } else {
+                        self.session.writeAddIndexMutation(index.id, (indexKeyBuffer.to_vec(), global::EMPTY_BINARY));
+                    }
+                }
+
+                continue;
+            }
+
             indexKeyBuffer.clear();
 
             // 如果是delete的话将txId写到头部
@@ -1071,10 +1283,29 @@ impl<'session> CommandExecutor<'session> {
                 indexKeyBuffer.put_u64(self.session.getTxId()?);
             }
 
-            // 遍历了index的各个column
-            for indexColumnName in &index.columnNames {
-                let columnValue = rowData.get(indexColumnName).unwrap();
-                columnValue.encode2ByteMut(indexKeyBuffer)?;
+            if index.isAnn() {
+                ann::encodeAnnIndexKey(index, rowData.get(&index.columnNames[0]).unwrap(), indexKeyBuffer);
+            } else if index.spatial {
+                spatial::encodeSpatialIndexKey(rowData.get(&index.columnNames[0]).unwrap(), indexKeyBuffer);
+            } else {
+                // 遍历了index的各个column
+                for indexColumnName in &index.columnNames {
+                    let columnValue = rowData.get(indexColumnName).unwrap();
+                    columnValue.encode2ByteMut(indexKeyBuffer)?;
+                }
+
+                // unique index 要先确认没有相同的值
+                if index.unique && delete == false && unique::uniqueCheckNeeded(index, rowData) {
+                    self.checkUniqueIndex(table, index, indexKeyBuffer.as_ref(), dataKey)?;
+
+                    self.session.writeCommitCheck(CommitCheck::UniqueIndex {
+                        tableId: table.id,
+                        indexId: index.id,
+                        indexName: index.name.clone(),
+                        indexValue: indexKeyBuffer.to_vec(),
+                        dataKey,
+                    });
+                }
             }
 
             // indexKey的末尾写上dataKey,这样就算row上的data相同也能区分
@@ -1090,9 +1321,87 @@ impl<'session> CommandExecutor<'session> {
 
         Ok(())
     }
+
+    /// commit时候 在COMMIT_LOCK里边对最新落地的校验 要删掉的primary key写到batch
+    pub(crate) fn validateCommitChecks(&self, batch: &mut WriteBatchWithTransaction<false>) -> Result<()> {
+        let commitChecks = mem::take(&mut *self.session.commitChecks.write().unwrap());
+
+        for commitCheck in &commitChecks {
+            match commitCheck {
+                CommitCheck::UniqueIndex { tableId, indexId, indexName, indexValue, dataKey } =>
+                    self.checkUniqueIndexLatest(*tableId, *indexId, indexName, indexValue, *dataKey)?,
+                CommitCheck::PrimaryKey { tableId, primaryKeyId, tableName, primaryKey, dataKey } =>
+                    self.checkPrimaryKeyLatest(*tableId, *primaryKeyId, tableName, primaryKey, *dataKey)?,
+                CommitCheck::PrimaryKeyDeleted { primaryKeyId, primaryKey, dataKey } =>
+                    self.deletePrimaryKeyLatest(*primaryKeyId, primaryKey, *dataKey, batch)?,
+            }
+        }
+
+        Ok(())
+    }
 }
 
 pub(in crate::executor) enum IndexSearchResult {
     Direct((DataKey, RowData)),
     Redirect(DataKey),
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::meta;
+    use crate::session::Session;
+
+    #[test]
+    pub fn testIndexEqualDuplicateValues() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table indexCustomer");
+        session.executeSql("create table indexCustomer (id integer, city string)").unwrap();
+        session.executeSql("create index indexCustomerCity on indexCustomer[city]").unwrap();
+        session.executeSql("insert into indexCustomer values (1, 'a')").unwrap();
+        session.executeSql("insert into indexCustomer values (2, 'b')").unwrap();
+        session.executeSql("insert into indexCustomer values (3, 'a')").unwrap();
+        session.executeSql("insert into indexCustomer values (4, 'a')").unwrap();
+
+        // index上相同value的各个data都要得到
+        let mut ids: Vec<i64> =
+            session.executeSql("select indexCustomer[id](city = 'a')").unwrap().remove(0)
+                .iter().map(|row| row["id"].as_i64().unwrap()).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 3, 4]);
+
+        assert_eq!(session.executeSql("select indexCustomer[id](city = 'b')").unwrap().remove(0), vec![json!({"id": 2})]);
+    }
+
+    #[test]
+    pub fn testIndexOrderAcrossBatches() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table indexScore");
+        session.executeSql("create table indexScore (id integer, score integer)").unwrap();
+        session.executeSql("create index indexScoreScore on indexScore[score]").unwrap();
+
+        for id in 0..300 {
+            session.executeSql(&format!("insert into indexScore values ({}, {})", id, id * 7 % 300)).unwrap();
+        }
+
+        let explain = session.executeSql("explain select indexScore order by score limit 1").unwrap().remove(0);
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("index"));
+
+        let scores = |session: &mut Session, sql: &str| -> Vec<i64> {
+            session.executeSql(sql).unwrap().remove(0).iter().map(|row| row["score"].as_i64().unwrap()).collect()
+        };
+
+        // offset limit 跨过了1趟的dataKey数量
+        assert_eq!(scores(&mut session, "select indexScore[score] order by score limit 3 offset 127"), vec![127, 128, 129]);
+        assert_eq!(scores(&mut session, "select indexScore[score] order by score desc limit 3 offset 200"), vec![99, 98, 97]);
+        assert_eq!(scores(&mut session, "select indexScore[score] order by score limit 10 offset 295"), vec![295, 296, 297, 298, 299]);
+
+        session.executeSql("drop table indexScore").unwrap();
+    }
+}
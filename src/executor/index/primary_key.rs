@@ -0,0 +1,403 @@
+use bytes::BytesMut;
+use rocksdb::WriteBatchWithTransaction;
+use crate::codec::BinaryCodec;
+use crate::executor::{explain, CommandExecutor};
+use crate::executor::index::inverted;
+use crate::executor::store::{ScanHooks, ScanParams};
+use crate::expr::Expr;
+use crate::graph_value::GraphValue;
+use crate::meta::Table;
+use crate::parser::element::Element;
+use crate::parser::op::{MathCmpOp, Op};
+use crate::session::{CommitCheck, Session};
+use crate::types::{Byte, CommittedPostProcessor, CommittedPreProcessor, DataKey, DBObjectId, DBRawIterator, RowData, TableMutations, UncommittedPostProcessor, UncommittedPreProcessor};
+use crate::{byte_slice_to_u64, meta, throwFormat, u64ToByteArrRef};
+use anyhow::Result;
+use hashbrown::HashMap;
+
+/// primary key 的key是各个primary key column的值依次encode value是dataKey
+fn encodePrimaryKey(table: &Table, rowData: &RowData, dest: &mut BytesMut) -> Result<()> {
+    dest.clear();
+
+    for primaryKeyColumnName in &table.primaryKeyColumnNames {
+        match rowData.get(primaryKeyColumnName) {
+            None | Some(GraphValue::Null) => throwFormat!("table: {}, primary key column: {} can not be null", table.name, primaryKeyColumnName),
+            Some(columnValue) => columnValue.encode2ByteMut(dest)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// tableFilter 最外层的and里边 各个primary key column 都有 column = 常量 <br>
+/// 得到的常量要和column type 匹配 不然交给原来的scan
+fn extractPrimaryKeyValues(table: &Table, tableFilter: &Expr) -> Result<Option<RowData>> {
+    let mut andExprs = Vec::new();
+    inverted::collectAndExprs(tableFilter, &mut andExprs);
+
+    let mut primaryKeyValues: RowData = HashMap::with_capacity(table.primaryKeyColumnNames.len());
+
+    for andExpr in andExprs {
+        let (columnName, constExpr) =
+            match andExpr {
+                Expr::BiDirection { leftExpr, op: Op::MathCmpOp(MathCmpOp::Equal), rightExprs } if rightExprs.len() == 1 => {
+                    // id = 1 和 1 = id 都可以
+                    match (&**leftExpr, rightExprs[0].as_ref()) {
+                        (Expr::Single(Element::TextLiteral(columnName)), constExpr) => (columnName, constExpr),
+                        (constExpr, Expr::Single(Element::TextLiteral(columnName))) => (columnName, constExpr),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+        if table.primaryKeyColumnNames.contains(columnName) == false || primaryKeyValues.contains_key(columnName) {
+            continue;
+        }
+
+        if constExpr.needAcutalRowData() {
+            continue;
+        }
+
+        let mut constValue = constExpr.calc(None)?;
+
+        let column = table.columns.iter().find(|column| &column.name == columnName).unwrap();
+
+        if matches!(constValue, GraphValue::Null) || column.type0.compatibleWithValue(&constValue) == false {
+            return Ok(None);
+        }
+
+        column.type0.fitValue(&mut constValue)?;
+
+        primaryKeyValues.insert(columnName.clone(), constValue);
+    }
+
+    if primaryKeyValues.len() != table.primaryKeyColumnNames.len() {
+        return Ok(None);
+    }
+
+    Ok(Some(primaryKeyValues))
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// insert和update时候 写入primary key -> dataKey, 之前要确认没有相同的primary key的可见的data <br>
+    /// delete和update的老的data 当前tx上的primary key指向它的话去掉 已落地的到commit时候再删掉
+    pub(in crate::executor) fn generatePrimaryKeyData(&self,
+                                                      table: &Table,
+                                                      dataKey: DataKey,
+                                                      rowData: &RowData,
+                                                      delete: bool) -> Result<()> {
+        if table.hasPrimaryKey() == false {
+            return Ok(());
+        }
+
+        let mut primaryKeyBuffer = self.newIn();
+        encodePrimaryKey(table, rowData, &mut primaryKeyBuffer)?;
+
+        if delete {
+            {
+                let mut dbObjectId_mutations = self.session.dbObjectId_mutations.write().unwrap();
+
+                if let Some(primaryKeyMutations) = dbObjectId_mutations.get_mut(&table.primaryKeyId) {
+                    let pointToSelf =
+                        match primaryKeyMutations.get(primaryKeyBuffer.as_ref()) {
+                            Some(dataKeyBinary) => byte_slice_to_u64!(dataKeyBinary) == dataKey,
+                            None => false,
+                        };
+
+                    if pointToSelf {
+                        primaryKeyMutations.remove(primaryKeyBuffer.as_ref());
+                    }
+                }
+            }
+
+            self.session.writeCommitCheck(CommitCheck::PrimaryKeyDeleted {
+                primaryKeyId: table.primaryKeyId,
+                primaryKey: primaryKeyBuffer.to_vec(),
+                dataKey,
+            });
+
+            return Ok(());
+        }
+
+        if let Some(existDataKey) = self.getDataKeyByPrimaryKey(table, primaryKeyBuffer.as_ref())? {
+            if existDataKey != dataKey && self.getVisibleRowData(table, existDataKey, &ScanParams { table, ..Default::default() }, &mut ScanHooks::default())?.is_some() {
+                throwFormat!("duplicate primary key on table: {}", table.name);
+            }
+        }
+
+        // update时候的新data 直接覆盖原来的
+        self.session.writeAddPrimaryKeyMutation(table.primaryKeyId, (primaryKeyBuffer.to_vec(), u64ToByteArrRef!(dataKey).to_vec()));
+
+        self.session.writeCommitCheck(CommitCheck::PrimaryKey {
+            tableId: table.id,
+            primaryKeyId: table.primaryKeyId,
+            tableName: table.name.clone(),
+            primaryKey: primaryKeyBuffer.to_vec(),
+            dataKey,
+        });
+
+        Ok(())
+    }
+
+    /// commit时候 对最新落地的primary key再校验1趟 别的tx可能同时写入了相同的primary key并先提交了
+    pub(super) fn checkPrimaryKeyLatest(&self,
+                                        tableId: DBObjectId,
+                                        primaryKeyId: DBObjectId,
+                                        tableName: &str,
+                                        primaryKey: &[Byte],
+                                        selfDataKey: DataKey) -> Result<()> {
+        let duplicate =
+            self.latestDuplicateAlive(tableId, primaryKeyId, selfDataKey, |primaryKeyColumnFamily| {
+                match self.session.getWithoutSnapshot(primaryKey, primaryKeyColumnFamily)? {
+                    Some(dataKeyBinary) => Ok(vec![byte_slice_to_u64!(dataKeyBinary.as_slice())]),
+                    None => Ok(vec![]),
+                }
+            })?;
+
+        if duplicate {
+            throwFormat!("duplicate primary key on table: {}", tableName);
+        }
+
+        Ok(())
+    }
+
+    /// commit时候 当前tx没有再写入这个primary key 而且最新落地的还指向删掉的data的话 把primary key删掉 <br>
+    /// 别的tx同时写入了这个primary key并先提交了的话 指向的便不是删掉的data了
+    pub(super) fn deletePrimaryKeyLatest(&self,
+                                         primaryKeyId: DBObjectId,
+                                         primaryKey: &[Byte],
+                                         deletedDataKey: DataKey,
+                                         batch: &mut WriteBatchWithTransaction<false>) -> Result<()> {
+        let primaryKeyColumnFamily =
+            match Session::getColumnFamily(primaryKeyId) {
+                Ok(primaryKeyColumnFamily) => primaryKeyColumnFamily,
+                Err(_) => return Ok(()),
+            };
+
+        {
+            let dbObjectId_mutations = self.session.dbObjectId_mutations.read().unwrap();
+
+            if let Some(primaryKeyMutations) = dbObjectId_mutations.get(&primaryKeyId) {
+                if primaryKeyMutations.contains_key(primaryKey) {
+                    return Ok(());
+                }
+            }
+        }
+
+        if let Some(dataKeyBinary) = self.session.getWithoutSnapshot(primaryKey, &primaryKeyColumnFamily)? {
+            if byte_slice_to_u64!(dataKeyBinary.as_slice()) == deletedDataKey {
+                batch.delete_cf(&primaryKeyColumnFamily, primaryKey);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// tableFilter 是primary key上的等值的话 直接通过primary key得到dataKey 不用去规划scan <br>
+    /// 返回none说明用不了primary key
+    pub(in crate::executor) fn scanByPrimaryKey<A, B, C, D>(&self,
+                                                            scanParams: &ScanParams,
+                                                            scanHooks: &mut ScanHooks<A, B, C, D>) -> Result<Option<Vec<(DataKey, RowData)>>>
+    where
+        A: CommittedPreProcessor,
+        B: CommittedPostProcessor,
+        C: UncommittedPreProcessor,
+        D: UncommittedPostProcessor,
+    {
+        let table = scanParams.table;
+
+        if table.hasPrimaryKey() == false {
+            return Ok(None);
+        }
+
+        let primaryKeyValues =
+            match scanParams.tableFilter {
+                Some(tableFilter) => match extractPrimaryKeyValues(table, tableFilter)? {
+                    Some(primaryKeyValues) => primaryKeyValues,
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            };
+
+        self.recordScanStage(|scanStage| scanStage.accessPath = explain::ACCESS_PATH_PRIMARY_KEY);
+
+        if self.explainWithoutAnalyze() {
+            return Ok(Some(Vec::new()));
+        }
+
+        let mut primaryKeyBuffer = self.newIn();
+        encodePrimaryKey(table, &primaryKeyValues, &mut primaryKeyBuffer)?;
+
+        let dataKey =
+            match self.getDataKeyByPrimaryKey(table, primaryKeyBuffer.as_ref())? {
+                Some(dataKey) => dataKey,
+                None => return Ok(Some(Vec::new())),
+            };
+
+        // 只会有1条 offset大于0的话便是没有的
+        if scanParams.offset.unwrap_or(0) > 0 || scanParams.limit == Some(0) {
+            return Ok(Some(Vec::new()));
+        }
+
+        match self.getVisibleRowData(table, dataKey, scanParams, scanHooks)? {
+            Some(dataKeyRowData) => Ok(Some(vec![dataKeyRowData])),
+            None => Ok(Some(Vec::new())),
+        }
+    }
+
+    /// 先看当前tx上的 然后是已提交的
+    fn getDataKeyByPrimaryKey(&self, table: &Table, primaryKey: &[Byte]) -> Result<Option<DataKey>> {
+        {
+            let dbObjectId_mutations = self.session.dbObjectId_mutations.read().unwrap();
+
+            if let Some(primaryKeyMutations) = dbObjectId_mutations.get(&table.primaryKeyId) {
+                if let Some(dataKeyBinary) = primaryKeyMutations.get(primaryKey) {
+                    return Ok(Some(byte_slice_to_u64!(dataKeyBinary)));
+                }
+            }
+        }
+
+        let columnFamily = Session::getColumnFamily(table.primaryKeyId)?;
+
+        match self.session.getSnapshot()?.get_cf(&columnFamily, primaryKey)? {
+            Some(dataKeyBinary) => Ok(Some(byte_slice_to_u64!(dataKeyBinary.as_slice()))),
+            None => Ok(None),
+        }
+    }
+
+    /// primary key 指向的data 可能已经被删掉了(甚至被vaccum掉了) 要经过mvcc筛选
+    fn getVisibleRowData<A, B, C, D>(&self,
+                                     table: &Table,
+                                     dataKey: DataKey,
+                                     scanParams: &ScanParams,
+                                     scanHooks: &mut ScanHooks<A, B, C, D>) -> Result<Option<(DataKey, RowData)>>
+    where
+        A: CommittedPreProcessor,
+        B: CommittedPostProcessor,
+        C: UncommittedPreProcessor,
+        D: UncommittedPostProcessor,
+    {
+        {
+            let dbObjectId_mutations = self.session.dbObjectId_mutations.read().unwrap();
+
+            // 当前tx上add的data 不会是已提交的 不能交给getRowDatasByDataKeys 不然不可见的话会去已提交的里边找
+            if let Some(tableMutations) = dbObjectId_mutations.get(&table.id) {
+                if let Some(addedValueBinary) = tableMutations.get(u64ToByteArrRef!(dataKey).as_ref()) {
+                    let mut mvccKeyBuffer = self.withCapacityIn(meta::MVCC_KEY_BYTE_LEN);
+
+                    if self.uncommittedDataVisible(tableMutations, &mut mvccKeyBuffer, dataKey)? == false {
+                        return Ok(None);
+                    }
+
+                    if scanHooks.preProcessUncommitted(tableMutations, dataKey)? == false {
+                        return Ok(None);
+                    }
+
+                    if let Some(rowData) = self.readRowDataBinary(addedValueBinary.as_slice(), scanParams)? {
+                        if scanHooks.postProcessUncommitted(tableMutations, dataKey, &rowData)? {
+                            return Ok(Some((dataKey, rowData)));
+                        }
+                    }
+
+                    return Ok(None);
+                }
+            }
+        }
+
+        // 已提交的data 可能已被vaccum掉了
+        let columnFamily = Session::getColumnFamily(table.id)?;
+        if self.session.getSnapshot()?.get_cf(&columnFamily, u64ToByteArrRef!(dataKey))?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(self.getRowDatasByDataKeys(&[dataKey], scanParams, scanHooks)?.pop())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+    use crate::codec::BinaryCodec;
+    use crate::graph_value::GraphValue;
+    use crate::meta;
+    use crate::session::Session;
+
+    fn rows(session: &mut Session, sql: &str) -> Vec<serde_json::Value> {
+        session.executeSql(sql).unwrap().remove(0)
+    }
+
+    /// 已落地的primary key指向的dataKey
+    fn primaryKeyDataKeyLatest(session: &Session, tableName: &str, id: i64) -> Option<Vec<u8>> {
+        let primaryKeyId = Session::getDBObjectByName(tableName).unwrap().asTable().unwrap().primaryKeyId;
+        let columnFamily = Session::getColumnFamily(primaryKeyId).unwrap();
+
+        let mut primaryKey = BytesMut::new();
+        GraphValue::Integer(id).encode2ByteMut(&mut primaryKey).unwrap();
+
+        session.getWithoutSnapshot(primaryKey.as_ref(), &columnFamily).unwrap()
+    }
+
+    #[test]
+    pub fn testPrimaryKey() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table pkUser");
+        session.executeSql("create table pkUser (id integer, name string, primary key (id))").unwrap();
+        session.executeSql("insert into pkUser values (1, 'a')").unwrap();
+        session.executeSql("insert into pkUser values (2, 'b')").unwrap();
+
+        assert!(session.executeSql("insert into pkUser values (1, 'c')").is_err());
+
+        // 删掉后已落地的primary key也要去掉 不然会指向已删掉的data
+        session.executeSql("delete from pkUser(id = 2)").unwrap();
+        assert!(primaryKeyDataKeyLatest(&session, "pkUser", 2).is_none());
+        assert!(rows(&mut session, "select pkUser(id = 2)").is_empty());
+
+        session.executeSql("insert into pkUser values (2, 'd')").unwrap();
+        assert!(primaryKeyDataKeyLatest(&session, "pkUser", 2).is_some());
+        assert_eq!(rows(&mut session, "select pkUser[name](id = 2)"), vec![serde_json::json!({"name": "d"})]);
+
+        // update改掉了primary key的值 老的primary key要去掉
+        session.executeSql("update pkUser[id = 3](id = 1)").unwrap();
+        assert!(primaryKeyDataKeyLatest(&session, "pkUser", 1).is_none());
+        assert_eq!(rows(&mut session, "select pkUser[name](id = 3)"), vec![serde_json::json!({"name": "a"})]);
+        session.executeSql("insert into pkUser values (1, 'e')").unwrap();
+
+        // 同1个tx里边先删掉再写入相同的primary key
+        session.setAutoCommit(false).unwrap();
+        session.executeSql("delete from pkUser(id = 1)").unwrap();
+        session.executeSql("insert into pkUser values (1, 'f')").unwrap();
+        session.commit().unwrap();
+        session.setAutoCommit(true).unwrap();
+
+        assert!(primaryKeyDataKeyLatest(&session, "pkUser", 1).is_some());
+        assert_eq!(rows(&mut session, "select pkUser[name](id = 1)"), vec![serde_json::json!({"name": "f"})]);
+        assert_eq!(rows(&mut session, "select pkUser").len(), 3);
+
+        session.executeSql("drop table pkUser").unwrap();
+    }
+
+    /// explain 不去读取data 也要标明走的是primary key
+    #[test]
+    pub fn testExplainPrimaryKey() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table pkOrder");
+        session.executeSql("create table pkOrder (id integer, code string, primary key (id))").unwrap();
+        session.executeSql("insert into pkOrder values (1, 'a')").unwrap();
+
+        let explain = rows(&mut session, "explain select pkOrder(id = 1)");
+        assert_eq!(explain[0]["analyze"], false);
+        assert_eq!(explain[0]["scans"][0]["accessPath"], "primaryKey");
+
+        let explain = rows(&mut session, "explain select pkOrder(code = 'a')");
+        assert_ne!(explain[0]["scans"][0]["accessPath"], "primaryKey");
+
+        session.executeSql("drop table pkOrder").unwrap();
+    }
+}
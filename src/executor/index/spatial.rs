@@ -0,0 +1,189 @@
+use bytes::{BufMut, BytesMut};
+use dashmap::mapref::one::Ref;
+use crate::executor::CommandExecutor;
+use crate::executor::index::inverted;
+use crate::executor::store::{ScanHooks, ScanParams};
+use crate::expr::Expr;
+use crate::geo::BoundingBox;
+use crate::graph_value::GraphValue;
+use crate::meta::{DBObject, Table};
+use crate::parser::element::Element;
+use crate::parser::op::{MathCmpOp, Op};
+use crate::session::Session;
+use crate::types::{CommittedPostProcessor, CommittedPreProcessor, DataKey, DBRawIterator, RowData, UncommittedPostProcessor, UncommittedPreProcessor};
+use crate::{byte_slice_to_u64, decimal, geo, getKeyIfSome, meta};
+use anyhow::Result;
+
+/// ```within_radius(location, point(30.27, 120.15), 5000)```, ```within_box(location, '[30, 120]', '[31, 121]')``` <br>
+/// 和 ```distance(location, point(30.27, 120.15)) < 5000``` 使用spatial index
+pub(in crate::executor) struct SpatialIndexSearch<'a> {
+    pub dbObjectIndex: Ref<'a, String, DBObject>,
+    /// 覆盖了要找的范围的各个cell对应的geohash范围 [start, end)
+    pub geohashRanges: Vec<(u64, u64)>,
+}
+
+/// spatial index 的key是 geohash(u64) + dataKey, 这里只写geohash
+pub(in crate::executor) fn encodeSpatialIndexKey(columnValue: &GraphValue, indexKeyBuffer: &mut BytesMut) {
+    let geohash =
+        match columnValue {
+            GraphValue::Point(point) => geo::geohash(point),
+            _ => geo::GEOHASH_NULL,
+        };
+
+    indexKeyBuffer.put_u64(geohash);
+}
+
+/// 得到 (column名, 要找的范围)
+fn extractSpatialPredicate<'a>(expr: &'a Expr) -> Result<Option<(&'a String, Vec<BoundingBox>)>> {
+    let (functionName, args, radius) =
+        match expr {
+            Expr::Function { functionName, args } => (functionName, args, None),
+            // distance(location, point(30.27, 120.15)) < 5000
+            Expr::BiDirection {
+                leftExpr,
+                op: Op::MathCmpOp(MathCmpOp::LessThan | MathCmpOp::LessEqual),
+                rightExprs
+            } if rightExprs.len() == 1 && rightExprs[0].needAcutalRowData() == false => {
+                match &**leftExpr {
+                    Expr::Function { functionName, args } if functionName.eq_ignore_ascii_case("distance") => (functionName, args, Some(&rightExprs[0])),
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+
+    // distance('[30.27, 120.15]', location) 也可以
+    let (columnName, constArgs) =
+        match (functionName.to_lowercase().as_str(), args.as_slice()) {
+            ("within_radius" | "within_box", [column, constArgs @ ..]) if constArgs.len() == 2 => (column, vec![&constArgs[0], &constArgs[1]]),
+            ("distance", [column, center]) if radius.is_some() => (column, vec![center, radius.unwrap()]),
+            ("distance", [center, column]) if radius.is_some() => (column, vec![center, radius.unwrap()]),
+            _ => return Ok(None),
+        };
+
+    let columnName =
+        match &**columnName {
+            Expr::Single(Element::TextLiteral(columnName)) => columnName,
+            _ => return Ok(None),
+        };
+
+    if constArgs.iter().any(|constArg| constArg.needAcutalRowData()) {
+        return Ok(None);
+    }
+
+    let constValues = [constArgs[0].calc(None)?, constArgs[1].calc(None)?];
+
+    // null的话交给原来的filter
+    if constValues.iter().any(|constValue| matches!(constValue, GraphValue::Null)) {
+        return Ok(None);
+    }
+
+    let boundingBoxes =
+        if functionName.eq_ignore_ascii_case("within_box") {
+            geo::boxBoundingBoxes(&geo::fromGraphValue(&constValues[0])?, &geo::fromGraphValue(&constValues[1])?)
+        } else {
+            let meters =
+                match constValues[1] {
+                    GraphValue::Integer(integer) => integer as f64,
+                    GraphValue::Double(double) => double,
+                    GraphValue::Decimal(ref decimal) => decimal::toF64(decimal),
+                    _ => return Ok(None),
+                };
+
+            if meters.is_finite() == false || meters < 0.0 {
+                return Ok(None);
+            }
+
+            geo::radiusBoundingBoxes(&geo::fromGraphValue(&constValues[0])?, meters)
+        };
+
+    Ok(Some((columnName, boundingBoxes)))
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// tableFilter 最外层的and的某个部分是 point column 上的 within_radius within_box distance < 常量, 且这个column有spatial index <br>
+    /// 得到的只是候选 读取rowData后还要使用完整的tableFilter过滤
+    pub(in crate::executor) fn getSpatialIndexSearch<'a>(&self,
+                                                         table: &'a Table,
+                                                         tableFilter: &Expr) -> Result<Option<SpatialIndexSearch<'a>>> {
+        if table.indexNames.is_empty() {
+            return Ok(None);
+        }
+
+        let mut andExprs = Vec::new();
+        inverted::collectAndExprs(tableFilter, &mut andExprs);
+
+        for andExpr in andExprs {
+            let (columnName, boundingBoxes) =
+                match extractSpatialPredicate(andExpr)? {
+                    Some(spatialPredicate) => spatialPredicate,
+                    None => continue,
+                };
+
+            for indexName in &table.indexNames {
+                let dbObjectIndex = Session::getDBObjectByName(indexName)?;
+                let index = dbObjectIndex.asIndex()?;
+
+                if index.spatial == false || &index.columnNames[0] != columnName {
+                    continue;
+                }
+
+                log::info!("use spatial index: {}", indexName);
+
+                return Ok(Some(SpatialIndexSearch {
+                    dbObjectIndex,
+                    geohashRanges: geo::coverGeohashRanges(&boundingBoxes),
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 各个geohash范围分别seek得到dataKey <br>
+    /// 和searchByIndex相同 只应对committed的, 当前tx的mutation由scanSatisfiedRows应对
+    pub(in crate::executor) fn scanBySpatialIndex<A, B, C, D>(&self,
+                                                              spatialIndexSearch: SpatialIndexSearch,
+                                                              scanParams: &ScanParams,
+                                                              scanHooks: &mut ScanHooks<A, B, C, D>) -> Result<Vec<(DataKey, RowData)>>
+    where
+        A: CommittedPreProcessor,
+        B: CommittedPostProcessor,
+        C: UncommittedPreProcessor,
+        D: UncommittedPostProcessor,
+    {
+        let index = spatialIndexSearch.dbObjectIndex.asIndex()?;
+
+        let indexColumnFamily = Session::getColumnFamily(index.id)?;
+        let mut indexDBRawIterator: DBRawIterator = self.session.getDBRawIterator(&indexColumnFamily)?;
+
+        let mut dataKeys: Vec<DataKey> = Vec::new();
+
+        for (start, end) in &spatialIndexSearch.geohashRanges {
+            indexDBRawIterator.seek(start.to_be_bytes());
+
+            loop {
+                let indexKey = getKeyIfSome!(indexDBRawIterator);
+
+                // 打头的8个byte是geohash
+                let geohash = byte_slice_to_u64!(indexKey);
+                if geohash >= *end {
+                    break;
+                }
+
+                dataKeys.push(extractDataKeyFromIndexKey!(indexKey));
+
+                indexDBRawIterator.next();
+            }
+        }
+
+        // 各个geohash范围是不重叠的
+        dataKeys.sort();
+
+        self.recordScanStage(|scanStage| scanStage.rowsScanned += dataKeys.len());
+
+        let rowDatas = self.getRowDatasByDataKeys(dataKeys.as_slice(), scanParams, scanHooks)?;
+
+        Ok(rowDatas.into_iter().skip(scanParams.offset.unwrap_or(0)).take(scanParams.limit.unwrap_or(usize::MAX)).collect())
+    }
+}
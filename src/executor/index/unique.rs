@@ -0,0 +1,303 @@
+use bytes::BytesMut;
+use crate::executor::CommandExecutor;
+use crate::executor::store::{ScanHooks, ScanParams};
+use crate::executor::mvcc::BytesMutExt;
+use crate::graph_value::GraphValue;
+use crate::meta::{Index, Table};
+use crate::session::Session;
+use crate::types::{Byte, ColumnFamily, DataKey, DBObjectId, DBRawIterator, RowData, TableMutations};
+use crate::{byte_slice_to_u64, extractRowIdFromDataKey, extractTxIdFromMvccKey, getKeyIfSome, keyPrefixAddRowId, meta, throwFormat, u64ToByteArrRef};
+use anyhow::Result;
+
+/// 有null的话不参与unique校验 和sql的习惯相同 null和null不算重复
+pub(in crate::executor) fn uniqueCheckNeeded(index: &Index, rowData: &RowData) -> bool {
+    index.columnNames.iter().all(|columnName| {
+        !matches!(rowData.get(columnName), None | Some(GraphValue::Null))
+    })
+}
+
+/// 新建unique index时候 已有的data是不是被已提交的tx删掉了 <br>
+/// 落地的只会是已提交的 故而只要有txId不是TX_ID_INVALID的xmax便是删掉了
+pub(in crate::executor) fn committedDataDeleted(mvccKeyBuffer: &mut BytesMut,
+                                                dbRawIterator: &mut DBRawIterator,
+                                                dataKey: DataKey) -> bool {
+    mvccKeyBuffer.writeDataMvccXmax(dataKey, meta::TX_ID_MAX);
+    dbRawIterator.seek_for_prev(mvccKeyBuffer.as_ref());
+
+    match dbRawIterator.key() {
+        // 打头的 rowId + xmax的tag 要相同
+        Some(mvccKey) if mvccKey.len() == meta::MVCC_KEY_BYTE_LEN &&
+            mvccKey[..meta::MVCC_KEY_BYTE_LEN - meta::TX_ID_BYTE_LEN] == mvccKeyBuffer[..meta::MVCC_KEY_BYTE_LEN - meta::TX_ID_BYTE_LEN] => {
+            extractTxIdFromMvccKey!(mvccKey) != meta::TX_ID_INVALID
+        }
+        _ => false
+    }
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// insert和update时候 unique index 上不能已经有相同的值 <br>
+    /// 要看 已提交的index数据 和 当前tx的index mutations 里边同值的各个dataKey 对应的row是不是还可见 <br>
+    /// indexValueBinary 是各个column的值encode后的 不含末尾的dataKey
+    pub(super) fn checkUniqueIndex(&self,
+                                   table: &Table,
+                                   index: &Index,
+                                   indexValueBinary: &[Byte],
+                                   selfDataKey: DataKey) -> Result<()> {
+        // 同值的indexKey只会是这个长度 长度不同的只是前缀恰好相同
+        let indexKeyLen = indexValueBinary.len() + meta::DATA_KEY_BYTE_LEN;
+
+        let mut committedDataKeys: Vec<DataKey> = Vec::new();
+
+        // 已提交的
+        let indexColumnFamily = Session::getColumnFamily(index.id)?;
+        let mut indexDBRawIterator: DBRawIterator = self.session.getDBRawIterator(&indexColumnFamily)?;
+        indexDBRawIterator.seek(indexValueBinary);
+
+        loop {
+            let indexKey = getKeyIfSome!(indexDBRawIterator);
+
+            if indexKey.starts_with(indexValueBinary) == false {
+                break;
+            }
+
+            if indexKey.len() == indexKeyLen {
+                committedDataKeys.push(extractDataKeyFromIndexKey!(indexKey));
+            }
+
+            indexDBRawIterator.next();
+        }
+
+        let mut mvccKeyBuffer = self.withCapacityIn(meta::MVCC_KEY_BYTE_LEN);
+
+        {
+            let dbObjectId_mutations = self.session.dbObjectId_mutations.read().unwrap();
+            let tableMutations: Option<&TableMutations> = dbObjectId_mutations.get(&table.id);
+
+            // 当前tx上的
+            if let Some(indexMutations) = dbObjectId_mutations.get(&index.id) {
+                for (indexKey, _) in indexMutations.range(indexValueBinary.to_vec()..) {
+                    if indexKey.starts_with(indexValueBinary) == false {
+                        break;
+                    }
+
+                    if indexKey.len() != indexKeyLen {
+                        continue;
+                    }
+
+                    let dataKey = extractDataKeyFromIndexKey!(indexKey);
+                    if dataKey == selfDataKey {
+                        continue;
+                    }
+
+                    // 当前tx上新add的data
+                    if let Some(tableMutations) = tableMutations {
+                        if tableMutations.contains_key(u64ToByteArrRef!(dataKey).as_ref()) {
+                            if self.uncommittedDataVisible(tableMutations, &mut mvccKeyBuffer, dataKey)? {
+                                throwFormat!("duplicate value on unique index: {}", index.name);
+                            }
+
+                            continue;
+                        }
+                    }
+
+                    committedDataKeys.push(dataKey);
+                }
+            }
+        }
+
+        committedDataKeys.retain(|dataKey| *dataKey != selfDataKey);
+        committedDataKeys.sort();
+        committedDataKeys.dedup();
+
+        let scanParams = ScanParams {
+            table,
+            selectedColumnNames: Some(&index.columnNames),
+            ..Default::default()
+        };
+
+        // 已提交的data要经过mvcc筛选 看看是不是被别的已提交的tx或当前tx删掉了
+        if self.getRowDatasByDataKeys(committedDataKeys.as_slice(), &scanParams, &mut ScanHooks::default())?.is_empty() == false {
+            throwFormat!("duplicate value on unique index: {}", index.name);
+        }
+
+        Ok(())
+    }
+
+    /// commit时候 对最新落地的index数据再校验1趟 别的tx可能同时写入了相同的值并先提交了
+    pub(super) fn checkUniqueIndexLatest(&self,
+                                         tableId: DBObjectId,
+                                         indexId: DBObjectId,
+                                         indexName: &str,
+                                         indexValueBinary: &[Byte],
+                                         selfDataKey: DataKey) -> Result<()> {
+        let indexKeyLen = indexValueBinary.len() + meta::DATA_KEY_BYTE_LEN;
+
+        let duplicate =
+            self.latestDuplicateAlive(tableId, indexId, selfDataKey, |indexColumnFamily| {
+                let mut indexDBRawIterator: DBRawIterator = self.session.getDBRawIteratorWithoutSnapshot(indexColumnFamily)?;
+                indexDBRawIterator.seek(indexValueBinary);
+
+                let mut dataKeys = Vec::new();
+
+                loop {
+                    let indexKey = getKeyIfSome!(indexDBRawIterator);
+
+                    if indexKey.starts_with(indexValueBinary) == false {
+                        break;
+                    }
+
+                    if indexKey.len() == indexKeyLen {
+                        dataKeys.push(extractDataKeyFromIndexKey!(indexKey));
+                    }
+
+                    indexDBRawIterator.next();
+                }
+
+                Ok(dataKeys)
+            })?;
+
+        if duplicate {
+            throwFormat!("duplicate value on unique index: {}", indexName);
+        }
+
+        Ok(())
+    }
+
+    /// unique index 和 primary key 在commit时候共用的校验 <br>
+    /// latestDataKeys 从最新落地的index或primary key上读取相同值对应的dataKey, 除了自身以外有活着的便是重复了
+    pub(super) fn latestDuplicateAlive(&self,
+                                       tableId: DBObjectId,
+                                       indexId: DBObjectId,
+                                       selfDataKey: DataKey,
+                                       latestDataKeys: impl FnOnce(&ColumnFamily) -> Result<Vec<DataKey>>) -> Result<bool> {
+        // table或index已被drop了
+        let (tableColumnFamily, indexColumnFamily) =
+            match (Session::getColumnFamily(tableId), Session::getColumnFamily(indexId)) {
+                (Ok(tableColumnFamily), Ok(indexColumnFamily)) => (tableColumnFamily, indexColumnFamily),
+                _ => return Ok(false),
+            };
+
+        let dbObjectId_mutations = self.session.dbObjectId_mutations.read().unwrap();
+        let tableMutations: Option<&TableMutations> = dbObjectId_mutations.get(&tableId);
+
+        let mut mvccKeyBuffer = self.withCapacityIn(meta::MVCC_KEY_BYTE_LEN);
+
+        // 自身后来又被当前tx删掉了
+        if let Some(tableMutations) = tableMutations {
+            if self.uncommittedDataVisible(tableMutations, &mut mvccKeyBuffer, selfDataKey)? == false {
+                return Ok(false);
+            }
+        }
+
+        let mut dataDBRawIterator: DBRawIterator = self.session.getDBRawIteratorWithoutSnapshot(&tableColumnFamily)?;
+
+        for dataKey in latestDataKeys(&indexColumnFamily)? {
+            if dataKey != selfDataKey &&
+                self.committedDataLatestAlive(&mut mvccKeyBuffer, &mut dataDBRawIterator, dataKey, &tableColumnFamily, tableMutations)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// commit时候的校验用的 看的是最新落地的 不是snapshot <br>
+    /// 落地的都是已提交的 没有被已提交的tx删掉 不是被update后多出来的 也没有被当前tx删掉 便是存在的
+    pub(super) fn committedDataLatestAlive(&self,
+                                           mvccKeyBuffer: &mut BytesMut,
+                                           dbRawIterator: &mut DBRawIterator,
+                                           dataKey: DataKey,
+                                           columnFamily: &ColumnFamily,
+                                           tableMutations: Option<&TableMutations>) -> Result<bool> {
+        // 可能已被vaccum掉了
+        if self.session.getWithoutSnapshot(u64ToByteArrRef!(dataKey), columnFamily)?.is_none() {
+            return Ok(false);
+        }
+
+        if committedDataDeleted(mvccKeyBuffer, dbRawIterator, dataKey) {
+            return Ok(false);
+        }
+
+        // 和committedDataVisible相同 多个tx对相同的data的update 只有xmin是origin最新的xmax的那条是有效的
+        let originDataKeyKey = u64ToByteArrRef!(keyPrefixAddRowId!(meta::KEY_PREFIX_KEY_2_ORIGIN_DATA_KEY, extractRowIdFromDataKey!(dataKey)));
+        if let Some(originDataKey) = self.session.getWithoutSnapshot(originDataKeyKey, columnFamily)? {
+            let originDataKey = byte_slice_to_u64!(originDataKey);
+
+            if originDataKey != meta::DATA_KEY_INVALID {
+                mvccKeyBuffer.writeDataMvccXmin(dataKey, meta::TX_ID_FROZEN);
+                dbRawIterator.seek(mvccKeyBuffer.as_ref());
+                let xmin = extractTxIdFromMvccKey!(dbRawIterator.key().unwrap());
+
+                mvccKeyBuffer.writeDataMvccXmax(originDataKey, meta::TX_ID_MAX);
+                dbRawIterator.seek_for_prev(mvccKeyBuffer.as_ref());
+                let originDataXmax = extractTxIdFromMvccKey!(dbRawIterator.key().unwrap());
+
+                if xmin != originDataXmax {
+                    return Ok(false);
+                }
+            }
+        }
+
+        // 被当前tx删掉了
+        if let Some(tableMutations) = tableMutations {
+            mvccKeyBuffer.writeDataMvccXmax(dataKey, self.session.getTxId()?);
+            if tableMutations.contains_key(mvccKeyBuffer.as_ref()) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::meta;
+    use crate::session::Session;
+
+    fn rowCount(session: &mut Session, sql: &str) -> usize {
+        session.executeSql(sql).unwrap().remove(0).len()
+    }
+
+    #[test]
+    pub fn testUniqueIndex() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table uniqueAccount");
+        session.executeSql("create table uniqueAccount (id integer, email string)").unwrap();
+        session.executeSql("insert into uniqueAccount values (1, 'a')").unwrap();
+        session.executeSql("insert into uniqueAccount values (2, 'a')").unwrap();
+
+        // 已有的data重复的话不能建立
+        assert!(session.executeSql("create unique index uniqueAccountEmail on uniqueAccount[email]").is_err());
+
+        session.executeSql("delete from uniqueAccount(id = 2)").unwrap();
+        session.executeSql("create unique index uniqueAccountEmail on uniqueAccount[email]").unwrap();
+
+        assert!(session.executeSql("insert into uniqueAccount values (3, 'a')").is_err());
+        session.executeSql("insert into uniqueAccount values (3, 'b')").unwrap();
+
+        assert!(session.executeSql("update uniqueAccount[email = 'a'](id = 3)").is_err());
+        // 值没有变化的update不算重复
+        session.executeSql("update uniqueAccount[email = 'b'](id = 3)").unwrap();
+
+        // null和null不算重复
+        session.executeSql("insert into uniqueAccount values (4, null)").unwrap();
+        session.executeSql("insert into uniqueAccount values (5, null)").unwrap();
+
+        // 同1个tx里边先删掉再写入相同的值
+        session.setAutoCommit(false).unwrap();
+        session.executeSql("delete from uniqueAccount(id = 1)").unwrap();
+        session.executeSql("insert into uniqueAccount values (6, 'a')").unwrap();
+        session.commit().unwrap();
+        session.setAutoCommit(true).unwrap();
+
+        assert_eq!(rowCount(&mut session, "select uniqueAccount(email = 'a')"), 1);
+        assert_eq!(rowCount(&mut session, "select uniqueAccount(id = 6)"), 1);
+        assert_eq!(rowCount(&mut session, "select uniqueAccount"), 4);
+
+        session.executeSql("drop table uniqueAccount").unwrap();
+    }
+}
@@ -37,6 +37,9 @@ impl<'session> CommandExecutor<'session> {
             // index的key应该是什么样的 columnData + dataKey
             let mut indexKeyBuffer = self.withCapacityIn(rowDataBinary.len() + meta::DATA_KEY_BYTE_LEN);
             self.generateIndexData(table, &mut indexKeyBuffer, dataKey, &rowData, false)?;
+
+            // primary key -> dataKey
+            self.generatePrimaryKeyData(table, dataKey, &rowData, false)?;
         }
 
         Ok(CommandExecResult::DmlResult)
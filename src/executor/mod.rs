@@ -1,9 +1,11 @@
+use std::cell::RefCell;
 use std::hash::Hash;
 use std::sync::atomic::AtomicU64;
 use dashmap::mapref::one::Ref;
 use serde_json::Value;
 use strum_macros::Display;
 use crate::meta::{DBObject, Index, Table};
+use crate::executor::explain::ExplainCollector;
 use crate::session::Session;
 use crate::{meta, throwFormat};
 use crate::parser::command::Command;
@@ -30,6 +32,11 @@ mod optimizer;
 mod drop;
 mod show;
 mod alter;
+mod sort;
+mod aggregate;
+mod path;
+mod explain;
+mod subquery;
 
 #[macro_export]
 macro_rules! JSON_ENUM_UNTAGGED {
@@ -53,12 +60,15 @@ enum CommandExecResult {
 
 pub struct CommandExecutor<'session> {
     session: &'session mut Session,
+    /// explain 的时候才有
+    explainCollector: RefCell<Option<ExplainCollector>>,
 }
 
 impl<'session> CommandExecutor<'session> {
     pub fn new(session: &'session mut Session) -> Self {
         CommandExecutor {
-            session
+            session,
+            explainCollector: RefCell::new(None),
         }
     }
 
@@ -79,6 +89,8 @@ impl<'session> CommandExecutor<'session> {
                 self.session.generateTx()?;
             }
 
+            self.foldSubqueries(command)?;
+
             let executionResult = match command {
                 Command::CreateTable(table) => {
                     let table = Table {
@@ -89,6 +101,8 @@ impl<'session> CommandExecutor<'session> {
                         rowIdCounter: AtomicU64::new(meta::ROW_ID_MIN),
                         createIfNotExist: table.createIfNotExist,
                         indexNames: table.indexNames.clone(),
+                        primaryKeyColumnNames: table.primaryKeyColumnNames.clone(),
+                        primaryKeyId: DBObjectId::default(),
                         invalid: table.invalid,
                     };
 
@@ -96,7 +110,7 @@ impl<'session> CommandExecutor<'session> {
                 }
                 Command::DropTable(tableName) => self.dropTable(tableName)?,
                 Command::DropRelation(relationName) => self.dropRelation(relationName)?,
-                Command::DropIndex(indexName) => self.dropIndex(indexName, None, None)?,
+                Command::DropIndex(indexName) => self.dropIndex(indexName)?,
                 Command::CreateIndex(index) => {
                     let index = Index {
                         id: DBObjectId::default(),
@@ -104,6 +118,10 @@ impl<'session> CommandExecutor<'session> {
                         name: index.name.clone(),
                         tableName: index.tableName.clone(),
                         columnNames: index.columnNames.clone(),
+                        ivfCentroids: None,
+                        inverted: false,
+                        spatial: false,
+                        unique: index.unique,
                         createIfNotExist: index.createIfNotExist,
                         invalid: index.invalid,
                     };
@@ -118,6 +136,8 @@ impl<'session> CommandExecutor<'session> {
                         rowIdCounter: AtomicU64::new(meta::ROW_ID_MIN),
                         createIfNotExist: table.createIfNotExist,
                         indexNames: table.indexNames.clone(),
+                        primaryKeyColumnNames: table.primaryKeyColumnNames.clone(),
+                        primaryKeyId: DBObjectId::default(),
                         invalid: table.invalid,
                     };
 
@@ -125,6 +145,7 @@ impl<'session> CommandExecutor<'session> {
                 }
                 Command::Insert(insert) => self.insert(insert)?,
                 Command::Select(select) => self.select(select)?,
+                Command::Explain(explain) => self.explain(explain)?,
                 Command::Link(link) => self.link(link)?,
                 Command::Delete(delete) => self.delete(delete)?,
                 Command::Update(update) => self.update(update)?,
@@ -139,6 +139,11 @@ pub(in crate::executor) fn processTableFilter(tableFilter: &Expr) -> Result<Tabl
             return Ok(TableFilterProcResult::AllIndexableTableFilterColsAreNonsenseWhenIsPureAnd { hasExprAbandonedByIndex });
         }
     } else {
+        // a = 1 or b != 2 和 a = 1 or not (b = 2) 这样的 只用a上的index会漏掉b那边的 和nonsense相同不能用index
+        if hasExprAbandonedByIndex {
+            orHasNonsense = true;
+        }
+
         for (tableFilterColumnName, opValuesVec) in &indexableTableFilterColName_opValuesVec {
             // 扁平化opValuesVec 变为 opValueVec
             let opValueVec = {
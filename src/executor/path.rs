@@ -0,0 +1,864 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::mem;
+use std::ops::Bound;
+use hashbrown::{HashMap, HashSet};
+use serde_json::{json, Value};
+use crate::executor::{CommandExecResult, CommandExecutor};
+use crate::executor::sort;
+use crate::executor::store;
+use crate::executor::store::{ScanHooks, ScanParams};
+use crate::expr::Expr;
+use crate::graph_value::GraphValue;
+use crate::meta::{ColumnType, Table};
+use crate::parser::command::select::{PathSearch, PathSemantics, RelationDirection, SelectRel};
+use crate::session::Session;
+use crate::types::{DataKey, KeyTag, RowData};
+use crate::{decimal, global, meta, throw, throwFormat, JSON_ENUM_UNTAGGED};
+use anyhow::Result;
+use rust_decimal::Decimal;
+
+/// 由node经由1条relation到达相邻的node
+pub(super) struct Hop {
+    pub(super) relationDataKey: DataKey,
+    pub(super) relationRowData: RowData,
+    pub(super) nodeDataKey: DataKey,
+    pub(super) nodeRowData: RowData,
+}
+
+/// 沿着某个方向走的时候用到的pointerKeyTag
+pub(super) struct DirectionTags {
+    /// node上指向relation的
+    pub(super) nodeToRelation: KeyTag,
+    /// relation上指向出发的那端的
+    pub(super) relationToFrom: KeyTag,
+    /// relation上指向到达的那端的
+    pub(super) relationToTo: KeyTag,
+}
+
+const FORWARD_TAGS: DirectionTags = DirectionTags {
+    nodeToRelation: meta::POINTER_KEY_TAG_DOWNSTREAM_REL_ID,
+    relationToFrom: meta::POINTER_KEY_TAG_SRC_TABLE_ID,
+    relationToTo: meta::POINTER_KEY_TAG_DEST_TABLE_ID,
+};
+
+const REVERSE_TAGS: DirectionTags = DirectionTags {
+    nodeToRelation: meta::POINTER_KEY_TAG_UPSTREAM_REL_ID,
+    relationToFrom: meta::POINTER_KEY_TAG_DEST_TABLE_ID,
+    relationToTo: meta::POINTER_KEY_TAG_SRC_TABLE_ID,
+};
+
+/// either的话两个方向都要走
+pub(super) fn directionTags(relationDirection: RelationDirection) -> &'static [DirectionTags] {
+    match relationDirection {
+        RelationDirection::Forward => &[FORWARD_TAGS],
+        RelationDirection::Reverse => &[REVERSE_TAGS],
+        RelationDirection::Either => &[FORWARD_TAGS, REVERSE_TAGS],
+    }
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// node上的沿着relationDirection的relation 满足relationFilter的 再到relation的另1端 <br>
+    /// 相邻的node不过滤 过程中的node是不用满足destFilter的
+    pub(super) fn expandNode(&self,
+                             nodeTable: &Table, nodeDataKey: DataKey,
+                             relation: &Table, relationFilter: Option<&Expr>,
+                             relationDirection: RelationDirection) -> Result<Vec<Hop>> {
+        let mut hops = Vec::new();
+
+        for directionTags in directionTags(relationDirection) {
+            let relationDatas =
+                self.searchDataByPointerKeyPrefix(nodeTable, nodeDataKey,
+                                                  directionTags.nodeToRelation,
+                                                  relation, relationFilter)?;
+
+            for (relationDataKey, relationRowData) in relationDatas {
+                let nodeDatas =
+                    self.searchDataByPointerKeyPrefix(relation, relationDataKey,
+                                                      directionTags.relationToTo,
+                                                      nodeTable, None)?;
+
+                for (nodeDataKey, nodeRowData) in nodeDatas {
+                    hops.push(Hop {
+                        relationDataKey,
+                        relationRowData: relationRowData.clone(),
+                        nodeDataKey,
+                        nodeRowData,
+                    });
+                }
+            }
+        }
+
+        Ok(hops)
+    }
+
+    /// ```select user(id=1) -likes shortest recursive[1..10]-> user(id=7)``` <br>
+    /// ```select user -likes recursive[2..4]-> user return paths```
+    pub(super) fn selectRelPath(&self, selectRel: &SelectRel) -> Result<CommandExecResult> {
+        let table = Session::getDBObjectByName(&selectRel.srcTableName)?;
+        let table = table.asTable()?;
+
+        let relation = Session::getDBObjectByName(selectRel.relationName.as_str())?;
+        let relation = relation.asRelation()?;
+
+        let (startDepth, endDepth) =
+            match selectRel.relationDepth {
+                Some((Bound::Included(startDepth), Bound::Included(endDepth))) => (startDepth, endDepth),
+                _ => panic!("impossible"),
+            };
+
+        let srcRowDatas = {
+            let scanParams = ScanParams {
+                table,
+                tableFilter: selectRel.srcFilter.as_ref(),
+                ..Default::default()
+            };
+
+            self.scanSatisfiedRows(scanParams, true, ScanHooks::default())?
+        };
+
+        let paths =
+            match selectRel.relationPathSearch {
+                Some(PathSearch::Shortest) => self.searchShortestPaths(srcRowDatas, table, relation, selectRel, startDepth, endDepth)?,
+                Some(PathSearch::Weighted { ref columnName }) => self.searchWeightedPaths(srcRowDatas, table, relation, selectRel, columnName)?,
+                // return paths
+                None => self.searchPaths(srcRowDatas, table, relation, selectRel, startDepth, endDepth)?,
+            };
+
+        let values = JSON_ENUM_UNTAGGED!(self.pathsToDisplay(paths, selectRel)?);
+
+        Ok(CommandExecResult::SelectResult(values))
+    }
+
+    /// 由src的各个node出发逐层延伸 罗列深度在[startDepth, endDepth]且终点满足destFilter的全部路径 <br>
+    /// 路径上只记录dataKey rowData另外保存 路径的数量超过recursiveMaxCount报错
+    fn searchPaths(&self,
+                   srcRowDatas: Vec<(DataKey, RowData)>,
+                   table: &Table, relation: &Table,
+                   selectRel: &SelectRel,
+                   startDepth: usize, endDepth: usize) -> Result<Vec<Path>> {
+        let mut nodeDataKey_rowData: HashMap<DataKey, RowData> = HashMap::new();
+        let mut relationDataKey_rowData: HashMap<DataKey, RowData> = HashMap::new();
+
+        // (nodeDataKeys, relationDataKeys)
+        let mut pendingPaths: Vec<(Vec<DataKey>, Vec<DataKey>)> = Vec::with_capacity(srcRowDatas.len());
+        for (srcDataKey, srcRowData) in srcRowDatas {
+            nodeDataKey_rowData.insert(srcDataKey, srcRowData);
+            pendingPaths.push((vec![srcDataKey], Vec::new()));
+        }
+
+        let mut foundPaths = Vec::new();
+
+        for depth in 1..=endDepth {
+            if pendingPaths.is_empty() {
+                break;
+            }
+
+            let mut nextPendingPaths = Vec::new();
+
+            for (nodeDataKeys, relationDataKeys) in pendingPaths {
+                let lastNodeDataKey = *nodeDataKeys.last().unwrap();
+
+                for hop in self.expandNode(table, lastNodeDataKey, relation, selectRel.relationFilter.as_ref(), selectRel.relationDirection)? {
+                    let repeated =
+                        match selectRel.relationPathSemantics {
+                            PathSemantics::Walk => false,
+                            PathSemantics::Trail => relationDataKeys.contains(&hop.relationDataKey),
+                            PathSemantics::Simple => nodeDataKeys.contains(&hop.nodeDataKey),
+                        };
+
+                    if repeated {
+                        continue;
+                    }
+
+                    let mut nodeDataKeys = nodeDataKeys.clone();
+                    let mut relationDataKeys = relationDataKeys.clone();
+                    nodeDataKeys.push(hop.nodeDataKey);
+                    relationDataKeys.push(hop.relationDataKey);
+
+                    if depth >= startDepth && satisfyFilter(selectRel.destFilter.as_ref(), &hop.nodeRowData)? {
+                        foundPaths.push((nodeDataKeys.clone(), relationDataKeys.clone()));
+                    }
+
+                    nodeDataKey_rowData.insert(hop.nodeDataKey, hop.nodeRowData);
+                    relationDataKey_rowData.insert(hop.relationDataKey, hop.relationRowData);
+
+                    nextPendingPaths.push((nodeDataKeys, relationDataKeys));
+
+                    if nextPendingPaths.len() + foundPaths.len() > self.session.recursiveMaxCount {
+                        throwFormat!("recursive relation:{} exceeds {} paths, use trail or simple or a smaller depth", relation.name, self.session.recursiveMaxCount);
+                    }
+                }
+            }
+
+            pendingPaths = nextPendingPaths;
+        }
+
+        let mut paths = Vec::with_capacity(foundPaths.len());
+
+        for (nodeDataKeys, relationDataKeys) in foundPaths {
+            paths.push(Path {
+                nodes: nodeDataKeys.into_iter().map(|nodeDataKey| (nodeDataKey, nodeDataKey_rowData[&nodeDataKey].clone())).collect(),
+                relations: relationDataKeys.into_iter().map(|relationDataKey| (relationDataKey, relationDataKey_rowData[&relationDataKey].clone())).collect(),
+                cost: None,
+            });
+        }
+
+        Ok(paths)
+    }
+
+    /// 由src的node出发 深度在[startDepth, endDepth]且满足destFilter的node 不关心路径 <br>
+    /// walk的时候各个深度上能到达的node集合只依赖上个深度的 以集合逐层推进 <br>
+    /// 集合重复出现说明进入了循环 已覆盖了1整个循环便可停下 <br>
+    /// trail和simple依赖各自的路径 只能罗列路径
+    pub(super) fn selectRelRecursive(&self,
+                                     srcRowData: (DataKey, RowData),
+                                     table: &Table, relation: &Table,
+                                     selectRel: &SelectRel,
+                                     startDepth: usize, endDepth: usize) -> Result<Vec<(DataKey, RowData)>> {
+        let mut destDataKey_rowData: HashMap<DataKey, RowData> = HashMap::new();
+
+        if selectRel.relationPathSemantics != PathSemantics::Walk {
+            for mut path in self.searchPaths(vec![srcRowData], table, relation, selectRel, startDepth, endDepth)? {
+                let (destDataKey, destRowData) = path.nodes.pop().unwrap();
+                destDataKey_rowData.insert(destDataKey, destRowData);
+            }
+
+            return Ok(destDataKey_rowData.into_iter().collect());
+        }
+
+        // 排好序的node集合 -> 最近出现的深度
+        let mut frontier_depth: HashMap<Vec<DataKey>, usize> = HashMap::new();
+
+        let mut frontier = vec![srcRowData.0];
+        let mut expandCount = 0usize;
+
+        for depth in 1..=endDepth {
+            let mut nextFrontier = HashSet::new();
+
+            for nodeDataKey in frontier {
+                for hop in self.expandNode(table, nodeDataKey, relation, selectRel.relationFilter.as_ref(), selectRel.relationDirection)? {
+                    if depth >= startDepth && destDataKey_rowData.contains_key(&hop.nodeDataKey) == false {
+                        if satisfyFilter(selectRel.destFilter.as_ref(), &hop.nodeRowData)? {
+                            destDataKey_rowData.insert(hop.nodeDataKey, hop.nodeRowData);
+                        }
+                    }
+
+                    nextFrontier.insert(hop.nodeDataKey);
+                }
+            }
+
+            if nextFrontier.is_empty() {
+                break;
+            }
+
+            expandCount += nextFrontier.len();
+            if expandCount > self.session.recursiveMaxCount {
+                throwFormat!("recursive relation:{} exceeds {} nodes, use a smaller depth", relation.name, self.session.recursiveMaxCount);
+            }
+
+            let mut nextFrontier: Vec<DataKey> = nextFrontier.into_iter().collect();
+            nextFrontier.sort();
+
+            // 上趟出现是在prevDepth 循环的长度是depth - prevDepth
+            // prevDepth已过了startDepth的话 之后的各个深度都是重复 可以停下
+            if let Some(prevDepth) = frontier_depth.insert(nextFrontier.clone(), depth) {
+                if prevDepth >= startDepth {
+                    break;
+                }
+            }
+
+            frontier = nextFrontier;
+        }
+
+        Ok(destDataKey_rowData.into_iter().collect())
+    }
+
+    /// 广度优先 以src的全部node同时出发 node只会到访1趟 <br>
+    /// 某深度上出现了满足destFilter的node后 走完该深度便停下 <br>
+    /// 最短路径的长度小于startDepth的node不会出现在结果中
+    fn searchShortestPaths(&self,
+                           srcRowDatas: Vec<(DataKey, RowData)>,
+                           table: &Table, relation: &Table,
+                           selectRel: &SelectRel,
+                           startDepth: usize, endDepth: usize) -> Result<Vec<Path>> {
+        // node -> 到达它的 (上个node, relation)
+        let mut nodeDataKey_prev: HashMap<DataKey, Option<(DataKey, DataKey)>> = HashMap::new();
+        let mut nodeDataKey_rowData: HashMap<DataKey, RowData> = HashMap::new();
+        let mut relationDataKey_rowData: HashMap<DataKey, RowData> = HashMap::new();
+
+        let mut frontier = Vec::with_capacity(srcRowDatas.len());
+        for (srcDataKey, srcRowData) in srcRowDatas {
+            nodeDataKey_prev.insert(srcDataKey, None);
+            nodeDataKey_rowData.insert(srcDataKey, srcRowData);
+            frontier.push(srcDataKey);
+        }
+
+        let mut foundDataKeys = Vec::new();
+
+        for depth in 1..=endDepth {
+            if frontier.is_empty() {
+                break;
+            }
+
+            let mut nextFrontier = Vec::new();
+
+            for nodeDataKey in frontier {
+                for hop in self.expandNode(table, nodeDataKey, relation, selectRel.relationFilter.as_ref(), selectRel.relationDirection)? {
+                    if nodeDataKey_prev.contains_key(&hop.nodeDataKey) {
+                        continue;
+                    }
+
+                    nodeDataKey_prev.insert(hop.nodeDataKey, Some((nodeDataKey, hop.relationDataKey)));
+                    relationDataKey_rowData.insert(hop.relationDataKey, hop.relationRowData);
+
+                    if depth >= startDepth && satisfyFilter(selectRel.destFilter.as_ref(), &hop.nodeRowData)? {
+                        foundDataKeys.push(hop.nodeDataKey);
+                    }
+
+                    nodeDataKey_rowData.insert(hop.nodeDataKey, hop.nodeRowData);
+                    nextFrontier.push(hop.nodeDataKey);
+                }
+            }
+
+            if foundDataKeys.is_empty() == false {
+                break;
+            }
+
+            frontier = nextFrontier;
+        }
+
+        let mut paths = Vec::with_capacity(foundDataKeys.len());
+
+        for foundDataKey in foundDataKeys {
+            let (nodes, relations) = tracePath(foundDataKey, &nodeDataKey_prev, &nodeDataKey_rowData, &relationDataKey_rowData);
+            paths.push(Path { nodes, relations, cost: None });
+        }
+
+        Ok(paths)
+    }
+
+    /// dijkstra 以src的全部node同时出发 cost是0 <br>
+    /// 弹出的node满足destFilter的话 它的cost便是最小的 cost相同的dest都收下
+    fn searchWeightedPaths(&self,
+                           srcRowDatas: Vec<(DataKey, RowData)>,
+                           table: &Table, relation: &Table,
+                           selectRel: &SelectRel,
+                           weightColumnName: &String) -> Result<Vec<Path>> {
+        let weightColumnType =
+            match relation.columns.iter().find(|column| &column.name == weightColumnName) {
+                Some(column) => column.type0.clone(),
+                None => throwFormat!("relation:{} has no column:{}", relation.name, weightColumnName),
+            };
+
+        if let ColumnType::Integer | ColumnType::Decimal(_) | ColumnType::Double = weightColumnType {} else {
+            throwFormat!("weight column:{} should be integer, decimal or double", weightColumnName);
+        }
+
+        let mut nodeDataKey_prev: HashMap<DataKey, Option<(DataKey, DataKey)>> = HashMap::new();
+        let mut nodeDataKey_cost: HashMap<DataKey, Cost> = HashMap::new();
+        let mut nodeDataKey_rowData: HashMap<DataKey, RowData> = HashMap::new();
+        let mut relationDataKey_rowData: HashMap<DataKey, RowData> = HashMap::new();
+
+        let mut heap = BinaryHeap::new();
+
+        let zeroCost = Cost::zero(&weightColumnType);
+
+        for (srcDataKey, srcRowData) in srcRowDatas {
+            nodeDataKey_prev.insert(srcDataKey, None);
+            nodeDataKey_cost.insert(srcDataKey, zeroCost);
+            nodeDataKey_rowData.insert(srcDataKey, srcRowData);
+            heap.push(HeapEntry { cost: zeroCost, nodeDataKey: srcDataKey });
+        }
+
+        // 已确定了最小cost的
+        let mut settledDataKeys = HashSet::new();
+
+        let mut foundDataKeys = Vec::new();
+        let mut foundCost: Option<Cost> = None;
+
+        while let Some(HeapEntry { cost, nodeDataKey }) = heap.pop() {
+            if let Some(foundCost) = foundCost {
+                if cost.cmp(&foundCost) == Ordering::Greater {
+                    break;
+                }
+            }
+
+            if settledDataKeys.insert(nodeDataKey) == false {
+                continue;
+            }
+
+            // 起点自身不算
+            if nodeDataKey_prev[&nodeDataKey].is_some() {
+                if satisfyFilter(selectRel.destFilter.as_ref(), &nodeDataKey_rowData[&nodeDataKey])? {
+                    foundDataKeys.push(nodeDataKey);
+                    foundCost = Some(cost);
+                    continue;
+                }
+            }
+
+            for hop in self.expandNode(table, nodeDataKey, relation, selectRel.relationFilter.as_ref(), selectRel.relationDirection)? {
+                let weight =
+                    match hop.relationRowData.get(weightColumnName) {
+                        Some(GraphValue::Null) | None => throwFormat!("relation:{} column:{} is null", relation.name, weightColumnName),
+                        Some(weight) => weight,
+                    };
+
+                let nextCost = cost.add(weight)?;
+
+                // 要在跳过settled的之前 指向settled的node的负数weight也要发现
+                if nextCost.cmp(&cost) == Ordering::Less {
+                    throwFormat!("relation:{} column:{} has negative weight:{:?}", relation.name, weightColumnName, weight);
+                }
+
+                if settledDataKeys.contains(&hop.nodeDataKey) {
+                    continue;
+                }
+
+                if let Some(existCost) = nodeDataKey_cost.get(&hop.nodeDataKey) {
+                    if nextCost.cmp(existCost) != Ordering::Less {
+                        continue;
+                    }
+                }
+
+                nodeDataKey_cost.insert(hop.nodeDataKey, nextCost);
+                nodeDataKey_prev.insert(hop.nodeDataKey, Some((nodeDataKey, hop.relationDataKey)));
+                nodeDataKey_rowData.insert(hop.nodeDataKey, hop.nodeRowData);
+                relationDataKey_rowData.insert(hop.relationDataKey, hop.relationRowData);
+
+                heap.push(HeapEntry { cost: nextCost, nodeDataKey: hop.nodeDataKey });
+            }
+        }
+
+        let mut paths = Vec::with_capacity(foundDataKeys.len());
+
+        for foundDataKey in foundDataKeys {
+            let (nodes, relations) = tracePath(foundDataKey, &nodeDataKey_prev, &nodeDataKey_rowData, &relationDataKey_rowData);
+
+            let cost = nodeDataKey_cost[&foundDataKey].toGraphValue();
+
+            paths.push(Path { nodes, relations, cost: Some(cost) });
+        }
+
+        Ok(paths)
+    }
+
+    /// ```{"depth":2,"path":[{"user":{}},{"likes":{}},{"user":{}},{"likes":{}},{"user":{}}]}``` <br>
+    /// 两端的node应对order by和limit offset 以及column挑选和projection
+    fn pathsToDisplay(&self, mut paths: Vec<Path>, selectRel: &SelectRel) -> Result<Vec<Value>> {
+        if let Some(ref destOrderBys) = selectRel.destOrderBys {
+            for path in &mut paths {
+                sort::calcOrderByExprs(destOrderBys, &mut path.nodes.last_mut().unwrap().1)?;
+            }
+
+            paths.sort_by(|path, path0| sort::compareRowData(destOrderBys, &path.nodes.last().unwrap().1, &path0.nodes.last().unwrap().1));
+
+            for path in &mut paths {
+                sort::removeOrderByExprValues(destOrderBys, &mut path.nodes.last_mut().unwrap().1);
+            }
+        }
+
+        let srcName = selectRel.srcAlias.as_ref().unwrap_or(&selectRel.srcTableName);
+        let relationName = selectRel.relationAlias.as_ref().unwrap_or(&selectRel.relationName);
+        let destName = selectRel.destAlias.as_ref().unwrap_or(&selectRel.destTableName);
+
+        let mut values = Vec::with_capacity(paths.len());
+
+        for mut path in paths.into_iter().skip(selectRel.destOffset.unwrap_or(0)).take(selectRel.destLimit.unwrap_or(usize::MAX)) {
+            let depth = path.relations.len();
+            let cost = path.cost.take();
+
+            if selectRel.srcColumnNames.is_some() {
+                let srcRowData = &mut path.nodes.first_mut().unwrap().1;
+                *srcRowData = store::projectRowData(mem::take(srcRowData), selectRel.srcColumnNames.as_ref(), selectRel.srcProjections.as_ref())?;
+            }
+
+            if selectRel.destColumnNames.is_some() {
+                let destRowData = &mut path.nodes.last_mut().unwrap().1;
+                *destRowData = store::projectRowData(mem::take(destRowData), selectRel.destColumnNames.as_ref(), selectRel.destProjections.as_ref())?;
+            }
+
+            let mut elements = Vec::with_capacity(path.nodes.len() + path.relations.len());
+
+            let mut relations = path.relations.into_iter();
+
+            for (index, (_, nodeRowData)) in path.nodes.into_iter().enumerate() {
+                let nodeName =
+                    if index == 0 {
+                        srcName
+                    } else if index == depth {
+                        destName
+                    } else {
+                        &selectRel.srcTableName
+                    };
+
+                elements.push(json!({nodeName.as_str(): nodeRowData}));
+
+                if let Some((_, relationRowData)) = relations.next() {
+                    let relationRowData = store::projectRowData(relationRowData, selectRel.relationColumnNames.as_ref(), None)?;
+                    elements.push(json!({relationName.as_str(): relationRowData}));
+                }
+            }
+
+            match cost {
+                Some(cost) => values.push(json!({"depth": depth, "cost": cost, "path": elements})),
+                None => values.push(json!({"depth": depth, "path": elements})),
+            }
+        }
+
+        Ok(values)
+    }
+}
+
+/// 起点到终点 nodes比relations多1个
+pub(super) struct Path {
+    pub(super) nodes: Vec<(DataKey, RowData)>,
+    pub(super) relations: Vec<(DataKey, RowData)>,
+    /// weighted的时候有
+    pub(super) cost: Option<GraphValue>,
+}
+
+/// 以weight column的type累加 integer和decimal要是经由f64的话 超过2^53的integer和decimal会丢失精度
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cost {
+    Integer(i64),
+    Decimal(Decimal),
+    Double(f64),
+}
+
+impl Cost {
+    fn zero(weightColumnType: &ColumnType) -> Cost {
+        match weightColumnType {
+            ColumnType::Integer => Cost::Integer(0),
+            ColumnType::Decimal(_) => Cost::Decimal(Decimal::ZERO),
+            _ => Cost::Double(0f64),
+        }
+    }
+
+    /// weight的type是column的type fitValue时候确保了
+    fn add(&self, weight: &GraphValue) -> Result<Cost> {
+        let nextCost =
+            match (self, weight) {
+                (Cost::Integer(cost), GraphValue::Integer(weight)) => cost.checked_add(*weight).map(Cost::Integer),
+                (Cost::Decimal(cost), GraphValue::Decimal(weight)) => cost.checked_add(*weight).map(Cost::Decimal),
+                (Cost::Decimal(cost), GraphValue::Integer(weight)) => cost.checked_add(Decimal::from(*weight)).map(Cost::Decimal),
+                (Cost::Double(cost), GraphValue::Double(weight)) => Some(Cost::Double(cost + weight)),
+                (Cost::Double(cost), GraphValue::Integer(weight)) => Some(Cost::Double(cost + *weight as f64)),
+                (Cost::Double(cost), GraphValue::Decimal(weight)) => Some(Cost::Double(cost + decimal::toF64(weight))),
+                _ => throwFormat!("weight:{:?} does not match cost:{:?}", weight, self),
+            };
+
+        match nextCost {
+            Some(nextCost) => Ok(nextCost),
+            None => throwFormat!("cost overflow, {:?} + {:?}", self, weight),
+        }
+    }
+
+    /// 同1趟search的cost都是相同的type
+    fn cmp(&self, other: &Cost) -> Ordering {
+        match (self, other) {
+            (Cost::Integer(cost), Cost::Integer(cost0)) => cost.cmp(cost0),
+            (Cost::Decimal(cost), Cost::Decimal(cost0)) => cost.cmp(cost0),
+            (Cost::Double(cost), Cost::Double(cost0)) => cost.total_cmp(cost0),
+            _ => panic!("impossible"),
+        }
+    }
+
+    fn toGraphValue(self) -> GraphValue {
+        match self {
+            Cost::Integer(cost) => GraphValue::Integer(cost),
+            Cost::Decimal(cost) => GraphValue::Decimal(cost),
+            Cost::Double(cost) => GraphValue::Double(cost),
+        }
+    }
+}
+
+/// BinaryHeap是大顶堆 反过来比较使得cost小的先出
+struct HeapEntry {
+    cost: Cost,
+    nodeDataKey: DataKey,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost).then_with(|| other.nodeDataKey.cmp(&self.nodeDataKey))
+    }
+}
+
+/// 由终点沿着prev回溯到起点
+fn tracePath(destDataKey: DataKey,
+             nodeDataKey_prev: &HashMap<DataKey, Option<(DataKey, DataKey)>>,
+             nodeDataKey_rowData: &HashMap<DataKey, RowData>,
+             relationDataKey_rowData: &HashMap<DataKey, RowData>) -> (Vec<(DataKey, RowData)>, Vec<(DataKey, RowData)>) {
+    let mut nodes = vec![(destDataKey, nodeDataKey_rowData[&destDataKey].clone())];
+    let mut relations = Vec::new();
+
+    let mut current = destDataKey;
+    while let Some((prevDataKey, relationDataKey)) = nodeDataKey_prev[&current] {
+        relations.push((relationDataKey, relationDataKey_rowData[&relationDataKey].clone()));
+        nodes.push((prevDataKey, nodeDataKey_rowData[&prevDataKey].clone()));
+        current = prevDataKey;
+    }
+
+    nodes.reverse();
+    relations.reverse();
+
+    (nodes, relations)
+}
+
+fn satisfyFilter(filter: Option<&Expr>, rowData: &RowData) -> Result<bool> {
+    match filter {
+        Some(filter) => {
+            match filter.calc(Some(rowData))? {
+                GraphValue::Boolean(satisfy) => Ok(satisfy),
+                _ => throw!("table filter should get a boolean"),
+            }
+        }
+        None => Ok(true),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::decimal;
+    use crate::executor::path::Cost;
+    use serde_json::{json, Value};
+    use crate::graph_value::GraphValue;
+    use crate::meta;
+    use crate::meta::ColumnType;
+    use crate::session::Session;
+
+    /// 1->2->3->4 和 1->3 distance都是1 除了1->3是5
+    fn createPathCities(session: &mut Session) {
+        let _ = session.executeSql("drop relation pathRoad");
+        let _ = session.executeSql("drop table pathCity");
+        session.executeSql("create table pathCity (id integer, name string)").unwrap();
+        session.executeSql("insert into pathCity values (1, 'a')").unwrap();
+        session.executeSql("insert into pathCity values (2, 'b')").unwrap();
+        session.executeSql("insert into pathCity values (3, 'c')").unwrap();
+        session.executeSql("insert into pathCity values (4, 'd')").unwrap();
+        session.executeSql("create relation pathRoad (distance integer)").unwrap();
+        session.executeSql("link pathCity(id = 1) to pathCity(id = 2) by pathRoad(distance = 1)").unwrap();
+        session.executeSql("link pathCity(id = 2) to pathCity(id = 3) by pathRoad(distance = 1)").unwrap();
+        session.executeSql("link pathCity(id = 1) to pathCity(id = 3) by pathRoad(distance = 5)").unwrap();
+        session.executeSql("link pathCity(id = 3) to pathCity(id = 4) by pathRoad(distance = 1)").unwrap();
+    }
+
+    /// path上依次的各个node的id
+    fn pathNodeIds(path: &Value) -> Vec<i64> {
+        path["path"].as_array().unwrap().iter().step_by(2).map(|node| node["pathCity"]["id"].as_i64().unwrap()).collect()
+    }
+
+    fn dropPathCities(session: &mut Session) {
+        session.executeSql("drop relation pathRoad").unwrap();
+        session.executeSql("drop table pathCity").unwrap();
+    }
+
+    #[test]
+    pub fn testCostAdd() {
+        // 超过2^53的integer 经由f64的话会丢失
+        let cost = Cost::zero(&ColumnType::Integer).add(&GraphValue::Integer((1 << 53) + 1)).unwrap();
+        let cost = cost.add(&GraphValue::Integer(2)).unwrap();
+        assert_eq!(cost.toGraphValue(), GraphValue::Integer((1 << 53) + 3));
+        assert!(cost.add(&GraphValue::Integer(i64::MAX)).is_err());
+
+        let d = |text: &str| decimal::parse(text).unwrap();
+
+        let cost = Cost::zero(&ColumnType::Decimal(Some((10, 2)))).add(&GraphValue::Decimal(d("0.10"))).unwrap();
+        let cost = cost.add(&GraphValue::Decimal(d("0.20"))).unwrap();
+        assert_eq!(cost.toGraphValue(), GraphValue::Decimal(d("0.30")));
+
+        let cost = Cost::zero(&ColumnType::Double).add(&GraphValue::Double(1.5)).unwrap();
+        assert_eq!(cost.toGraphValue(), GraphValue::Double(1.5));
+    }
+
+    #[test]
+    pub fn testNegativeWeightToSettledNode() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop relation pathRoad");
+        let _ = session.executeSql("drop table pathCity");
+        session.executeSql("create table pathCity (id integer, name string)").unwrap();
+        session.executeSql("insert into pathCity values (1, 'a')").unwrap();
+        session.executeSql("insert into pathCity values (2, 'b')").unwrap();
+        session.executeSql("insert into pathCity values (3, 'c')").unwrap();
+        session.executeSql("create relation pathRoad (distance integer)").unwrap();
+        session.executeSql("link pathCity(id = 1) to pathCity(id = 2) by pathRoad(distance = 1)").unwrap();
+
+        // 指回已经settled的起点的负数weight
+        session.executeSql("link pathCity(id = 2) to pathCity(id = 1) by pathRoad(distance = 0 - 5)").unwrap();
+
+        let error = session.executeSql("select pathCity(id = 1) -pathRoad weighted by distance-> pathCity(id = 3)").unwrap_err();
+        assert!(error.to_string().contains("negative weight"));
+
+        session.executeSql("drop relation pathRoad").unwrap();
+        session.executeSql("drop table pathCity").unwrap();
+    }
+
+    #[test]
+    pub fn testShortestPath() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        createPathCities(&mut session);
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        // 跳数最少的 不看distance
+        assert_eq!(rows("select pathCity[id](id = 1) -pathRoad shortest-> pathCity[id](id = 4)"),
+                   vec![json!({"depth": 2, "path": [{"pathCity": {"id": 1}}, {"pathRoad": {"distance": 5}}, {"pathCity": {"id": 3, "name": "c"}}, {"pathRoad": {"distance": 1}}, {"pathCity": {"id": 4}}]})]);
+
+        // 有多个终点的话是最近的那个
+        let paths = rows("select pathCity[id](id = 1) -pathRoad shortest-> pathCity[id](id > 2)");
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0]["depth"], json!(1));
+        assert_eq!(paths[0]["path"][2], json!({"pathCity": {"id": 3}}));
+
+        // 超出了深度 或者 方向相反 都是没有的
+        assert_eq!(rows("select pathCity[id](id = 1) -pathRoad shortest recursive[1..1]-> pathCity[id](id = 4)"), Vec::<Value>::new());
+        assert_eq!(rows("select pathCity[id](id = 4) -pathRoad shortest-> pathCity[id](id = 1)"), Vec::<Value>::new());
+
+        let paths = rows("select pathCity[id](id = 1) -pathRoad weighted by distance-> pathCity[id](id = 4)");
+        assert_eq!(paths.len(), 1);
+        assert_eq!((&paths[0]["cost"], &paths[0]["depth"]), (&json!(3), &json!(3)));
+
+        dropPathCities(&mut session);
+    }
+
+    #[test]
+    pub fn testReturnPaths() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        createPathCities(&mut session);
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        // node和relation交替 两端的node是select的column 中间的是整行
+        assert_eq!(rows("select pathCity[id](id = 1) -pathRoad recursive[2..2]-> pathCity[id](id = 4) return paths"),
+                   vec![json!({"depth": 2, "path": [{"pathCity": {"id": 1}}, {"pathRoad": {"distance": 5}}, {"pathCity": {"id": 3, "name": "c"}}, {"pathRoad": {"distance": 1}}, {"pathCity": {"id": 4}}]})]);
+
+        let paths = rows("select pathCity[id](id = 1) -pathRoad recursive[1..3]-> pathCity[id] return paths");
+        let mut nodeIdsVec: Vec<Vec<i64>> = paths.iter().map(pathNodeIds).collect();
+        nodeIdsVec.sort();
+        assert_eq!(nodeIdsVec, vec![vec![1, 2], vec![1, 2, 3], vec![1, 2, 3, 4], vec![1, 3], vec![1, 3, 4]]);
+
+        for path in &paths {
+            assert_eq!(path["depth"], json!(pathNodeIds(path).len() - 1));
+        }
+
+        // 不return paths的话 只有终点
+        let rows = rows("select pathCity[id](id = 1) -pathRoad recursive[1..3]-> pathCity[id]");
+        let mut ids: Vec<i64> = rows[0]["pathCity"].as_array().unwrap().iter().map(|node| node["id"].as_i64().unwrap()).collect();
+        ids.sort();
+        assert_eq!(ids, vec![2, 3, 4]);
+
+        dropPathCities(&mut session);
+    }
+
+    #[test]
+    pub fn testRecursiveOnCycle() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        createPathCities(&mut session);
+
+        // 1->3->4->1 和 1->2->3->4->1 两个环
+        session.executeSql("link pathCity(id = 4) to pathCity(id = 1) by pathRoad(distance = 1)").unwrap();
+
+        let mut nodeIdsVec = |sql: &str| {
+            let mut nodeIdsVec: Vec<Vec<i64>> = session.executeSql(sql).unwrap().remove(0).iter().map(pathNodeIds).collect();
+            nodeIdsVec.sort();
+            nodeIdsVec
+        };
+
+        // walk 环可以绕多趟
+        let walk = nodeIdsVec("select pathCity[id](id = 1) -pathRoad recursive[1..8] walk-> pathCity[id](id = 1) return paths");
+        assert_eq!(walk, vec![
+            vec![1, 2, 3, 4, 1],
+            vec![1, 2, 3, 4, 1, 2, 3, 4, 1],
+            vec![1, 2, 3, 4, 1, 3, 4, 1],
+            vec![1, 3, 4, 1],
+            vec![1, 3, 4, 1, 2, 3, 4, 1],
+            vec![1, 3, 4, 1, 3, 4, 1],
+        ]);
+
+        // 默认是walk
+        assert_eq!(nodeIdsVec("select pathCity[id](id = 1) -pathRoad recursive[1..8]-> pathCity[id](id = 1) return paths"), walk);
+
+        // trail 同1个relation只能走1趟 node可以重复
+        assert_eq!(nodeIdsVec("select pathCity[id](id = 1) -pathRoad recursive[1..8] trail-> pathCity[id](id = 1) return paths"),
+                   vec![vec![1, 2, 3, 4, 1], vec![1, 3, 4, 1]]);
+
+        // simple node不能重复 回不到起点
+        assert_eq!(nodeIdsVec("select pathCity[id](id = 1) -pathRoad recursive[1..8] simple-> pathCity[id](id = 1) return paths"), Vec::<Vec<i64>>::new());
+        assert_eq!(nodeIdsVec("select pathCity[id](id = 1) -pathRoad recursive[1..8] simple-> pathCity[id](id = 4) return paths"),
+                   vec![vec![1, 2, 3, 4], vec![1, 3, 4]]);
+
+        // 超过了recursiveMaxCount便报错
+        session.recursiveMaxCount = 3;
+
+        let error = session.executeSql("select pathCity[id](id = 1) -pathRoad recursive[1..8] walk-> pathCity[id](id = 1) return paths").unwrap_err();
+        assert!(error.to_string().contains("exceeds 3 paths"));
+
+        let error = session.executeSql("select pathCity[id](id = 1) -pathRoad recursive[1..8]-> pathCity[id]").unwrap_err();
+        assert!(error.to_string().contains("exceeds 3 nodes"));
+
+        dropPathCities(&mut session);
+    }
+
+    #[test]
+    pub fn testRelationDirection() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        createPathCities(&mut session);
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        let destIds = |rows: &[Value]| {
+            let mut ids: Vec<i64> =
+                rows.iter()
+                    .flat_map(|row| row["pathCity"].as_array().unwrap().iter())
+                    .map(|node| node["id"].as_i64().unwrap()).collect();
+            ids.sort();
+            ids
+        };
+
+        // 反方向 是指向4的
+        assert_eq!(rows("select pathCity[id](id = 4) <-pathRoad- pathCity[id]"), vec![json!({"pathCity": [{"id": 3}], "pathRoad": {"distance": 1}})]);
+        assert_eq!(destIds(&rows("select pathCity[id](id = 4) <-pathRoad recursive[1..3]- pathCity[id]")), vec![1, 2, 3]);
+
+        let paths = rows("select pathCity[id](id = 4) <-pathRoad shortest- pathCity[id](id = 1)");
+        assert_eq!(paths.iter().map(pathNodeIds).collect::<Vec<_>>(), vec![vec![4, 3, 1]]);
+
+        // 不分方向 两边的都算
+        assert_eq!(destIds(&rows("select pathCity[id](id = 2) -pathRoad- pathCity[id]")), vec![1, 3]);
+        assert_eq!(destIds(&rows("select pathCity[id](id = 2) -pathRoad recursive[1..2] simple- pathCity[id]")), vec![1, 3, 4]);
+
+        // 正方向的话2到不了1
+        assert_eq!(destIds(&rows("select pathCity[id](id = 2) -pathRoad recursive[1..3]-> pathCity[id]")), vec![3, 4]);
+
+        dropPathCities(&mut session);
+    }
+}
@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use std::mem;
 use std::fs::OpenOptions;
 use hashbrown::{HashMap, HashSet};
 use std::ops::{Bound, RangeFrom};
@@ -6,7 +7,7 @@ use std::os::fd::RawFd;
 use bytes::BytesMut;
 use serde_json::{json, Value};
 use crate::executor::{CommandExecResult, CommandExecutor, IterationCmd};
-use crate::{extractTargetDataKeyFromPointerKey, JSON_ENUM_UNTAGGED, meta, suffix_plus_plus, byte_slice_to_u64, types, utils, throw, prefix_minus_minus, config};
+use crate::{extractTargetDataKeyFromPointerKey, JSON_ENUM_UNTAGGED, meta, suffix_plus_plus, byte_slice_to_u64, types, utils, throw, throwFormat, prefix_minus_minus, config};
 use crate::executor::mvcc::BytesMutExt;
 use crate::graph_value::{GraphValue};
 use crate::meta::{DBObject, Table};
@@ -14,11 +15,27 @@ use crate::types::{Byte, ColumnFamily, DataKey, KeyTag, RowData, DBRawIterator,
 use crate::global;
 use crate::parser::command::select::{EndPointType, RelDesc, Select, SelectRel, SelectTable, SelectTableUnderRels};
 use anyhow::{anyhow, Result};
+use crate::executor::aggregate;
+use crate::executor::path;
+use crate::executor::sort;
+use crate::executor::store;
 use crate::executor::store::{ScanHooks, ScanParams, SearchPointerKeyHooks};
 use crate::expr::Expr;
 use crate::session::Session;
 use crate::types::{CommittedPreProcessor, CommittedPostProcessor, UncommittedPreProcessor, UncommittedPostProcessor};
 
+/// 对应1个realtion的query的多个条目的1个
+#[derive(Debug)]
+struct SelectResult {
+    srcName: String,
+    srcRowDatas: Vec<(DataKey, RowData)>,
+    /// 目前 当使用recursive后 relation相应当name和data不显示 未想好如何显示
+    relationName: Option<String>,
+    relationData: Option<RowData>,
+    destName: String,
+    destRowDatas: Vec<(DataKey, RowData)>,
+}
+
 impl<'session> CommandExecutor<'session> {
     /// 如果不是含有relation的select 便是普通的select
     pub(super) fn select(&self, selectFamily: &Select) -> Result<CommandExecResult> {
@@ -33,6 +50,15 @@ impl<'session> CommandExecutor<'session> {
 
     /// 普通的和rdbms相同的 select
     fn selectTable(&self, selectTable: &SelectTable) -> Result<CommandExecResult> {
+        let rowDatas = self.selectTableRowDatas(selectTable)?;
+        let values: Vec<Value> = self.processRowDatasToDisplay(rowDatas);
+        // JSON_ENUM_UNTAGGED!(println!("{}", serde_json::to_string(&rows)?));
+
+        Ok(CommandExecResult::SelectResult(values))
+    }
+
+    /// 子查询要直接使用得到的rowData 经由显示用的json的话decimal timestamp等的type会丢失
+    pub(super) fn selectTableRowDatas(&self, selectTable: &SelectTable) -> Result<Vec<(DataKey, RowData)>> {
         let table = Session::getDBObjectByName(selectTable.tableName.as_str())?;
 
         let table = match table.value() {
@@ -42,40 +68,175 @@ impl<'session> CommandExecutor<'session> {
         };
         // let table = table.asTable()?;
 
+        // 含有聚合
+        if selectTable.aggregates.is_some() || selectTable.groupBys.is_some() || selectTable.having.is_some() {
+            return self.selectTableAggregate(selectTable, table);
+        }
+
+        // unnest使得1行成为多行 limit offset 是对unnest之后的
+        let hasUnnest = store::hasUnnest(selectTable.projections.as_ref());
+
         let rowDatas = {
             let scanParams = ScanParams {
                 table,
                 tableFilter: selectTable.tableFilterExpr.as_ref(),
-                selectedColumnNames: selectTable.selectedColNames.as_ref(),
-                limit: selectTable.limit,
-                offset: selectTable.offset,
+                // projection要用到的column未必在selectedColNames中 不能提前prune
+                selectedColumnNames: if selectTable.projections.is_some() { None } else { selectTable.selectedColNames.as_ref() },
+                limit: if hasUnnest { None } else { selectTable.limit },
+                offset: if hasUnnest { None } else { selectTable.offset },
                 ..Default::default()
             };
 
-            self.scanSatisfiedRows(scanParams, true, ScanHooks::default())?
-        };
+            let rowDatas =
+                match selectTable.orderBys {
+                    // 排序需要order by的column 到了下边才能prune
+                    Some(ref orderBys) => self.scanSatisfiedRowsOrderBy(scanParams, &sort::resolveOrderByAliases(orderBys, selectTable.projections.as_ref()))?,
+                    None => self.scanSatisfiedRows(scanParams, true, ScanHooks::default())?
+                };
 
-        let values: Vec<Value> = self.processRowDatasToDisplay(rowDatas);
-        // JSON_ENUM_UNTAGGED!(println!("{}", serde_json::to_string(&rows)?));
+            if selectTable.orderBys.is_some() || selectTable.projections.is_some() {
+                let mut projectedRowDatas = Vec::with_capacity(rowDatas.len());
+                for (dataKey, rowData) in rowDatas {
+                    let rowData = store::projectRowData(rowData, selectTable.selectedColNames.as_ref(), selectTable.projections.as_ref())?;
 
-        Ok(CommandExecResult::SelectResult(values))
+                    for rowData in store::unnestRowData(rowData, selectTable.projections.as_ref()) {
+                        projectedRowDatas.push((dataKey, rowData));
+                    }
+                }
+
+                if hasUnnest {
+                    projectedRowDatas.into_iter()
+                        .skip(selectTable.offset.unwrap_or(0))
+                        .take(selectTable.limit.unwrap_or(usize::MAX))
+                        .collect()
+                } else {
+                    projectedRowDatas
+                }
+            } else {
+                rowDatas
+            }
+        };
+
+        Ok(rowDatas)
     }
 
     /// graph特色的 rel select
     /// ```select user[id,name](id=1 and 0=0) as user0 -usage(number > 9) as usage0-> car -own(number=1)-> tyre```
     fn selectRels(&self, selectRels: &Vec<SelectRel>) -> Result<CommandExecResult> {
-        // 对应1个realtion的query的多个条目的1个
-        #[derive(Debug)]
-        struct SelectResult {
-            srcName: String,
-            srcRowDatas: Vec<(DataKey, RowData)>,
-            /// 目前 当使用recursive后 relation相应当name和data不显示 未想好如何显示
-            relationName: Option<String>,
-            relationData: Option<RowData>,
-            destName: String,
-            destRowDatas: Vec<(DataKey, RowData)>,
+        // 寻找路径 parse时候已限定了只有1个selectRel
+        if let [selectRel] = selectRels.as_slice() {
+            if selectRel.relationPathSearch.is_some() || selectRel.returnPaths {
+                return self.selectRelPath(selectRel);
+            }
+        }
+
+        let selectResultVecVec = self.selectRelsResults(selectRels)?;
+
+        /// ```[[[第1个select的第1行data],[第1个select的第2行data]],[[第2个select的第1行data],[第2个select的第2行data]]]```
+        /// 到时候要生成4条脉络
+        fn handleResult(selectResultVecVec: Vec<Vec<SelectResult>>) -> Vec<Value> {
+            let mut valueVec = Vec::new();
+
+            if selectResultVecVec.is_empty() {
+                return valueVec;
+            }
+
+            // level0上横向遍历
+            for selectResult in &selectResultVecVec[0] {
+                let mut json = json!({});
+
+                // 把tuple的position干掉
+                let srcRowDatas: Vec<&RowData> =
+                    selectResult.srcRowDatas.iter().map(
+                        |(_, rownData)| rownData
+                    ).collect();
+
+                let destRowDatas: Vec<&RowData> =
+                    selectResult.destRowDatas.iter().map(
+                        |(_, rowData)| rowData
+                    ).collect();
+
+                // 对json::Value来说需要注意的是serialize的调用发生在这边 而不是serde_json::to_string()
+                json[selectResult.srcName.as_str()] = json!(srcRowDatas);
+                if selectResult.relationName.is_some() {
+                    json[selectResult.relationName.as_ref().unwrap().as_str()] = json!(selectResult.relationData.as_ref().unwrap());
+                }
+                json[selectResult.destName.as_str()] = json!(destRowDatas);
+
+                // 深度上向下
+                extendDown(&selectResultVecVec, 1, &selectResult.destRowDatas, json, &mut valueVec);
+            }
+
+            valueVec
+        }
+
+        /// 下1层的src和上层的dest相连的result 各自生成1条脉络
+        fn extendDown(selectResultVecVec: &Vec<Vec<SelectResult>>,
+                      level: usize,
+                      prevDestRowDatas: &[(DataKey, RowData)],
+                      json: Value,
+                      valueVec: &mut Vec<Value>) {
+            if level == selectResultVecVec.len() {
+                valueVec.push(json);
+                return;
+            }
+
+            for selectResult in &selectResultVecVec[level] {
+                let connected =
+                    selectResult.srcRowDatas.iter().any(
+                        |(srcDataKey, _)| prevDestRowDatas.iter().any(|(prevDestDataKey, _)| prevDestDataKey == srcDataKey)
+                    );
+
+                if connected == false {
+                    continue;
+                }
+
+                let mut json = json.clone();
+
+                if selectResult.relationName.is_some() {
+                    json[selectResult.relationName.as_ref().unwrap().as_str()] = json!(selectResult.relationData.as_ref().unwrap());
+                }
+
+                let destRowDatas: Vec<&RowData> = selectResult.destRowDatas.iter().map(|(_, rowData)| rowData).collect();
+                json[selectResult.destName.as_str()] = json!(destRowDatas);
+
+                extendDown(selectResultVecVec, level + 1, &selectResult.destRowDatas, json, valueVec);
+            }
+        }
+
+        let valueVec = JSON_ENUM_UNTAGGED!(handleResult(selectResultVecVec));
+        //println!("{}", serde_json::to_string(&valueVec)?);
+
+        Ok(CommandExecResult::SelectResult(valueVec))
+    }
+
+    /// 子查询用的 最后1个selectRel的各个dest <br>
+    /// 最后1层的各个result都是和第1层连着的 不用像显示那样生成脉络 同1个dest经由多条relation到达的只算1个
+    pub(super) fn selectRelsDestRowDatas(&self, selectRels: &Vec<SelectRel>) -> Result<Vec<(DataKey, RowData)>> {
+        let selectResultVecVec = self.selectRelsResults(selectRels)?;
+
+        // 连线中途断掉了
+        if selectResultVecVec.len() != selectRels.len() {
+            return Ok(Vec::new());
+        }
+
+        let mut destDataKeys = HashSet::new();
+        let mut destRowDatas = Vec::new();
+
+        for selectResult in selectResultVecVec.into_iter().last().unwrap() {
+            for (destDataKey, destRowData) in selectResult.destRowDatas {
+                // dest上聚合得到的是以src分组的 dataKey都是DATA_KEY_INVALID
+                if destDataKey == meta::DATA_KEY_INVALID || destDataKeys.insert(destDataKey) {
+                    destRowDatas.push((destDataKey, destRowData));
+                }
+            }
         }
 
+        Ok(destRowDatas)
+    }
+
+    /// 各个selectRel依次得到的result 连线断掉的话之后的selectRel没有result
+    fn selectRelsResults(&self, selectRels: &Vec<SelectRel>) -> Result<Vec<Vec<SelectResult>>> {
         // 给next轮用的
         let mut destDataKeysInPrevSelectRel: Option<HashSet<DataKey>> = None;
 
@@ -115,53 +276,99 @@ impl<'session> CommandExecutor<'session> {
             let relation = Session::getDBObjectByName(selectRel.relationName.as_str())?;
             let relation = relation.asRelation()?;
 
-            // 遍历当前的selectRel的多条relationData
-            'loopRelationData:
-            for (relationDataKey, relationData) in relationDatas {
-                let gatherTargetDatas =
-                    |pointerKeyTag: KeyTag, targetTable: &Table, targetFilter: Option<&Expr>| {
-                        // todo selectRels时候如何应对pointerKey的mvcc 完成
-                        let targetDatas =
-                            self.searchDataByPointerKeyPrefix(relation,
-                                                              relationDataKey,
-                                                              pointerKeyTag,
-                                                              targetTable, targetFilter)?;
-
-                        // todo 不知道要不要dedup
-                        Result::<Vec<(DataKey, RowData)>>::Ok(targetDatas)
-                    };
-
-                // 收罗该rel上的全部的src的dataKey
-                let mut srcRowDatas = {
-                    let srcRowDatas =
-                        gatherTargetDatas(meta::POINTER_KEY_TAG_SRC_TABLE_ID,
-                                          srcTable,
-                                          selectRel.srcFilter.as_ref())?;
+            let gatherTargetDatas =
+                |relationDataKey: DataKey, pointerKeyTag: KeyTag, targetTable: &Table, targetFilter: Option<&Expr>| {
+                    // todo selectRels时候如何应对pointerKey的mvcc 完成
+                    let targetDatas =
+                        self.searchDataByPointerKeyPrefix(relation,
+                                                          relationDataKey,
+                                                          pointerKeyTag,
+                                                          targetTable, targetFilter)?;
+
+                    // todo 不知道要不要dedup
+                    Result::<Vec<(DataKey, RowData)>>::Ok(targetDatas)
+                };
 
-                    if srcRowDatas.is_empty() {
-                        continue 'loopRelationData;
-                    }
+            // 各个条目的 (relationData, srcRowDatas, destRowDatas)
+            // 使用recursive的话以src的各个node为单位 没有relationData
+            let mut entries: Vec<(Option<RowData>, Vec<(DataKey, RowData)>, Vec<(DataKey, RowData)>)> = Vec::new();
+
+            let directionTagsVec = path::directionTags(selectRel.relationDirection);
+
+            match selectRel.relationDepth {
+                // 遍历当前的selectRel的多条relationData
+                None => {
+                    for (relationDataKey, relationData) in relationDatas {
+                        // either的话relation两端都可以作为src
+                        for directionTags in directionTagsVec {
+                            // 收罗该rel上的全部的src的dataKey
+                            let srcRowDatas =
+                                gatherTargetDatas(relationDataKey,
+                                                  directionTags.relationToFrom,
+                                                  srcTable,
+                                                  selectRel.srcFilter.as_ref())?;
+
+                            if srcRowDatas.is_empty() {
+                                continue;
+                            }
 
-                    srcRowDatas
-                };
+                            // 收罗该rel上的全部的dest的dataKey
+                            let destRowDatas =
+                                gatherTargetDatas(relationDataKey,
+                                                  directionTags.relationToTo,
+                                                  destTable,
+                                                  selectRel.destFilter.as_ref())?;
 
-                // 收罗该rel上的全部的dest的dataKey
-                let mut destRowDatas =
-                    if selectRel.relationDepth.is_none() {
-                        let destRowDatas =
-                            gatherTargetDatas(meta::POINTER_KEY_TAG_DEST_TABLE_ID,
-                                              destTable,
-                                              selectRel.destFilter.as_ref())?;
+                            if destRowDatas.is_empty() {
+                                continue;
+                            }
 
-                        if destRowDatas.is_empty() {
-                            continue 'loopRelationData;
+                            entries.push((Some(relationData.clone()), srcRowDatas, destRowDatas));
                         }
+                    }
+                }
+                // parse的时候已经限制了 要是recursive 是 [1..2) 之类的本质和没有recusive相同 会直接拦掉
+                Some((Bound::Included(startDepth), Bound::Included(endDepth))) => {
+                    let mut srcDataKeys = HashSet::new();
+
+                    for (relationDataKey, _) in relationDatas {
+                        for directionTags in directionTagsVec {
+                            let srcRowDatas =
+                                gatherTargetDatas(relationDataKey,
+                                                  directionTags.relationToFrom,
+                                                  srcTable,
+                                                  selectRel.srcFilter.as_ref())?;
+
+                            for srcRowData in srcRowDatas {
+                                // 同1个src会有多条relation
+                                if srcDataKeys.insert(srcRowData.0) == false {
+                                    continue;
+                                }
+
+                                // 和上轮的dest不相交的不必下钻
+                                if let Some(ref destDataKeysInPrevSelectRel) = destDataKeysInPrevSelectRel {
+                                    if destDataKeysInPrevSelectRel.contains(&srcRowData.0) == false {
+                                        continue;
+                                    }
+                                }
+
+                                let destRowDatas =
+                                    self.selectRelRecursive(srcRowData.clone(), srcTable, relation, selectRel, startDepth, endDepth)?;
 
-                        destRowDatas
-                    } else {
-                        Vec::new()
-                    };
+                                if destRowDatas.is_empty() {
+                                    continue;
+                                }
+
+                                entries.push((None, vec![srcRowData], destRowDatas));
+                            }
+                        }
+                    }
+                }
+                _ => panic!("impossible"),
+            }
 
+            'loopEntry:
+            for (relationData, mut srcRowDatas, mut destRowDatas) in entries {
                 let srcRowDatas = {
                     let srcRowDatas =
                         match destDataKeysInPrevSelectRel {
@@ -180,28 +387,7 @@ impl<'session> CommandExecutor<'session> {
 
                                 // 说明 当前的这个relation的src和上轮的dest没有重合的
                                 if intersectDataKeys.is_empty() {
-                                    continue 'loopRelationData;
-                                }
-
-                                // 当前的select的src确定了 还要回去修改上轮的dest
-                                if let Some(prevSelectResultVec) = selectResultVecVec.last_mut() {
-                                    // 遍历上轮的各个result的dest,把intersect之外的去掉
-                                    for prevSelectResult in &mut *prevSelectResultVec {
-                                        // https://blog.csdn.net/u011528645/article/details/123117829
-                                        prevSelectResult.destRowDatas.retain(
-                                            |(dataKey, _)| intersectDataKeys.contains(dataKey)
-                                        );
-                                    }
-
-                                    // destRowDatas是空的话那么把selectResult去掉
-                                    prevSelectResultVec.retain(
-                                        |prevSelectResult| prevSelectResult.destRowDatas.len() > 0
-                                    );
-
-                                    // 连线断掉
-                                    if prevSelectResultVec.is_empty() {
-                                        break 'loopSelectRel;
-                                    }
+                                    continue 'loopEntry;
                                 }
 
                                 srcRowDatas.retain(|(srcDataKey, _)| intersectDataKeys.contains(srcDataKey));
@@ -213,168 +399,168 @@ impl<'session> CommandExecutor<'session> {
                         };
 
                     if srcRowDatas.is_empty() {
-                        continue 'loopRelationData;
+                        continue 'loopEntry;
                     }
 
                     srcRowDatas
                 };
 
-
-                // 收集了当前的relationData的destDataKeys到
-                if selectRel.relationDepth.is_none() {
-                    for (destDataKey, _) in &destRowDatas {
-                        destDataKeysInSelectRel.insert(*destDataKey);
-                    }
+                // 收集了当前的条目的destDataKeys到
+                for (destDataKey, _) in &destRowDatas {
+                    destDataKeysInSelectRel.insert(*destDataKey);
                 }
 
-                // todo selectRelRecursive 如何应对环状的情况 尤其是在不限制endDepth时候
-                // parse的时候已经限制了 要是recursive 是 [1..2) 之类的本质和没有recusive相同 会直接拦掉
-                if let Some(relationDepth) = selectRel.relationDepth {
-                    let mut a = HashMap::new();
-
-                    match relationDepth {
-                        (Bound::Included(startDepth), Bound::Included(endDepth)) => {
-                            let mut initial: Vec<DataKey> = srcRowDatas.iter().map(|(srcDataKey, _)| *srcDataKey).collect();
-
-                            for depth in startDepth..=endDepth {
-                                // 例如recursive[9,13] 要下钻 9,10,11,12,13 这几个深度的
-                                // 那么第1趟下钻9级会比较辛苦,后边的10级 只要依赖9级下钻的成果便可以了
-                                let depth =
-                                    if depth == startDepth {  // 第1趟的时候
-                                        depth
-                                    } else {
-                                        1
-                                    };
-
-                                let destRowDatasRecursive =
-                                    self.selectRelRecursive(initial, srcTable, selectRel.srcFilter.as_ref(),
-                                                            meta::POINTER_KEY_TAG_DOWNSTREAM_REL_ID,
-                                                            relation, selectRel.relationFilter.as_ref(),
-                                                            meta::POINTER_KEY_TAG_DEST_TABLE_ID,
-                                                            depth)?;
-
-                                // 不用再钻了
-                                if destRowDatasRecursive.is_empty() {
-                                    break;
-                                }
-
-                                initial = destRowDatasRecursive.iter().map(|(destDataKeyRecursive, _)| *destDataKeyRecursive).collect();
-
-                                for destDataKeyRecursive in destRowDatasRecursive {
-                                    destDataKeysInSelectRel.insert(destDataKeyRecursive.0);
-                                    a.insert(destDataKeyRecursive.0, destDataKeyRecursive.1);
-                                }
-                            }
-                        }
-                        _ => panic!("impossible"),
-                    };
-
-                    destRowDatas = a.into_iter().map(|entry| { entry }).collect();
-                }
-
-                if destRowDatas.is_empty() {
-                    continue 'loopRelationData;
-                }
-
-                // 当前使用递归的话不显示relation 因为尚未的想好如何显示
-                let selectResult =
-                    if selectRel.relationDepth.is_some() {
-                        SelectResult {
-                            srcName: selectRel.srcAlias.as_ref().unwrap_or_else(|| &selectRel.srcTableName).clone(),
-                            srcRowDatas,
-                            relationName: None,
-                            relationData: None,
-                            destName: selectRel.destAlias.as_ref().unwrap_or_else(|| &selectRel.destTableName).clone(),
-                            destRowDatas,
-                        }
-                    } else {
-                        SelectResult {
-                            srcName: selectRel.srcAlias.as_ref().unwrap_or_else(|| &selectRel.srcTableName).clone(),
-                            srcRowDatas,
-                            relationName: Some(selectRel.relationAlias.as_ref().unwrap_or_else(|| &selectRel.relationName).clone()),
-                            relationData: Some(relationData),
-                            destName: selectRel.destAlias.as_ref().unwrap_or_else(|| &selectRel.destTableName).clone(),
-                            destRowDatas,
-                        }
-                    };
+                let mut srcRowDatas = srcRowDatas;
+                sort::sortRowDatas(&mut srcRowDatas, selectRel.srcOrderBys.as_ref(), selectRel.srcProjections.as_ref())?;
+                sort::sortRowDatas(&mut destRowDatas, selectRel.destOrderBys.as_ref(), selectRel.destProjections.as_ref())?;
+
+                // 当前使用递归的话不显示relation 要看relation的话使用return paths
+                let selectResult = SelectResult {
+                    srcName: selectRel.srcAlias.as_ref().unwrap_or_else(|| &selectRel.srcTableName).clone(),
+                    srcRowDatas,
+                    relationName: relationData.as_ref().map(|_| selectRel.relationAlias.as_ref().unwrap_or_else(|| &selectRel.relationName).clone()),
+                    relationData,
+                    destName: selectRel.destAlias.as_ref().unwrap_or_else(|| &selectRel.destTableName).clone(),
+                    destRowDatas,
+                };
 
                 selectResultVecInSelectRel.push(selectResult);
             }
 
             // 到了这边遍历relationData结束
 
-            destDataKeysInPrevSelectRel = {
-                // 当前的relation select 的多个realtion对应destDataKey全都是empty的
-                if destDataKeysInSelectRel.is_empty() {
-                    // todo 是不是应该全都没有了
-                    break 'loopSelectRel;
+            // dest上的limit offset 是对全部条目的dest合起来的 例如 好友买过的最相似的10个商品
+            if (selectRel.destLimit.is_some() || selectRel.destOffset.is_some()) && selectRel.destAggregates.is_none() {
+                let mut destDataKeys = HashSet::new();
+                let mut destRowDatas = Vec::new();
+
+                for selectResult in &selectResultVecInSelectRel {
+                    for (destDataKey, destRowData) in &selectResult.destRowDatas {
+                        if destDataKeys.insert(*destDataKey) {
+                            destRowDatas.push((*destDataKey, destRowData.clone()));
+                        }
+                    }
                 }
 
-                Some(destDataKeysInSelectRel)
-            };
+                sort::sortRowDatas(&mut destRowDatas, selectRel.destOrderBys.as_ref(), selectRel.destProjections.as_ref())?;
 
-            selectResultVecVec.push(selectResultVecInSelectRel);
-        }
+                let retainedDataKeys: HashSet<DataKey> =
+                    destRowDatas.into_iter()
+                        .skip(selectRel.destOffset.unwrap_or(0))
+                        .take(selectRel.destLimit.unwrap_or(usize::MAX))
+                        .map(|(destDataKey, _)| destDataKey)
+                        .collect();
 
-        /// ```[[[第1个select的第1行data],[第1个select的第2行data]],[[第2个select的第1行data],[第2个select的第2行data]]]```
-        /// 到时候要生成4条脉络
-        fn handleResult(selectResultVecVec: Vec<Vec<SelectResult>>) -> Vec<Value> {
-            let mut valueVec = Vec::new();
+                for selectResult in &mut selectResultVecInSelectRel {
+                    selectResult.destRowDatas.retain(|(destDataKey, _)| retainedDataKeys.contains(destDataKey));
+                }
 
-            if selectResultVecVec.is_empty() {
-                return valueVec;
+                selectResultVecInSelectRel.retain(|selectResult| selectResult.destRowDatas.len() > 0);
+                destDataKeysInSelectRel.retain(|destDataKey| retainedDataKeys.contains(destDataKey));
             }
 
-            // level0上横向遍历
-            for selectResult in &selectResultVecVec[0] {
-                let mut json = json!({});
+            // 当前的select的src确定了 还要回去修改上轮的dest
+            // 要以全部条目的src合起来修改 逐个条目修改的话 前边的条目会把后边的条目用到的上轮的dest去掉
+            if destDataKeysInPrevSelectRel.is_some() {
+                if let Some(prevSelectResultVec) = selectResultVecVec.last_mut() {
+                    let srcDataKeysInSelectRel: HashSet<DataKey> =
+                        selectResultVecInSelectRel.iter()
+                            .flat_map(|selectResult| selectResult.srcRowDatas.iter().map(|(srcDataKey, _)| *srcDataKey))
+                            .collect();
+
+                    // 遍历上轮的各个result的dest,把当前的src之外的去掉
+                    for prevSelectResult in &mut *prevSelectResultVec {
+                        // https://blog.csdn.net/u011528645/article/details/123117829
+                        prevSelectResult.destRowDatas.retain(
+                            |(dataKey, _)| srcDataKeysInSelectRel.contains(dataKey)
+                        );
+                    }
 
-                // 把tuple的position干掉
-                let srcRowDatas: Vec<&RowData> =
-                    selectResult.srcRowDatas.iter().map(
-                        |(_, rownData)| rownData
-                    ).collect();
+                    // destRowDatas是空的话那么把selectResult去掉
+                    prevSelectResultVec.retain(
+                        |prevSelectResult| prevSelectResult.destRowDatas.len() > 0
+                    );
 
-                let destRowDatas: Vec<&RowData> =
-                    selectResult.destRowDatas.iter().map(
-                        |(_, rowData)| rowData
-                    ).collect();
+                    // 连线断掉
+                    if prevSelectResultVec.is_empty() {
+                        break 'loopSelectRel;
+                    }
+                }
+            }
 
-                // 对json::Value来说需要注意的是serialize的调用发生在这边 而不是serde_json::to_string()
-                json[selectResult.srcName.as_str()] = json!(srcRowDatas);
-                if selectResult.relationName.is_some() {
-                    json[selectResult.relationName.as_ref().unwrap().as_str()] = json!(selectResult.relationData.as_ref().unwrap());
+            // 两端的column挑选和projection 只是为了显示 dataKey还是原来的
+            for selectResult in &mut selectResultVecInSelectRel {
+                if selectRel.srcColumnNames.is_some() {
+                    for (_, srcRowData) in &mut selectResult.srcRowDatas {
+                        *srcRowData = store::projectRowData(mem::take(srcRowData), selectRel.srcColumnNames.as_ref(), selectRel.srcProjections.as_ref())?;
+                    }
                 }
-                json[selectResult.destName.as_str()] = json!(destRowDatas);
 
-                let mut selectVecResultVecVecIndex = 1usize;
-                loop {
-                    // 深度上向下
-                    let outerIndex = suffix_plus_plus!(selectVecResultVecVecIndex);
-                    if outerIndex == selectResultVecVec.len() {
-                        break;
+                // dest上有聚合的话 聚合要用到完整的rowData
+                if selectRel.destColumnNames.is_some() && selectRel.destAggregates.is_none() {
+                    for (_, destRowData) in &mut selectResult.destRowDatas {
+                        *destRowData = store::projectRowData(mem::take(destRowData), selectRel.destColumnNames.as_ref(), selectRel.destProjections.as_ref())?;
                     }
+                }
+            }
 
-                    for selectResult in selectResultVecVec.get(outerIndex).unwrap() {
-                        if selectResult.relationName.is_some() {
-                            json[selectResult.relationName.as_ref().unwrap().as_str()] = json!(selectResult.relationData.as_ref().unwrap());
+            // dest上的聚合 以src的各条data分组 同1个src经由多条relation到达的dest要合并
+            if let Some(ref destAggregates) = selectRel.destAggregates {
+                for destAggregate in destAggregates {
+                    if let Some(ref columnName) = destAggregate.columnName {
+                        if destTable.columns.iter().any(|column| &column.name == columnName) == false {
+                            throwFormat!("table:{} has no column:{}", destTable.name, columnName);
                         }
+                    }
+                }
+
+                let mut srcDataKey_index: HashMap<DataKey, usize> = HashMap::new();
+                let mut srcRowData_destRowDatas: Vec<((DataKey, RowData), HashMap<DataKey, RowData>)> = Vec::new();
+
+                for selectResult in selectResultVecInSelectRel {
+                    for srcRowData in selectResult.srcRowDatas {
+                        let index =
+                            *srcDataKey_index.entry(srcRowData.0).or_insert_with(|| {
+                                srcRowData_destRowDatas.push((srcRowData, HashMap::new()));
+                                srcRowData_destRowDatas.len() - 1
+                            });
 
-                        let destRowDatas: Vec<&RowData> = selectResult.destRowDatas.iter().map(|(_, rowData)| rowData).collect();
-                        json[selectResult.destName.as_str()] = json!(destRowDatas);
+                        for (destDataKey, destRowData) in &selectResult.destRowDatas {
+                            srcRowData_destRowDatas[index].1.insert(*destDataKey, destRowData.clone());
+                        }
                     }
                 }
 
-                valueVec.push(json);
+                selectResultVecInSelectRel = Vec::with_capacity(srcRowData_destRowDatas.len());
+
+                for (srcRowData, destDataKey_rowData) in srcRowData_destRowDatas {
+                    let destRowDatas: Vec<(DataKey, RowData)> = destDataKey_rowData.into_iter().collect();
+
+                    selectResultVecInSelectRel.push(SelectResult {
+                        srcName: selectRel.srcAlias.as_ref().unwrap_or_else(|| &selectRel.srcTableName).clone(),
+                        srcRowDatas: vec![srcRowData],
+                        relationName: None,
+                        relationData: None,
+                        destName: selectRel.destAlias.as_ref().unwrap_or_else(|| &selectRel.destTableName).clone(),
+                        destRowDatas: vec![(meta::DATA_KEY_INVALID, aggregate::aggregateRowDatas(destAggregates, &destRowDatas)?)],
+                    });
+                }
             }
 
-            valueVec
-        }
+            destDataKeysInPrevSelectRel = {
+                // 当前的relation select 的多个realtion对应destDataKey全都是empty的
+                if destDataKeysInSelectRel.is_empty() {
+                    // todo 是不是应该全都没有了
+                    break 'loopSelectRel;
+                }
 
-        let valueVec = JSON_ENUM_UNTAGGED!(handleResult(selectResultVecVec));
-        //println!("{}", serde_json::to_string(&valueVec)?);
+                Some(destDataKeysInSelectRel)
+            };
 
-        Ok(CommandExecResult::SelectResult(valueVec))
+            selectResultVecVec.push(selectResultVecInSelectRel);
+        }
+
+        Ok(selectResultVecVec)
     }
 
     /// select user(id = 1 ) as user0 ,in usage (number = 7) ,end in own(number =7) <br>
@@ -386,6 +572,13 @@ impl<'session> CommandExecutor<'session> {
     /// <br>
     /// 相当是在原来基础上再加上对data指向的rel的筛选
     fn selectTableUnderRels(&self, selectTableUnderRels: &SelectTableUnderRels) -> Result<CommandExecResult> {
+        let rowDatas = self.selectTableUnderRelsRowDatas(selectTableUnderRels)?;
+        let values = self.processRowDatasToDisplay(rowDatas);
+
+        Ok(CommandExecResult::SelectResult(values))
+    }
+
+    pub(super) fn selectTableUnderRelsRowDatas(&self, selectTableUnderRels: &SelectTableUnderRels) -> Result<Vec<(DataKey, RowData)>> {
         // 先要以普通select table体系筛选 然后对pointerKey筛选
         let table = Session::getDBObjectByName(selectTableUnderRels.selectTable.tableName.as_str())?;
         let table = table.asTable()?;
@@ -507,73 +700,30 @@ impl<'session> CommandExecutor<'session> {
             let scanParams = ScanParams {
                 table,
                 tableFilter: selectTableUnderRels.selectTable.tableFilterExpr.as_ref(),
-                selectedColumnNames: selectTableUnderRels.selectTable.selectedColNames.as_ref(),
+                selectedColumnNames: if selectTableUnderRels.selectTable.projections.is_some() { None } else { selectTableUnderRels.selectTable.selectedColNames.as_ref() },
                 ..Default::default()
             };
 
-            self.scanSatisfiedRows(scanParams, true, scanHooks)?
-        };
+            let rowDatas = self.scanSatisfiedRows(scanParams, true, scanHooks)?;
 
-        let values = self.processRowDatasToDisplay(rowDatas);
+            match selectTableUnderRels.selectTable.projections {
+                Some(ref projections) => {
+                    let mut projectedRowDatas = Vec::with_capacity(rowDatas.len());
+                    for (dataKey, rowData) in rowDatas {
+                        let rowData = store::projectRowData(rowData, selectTableUnderRels.selectTable.selectedColNames.as_ref(), Some(projections))?;
 
-        Ok(CommandExecResult::SelectResult(values))
-    }
-
-    /// 使用 由端点不断下钻的套路
-    pub(super) fn selectRelRecursive(&self,
-                                     srcDataKeys: Vec<DataKey>, table: &Table, filter: Option<&Expr>,
-                                     pointerKeyTagOnNode: KeyTag,
-                                     relation: &Table, relationFilter: Option<&Expr>,
-                                     pointerKeyTagOnRelation: KeyTag,
-                                     mut depthRemaining: usize) -> Result<Vec<(DataKey, RowData)>> {
-        let lastRound = depthRemaining == 1;
-
-        // 和 srcDataKeys 对应的 destDataKeys,是融合1起的
-        let mut destRowDataKeysTotal = Vec::new();
-        let mut destRowDataTotal = Vec::new();
-
-        // 多个srcDataKey -> 多个relationData -> 多个destDatas
-        for srcDataKey in srcDataKeys {
-            // 得到对应的relation
-            // relationFilter在过程中是都要的
-            let relationDatas =
-                self.searchDataByPointerKeyPrefix(table, srcDataKey, pointerKeyTagOnNode, relation, relationFilter)?;
-
-            // 遍历各个relationData
-            for (relationDataKey, _) in &relationDatas {
-                // 得到这个relationDataKey上的destDataKeys
-                let destRowDatas =
-                    // 过程中对dest没有过滤需要
-                    if lastRound == false {
-                        self.searchDataByPointerKeyPrefix(relation, *relationDataKey, pointerKeyTagOnRelation, table, None)?
-                    } else {  // 说明已到了最后了,需要对destDataKeys使用filter
-                        self.searchDataByPointerKeyPrefix(relation, *relationDataKey, pointerKeyTagOnRelation, table, filter)?
-                    };
-
-                for (destDataKey, destRowData) in destRowDatas {
-                    destRowDataKeysTotal.push(destDataKey);
-
-                    if lastRound {
-                        destRowDataTotal.push((destDataKey, destRowData));
+                        for rowData in store::unnestRowData(rowData, Some(projections)) {
+                            projectedRowDatas.push((dataKey, rowData));
+                        }
                     }
+
+                    projectedRowDatas
                 }
+                None => rowDatas,
             }
-        }
-
-        if destRowDataKeysTotal.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        if lastRound {
-            return Ok(destRowDataTotal);
-        }
+        };
 
-        // 不断递归,以destRowDataKeysTotal起点再向下
-        self.selectRelRecursive(destRowDataKeysTotal, table, filter,
-                                pointerKeyTagOnNode,
-                                relation, relationFilter,
-                                pointerKeyTagOnRelation,
-                                prefix_minus_minus!(depthRemaining))
+        Ok(rowDatas)
     }
 
     #[inline]
@@ -584,4 +734,43 @@ impl<'session> CommandExecutor<'session> {
             ).collect()
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::meta;
+    use crate::session::Session;
+
+    #[test]
+    pub fn testUnnestLimitOffset() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table unnestPost");
+        session.executeSql("create table unnestPost (id integer, tags list<string>)").unwrap();
+        session.executeSql("insert into unnestPost values (1, ['a', 'b', 'c'])").unwrap();
+        session.executeSql("insert into unnestPost values (2, ['d', 'e'])").unwrap();
+
+        let mut tags = |sql: &str| {
+            let mut tags: Vec<String> =
+                session.executeSql(sql).unwrap().remove(0)
+                    .iter().map(|row| row["tag"].as_str().unwrap().to_string()).collect();
+            tags.sort();
+            tags
+        };
+
+        assert_eq!(tags("select unnestPost[unnest(tags) as tag]"), vec!["a", "b", "c", "d", "e"]);
+
+        // limit offset 对的是unnest之后的行
+        assert_eq!(tags("select unnestPost[unnest(tags) as tag](id = 1) limit 2"), vec!["a", "b"]);
+        assert_eq!(tags("select unnestPost[unnest(tags) as tag](id = 1) limit 2 offset 1"), vec!["b", "c"]);
+        assert_eq!(tags("select unnestPost[unnest(tags) as tag] limit 4").len(), 4);
+        assert_eq!(tags("select unnestPost[unnest(tags) as tag] limit 10 offset 4").len(), 1);
+
+        assert_eq!(session.executeSql("select unnestPost[id, unnest(tags) as tag] order by id desc limit 1").unwrap().remove(0), vec![json!({"id": 2, "tag": "d"})]);
+
+        session.executeSql("drop table unnestPost").unwrap();
+    }
+}
@@ -0,0 +1,407 @@
+use std::cmp::Ordering;
+use std::sync::Mutex;
+use std::time::Instant;
+use hashbrown::HashSet;
+use crate::executor::CommandExecutor;
+use crate::executor::store::{ResultContainer, ResultContainerIterator, ScanHooks, ScanParams};
+use crate::graph_value::GraphValue;
+use crate::expr::Expr;
+use crate::parser::command::select::{OrderBy, Projection};
+use crate::parser::element::Element;
+use crate::session::Session;
+use crate::throwFormat;
+use crate::types::{ColumnFamily, DataKey, HashMapExt, RowData, TableMutations};
+use crate::types::{CommittedPreProcessor, UncommittedPreProcessor};
+use anyhow::Result;
+
+/// 对order by的各个column依次比较 <br>
+/// null 当作最大的 和index上的顺序相同(null的type标识是最大的)
+pub(super) fn compareRowData(orderBys: &[OrderBy], rowData: &RowData, rowData0: &RowData) -> Ordering {
+    for orderBy in orderBys {
+        let value = rowData.get(&orderBy.columnName).unwrap_or(&GraphValue::Null);
+        let value0 = rowData0.get(&orderBy.columnName).unwrap_or(&GraphValue::Null);
+
+        let ordering =
+            match (value, value0) {
+                (GraphValue::Null, GraphValue::Null) => Ordering::Equal,
+                (GraphValue::Null, _) => Ordering::Greater,
+                (_, GraphValue::Null) => Ordering::Less,
+                _ => value.partial_cmp(value0).unwrap_or(Ordering::Equal),
+            };
+
+        let ordering = if orderBy.asc { ordering } else { ordering.reverse() };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// order by cosine(embedding, $q) 这样的函数 以columnName为key把算出的值放到rowData
+pub(super) fn calcOrderByExprs(orderBys: &[OrderBy], rowData: &mut RowData) -> Result<()> {
+    for orderBy in orderBys {
+        if let Some(ref expr) = orderBy.expr {
+            let value = expr.calc(Some(rowData))?;
+            rowData.insert(orderBy.columnName.clone(), value);
+        }
+    }
+
+    Ok(())
+}
+
+/// order by 用的可能是projection的alias 例如 ```select user[age * 2 as a] order by a``` <br>
+/// 排序的时候还没有projection 要换成alias对应的expr, expr只是column的话直接换成column 还能利用index的顺序
+pub(super) fn resolveOrderByAliases(orderBys: &[OrderBy], projections: Option<&Vec<Projection>>) -> Vec<OrderBy> {
+    orderBys.iter().enumerate().map(
+        |(position, orderBy)| {
+            let projection =
+                match (&orderBy.expr, projections) {
+                    (None, Some(projections)) => projections.iter().find(|projection| projection.alias == orderBy.columnName),
+                    _ => None,
+                };
+
+            match projection {
+                Some(Projection { expr: Expr::Single(Element::TextLiteral(columnName)), .. }) => {
+                    OrderBy {
+                        columnName: columnName.clone(),
+                        asc: orderBy.asc,
+                        expr: None,
+                    }
+                }
+                // 和order by中的函数相同 不会和column的名字冲突
+                Some(projection) => {
+                    OrderBy {
+                        columnName: format!("{}#{}", projection.alias, position),
+                        asc: orderBy.asc,
+                        expr: Some(projection.expr.clone()),
+                    }
+                }
+                None => orderBy.clone(),
+            }
+        }
+    ).collect()
+}
+
+/// 排序完了后去掉calcOrderByExprs放入的
+pub(super) fn removeOrderByExprValues(orderBys: &[OrderBy], rowData: &mut RowData) {
+    for orderBy in orderBys {
+        if orderBy.expr.is_some() {
+            rowData.remove(&orderBy.columnName);
+        }
+    }
+}
+
+/// 内存中的rowData超过workingMemorySize后 排序后以ResultContainer落盘成为1段 <br>
+/// 最后对各段多路归并
+pub(super) struct RowDataSorter<'a> {
+    session: &'a Session,
+    orderBys: &'a [OrderBy],
+
+    memoryData: Vec<(DataKey, RowData)>,
+    memoryDataSize: usize,
+
+    /// 已排好序落盘的各段
+    sortedRuns: Vec<ResultContainer<'a>>,
+}
+
+impl<'a> RowDataSorter<'a> {
+    pub(super) fn new(session: &'a Session, orderBys: &'a [OrderBy]) -> Self {
+        RowDataSorter {
+            session,
+            orderBys,
+            memoryData: Vec::new(),
+            memoryDataSize: 0,
+            sortedRuns: Vec::new(),
+        }
+    }
+
+    pub(super) fn add(&mut self, dataKey: DataKey, rowData: RowData) -> Result<()> {
+        self.memoryDataSize += rowData.getRowSize();
+        self.memoryData.push((dataKey, rowData));
+
+        if self.memoryDataSize > self.session.workingMemorySize {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        let orderBys = self.orderBys;
+        self.memoryData.sort_by(|(_, rowData), (_, rowData0)| compareRowData(orderBys, rowData, rowData0));
+
+        let mut sortedRun = ResultContainer::withMemoryDataSizeLimit(self.session, 0)?;
+
+        for (dataKey, rowData) in self.memoryData.drain(..) {
+            sortedRun.add(dataKey, rowData)?;
+        }
+
+        self.sortedRuns.push(sortedRun);
+        self.memoryDataSize = 0;
+
+        Ok(())
+    }
+
+    /// 跳过offset 收集limit
+    pub(super) fn finish(mut self, offset: Option<usize>, limit: Option<usize>) -> Result<Vec<(DataKey, RowData)>> {
+        let orderBys = self.orderBys;
+        self.memoryData.sort_by(|(_, rowData), (_, rowData0)| compareRowData(orderBys, rowData, rowData0));
+
+        let mut offsetRemaining = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(usize::MAX);
+
+        // 未曾落盘
+        if self.sortedRuns.is_empty() {
+            return Ok(self.memoryData.into_iter().skip(offsetRemaining).take(limit).collect());
+        }
+
+        let mut sortedRunIters: Vec<ResultContainerIterator> =
+            self.sortedRuns.into_iter().map(|sortedRun| sortedRun.into_iter()).collect();
+
+        let mut memoryDataIter = self.memoryData.into_iter();
+
+        // 各段当前打头的 最后1个是memoryData的
+        let mut heads: Vec<Option<(DataKey, RowData)>> = Vec::with_capacity(sortedRunIters.len() + 1);
+        for sortedRunIter in &mut sortedRunIters {
+            heads.push(sortedRunIter.next().transpose()?);
+        }
+        heads.push(memoryDataIter.next());
+
+        let mut sortedRows = Vec::new();
+
+        while sortedRows.len() < limit {
+            // 段的数量不多 线性的找最小的便可 相等的时候靠前的段优先保持稳定
+            let mut minIndex: Option<usize> = None;
+
+            for (index, head) in heads.iter().enumerate() {
+                if let Some((_, rowData)) = head {
+                    match minIndex {
+                        Some(minIndex0) => {
+                            let (_, minRowData) = heads[minIndex0].as_ref().unwrap();
+                            if compareRowData(orderBys, rowData, minRowData) == Ordering::Less {
+                                minIndex = Some(index);
+                            }
+                        }
+                        None => minIndex = Some(index),
+                    }
+                }
+            }
+
+            let minIndex = match minIndex {
+                Some(minIndex) => minIndex,
+                None => break,
+            };
+
+            let next =
+                if minIndex == sortedRunIters.len() {
+                    memoryDataIter.next()
+                } else {
+                    sortedRunIters[minIndex].next().transpose()?
+                };
+
+            let row = std::mem::replace(&mut heads[minIndex], next).unwrap();
+
+            if offsetRemaining > 0 {
+                offsetRemaining -= 1;
+                continue;
+            }
+
+            sortedRows.push(row);
+        }
+
+        Ok(sortedRows)
+    }
+}
+
+impl<'session> CommandExecutor<'session> {
+    /// 能用上index的顺序的话直接以index读取 不然scan后排序 <br>
+    /// 返回的rowData已经应对了offset limit 然而未prune
+    pub(super) fn scanSatisfiedRowsOrderBy(&self,
+                                           scanParams: ScanParams,
+                                           orderBys: &[OrderBy]) -> Result<Vec<(DataKey, RowData)>> {
+        let table = scanParams.table;
+
+        for orderBy in orderBys {
+            let mut columnNames = HashSet::new();
+
+            match orderBy.expr {
+                Some(ref expr) => expr.extractColumnNames(&mut columnNames)?,
+                None => {
+                    columnNames.insert(orderBy.columnName.clone());
+                }
+            }
+
+            for columnName in columnNames {
+                if table.columns.iter().any(|column| column.name == columnName) == false {
+                    throwFormat!("table:{} has no column:{}", table.name, columnName);
+                }
+            }
+        }
+
+        // 当前tx在这个table上有mutation的话 index上是没有的 只能排序
+        let tableHasMutationsCurrentTx =
+            self.session.dbObjectId_mutations.read().unwrap().contains_key(&table.id);
+
+        if tableHasMutationsCurrentTx == false {
+            let start = Instant::now();
+
+            let satisfiedRows =
+                if let Some(dbObjectIndex) = self.getIndexSuitableForOrderBy(table, orderBys)? {
+                    Some(self.scanByIndexInOrder(dbObjectIndex.value(), &scanParams, orderBys[0].asc)?)
+                } else if let Some(annSearch) = self.getAnnSearch(table, orderBys, scanParams.limit)? {
+                    Some(self.scanByAnnIndex(annSearch, &scanParams, orderBys)?)
+                } else {
+                    None
+                };
+
+            // 这2个不经过scanSatisfiedRows 耗时要自己记录
+            if let Some(satisfiedRows) = satisfiedRows {
+                let elapsed = start.elapsed();
+                self.recordScanStage(|scanStage| scanStage.elapsed = elapsed);
+
+                return Ok(satisfiedRows);
+            }
+        }
+
+        let offset = scanParams.offset;
+        let limit = scanParams.limit;
+
+        // 要用order by的column排序 不能提前prune 也不能提前应对offset limit
+        let scanParams = ScanParams {
+            table,
+            tableFilter: scanParams.tableFilter,
+            ..Default::default()
+        };
+
+        let sorter = Mutex::new(RowDataSorter::new(self.session, orderBys));
+
+        // 通过hook把rowData喂给sorter 返回false使得scanSatisfiedRows自身不去收集
+        let scanHooks = ScanHooks {
+            committedPreProcessor: Option::<Box<dyn CommittedPreProcessor>>::None,
+            committedPostProcessor: Some(
+                |_: &ColumnFamily, committedDataKey: DataKey, rowData: &RowData| {
+                    let mut rowData = rowData.clone();
+                    calcOrderByExprs(orderBys, &mut rowData)?;
+                    sorter.lock().unwrap().add(committedDataKey, rowData)?;
+                    Result::<bool>::Ok(false)
+                }
+            ),
+            uncommittedPreProcessor: Option::<Box<dyn UncommittedPreProcessor>>::None,
+            uncommittedPostProcessor: Some(
+                |_: &TableMutations, addedDataKey: DataKey, rowData: &RowData| {
+                    let mut rowData = rowData.clone();
+                    calcOrderByExprs(orderBys, &mut rowData)?;
+                    sorter.lock().unwrap().add(addedDataKey, rowData)?;
+                    Result::<bool>::Ok(false)
+                }
+            ),
+        };
+
+        self.scanSatisfiedRows(scanParams, true, scanHooks)?;
+
+        let mut sortedRows = sorter.into_inner().unwrap().finish(offset, limit)?;
+
+        for (_, rowData) in &mut sortedRows {
+            removeOrderByExprValues(orderBys, rowData);
+        }
+
+        Ok(sortedRows)
+    }
+}
+
+/// relation select 两端的rowData数量有限 直接内存中排序
+pub(super) fn sortRowDatas(rowDatas: &mut Vec<(DataKey, RowData)>,
+                           orderBys: Option<&Vec<OrderBy>>,
+                           projections: Option<&Vec<Projection>>) -> Result<()> {
+    if let Some(orderBys) = orderBys {
+        let orderBys = &resolveOrderByAliases(orderBys, projections);
+
+        for (_, rowData) in rowDatas.iter_mut() {
+            calcOrderByExprs(orderBys, rowData)?;
+        }
+
+        rowDatas.sort_by(|(_, rowData), (_, rowData0)| compareRowData(orderBys, rowData, rowData0));
+
+        for (_, rowData) in rowDatas.iter_mut() {
+            removeOrderByExprValues(orderBys, rowData);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::meta;
+    use crate::session::Session;
+
+    #[test]
+    pub fn testOrderByProjectionAlias() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop relation sortOwn");
+        let _ = session.executeSql("drop table sortUser");
+        let _ = session.executeSql("drop table sortCar");
+        session.executeSql("create table sortUser (id integer, age integer, name string)").unwrap();
+        session.executeSql("insert into sortUser values (1, 30, 'a')").unwrap();
+        session.executeSql("insert into sortUser values (2, 10, 'b')").unwrap();
+        session.executeSql("insert into sortUser values (3, 20, 'c')").unwrap();
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        assert_eq!(rows("select sortUser[age * 2 as a] order by a"), vec![json!({"a": 20}), json!({"a": 40}), json!({"a": 60})]);
+        assert_eq!(rows("select sortUser[id, age * 2 as a] order by a desc limit 2"), vec![json!({"id": 1, "a": 60}), json!({"id": 3, "a": 40})]);
+        assert_eq!(rows("select sortUser[id, name as n] order by n desc"), vec![json!({"id": 3, "n": "c"}), json!({"id": 2, "n": "b"}), json!({"id": 1, "n": "a"})]);
+
+        // alias和column同名的话 是alias
+        assert_eq!(rows("select sortUser[id, 0 - age as age] order by age"), vec![json!({"id": 1, "age": -30}), json!({"id": 3, "age": -20}), json!({"id": 2, "age": -10})]);
+
+        assert!(session.executeSql("select sortUser[id] order by a").is_err());
+
+        // relation select 的两端
+        session.executeSql("create table sortCar (id integer, price integer)").unwrap();
+        session.executeSql("insert into sortCar values (7, 300)").unwrap();
+        session.executeSql("insert into sortCar values (8, 100)").unwrap();
+        session.executeSql("insert into sortCar values (9, 200)").unwrap();
+        session.executeSql("create relation sortOwn (number integer)").unwrap();
+        session.executeSql("link sortUser(id = 1) to sortCar(price > 0) by sortOwn(number = 1)").unwrap();
+
+        let rows = session.executeSql("select sortUser[id](id = 1) -sortOwn-> sortCar[id, 0 - price as p] order by p limit 1").unwrap().remove(0);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["sortCar"], json!([{"id": 7, "p": -300}]));
+
+        session.executeSql("drop relation sortOwn").unwrap();
+        session.executeSql("drop table sortUser").unwrap();
+        session.executeSql("drop table sortCar").unwrap();
+    }
+
+    #[test]
+    pub fn testIndexOrderByElapsed() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table sortItem");
+        session.executeSql("create table sortItem (id integer, price integer)").unwrap();
+        session.executeSql("create index sortItemPrice on sortItem[price]").unwrap();
+
+        for id in 1..=20 {
+            session.executeSql(&format!("insert into sortItem values ({}, {})", id, id * 7 % 20)).unwrap();
+        }
+
+        let plan = session.executeSql("explain analyze select sortItem order by price limit 3").unwrap().remove(0).remove(0);
+        let scan = &plan["scans"][0];
+
+        // 按照index顺序读取的 不经过scanSatisfiedRows 也要有耗时
+        assert_eq!(scan["accessPath"], json!("index"));
+        assert_eq!(scan["rowsReturned"], json!(3));
+        assert!(scan["elapsedMs"].as_f64().unwrap() > 0.0);
+
+        session.executeSql("drop table sortItem").unwrap();
+    }
+}
@@ -3,17 +3,18 @@ use std::cell::RefCell;
 use hashbrown::{HashMap, HashSet};
 use std::collections::{BTreeMap};
 use std::ops::{Range, RangeFrom};
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::{cmp, hint, mem, ptr, thread};
 use std::io::{IoSlice, Write};
 use std::os::fd::RawFd;
 use std::os::unix::fs::FileExt;
 use std::rc::Rc;
+use std::time::Instant;
 use std::sync::mpsc;
 use std::sync::mpsc::SyncSender;
 use bytes::{Bytes, BytesMut};
 use rocksdb::{AsColumnFamilyRef, Direction, IteratorMode};
-use crate::executor::{CommandExecutor, index, IterationCmd};
+use crate::executor::{CommandExecutor, explain, index, IterationCmd};
 use crate::expr::Expr;
 use crate::{byte_slice_to_u32, byte_slice_to_u64, config, extractPrefixFromKeySlice, extractTargetDataKeyFromPointerKey};
 use crate::{keyPrefixAddRowId, suffix_plus_plus, throw, u64ToByteArrRef, prefix_plus_plus, throwFormat};
@@ -22,6 +23,7 @@ use crate::codec::{BinaryCodec, MyBytes, SliceWrapper};
 use crate::graph_value::GraphValue;
 use crate::meta::{Column, DBObject, Table};
 use crate::parser::command::insert::Insert;
+use crate::parser::command::select::Projection;
 use crate::parser::element::Element;
 use crate::types::{
     Byte, ColumnFamily, DataKey, DBRawIterator, RowData, TableMutations,
@@ -175,7 +177,8 @@ impl<'Table> Default for ScanParams<'Table, '_, '_> {
 impl<'session> CommandExecutor<'session> {
     // todo 实现不实际捞取数据的
     // todo getRowDatasByDataKeys 也要有hook 因为scan时候的index搜索得到dataKeys后会调用到该函数
-    /// 目前使用的场合是通过realtion保存的两边node的position得到相应的node
+    /// 目前使用的场合是通过realtion保存的两边node的position得到相应的node <br>
+    /// 以及index搜索得到dataKeys后 会应对mvcc的visibility和tableFilter
     pub(super) fn getRowDatasByDataKeys<A, B, C, D>(&self,
                                                     dataKeys: &[DataKey],
                                                     scanParams: &ScanParams,
@@ -259,8 +262,15 @@ impl<'session> CommandExecutor<'session> {
                 Ok(())
             };
 
-        for dataKey in dataKeys[1]..=dataKeys[2] {
-            processDataKey(dataKey, None)?;
+        // 要得到表的全部的data
+        if dataKeys[0] == global::TOTAL_DATA_OF_TABLE {
+            for dataKey in dataKeys[1]..=dataKeys[2] {
+                processDataKey(dataKey, None)?;
+            }
+        } else { // 以dataKeys的顺序 order by 用到index的时候依赖这点
+            for dataKey in dataKeys {
+                processDataKey(*dataKey, None)?;
+            }
         }
 
         // 要得到表的全部的data
@@ -325,13 +335,51 @@ impl<'session> CommandExecutor<'session> {
     pub(super) fn scanSatisfiedRows<A, B, C, D>(&self,
                                                 scanParams: ScanParams,
                                                 select: bool,
-                                                mut scanHooks: ScanHooks<A, B, C, D>) -> Result<Vec<(DataKey, RowData)>>
+                                                scanHooks: ScanHooks<A, B, C, D>) -> Result<Vec<(DataKey, RowData)>>
     where
         A: CommittedPreProcessor,
         B: CommittedPostProcessor,
         C: UncommittedPreProcessor,
         D: UncommittedPostProcessor,
     {
+        if self.explaining() == false {
+            return self.scanSatisfiedRows0(scanParams, select, scanHooks);
+        }
+
+        self.beginScanStage(scanParams.table.name.as_str());
+
+        let start = Instant::now();
+        let satisfiedRows = self.scanSatisfiedRows0(scanParams, select, scanHooks)?;
+        let elapsed = start.elapsed();
+
+        self.recordScanStage(|scanStage| scanStage.elapsed = elapsed);
+
+        Ok(satisfiedRows)
+    }
+
+    fn scanSatisfiedRows0<A, B, C, D>(&self,
+                                      scanParams: ScanParams,
+                                      select: bool,
+                                      mut scanHooks: ScanHooks<A, B, C, D>) -> Result<Vec<(DataKey, RowData)>>
+    where
+        A: CommittedPreProcessor,
+        B: CommittedPostProcessor,
+        C: UncommittedPreProcessor,
+        D: UncommittedPostProcessor,
+    {
+        // primary key 上的等值 直接定位到data 不用去规划scan
+        if let Some(satisfiedRows) = self.scanByPrimaryKey(&scanParams, &mut scanHooks)? {
+            self.recordScanStage(|scanStage| {
+                scanStage.rowsScanned += satisfiedRows.len();
+                scanStage.rowsReturned += satisfiedRows.len();
+            });
+
+            return Ok(satisfiedRows);
+        }
+
+        // 给explain analyze用的 读取并计算过filter的数量 和 满足的数量
+        let mut scannedCount = 0usize;
+        let mut satisfiedCount = 0usize;
 
         // todo 使用table id 为 column family 标识
         let columnFamily = Session::getColumnFamily(scanParams.table.id)?;
@@ -351,7 +399,11 @@ impl<'session> CommandExecutor<'session> {
                 if scanParams.tableFilter.is_some() {
                     let tableFilter = scanParams.tableFilter.as_ref().unwrap();
 
-                    match filter::processTableFilter(tableFilter)? {
+                    let tableFilterProcResult = filter::processTableFilter(tableFilter)?;
+
+                    self.recordScanStage(|scanStage| scanStage.recordTableFilterProcResult(&tableFilterProcResult));
+
+                    match tableFilterProcResult {
                         TableFilterProcResult::IndexableTableFilterColHasNonesenseWhenIsPureOr => {
                             // 没有 tableFilter
                         }
@@ -404,12 +456,64 @@ impl<'session> CommandExecutor<'session> {
 
                                 indexSearch.scanHooksPtr = utils::refMut2Ptr(&mut scanHooks);
 
+                                self.recordScanStage(|scanStage| {
+                                    scanStage.accessPath = explain::ACCESS_PATH_INDEX;
+                                    scanStage.chosenIndexName = Some(indexSearch.dbObjectIndex.getName().clone());
+                                    scanStage.indexLocalSearch = indexSearch.indexLocalSearch;
+                                });
+
+                                if self.explainWithoutAnalyze() {
+                                    return Ok(vec![]);
+                                }
+
                                 satisfiedRows = self.searchByIndex::<A, B, C, D>(indexSearch)?;
 
+                                satisfiedCount += satisfiedRows.len();
+
                                 scanSearch = false;
                             }
                         }
                     }
+
+                    // tags contains 'rust' 这样的 普通的index用不了 看看有没有inverted index
+                    if scanSearch {
+                        if let Some(invertedIndexSearch) = self.getInvertedIndexSearch(scanParams.table, tableFilter)? {
+                            self.recordScanStage(|scanStage| {
+                                scanStage.accessPath = explain::ACCESS_PATH_INVERTED_INDEX;
+                                scanStage.chosenIndexName = Some(invertedIndexSearch.dbObjectIndex.getName().clone());
+                            });
+
+                            if self.explainWithoutAnalyze() {
+                                return Ok(vec![]);
+                            }
+
+                            satisfiedRows = self.scanByInvertedIndex(invertedIndexSearch, &scanParams, &mut scanHooks)?;
+
+                            satisfiedCount += satisfiedRows.len();
+
+                            scanSearch = false;
+                        }
+                    }
+
+                    // within_radius(location, point(30.27, 120.15), 5000) 这样的 看看有没有spatial index
+                    if scanSearch {
+                        if let Some(spatialIndexSearch) = self.getSpatialIndexSearch(scanParams.table, tableFilter)? {
+                            self.recordScanStage(|scanStage| {
+                                scanStage.accessPath = explain::ACCESS_PATH_SPATIAL_INDEX;
+                                scanStage.chosenIndexName = Some(spatialIndexSearch.dbObjectIndex.getName().clone());
+                            });
+
+                            if self.explainWithoutAnalyze() {
+                                return Ok(vec![]);
+                            }
+
+                            satisfiedRows = self.scanBySpatialIndex(spatialIndexSearch, &scanParams, &mut scanHooks)?;
+
+                            satisfiedCount += satisfiedRows.len();
+
+                            scanSearch = false;
+                        }
+                    }
                 }
 
                 let mut serialScan = true;
@@ -432,6 +536,22 @@ impl<'session> CommandExecutor<'session> {
                             concurrency = self.session.scanConcurrency as u64;
                         }
 
+                        self.recordScanStage(|scanStage| {
+                            scanStage.accessPath = explain::ACCESS_PATH_SCAN;
+                            scanStage.concurrentScan = true;
+                            scanStage.concurrency = Some(concurrency);
+                        });
+
+                        if self.explainWithoutAnalyze() {
+                            return Ok(vec![]);
+                        }
+
+                        // 各个thread上累加的
+                        let scannedCountConcurrent = AtomicUsize::new(0);
+                        let satisfiedCountConcurrent = AtomicUsize::new(0);
+                        let scannedCountConcurrent = &scannedCountConcurrent;
+                        let satisfiedCountConcurrent = &satisfiedCountConcurrent;
+
                         let rowCountPerThread = distance / concurrency;
 
                         // range的两边都是闭区间
@@ -546,8 +666,12 @@ impl<'session> CommandExecutor<'session> {
                                                 ..Default::default()
                                             };
 
+                                            scannedCountConcurrent.fetch_add(1, Ordering::Relaxed);
+
                                             match commandExecutor.readRowDataBinary(&*rowDataBinary, &scanParams)? {
                                                 Some(rowData) => {
+                                                    satisfiedCountConcurrent.fetch_add(1, Ordering::Relaxed);
+
                                                     // committed post
                                                     if scanHooks.postProcessCommitted(&columnFamily, dataKey, &rowData)? == false {
                                                         continue;
@@ -597,11 +721,20 @@ impl<'session> CommandExecutor<'session> {
 
                             Result::<Vec<(DataKey, RowData)>>::Ok(satisfiedRows)
                         })?;
+
+                        scannedCount += scannedCountConcurrent.load(Ordering::Relaxed);
+                        satisfiedCount += satisfiedCountConcurrent.load(Ordering::Relaxed);
                     }
                 }
 
                 // 虽然设置了可以对线程scan 然而可能因为实际的数据量不够还是用不到
                 if scanSearch && serialScan {
+                    self.recordScanStage(|scanStage| scanStage.accessPath = explain::ACCESS_PATH_SCAN);
+
+                    if self.explainWithoutAnalyze() {
+                        return Ok(vec![]);
+                    }
+
                     let snapshot = self.session.getSnapshot()?;
 
                     // mvcc的visibility筛选
@@ -633,8 +766,12 @@ impl<'session> CommandExecutor<'session> {
                             continue;
                         }
 
+                        suffix_plus_plus!(scannedCount);
+
                         // mvcc筛选过了 对rowData本身的筛选
                         if let Some(rowData) = self.readRowDataBinary(&*rowDataBinary, &scanParams)? {
+                            suffix_plus_plus!(satisfiedCount);
+
                             // postProcessCommitted
                             if scanHooks.postProcessCommitted(&columnFamily, dataKey, &rowData)? == false {
                                 continue;
@@ -710,7 +847,11 @@ impl<'session> CommandExecutor<'session> {
                     continue;
                 }
 
+                suffix_plus_plus!(scannedCount);
+
                 if let Some(rowData) = self.readRowDataBinary(addRowDataBinaryCurrentTx, &scanParams)? {
+                    suffix_plus_plus!(satisfiedCount);
+
                     // postProcessUncommitted
                     if scanHooks.postProcessUncommitted(tableMutationsCurrentTx,
                                                         addedDataKeyCurrentTx,
@@ -723,6 +864,11 @@ impl<'session> CommandExecutor<'session> {
             }
         }
 
+        self.recordScanStage(|scanStage| {
+            scanStage.rowsScanned += scannedCount;
+            scanStage.rowsReturned += satisfiedCount;
+        });
+
         Ok(satisfiedRows)
     }
 
@@ -748,7 +894,7 @@ impl<'session> CommandExecutor<'session> {
         }
 
         if scanParams.tableFilter.is_none() {
-            return Ok(Some(rowData));
+            return Ok(Some(pruneRowData(rowData, scanParams.selectedColumnNames)?));
         }
 
         // todo  select user[id](name like 'tom') 因为未选取name 使得name过滤的时候报错 不能提前prune 完成
@@ -859,13 +1005,15 @@ impl<'session> CommandExecutor<'session> {
                     let columnExpr = columnName_columnExpr.get(&column.name).unwrap();
 
                     // 计算得到value
-                    let columnValue = columnExpr.calc(None)?;
+                    let mut columnValue = columnExpr.calc(None)?;
 
                     // columnType和value要对上
                     if column.type0.compatibleWithValue(&columnValue) == false {
                         throwFormat!("column:{}, type:{} is not compatible with value:{}", column.name, column.type0, columnValue);
                     }
 
+                    column.type0.fitValue(&mut columnValue)?;
+
                     columnValue.encode2ByteMut(&mut destByteSlice)?;
 
                     rowData.insert(column.name.clone(), columnValue);
@@ -1045,6 +1193,81 @@ pub(super) fn pruneRowData(mut rowData: RowData, selectedColName: Option<&Vec<St
     Ok(prunedRowData)
 }
 
+/// 有projection的时候rowData需要是未prune的 先计算projection再prune
+pub(super) fn projectRowData(rowData: RowData,
+                             selectedColNames: Option<&Vec<String>>,
+                             projections: Option<&Vec<Projection>>) -> Result<RowData> {
+    let projections = match projections {
+        Some(projections) => projections,
+        None => return pruneRowData(rowData, selectedColNames),
+    };
+
+    let mut projectedRowData: RowData = HashMap::with_capacity(projections.len() + selectedColNames.map_or(0, |selectedColNames| selectedColNames.len()));
+
+    for projection in projections {
+        projectedRowData.insert(projection.alias.clone(), projection.expr.calc(Some(&rowData))?);
+    }
+
+    for (columnName, columnValue) in pruneRowData(rowData, selectedColNames)? {
+        projectedRowData.insert(columnName, columnValue);
+    }
+
+    Ok(projectedRowData)
+}
+
+#[inline]
+fn isUnnest(projection: &Projection) -> bool {
+    matches!(&projection.expr, Expr::Function { functionName, .. } if functionName.eq_ignore_ascii_case("unnest"))
+}
+
+/// unnest使得1行成为多行 limit offset要在unnest之后应对
+pub(super) fn hasUnnest(projections: Option<&Vec<Projection>>) -> bool {
+    projections.is_some_and(|projections| projections.iter().any(isUnnest))
+}
+
+/// projection最外层是unnest的话 list的各个元素各自成为1行, 多个unnest按位置对齐 短的补null <br>
+/// list是null或是空的话 这行便没有了
+pub(super) fn unnestRowData(rowData: RowData, projections: Option<&Vec<Projection>>) -> Vec<RowData> {
+    let unnestAliases: Vec<&String> =
+        match projections {
+            Some(projections) => projections.iter().filter(|projection| isUnnest(projection)).map(|projection| &projection.alias).collect(),
+            None => Vec::new(),
+        };
+
+    if unnestAliases.is_empty() {
+        return vec![rowData];
+    }
+
+    let getElements = |alias: &String| {
+        match rowData.get(alias) {
+            Some(GraphValue::List(list)) => list.as_slice(),
+            _ => &[],
+        }
+    };
+
+    let rowCount = unnestAliases.iter().map(|alias| getElements(alias).len()).max().unwrap_or(0);
+
+    let mut rowDatas = Vec::with_capacity(rowCount);
+
+    for index in 0..rowCount {
+        let mut unnestedRowData = rowData.clone();
+
+        for alias in &unnestAliases {
+            let element = getElements(alias).get(index).cloned().unwrap_or(GraphValue::Null);
+            unnestedRowData.insert((*alias).clone(), element);
+        }
+
+        rowDatas.push(unnestedRowData);
+    }
+
+    rowDatas
+}
+
+lazy_static! {
+    /// 用来区分同1个tx下的多个临时文件
+    static ref TEMP_FILE_SEQ: AtomicU64 = AtomicU64::new(0);
+}
+
 /// 替换 Vec<(DataKey, RowData)>
 pub(super) struct ResultContainer<'a> {
     session: &'a Session,
@@ -1052,16 +1275,24 @@ pub(super) struct ResultContainer<'a> {
     /// 在内存部分的结果
     memoryData: Vec<(DataKey, RowData)>,
     memoryDataSize: usize,
+    /// 内存部分的上限 默认是session的workingMemorySize
+    memoryDataSizeLimit: usize,
 
-    /// 结果太大后超出的部分保存在了临时文件 temp_data_tx_id_{txId}
+    /// 结果太大后超出的部分保存在了临时文件 temp_data_tx_id_{txId}_{seq}
     diskDataFile: Option<StdFile>,
 }
 
 impl<'a> ResultContainer<'a> {
     pub(super) fn new(session: &'a Session) -> Result<Self> {
+        Self::withMemoryDataSizeLimit(session, session.workingMemorySize)
+    }
+
+    /// memoryDataSizeLimit是0的话全部落盘
+    pub(super) fn withMemoryDataSizeLimit(session: &'a Session, memoryDataSizeLimit: usize) -> Result<Self> {
         Ok(Self {
             session,
             memoryDataSize: 0,
+            memoryDataSizeLimit,
             memoryData: Vec::new(),
             diskDataFile: None,
         })
@@ -1069,7 +1300,7 @@ impl<'a> ResultContainer<'a> {
 
     pub(super) fn add(&mut self, dataKey: DataKey, rowData: RowData) -> Result<()> {
         // 放到内存中
-        if self.session.workingMemorySize >= self.memoryDataSize {
+        if self.memoryDataSizeLimit > self.memoryDataSize {
             self.memoryDataSize += rowData.getRowSize();
             self.memoryData.push((dataKey, rowData));
 
@@ -1079,10 +1310,21 @@ impl<'a> ResultContainer<'a> {
         // 放到临时的文件文件
         if hint::unlikely(self.diskDataFile.is_none()) {
             let diskDataFile = {
+                let filePath =
+                    format!("{}/temp_data_tx_id_{}_{}",
+                            config::CONFIG.tempFileDir,
+                            self.session.getTxId()?,
+                            TEMP_FILE_SEQ.fetch_add(1, Ordering::AcqRel));
+
                 let mut openOptions = StdOpenOptions::new();
-                openOptions.read(true).write(true).create_new(true).truncate(true);
+                openOptions.read(true).write(true).create_new(true);
 
-                openOptions.open(&config::CONFIG.tempFileDir)?
+                let diskDataFile = openOptions.open(&filePath)?;
+
+                // 打开后便可删掉 fd还在便能读写 随着file的drop自动清理
+                std::fs::remove_file(&filePath)?;
+
+                diskDataFile
             };
 
             self.diskDataFile.replace(diskDataFile);
@@ -1148,15 +1390,9 @@ impl<'a> IntoIterator for ResultContainer<'a> {
     type IntoIter = ResultContainerIterator;
 
     fn into_iter(self) -> Self::IntoIter {
-        let memoryDataPtr = self.memoryData.as_ptr();
-        let memoryDataLen = self.memoryData.len();
-
-        mem::forget(self.memoryData);
-
         ResultContainerIterator {
-            memoryDataPtr,
-            memoryDataLen, // 直接的记录len减少后续的调用消耗
-            memoryDataIndex: 0,
+            // 直接使用vec的IntoIter 可以绕过RowData的clone直接得到非引用的(dataKey,rowData)
+            memoryDataIter: self.memoryData.into_iter(),
             diskDataFile: self.diskDataFile,
             diskDataPos: 0,
             diskDataBuf: {
@@ -1173,9 +1409,7 @@ impl<'a> IntoIterator for ResultContainer<'a> {
 }
 
 pub(super) struct ResultContainerIterator {
-    memoryDataPtr: *const (DataKey, RowData),
-    memoryDataLen: usize,
-    memoryDataIndex: usize,
+    memoryDataIter: std::vec::IntoIter<(DataKey, RowData)>,
 
     diskDataFile: Option<StdFile>,
     diskDataPos: u64,
@@ -1188,17 +1422,7 @@ impl Iterator for ResultContainerIterator {
 
     fn next(&mut self) -> Option<Self::Item> {
         // memoryData 还有内容的
-        if self.memoryDataLen > self.memoryDataIndex {
-            // 直接指针读取对应的index的data,这样可以绕过RowData的clone直接得到非引用的(dataKey,rowData)
-            // memoryData.remove(index)也是可以得到,然而会使得vec内部的元素迁移成本不小的
-            // 使用指针的话可以确保memoryData保持原样,然而需要在drop的时候特殊处理
-            let entry = unsafe {
-                // 不能使用"*"取指针的内容,报错"cannot move out of a raw pointer,需要实现copy"
-                ptr::read(self.memoryDataPtr.add(self.memoryDataIndex))
-            };
-
-            suffix_plus_plus!(self.memoryDataIndex);
-
+        if let Some(entry) = self.memoryDataIter.next() {
             return Some(Ok(entry));
         }
 
@@ -1214,9 +1438,16 @@ impl Iterator for ResultContainerIterator {
             if let Err(e) = diskDataFile.read_exact_at(
                 diskDataBuf.as_mut(), self.diskDataPos,
             ) {
+                // 读到了末尾
+                if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                    return None;
+                }
+
                 return Some(Err(anyhow::Error::new(e)));
             }
 
+            self.diskDataPos += diskDataBuf.len() as u64;
+
             let (dataKeySlice, lenSlice) = diskDataBuf.split_at(size_of::<DataKey>());
 
             let dataKey = byte_slice_to_u64!(dataKeySlice);
@@ -1226,53 +1457,20 @@ impl Iterator for ResultContainerIterator {
                 return Some(Ok((dataKey, global::DUMMY_ROW_DATA)));
             }
 
-            (dataKey, rowDataLen)
+            (dataKey, rowDataLen as usize)
         };
 
+        let mut rowDataJson = vec![0; rowDataLen];
 
-        todo!()
-    }
-}
-
-impl Drop for ResultContainerIterator {
-    fn drop(&mut self) {
-        // memoryData清理
-        let memoryDataPtrCurrent = unsafe {
-            self.memoryDataPtr.add(self.memoryDataIndex)
-        };
-
-        // 已经使用的部分当成纯数字(u8)清理
-        {
-            let len = memoryDataPtrCurrent as usize - self.memoryDataPtr as usize;
-
-            if len > 0 {
-                let vec = unsafe {
-                    Vec::from_raw_parts(
-                        self.memoryDataPtr as *mut u8,
-                        len,
-                        len,
-                    )
-                };
-
-                drop(vec);
-            }
+        if let Err(e) = diskDataFile.read_exact_at(rowDataJson.as_mut_slice(), self.diskDataPos) {
+            return Some(Err(anyhow::Error::new(e)));
         }
 
-        // 未使用的部分依然当成(dataKey,rowData)清理
-        {
-            let len = self.memoryDataLen - self.memoryDataIndex;
-
-            if len > 0 {
-                let vec = unsafe {
-                    Vec::from_raw_parts(
-                        unsafe { memoryDataPtrCurrent as *mut (DataKey, RowData) },
-                        len,
-                        len,
-                    )
-                };
-
-                drop(vec);
-            }
+        self.diskDataPos += rowDataLen as u64;
+
+        match serde_json::from_slice::<RowData>(rowDataJson.as_slice()) {
+            Ok(rowData) => Some(Ok((dataKey, rowData))),
+            Err(e) => Some(Err(anyhow::Error::new(e))),
         }
     }
-}
\ No newline at end of file
+}
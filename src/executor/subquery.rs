@@ -0,0 +1,159 @@
+use crate::executor::CommandExecutor;
+use crate::expr::Expr;
+use crate::graph_value::GraphValue;
+use crate::parser::command;
+use crate::parser::command::Command;
+use crate::parser::command::select::Select;
+use crate::parser::element::Element;
+use crate::parser::op::{Op, SqlOp};
+use crate::types::RowData;
+use crate::{throw, throwFormat};
+use anyhow::Result;
+
+impl<'session> CommandExecutor<'session> {
+    /// 子查询都是不相关的 在command执行前先各自跑1趟 把结果替换到原来的位置 <br>
+    /// in 右边的展开成多个值 别的地方的只能是单个值
+    pub(super) fn foldSubqueries(&self, command: &mut Command) -> Result<()> {
+        command.visitExprs(&mut |expr| self.foldSubquery(expr))
+    }
+
+    fn foldSubquery(&self, expr: &mut Expr) -> Result<()> {
+        match expr {
+            Expr::Subquery(select) => {
+                let values = self.executeSubquery(select)?;
+                *expr = Expr::Single(scalarElement(values)?);
+            }
+            Expr::BiDirection { leftExpr, op, rightExprs } => {
+                self.foldSubquery(leftExpr)?;
+
+                // a in (select ...)
+                let inSubqueryValues =
+                    match (&op, rightExprs.as_mut_slice()) {
+                        (Op::SqlOp(SqlOp::In), [rightExpr]) => {
+                            match rightExpr.as_mut() {
+                                Expr::Subquery(select) => Some(self.executeSubquery(select)?),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    };
+
+                match inSubqueryValues {
+                    // 子查询没有结果 in 必然不成立
+                    Some(values) if values.is_empty() => *expr = Expr::Single(Element::Boolean(false)),
+                    Some(values) => {
+                        let mut expandedExprs = Vec::with_capacity(values.len());
+                        for value in &values {
+                            expandedExprs.push(Box::new(Expr::Single(Element::try_from(value)?)));
+                        }
+
+                        *rightExprs = expandedExprs;
+                    }
+                    None => {
+                        for rightExpr in rightExprs {
+                            self.foldSubquery(rightExpr)?;
+                        }
+                    }
+                }
+            }
+            Expr::Function { args, .. } => {
+                for arg in args {
+                    self.foldSubquery(arg)?;
+                }
+            }
+            Expr::Case { .. } => {
+                for part in expr.casePartsMut() {
+                    self.foldSubquery(part)?;
+                }
+            }
+            Expr::Single(_) | Expr::None => {}
+        }
+
+        Ok(())
+    }
+
+    /// 子查询只能select 1个column 得到它的各个值 <br>
+    /// 直接使用rowData 不经由显示用的json 不然decimal timestamp date interval bytes 的type会丢失
+    fn executeSubquery(&self, select: &mut Select) -> Result<Vec<GraphValue>> {
+        // 子查询里边可能还有子查询
+        command::visitSelectExprs(select, &mut |expr| self.foldSubquery(expr))?;
+
+        let rowDatas =
+            match select {
+                Select::SelectTable(selectTable) => self.selectTableRowDatas(selectTable)?,
+                Select::SelectTableUnderRels(selectTableUnderRels) => self.selectTableUnderRelsRowDatas(selectTableUnderRels)?,
+                Select::SelectRels(selectRels) => {
+                    if selectRels.iter().any(|selectRel| selectRel.relationPathSearch.is_some() || selectRel.returnPaths) {
+                        throw!("subquery can not search paths");
+                    }
+
+                    // 取最后1个selectRel的dest
+                    self.selectRelsDestRowDatas(selectRels)?
+                }
+            };
+
+        let mut values = Vec::with_capacity(rowDatas.len());
+
+        for (_, rowData) in rowDatas {
+            values.push(singleColumnValue(rowData)?);
+        }
+
+        Ok(values)
+    }
+}
+
+fn singleColumnValue(rowData: RowData) -> Result<GraphValue> {
+    if rowData.len() != 1 {
+        let mut columnNames: Vec<&String> = rowData.keys().collect();
+        columnNames.sort();
+        throwFormat!("subquery should select exactly one column, but got:{:?}", columnNames);
+    }
+
+    Ok(rowData.into_values().next().unwrap())
+}
+
+/// 不是in的地方 子查询最多只能有1个值 没有的话是null
+fn scalarElement(values: Vec<GraphValue>) -> Result<Element> {
+    match values.len() {
+        0 => Ok(Element::Null),
+        1 => Ok(Element::try_from(&values[0])?),
+        n => throwFormat!("subquery used as scalar returned {} rows", n),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::meta;
+    use crate::session::Session;
+
+    #[test]
+    pub fn testFoldSubquery() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table subqueryEvent");
+        session.executeSql("create table subqueryEvent (id integer, name string, price decimal(10,2), at timestamp, day date)").unwrap();
+        session.executeSql("insert into subqueryEvent values (1, 'a', 1.10, timestamp '2024-01-02 03:04:05', date '2024-01-01')").unwrap();
+        session.executeSql("insert into subqueryEvent values (2, 'b', 2.20, timestamp '2024-02-02 03:04:05', date '2024-02-01')").unwrap();
+        session.executeSql("insert into subqueryEvent values (3, 'c', 3.30, timestamp '2024-03-02 03:04:05', date '2024-03-01')").unwrap();
+
+        let mut ids = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        // decimal的avg 不能变成json的string
+        assert_eq!(ids("select subqueryEvent[id](price > (select subqueryEvent[avg(price)]))"), vec![json!({"id": 3})]);
+        assert_eq!(ids("select subqueryEvent[id](name = (select subqueryEvent[name](id = 2)))"), vec![json!({"id": 2})]);
+        assert_eq!(ids("select subqueryEvent[id](name in (select subqueryEvent[name](id = 1)))"), vec![json!({"id": 1})]);
+        assert_eq!(ids("select subqueryEvent[id](price = (select subqueryEvent[max(price)]))"), vec![json!({"id": 3})]);
+        assert_eq!(ids("select subqueryEvent[id](price in (select subqueryEvent[price](id > 1)))"), vec![json!({"id": 2}), json!({"id": 3})]);
+        assert_eq!(ids("select subqueryEvent[id](at = (select subqueryEvent[at](id = 2)))"), vec![json!({"id": 2})]);
+        assert_eq!(ids("select subqueryEvent[id](day in (select subqueryEvent[day](id = 1)))"), vec![json!({"id": 1})]);
+        assert_eq!(ids("select subqueryEvent[id](id in (select subqueryEvent[id](id > 100)))"), Vec::<serde_json::Value>::new());
+
+        assert!(session.executeSql("select subqueryEvent(id = (select subqueryEvent[id]))").is_err());
+        assert!(session.executeSql("select subqueryEvent(id = (select subqueryEvent[id,price](id = 1)))").is_err());
+
+        session.executeSql("drop table subqueryEvent").unwrap();
+    }
+}
@@ -10,7 +10,6 @@ use crate::{throw, u64ToByteArrRef, byte_slice_to_u64, throwFormat};
 use crate::codec::BinaryCodec;
 use crate::executor::store::{ScanHooks, ScanParams, SearchPointerKeyHooks};
 use crate::expr::Expr;
-use crate::graph_error::GraphError;
 use crate::graph_value::GraphValue;
 use crate::parser::command::update::Update;
 use crate::types::{Byte, ColumnFamily, DataKey, DBIterator, KV, RowData, RowId, SessionHashMap, TableMutations};
@@ -104,17 +103,19 @@ impl<'session> CommandExecutor<'session> {
 
         let mut columnName_a = self.hashMapWithCapacityIn(update.columnName_expr.len());
 
-        let compatibleCheck = |columnName: &String, columnValue: &GraphValue| {
+        let compatibleCheck = |columnName: &String, columnValue: &mut GraphValue| {
             match columnName_column.get(columnName) {
                 Some(column) => {
                     if column.type0.compatibleWithValue(columnValue) == false {
                         throwFormat!("table:{} , column:{}, is not compatilbe with value:{:?}", update.tableName, columnName, columnValue);
                     }
+
+                    column.type0.fitValue(columnValue)?;
                 }
                 None => throwFormat!("table:{} has no column named:{}", update.tableName, columnName),
             }
 
-            Result::<(), GraphError>::Ok(())
+            Result::<()>::Ok(())
         };
 
         // column expr能直接计算的先计算 不要到后边的遍历里边重复计算了
@@ -122,10 +123,10 @@ impl<'session> CommandExecutor<'session> {
             if columnExpr.needAcutalRowData() {
                 columnName_a.insert(columnName.to_string(), A::NeedCalc(columnExpr));
             } else {
-                let columnValue = columnExpr.calc(None)?;
+                let mut columnValue = columnExpr.calc(None)?;
 
                 // update设置的值要和column type 相同
-                compatibleCheck(columnName, &columnValue)?;
+                compatibleCheck(columnName, &mut columnValue)?;
 
                 columnName_a.insert(columnName.to_string(), A::DirectValue(columnValue));
             }
@@ -135,19 +136,22 @@ impl<'session> CommandExecutor<'session> {
         // 遍历各个满足要求的row
         let mut keyBuffer = self.withCapacityIn(meta::MVCC_KEY_BYTE_LEN);
         let mut rowDataBuffer = self.newIn();
+        // indexKey的长度不定 不能复用上边定长的keyBuffer 不然扩容时候会realloc bump上的内存
+        let mut indexKeyBuffer = self.newIn();
 
         for (ref oldDataKey, rowData) in &mut targetRowDatas {
             // todo update时候如何干掉oldDataKey对应的index ✅
             // 趁着rowData还是原始模样的时候
-            self.generateIndexData(table, &mut keyBuffer, *oldDataKey, rowData, true)?;
+            self.generateIndexData(table, &mut indexKeyBuffer, *oldDataKey, rowData, true)?;
+            self.generatePrimaryKeyData(table, *oldDataKey, rowData, true)?;
 
             for (columnName, a) in &columnName_a {
                 match a {
                     A::NeedCalc(expr) => {
-                        let columnValue = expr.calc(Some(rowData))?;
+                        let mut columnValue = expr.calc(Some(rowData))?;
 
                         // update设置的值要和column type 相同
-                        compatibleCheck(columnName, &columnValue)?;
+                        compatibleCheck(columnName, &mut columnValue)?;
 
                         rowData.insert(columnName.to_string(), columnValue);
                     }
@@ -167,7 +171,7 @@ impl<'session> CommandExecutor<'session> {
 
             // 写新的data本身
             let newRowId: RowId = table.nextRowId();
-            let newDataKey = keyPrefixAddRowId!(meta::KEY_PREFIX_MVCC,newRowId);
+            let newDataKey = keyPrefixAddRowId!(meta::KEY_PREFIX_DATA, newRowId);
             let newData: KV = (u64ToByteArrRef!(newDataKey).to_vec(), rowDataBuffer.to_vec());
 
             // 写新的data的xmin,xmax 对应的2个的mvcc key
@@ -179,7 +183,10 @@ impl<'session> CommandExecutor<'session> {
             self.session.writeUpdateDataMutation(table.id, oldXmax, newData, newXmin, newXmax, origin);
 
             // 新的data的相应的index
-            self.generateIndexData(table, &mut keyBuffer, newDataKey, &rowData, false)?;
+            self.generateIndexData(table, &mut indexKeyBuffer, newDataKey, &rowData, false)?;
+
+            // primary key 改为指向新的data
+            self.generatePrimaryKeyData(table, newDataKey, &rowData, false)?;
         }
 
         Ok(CommandExecResult::DmlResult)
@@ -195,4 +202,46 @@ impl<'session> CommandExecutor<'session> {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::meta;
+    use crate::session::Session;
+
+    #[test]
+    pub fn testUpdateWithCase() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table caseUser");
+        session.executeSql("create table caseUser (id integer, score integer, level string)").unwrap();
+        session.executeSql("create index caseUserLevel on caseUser[level]").unwrap();
+        session.executeSql("insert into caseUser values (1, 95, null)").unwrap();
+        session.executeSql("insert into caseUser values (2, 70, null)").unwrap();
+        session.executeSql("insert into caseUser values (3, 10, null)").unwrap();
+
+        // 1趟update 各行分别计算
+        session.executeSql("update caseUser[level = case when score > 90 then 'gold' when score > 60 then 'silver' else 'none' end](id > 0)").unwrap();
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        assert_eq!(rows("select caseUser[id, level] order by id"),
+                   vec![json!({"id": 1, "level": "gold"}), json!({"id": 2, "level": "silver"}), json!({"id": 3, "level": "none"})]);
+
+        assert_eq!(rows("select caseUser[id, case score when 10 then 'ten' else 'other' end as c](id = 3)"), vec![json!({"id": 3, "c": "ten"})]);
+
+        // case不能使用index 和它and的可以
+        let explain = rows("explain select caseUser[id](level = 'gold' and case when score > 90 then true else false end)");
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("index"));
+        assert_eq!(rows("select caseUser[id](level = 'gold' and case when score > 90 then true else false end)"), vec![json!({"id": 1})]);
+
+        let explain = rows("explain select caseUser[id](level = case when id > 0 then 'gold' else 'none' end)");
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("scan"));
+        assert_eq!(rows("select caseUser[id](level = case when id > 0 then 'gold' else 'none' end)"), vec![json!({"id": 1})]);
+
+        session.executeSql("drop table caseUser").unwrap();
+    }
+}
@@ -5,9 +5,11 @@ use anyhow::Result;
 use futures_util::SinkExt;
 use serde::{Deserialize, Serialize};
 use crate::graph_value::GraphValue;
+use crate::parser::command;
+use crate::parser::command::select::Select;
 use crate::parser::element::Element;
 use crate::parser::op::{LogicalOp, MathCmpOp, Op, SqlOp};
-use crate::throw;
+use crate::{function, throw, throwFormat};
 use crate::types::RowData;
 use crate::utils::HashMapExt;
 
@@ -20,6 +22,19 @@ pub enum Expr {
         op: Op,
         rightExprs: Vec<Box<Expr>>,
     },
+    /// (select ...) 不相关的子查询 执行前会先跑1趟替换成它的结果
+    Subquery(Box<Select>),
+    /// lower(name) 标量函数 functionName是小写的
+    Function {
+        functionName: String,
+        args: Vec<Box<Expr>>,
+    },
+    /// case [operand] when .. then .. else .. end 有operand的话是和when的值比较是不是相等
+    Case {
+        operand: Option<Box<Expr>>,
+        whenThens: Vec<(Expr, Expr)>,
+        elseExpr: Option<Box<Expr>>,
+    },
     None,
 }
 
@@ -38,7 +53,10 @@ impl Expr {
                 match graphValue {
                     GraphValue::Pending(ref columnName) => {
                         if let Some(rowData) = rowData {
-                            Ok(rowData.get(columnName).unwrap().clone())
+                            match rowData.get(columnName) {
+                                Some(value) => Ok(value.clone()),
+                                None => throwFormat!("not have column:{}", columnName),
+                            }
                         } else {
                             throw!("need actual row data to get actual value")
                         }
@@ -49,14 +67,53 @@ impl Expr {
             Expr::BiDirection { leftExpr, op, rightExprs } => {
                 let leftValue = leftExpr.calc(rowData)?;
 
-                if rightExprs.is_empty() {
+                if rightExprs.is_empty() && op.isUnary() == false {
                     throw!("has no right values");
                 }
 
-                let rightValues: Vec<GraphValue> = rightExprs.iter().map(|rightExpr| { rightExpr.calc(rowData).unwrap() }).collect();
+                let rightValues: Vec<GraphValue> = rightExprs.iter().map(|rightExpr| rightExpr.calc(rowData)).collect::<Result<Vec<_>>>()?;
 
                 leftValue.calc(op.clone(), &rightValues)
             }
+            Expr::Subquery(_) => throw!("subquery should be folded before calc"),
+            Expr::Function { functionName, args } => {
+                let mut argValues = Vec::with_capacity(args.len());
+                for arg in args {
+                    argValues.push(arg.calc(rowData)?);
+                }
+
+                function::getScalarFunction(functionName)?.call(&argValues)
+            }
+            Expr::Case { operand, whenThens, elseExpr } => {
+                let operandValue =
+                    match operand {
+                        Some(operand) => Some(operand.calc(rowData)?),
+                        None => None,
+                    };
+
+                for (when, then) in whenThens {
+                    let whenValue = when.calc(rowData)?;
+
+                    let satisfied =
+                        match (&operandValue, &whenValue) {
+                            // null 和什么都不相等
+                            (Some(GraphValue::Null), _) | (Some(_), GraphValue::Null) => false,
+                            (Some(operandValue), _) => operandValue.calcOneToOne(Op::MathCmpOp(MathCmpOp::Equal), &whenValue)?.asBoolean()?,
+                            (None, GraphValue::Boolean(satisfied)) => *satisfied,
+                            (None, GraphValue::Null) => false,
+                            (None, _) => throwFormat!("case when should get a boolean, but get {whenValue:?}"),
+                        };
+
+                    if satisfied {
+                        return then.calc(rowData);
+                    }
+                }
+
+                match elseExpr {
+                    Some(elseExpr) => elseExpr.calc(rowData),
+                    None => Ok(GraphValue::Null),
+                }
+            }
             Expr::None => panic!("impossible"),
         }
     }
@@ -78,11 +135,32 @@ impl Expr {
 
                 Ok(value)
             }
+            // not (...) 内部的取反后没有办法给index用 和 a != 1 相同对待
+            Expr::BiDirection { leftExpr, op: op @ Op::LogicalOp(LogicalOp::Not), .. } => {
+                let mut columnNameExistInNot = false;
+
+                let leftValue =
+                    leftExpr.collectIndexableColNameOpValue(&mut HashMap::new(),
+                                                            &mut true,
+                                                            &mut true,
+                                                            &mut false,
+                                                            &mut columnNameExistInNot)?;
+
+                if columnNameExistInNot {
+                    *columnNameExist = true;
+                    *hasExprAbandonedByIndex = true;
+
+                    return Ok(GraphValue::IndexUseless);
+                }
+
+                // 内部都是常量的 能当场计算
+                leftValue.calc(*op, &[])
+            }
             Expr::BiDirection { leftExpr, op, rightExprs } => {
                 let leftValue = leftExpr.collectIndexableColNameOpValue(columnName_opValuesVec, isPureAnd, isPureOr, hasExprAbandonedByIndex, columnNameExist)?;
 
                 let rightValues: Vec<GraphValue> =
-                    rightExprs.iter().map(|rightExpr| { rightExpr.collectIndexableColNameOpValue(columnName_opValuesVec, isPureAnd, isPureOr, hasExprAbandonedByIndex, columnNameExist).unwrap() }).collect();
+                    rightExprs.iter().map(|rightExpr| rightExpr.collectIndexableColNameOpValue(columnName_opValuesVec, isPureAnd, isPureOr, hasExprAbandonedByIndex, columnNameExist)).collect::<Result<Vec<_>>>()?;
 
                 // 如何得到 columnName + 1 这样的index不能使用的情况
                 // val + (number >1 )
@@ -132,6 +210,19 @@ impl Expr {
 
                 Ok(graphValueIndex)
             }
+            Expr::Subquery(_) => throw!("subquery should be folded before calc"),
+            // lower(name) = 'a' 和 a + 1 = 2 相同 用不了index
+            Expr::Function { .. } | Expr::Case { .. } => {
+                if self.needAcutalRowData() {
+                    *columnNameExist = true;
+                    *hasExprAbandonedByIndex = true;
+
+                    return Ok(GraphValue::IndexUseless);
+                }
+
+                // 参数都是常量的 当场计算
+                self.calc(None)
+            }
             Expr::None => panic!("impossible"),
         }
     }
@@ -160,6 +251,10 @@ impl Expr {
 
                 false
             }
+            // 是不相关的 和rowData无关
+            Expr::Subquery(_) => false,
+            Expr::Function { args, .. } => args.iter().any(|arg| arg.needAcutalRowData()),
+            Expr::Case { .. } => self.caseParts().iter().any(|part| part.needAcutalRowData()),
             Expr::None => panic!("impossilble")
         }
     }
@@ -182,9 +277,101 @@ impl Expr {
 
                 Ok(())
             }
+            Expr::Subquery(_) => Ok(()),
+            Expr::Function { args, .. } => {
+                for arg in args {
+                    Self::extractColumnNames(&**arg, dest)?;
+                }
+
+                Ok(())
+            }
+            Expr::Case { .. } => {
+                for part in self.caseParts() {
+                    Self::extractColumnNames(part, dest)?;
+                }
+
+                Ok(())
+            }
             Expr::None => panic!("impossible")
         }
     }
+
+    /// prepared statement 执行时把placeholder替换成实际参数 参数只会成为字面量不会再经过解析
+    pub fn bindParams(&mut self, params: &[GraphValue]) -> Result<()> {
+        match self {
+            Expr::Single(Element::Placeholder(index)) => {
+                let param = match params.get(*index) {
+                    Some(param) => param,
+                    None => throwFormat!("parameter ${} is not bound", *index + 1),
+                };
+
+                *self = Expr::Single(Element::try_from(param)?);
+
+                Ok(())
+            }
+            Expr::Single(_) | Expr::None => Ok(()),
+            // 子查询里边的也要
+            Expr::Subquery(select) => command::visitSelectExprs(select, &mut |expr| expr.bindParams(params)),
+            Expr::BiDirection { leftExpr, rightExprs, .. } => {
+                leftExpr.bindParams(params)?;
+
+                for rightExpr in rightExprs {
+                    rightExpr.bindParams(params)?;
+                }
+
+                Ok(())
+            }
+            Expr::Function { args, .. } => {
+                for arg in args {
+                    arg.bindParams(params)?;
+                }
+
+                Ok(())
+            }
+            Expr::Case { .. } => {
+                for part in self.casePartsMut() {
+                    part.bindParams(params)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    /// case的各个组成部分 operand when then else
+    pub fn caseParts(&self) -> Vec<&Expr> {
+        let mut parts = Vec::new();
+
+        if let Expr::Case { operand, whenThens, elseExpr } = self {
+            parts.extend(operand.as_deref());
+
+            for (when, then) in whenThens {
+                parts.push(when);
+                parts.push(then);
+            }
+
+            parts.extend(elseExpr.as_deref());
+        }
+
+        parts
+    }
+
+    pub fn casePartsMut(&mut self) -> Vec<&mut Expr> {
+        let mut parts = Vec::new();
+
+        if let Expr::Case { operand, whenThens, elseExpr } = self {
+            parts.extend(operand.as_deref_mut());
+
+            for (when, then) in whenThens {
+                parts.push(when);
+                parts.push(then);
+            }
+
+            parts.extend(elseExpr.as_deref_mut());
+        }
+
+        parts
+    }
 }
 
 
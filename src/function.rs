@@ -0,0 +1,655 @@
+use hashbrown::HashMap;
+use lazy_static::lazy_static;
+use crate::graph_value::GraphValue;
+use crate::meta::ColumnType;
+use crate::parser::op::{MathCmpOp, Op};
+use crate::{decimal, geo, json, list, temporal, throw, throwFormat, vector};
+use crate::vector::DistanceMetric;
+use anyhow::Result;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use data_encoding::HEXLOWER;
+use serde_json::Value;
+
+pub type ScalarFunctionFn = fn(&[GraphValue]) -> Result<GraphValue>;
+
+/// 可以在filter projection update的set insert的values里边使用的标量函数 <br>
+/// 要新增的话只需要在SCALAR_FUNCTIONS里注册 parser是以名字来找的
+pub struct ScalarFunction {
+    pub name: &'static str,
+    pub minArgCount: usize,
+    /// None的话是不限的
+    pub maxArgCount: Option<usize>,
+    /// 参数有null的话不用调用直接得到null
+    pub nullInNullOut: bool,
+    pub function: ScalarFunctionFn,
+}
+
+impl ScalarFunction {
+    pub fn checkArgCount(&self, argCount: usize) -> Result<()> {
+        if argCount < self.minArgCount || self.maxArgCount.map_or(false, |maxArgCount| argCount > maxArgCount) {
+            match self.maxArgCount {
+                Some(maxArgCount) if maxArgCount == self.minArgCount => {
+                    throwFormat!("function {} needs {} args, but get {}", self.name, maxArgCount, argCount)
+                }
+                Some(maxArgCount) => {
+                    throwFormat!("function {} needs {} to {} args, but get {}", self.name, self.minArgCount, maxArgCount, argCount)
+                }
+                None => throwFormat!("function {} needs at least {} args, but get {}", self.name, self.minArgCount, argCount),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn call(&self, args: &[GraphValue]) -> Result<GraphValue> {
+        self.checkArgCount(args.len())?;
+
+        if self.nullInNullOut {
+            if args.iter().any(|arg| matches!(arg, GraphValue::Null)) {
+                return Ok(GraphValue::Null);
+            }
+        }
+
+        (self.function)(args)
+    }
+}
+
+lazy_static! {
+    static ref SCALAR_FUNCTIONS: HashMap<&'static str, ScalarFunction> = {
+        let scalarFunctions = [
+            ScalarFunction { name: "lower", minArgCount: 1, maxArgCount: Some(1), nullInNullOut: true, function: lower },
+            ScalarFunction { name: "upper", minArgCount: 1, maxArgCount: Some(1), nullInNullOut: true, function: upper },
+            ScalarFunction { name: "length", minArgCount: 1, maxArgCount: Some(1), nullInNullOut: true, function: length },
+            ScalarFunction { name: "substr", minArgCount: 2, maxArgCount: Some(3), nullInNullOut: true, function: substr },
+            ScalarFunction { name: "trim", minArgCount: 1, maxArgCount: Some(1), nullInNullOut: true, function: trim },
+            ScalarFunction { name: "concat", minArgCount: 1, maxArgCount: None, nullInNullOut: true, function: concat },
+            ScalarFunction { name: "abs", minArgCount: 1, maxArgCount: Some(1), nullInNullOut: true, function: abs },
+            ScalarFunction { name: "round", minArgCount: 1, maxArgCount: Some(2), nullInNullOut: true, function: round },
+            ScalarFunction { name: "floor", minArgCount: 1, maxArgCount: Some(1), nullInNullOut: true, function: floor },
+            ScalarFunction { name: "ceil", minArgCount: 1, maxArgCount: Some(1), nullInNullOut: true, function: ceil },
+            ScalarFunction { name: "coalesce", minArgCount: 1, maxArgCount: None, nullInNullOut: false, function: coalesce },
+            ScalarFunction { name: "nullif", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: false, function: nullif },
+            // cast(x as integer) 的 as integer 解析后成为第2个string参数
+            ScalarFunction { name: "cast", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: cast },
+            ScalarFunction { name: "now", minArgCount: 0, maxArgCount: Some(0), nullInNullOut: false, function: now },
+            ScalarFunction { name: "date_trunc", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: dateTrunc },
+            // profile->'address' profile->>'tier' 解析后分别成为 json_get json_get_text
+            ScalarFunction { name: "json_get", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: jsonGet },
+            ScalarFunction { name: "json_get_text", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: jsonGetText },
+            // order by cosine(embedding, $q) limit 10 可以使用ann index
+            ScalarFunction { name: "cosine", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: cosine },
+            ScalarFunction { name: "l2", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: l2 },
+            ScalarFunction { name: "dot", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: dot },
+            // ['rust', 'db'] 解析后成为 list
+            ScalarFunction { name: "list", minArgCount: 0, maxArgCount: None, nullInNullOut: false, function: list },
+            // 在projection的最外层的话 list的各个元素各自成为1行
+            ScalarFunction { name: "unnest", minArgCount: 1, maxArgCount: Some(1), nullInNullOut: true, function: unnest },
+            // point(纬度, 经度)
+            ScalarFunction { name: "point", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: point },
+            // 单位是米 within_radius within_box 和 distance < 常量 可以使用spatial index
+            ScalarFunction { name: "distance", minArgCount: 2, maxArgCount: Some(2), nullInNullOut: true, function: geoDistance },
+            ScalarFunction { name: "within_radius", minArgCount: 3, maxArgCount: Some(3), nullInNullOut: true, function: withinRadius },
+            // within_box(location, 西南角, 东北角)
+            ScalarFunction { name: "within_box", minArgCount: 3, maxArgCount: Some(3), nullInNullOut: true, function: withinBox },
+        ];
+
+        let mut name_scalarFunction = HashMap::with_capacity(scalarFunctions.len());
+
+        for scalarFunction in scalarFunctions {
+            name_scalarFunction.insert(scalarFunction.name, scalarFunction);
+        }
+
+        name_scalarFunction
+    };
+}
+
+/// 函数名不区分大小写
+pub fn getScalarFunction(functionName: &str) -> Result<&'static ScalarFunction> {
+    match SCALAR_FUNCTIONS.get(functionName.to_lowercase().as_str()) {
+        Some(scalarFunction) => Ok(scalarFunction),
+        None => throwFormat!("unknown function:{}", functionName),
+    }
+}
+
+fn stringArg<'a>(args: &'a [GraphValue], index: usize) -> Result<&'a str> {
+    match &args[index] {
+        GraphValue::String(s) => Ok(s.as_str()),
+        arg => throwFormat!("arg:{arg:?} should be string"),
+    }
+}
+
+fn integerArg(args: &[GraphValue], index: usize) -> Result<i64> {
+    match &args[index] {
+        GraphValue::Integer(integer) => Ok(*integer),
+        arg => throwFormat!("arg:{arg:?} should be integer"),
+    }
+}
+
+/// concat 和 cast(x as string) 使用
+fn toText(value: &GraphValue) -> Result<String> {
+    match value {
+        GraphValue::String(s) => Ok(s.clone()),
+        GraphValue::Integer(integer) => Ok(integer.to_string()),
+        GraphValue::Decimal(decimal) => Ok(decimal.to_string()),
+        GraphValue::Double(double) => Ok(double.to_string()),
+        GraphValue::Boolean(bool) => Ok(bool.to_string()),
+        GraphValue::Timestamp(timestamp) => Ok(temporal::formatTimestamp(*timestamp)),
+        GraphValue::Date(date) => Ok(temporal::formatDate(*date)),
+        GraphValue::Interval(interval) => Ok(interval.to_string()),
+        // 小写的hex
+        GraphValue::Bytes(bytes) => Ok(HEXLOWER.encode(bytes)),
+        GraphValue::Json(json) => Ok(json.to_string()),
+        GraphValue::Vector(vector) => Ok(vector::format(vector)),
+        GraphValue::List(list) => Ok(list::format(list)),
+        GraphValue::Point(point) => Ok(geo::format(point)),
+        _ => throwFormat!("{value:?} can not be transform to string"),
+    }
+}
+
+fn lower(args: &[GraphValue]) -> Result<GraphValue> {
+    Ok(GraphValue::String(stringArg(args, 0)?.to_lowercase()))
+}
+
+fn upper(args: &[GraphValue]) -> Result<GraphValue> {
+    Ok(GraphValue::String(stringArg(args, 0)?.to_uppercase()))
+}
+
+/// string是字符的数量 不是byte的数量, bytes是byte的数量, vector是维度, list是元素的数量
+fn length(args: &[GraphValue]) -> Result<GraphValue> {
+    match &args[0] {
+        GraphValue::Bytes(bytes) => return Ok(GraphValue::Integer(bytes.len() as i64)),
+        GraphValue::Vector(vector) => return Ok(GraphValue::Integer(vector.len() as i64)),
+        GraphValue::List(list) => return Ok(GraphValue::Integer(list.len() as i64)),
+        _ => {}
+    }
+
+    Ok(GraphValue::Integer(stringArg(args, 0)?.chars().count() as i64))
+}
+
+/// substr(s, start, len) start以1打头
+fn substr(args: &[GraphValue]) -> Result<GraphValue> {
+    let chars: Vec<char> = stringArg(args, 0)?.chars().collect();
+    let charCount = chars.len() as i64;
+
+    let begin = integerArg(args, 1)? - 1;
+
+    let end =
+        match args.get(2) {
+            Some(_) => {
+                let len = integerArg(args, 2)?;
+                if len < 0 {
+                    throw!("substr length can not be negative");
+                }
+
+                begin.saturating_add(len)
+            }
+            None => charCount,
+        };
+
+    let begin = begin.clamp(0, charCount) as usize;
+    let end = end.clamp(begin as i64, charCount) as usize;
+
+    Ok(GraphValue::String(chars[begin..end].iter().collect()))
+}
+
+fn trim(args: &[GraphValue]) -> Result<GraphValue> {
+    Ok(GraphValue::String(stringArg(args, 0)?.trim().to_string()))
+}
+
+fn concat(args: &[GraphValue]) -> Result<GraphValue> {
+    let mut s = String::new();
+
+    for arg in args {
+        s.push_str(&toText(arg)?);
+    }
+
+    Ok(GraphValue::String(s))
+}
+
+fn abs(args: &[GraphValue]) -> Result<GraphValue> {
+    match &args[0] {
+        GraphValue::Integer(integer) => {
+            match integer.checked_abs() {
+                Some(integer) => Ok(GraphValue::Integer(integer)),
+                None => throwFormat!("abs of {} overflow", integer),
+            }
+        }
+        GraphValue::Decimal(decimal) => Ok(GraphValue::Decimal(decimal.abs())),
+        GraphValue::Double(double) => Ok(GraphValue::Double(double.abs())),
+        arg => throwFormat!("abs can not be used on {arg:?}"),
+    }
+}
+
+/// round(x, 小数位数) 小数位数默认是0 可以是负数 decimal是四舍五入
+fn round(args: &[GraphValue]) -> Result<GraphValue> {
+    let digits = match args.get(1) {
+        Some(_) => integerArg(args, 1)?,
+        None => 0,
+    };
+
+    match &args[0] {
+        GraphValue::Integer(integer) => Ok(GraphValue::Integer(*integer)),
+        GraphValue::Decimal(decimal) => {
+            if digits >= 0 {
+                let digits = digits.min(decimal::MAX_PRECISION as i64) as u32;
+                return Ok(GraphValue::Decimal(decimal.round_dp_with_strategy(digits, RoundingStrategy::MidpointAwayFromZero)));
+            }
+
+            // round(1250, -2) 得到 1300
+            let factor = Decimal::from_i128_with_scale(10i128.pow(digits.unsigned_abs().min(decimal::MAX_PRECISION as u64) as u32), 0);
+            let rounded = (decimal / factor).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+
+            match rounded.checked_mul(factor) {
+                Some(rounded) => Ok(GraphValue::Decimal(rounded)),
+                None => throwFormat!("round of {} overflow", decimal),
+            }
+        }
+        GraphValue::Double(decimal) => {
+            let factor = 10f64.powi(digits.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
+            Ok(GraphValue::Double((decimal * factor).round() / factor))
+        }
+        arg => throwFormat!("round can not be used on {arg:?}"),
+    }
+}
+
+fn floor(args: &[GraphValue]) -> Result<GraphValue> {
+    match &args[0] {
+        GraphValue::Integer(integer) => Ok(GraphValue::Integer(*integer)),
+        GraphValue::Decimal(decimal) => Ok(GraphValue::Decimal(decimal.floor())),
+        GraphValue::Double(double) => Ok(GraphValue::Double(double.floor())),
+        arg => throwFormat!("floor can not be used on {arg:?}"),
+    }
+}
+
+fn ceil(args: &[GraphValue]) -> Result<GraphValue> {
+    match &args[0] {
+        GraphValue::Integer(integer) => Ok(GraphValue::Integer(*integer)),
+        GraphValue::Decimal(decimal) => Ok(GraphValue::Decimal(decimal.ceil())),
+        GraphValue::Double(double) => Ok(GraphValue::Double(double.ceil())),
+        arg => throwFormat!("ceil can not be used on {arg:?}"),
+    }
+}
+
+/// 第1个不是null的
+fn coalesce(args: &[GraphValue]) -> Result<GraphValue> {
+    for arg in args {
+        if let GraphValue::Null = arg {
+            continue;
+        }
+
+        return Ok(arg.clone());
+    }
+
+    Ok(GraphValue::Null)
+}
+
+/// 两者相等的话是null 不然是第1个
+fn nullif(args: &[GraphValue]) -> Result<GraphValue> {
+    if let (GraphValue::Null, _) | (_, GraphValue::Null) = (&args[0], &args[1]) {
+        return Ok(args[0].clone());
+    }
+
+    if args[0].calcOneToOne(Op::MathCmpOp(MathCmpOp::Equal), &args[1])?.asBoolean()? {
+        Ok(GraphValue::Null)
+    } else {
+        Ok(args[0].clone())
+    }
+}
+
+/// 当前的UTC时间 同1个sql里边的各处调用得到的可能不同
+fn now(_: &[GraphValue]) -> Result<GraphValue> {
+    Ok(GraphValue::Timestamp(temporal::now()))
+}
+
+/// date_trunc('day', since) 精度是 microsecond到year, date 得到的还是date
+fn dateTrunc(args: &[GraphValue]) -> Result<GraphValue> {
+    let unit = stringArg(args, 0)?;
+
+    match &args[1] {
+        GraphValue::Timestamp(timestamp) => Ok(GraphValue::Timestamp(temporal::truncateTimestamp(unit, *timestamp)?)),
+        GraphValue::Date(date) => Ok(GraphValue::Date(temporal::truncateDate(unit, *date)?)),
+        arg => throwFormat!("arg:{arg:?} should be timestamp or date"),
+    }
+}
+
+fn jsonArg(args: &[GraphValue], index: usize) -> Result<&Value> {
+    match &args[index] {
+        GraphValue::Json(json) => Ok(json),
+        arg => throwFormat!("arg:{arg:?} should be json"),
+    }
+}
+
+/// 字段不存在的话是null
+fn jsonGet(args: &[GraphValue]) -> Result<GraphValue> {
+    match json::get(jsonArg(args, 0)?, &args[1])? {
+        Some(json) => Ok(GraphValue::Json(json.clone())),
+        None => Ok(GraphValue::Null),
+    }
+}
+
+fn jsonGetText(args: &[GraphValue]) -> Result<GraphValue> {
+    match json::get(jsonArg(args, 0)?, &args[1])? {
+        Some(json) => Ok(json::toGraphValueText(json)),
+        None => Ok(GraphValue::Null),
+    }
+}
+
+fn distance(distanceMetric: DistanceMetric, args: &[GraphValue]) -> Result<GraphValue> {
+    let vector = vector::fromGraphValue(&args[0])?;
+    let vector0 = vector::fromGraphValue(&args[1])?;
+
+    match distanceMetric.distance(&vector, &vector0)? {
+        Some(distance) => Ok(GraphValue::Double(distance)),
+        None => Ok(GraphValue::Null),
+    }
+}
+
+/// 1 - cosine similarity, 有长度是0的vector的话是null
+fn cosine(args: &[GraphValue]) -> Result<GraphValue> {
+    distance(DistanceMetric::Cosine, args)
+}
+
+fn l2(args: &[GraphValue]) -> Result<GraphValue> {
+    distance(DistanceMetric::L2, args)
+}
+
+/// 内积的相反数 和别的distance相同 越小越相似
+fn dot(args: &[GraphValue]) -> Result<GraphValue> {
+    distance(DistanceMetric::Dot, args)
+}
+
+fn list(args: &[GraphValue]) -> Result<GraphValue> {
+    Ok(GraphValue::List(args.to_vec()))
+}
+
+/// 展开成多行是projection那边做的 这里只是校验
+fn unnest(args: &[GraphValue]) -> Result<GraphValue> {
+    match &args[0] {
+        GraphValue::List(_) => Ok(args[0].clone()),
+        arg => throwFormat!("unnest arg:{arg:?} should be list"),
+    }
+}
+
+fn numberArg(args: &[GraphValue], index: usize) -> Result<f64> {
+    match &args[index] {
+        GraphValue::Integer(integer) => Ok(*integer as f64),
+        GraphValue::Double(double) => Ok(*double),
+        GraphValue::Decimal(decimal) => Ok(decimal::toF64(decimal)),
+        arg => throwFormat!("arg:{arg:?} should be number"),
+    }
+}
+
+fn point(args: &[GraphValue]) -> Result<GraphValue> {
+    Ok(GraphValue::Point(geo::Point::new(numberArg(args, 0)?, numberArg(args, 1)?)?))
+}
+
+fn geoDistance(args: &[GraphValue]) -> Result<GraphValue> {
+    Ok(GraphValue::Double(geo::distance(&geo::fromGraphValue(&args[0])?, &geo::fromGraphValue(&args[1])?)))
+}
+
+fn withinRadius(args: &[GraphValue]) -> Result<GraphValue> {
+    let meters = numberArg(args, 2)?;
+    if meters.is_finite() == false || meters < 0.0 {
+        throwFormat!("radius should be non negative meters, but get {}", meters);
+    }
+
+    Ok(GraphValue::Boolean(geo::distance(&geo::fromGraphValue(&args[0])?, &geo::fromGraphValue(&args[1])?) <= meters))
+}
+
+fn withinBox(args: &[GraphValue]) -> Result<GraphValue> {
+    let southWest = geo::fromGraphValue(&args[1])?;
+    let northEast = geo::fromGraphValue(&args[2])?;
+
+    Ok(GraphValue::Boolean(geo::withinBox(&geo::fromGraphValue(&args[0])?, &southWest, &northEast)))
+}
+
+/// cast(x as integer|decimal|decimal(p, s)|double|string|boolean|timestamp|date|bytes|json|vector(n)|list<T>|point) <br>
+/// string 变为 bytes 是utf8, bytes 变为 string 是hex
+fn cast(args: &[GraphValue]) -> Result<GraphValue> {
+    let targetType: ColumnType = stringArg(args, 1)?.parse()?;
+
+    let value = &args[0];
+
+    match targetType {
+        ColumnType::Integer => {
+            match value {
+                GraphValue::Integer(integer) => Ok(GraphValue::Integer(*integer)),
+                GraphValue::Decimal(decimal) => {
+                    match decimal.trunc().to_i64() {
+                        Some(integer) => Ok(GraphValue::Integer(integer)),
+                        None => throwFormat!("can not cast {} to integer", decimal),
+                    }
+                }
+                GraphValue::Double(decimal) => {
+                    if decimal.is_finite() == false || *decimal < i64::MIN as f64 || *decimal >= i64::MAX as f64 {
+                        throwFormat!("can not cast {} to integer", decimal);
+                    }
+
+                    Ok(GraphValue::Integer(decimal.trunc() as i64))
+                }
+                GraphValue::Boolean(bool) => Ok(GraphValue::Integer(*bool as i64)),
+                GraphValue::String(s) => {
+                    match s.trim().parse::<i64>() {
+                        Ok(integer) => Ok(GraphValue::Integer(integer)),
+                        Err(_) => throwFormat!("can not cast '{}' to integer", s),
+                    }
+                }
+                _ => throwFormat!("can not cast {value:?} to integer"),
+            }
+        }
+        ColumnType::Decimal(precisionScale) => {
+            let decimal =
+                match value {
+                    GraphValue::Integer(integer) => Decimal::from(*integer),
+                    GraphValue::Decimal(decimal) => *decimal,
+                    GraphValue::Double(double) => decimal::fromF64(*double)?,
+                    GraphValue::Boolean(bool) => Decimal::from(*bool as i64),
+                    GraphValue::String(s) => {
+                        match decimal::parse(s) {
+                            Ok(decimal) => decimal,
+                            Err(_) => throwFormat!("can not cast '{}' to decimal", s),
+                        }
+                    }
+                    _ => throwFormat!("can not cast {value:?} to decimal"),
+                };
+
+            Ok(GraphValue::Decimal(decimal::fit(decimal, precisionScale)?))
+        }
+        ColumnType::Double => {
+            match value {
+                GraphValue::Integer(integer) => Ok(GraphValue::Double(*integer as f64)),
+                GraphValue::Decimal(decimal) => Ok(GraphValue::Double(decimal::toF64(decimal))),
+                GraphValue::Double(double) => Ok(GraphValue::Double(*double)),
+                GraphValue::Boolean(bool) => Ok(GraphValue::Double(*bool as i64 as f64)),
+                GraphValue::String(s) => {
+                    match s.trim().parse::<f64>() {
+                        Ok(double) => Ok(GraphValue::Double(double)),
+                        Err(_) => throwFormat!("can not cast '{}' to double", s),
+                    }
+                }
+                _ => throwFormat!("can not cast {value:?} to double"),
+            }
+        }
+        ColumnType::String => Ok(GraphValue::String(toText(value)?)),
+        ColumnType::Boolean => {
+            match value {
+                GraphValue::Boolean(bool) => Ok(GraphValue::Boolean(*bool)),
+                GraphValue::Integer(integer) => Ok(GraphValue::Boolean(*integer != 0)),
+                GraphValue::String(s) => {
+                    match s.trim().to_lowercase().as_str() {
+                        "true" => Ok(GraphValue::Boolean(true)),
+                        "false" => Ok(GraphValue::Boolean(false)),
+                        _ => throwFormat!("can not cast '{}' to boolean", s),
+                    }
+                }
+                _ => throwFormat!("can not cast {value:?} to boolean"),
+            }
+        }
+        ColumnType::Timestamp => {
+            match value {
+                GraphValue::Timestamp(_) | GraphValue::Date(_) => Ok(GraphValue::Timestamp(value.temporalMicros().unwrap())),
+                GraphValue::String(s) => Ok(GraphValue::Timestamp(temporal::parseTimestamp(s)?)),
+                _ => throwFormat!("can not cast {value:?} to timestamp"),
+            }
+        }
+        ColumnType::Date => {
+            match value {
+                GraphValue::Timestamp(timestamp) => Ok(GraphValue::Date(timestamp.div_euclid(temporal::MICROS_PER_DAY))),
+                GraphValue::Date(date) => Ok(GraphValue::Date(*date)),
+                GraphValue::String(s) => Ok(GraphValue::Date(temporal::parseDate(s)?)),
+                _ => throwFormat!("can not cast {value:?} to date"),
+            }
+        }
+        ColumnType::Bytes => {
+            match value {
+                GraphValue::Bytes(bytes) => Ok(GraphValue::Bytes(bytes.clone())),
+                GraphValue::String(s) => Ok(GraphValue::Bytes(s.as_bytes().to_vec())),
+                _ => throwFormat!("can not cast {value:?} to bytes"),
+            }
+        }
+        ColumnType::Json => {
+            match value {
+                GraphValue::Json(json) => Ok(GraphValue::Json(json.clone())),
+                GraphValue::String(s) => Ok(GraphValue::Json(json::parse(s)?)),
+                _ => throwFormat!("can not cast {value:?} to json"),
+            }
+        }
+        ColumnType::Vector(_) => {
+            let mut value = GraphValue::Vector(vector::fromGraphValue(value)?);
+            targetType.fitValue(&mut value)?;
+
+            Ok(value)
+        }
+        ColumnType::List(_) => {
+            let mut value = GraphValue::List(list::fromGraphValue(value)?);
+            targetType.fitValue(&mut value)?;
+
+            Ok(value)
+        }
+        ColumnType::Point => Ok(GraphValue::Point(geo::fromGraphValue(value)?)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::{decimal, function, meta};
+    use crate::graph_value::GraphValue;
+    use crate::session::Session;
+
+    fn call(functionName: &str, args: &[GraphValue]) -> GraphValue {
+        function::getScalarFunction(functionName).unwrap().call(args).unwrap()
+    }
+
+    #[test]
+    pub fn testScalarFunctions() {
+        let s = |s: &str| GraphValue::String(s.to_string());
+
+        assert_eq!(call("LOWER", &[s("AbC")]), s("abc"));
+        assert_eq!(call("length", &[s("汉字ab")]), GraphValue::Integer(4));
+        assert_eq!(call("substr", &[s("abcdef"), GraphValue::Integer(2), GraphValue::Integer(3)]), s("bcd"));
+        assert_eq!(call("substr", &[s("abcdef"), GraphValue::Integer(5)]), s("ef"));
+        assert_eq!(call("substr", &[s("abc"), GraphValue::Integer(0), GraphValue::Integer(2)]), s("a"));
+        assert_eq!(call("concat", &[s("a"), GraphValue::Integer(1)]), s("a1"));
+        assert_eq!(call("round", &[GraphValue::Double(2.456), GraphValue::Integer(1)]), GraphValue::Double(2.5));
+        assert_eq!(call("ceil", &[GraphValue::Double(-1.5)]), GraphValue::Double(-1.0));
+        assert_eq!(call("coalesce", &[GraphValue::Null, GraphValue::Integer(7)]), GraphValue::Integer(7));
+        assert_eq!(call("nullif", &[GraphValue::Integer(7), GraphValue::Integer(7)]), GraphValue::Null);
+        assert_eq!(call("cast", &[s(" 12 "), s("integer")]), GraphValue::Integer(12));
+        assert_eq!(call("cast", &[GraphValue::Double(-2.7), s("integer")]), GraphValue::Integer(-2));
+        assert_eq!(call("upper", &[GraphValue::Null]), GraphValue::Null);
+
+        let decimal = |text: &str| GraphValue::Decimal(decimal::parse(text).unwrap());
+        assert!(matches!(call("round", &[decimal("2.345"), GraphValue::Integer(2)]), GraphValue::Decimal(d) if d.to_string() == "2.35"));
+        assert!(matches!(call("round", &[decimal("1250"), GraphValue::Integer(-2)]), GraphValue::Decimal(d) if d.to_string() == "1300"));
+        assert!(matches!(call("floor", &[decimal("-1.5")]), GraphValue::Decimal(d) if d.to_string() == "-2"));
+        assert!(matches!(call("cast", &[s("1.005"), s("decimal(5,2)")]), GraphValue::Decimal(d) if d.to_string() == "1.01"));
+        assert!(matches!(call("cast", &[decimal("0.1"), s("double")]), GraphValue::Double(f) if f == 0.1));
+        assert_eq!(call("cast", &[decimal("-2.7"), s("integer")]), GraphValue::Integer(-2));
+        assert_eq!(call("concat", &[decimal("1.50")]), s("1.50"));
+
+        let bytes = GraphValue::Bytes(vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(call("length", &[bytes.clone()]), GraphValue::Integer(4));
+        assert_eq!(call("cast", &[bytes.clone(), s("string")]), s("deadbeef"));
+        assert_eq!(call("cast", &[s("ab"), s("bytes")]), GraphValue::Bytes(vec![b'a', b'b']));
+
+        let profile = call("cast", &[s(r#"{"address":{"city":"hz"},"tags":["a","b"]}"#), s("json")]);
+        assert_eq!(call("json_get_text", &[call("json_get", &[profile.clone(), s("address")]), s("city")]), s("hz"));
+        assert_eq!(call("json_get_text", &[call("json_get", &[profile.clone(), s("tags")]), GraphValue::Integer(1)]), s("b"));
+        assert_eq!(call("json_get", &[profile.clone(), s("missing")]), GraphValue::Null);
+        assert_eq!(call("json_get", &[GraphValue::Null, s("tags")]), GraphValue::Null);
+        assert!(function::getScalarFunction("json_get").unwrap().call(&[s("{}"), s("a")]).is_err());
+
+        let embedding = call("cast", &[s("[3, 4]"), s("vector(2)")]);
+        assert_eq!(embedding, GraphValue::Vector(vec![3.0, 4.0]));
+        assert_eq!(call("length", &[embedding.clone()]), GraphValue::Integer(2));
+        assert_eq!(call("cast", &[embedding.clone(), s("string")]), s("[3,4]"));
+        assert_eq!(call("l2", &[embedding.clone(), s("[0, 0]")]), GraphValue::Double(5.0));
+        assert_eq!(call("dot", &[embedding.clone(), s("[1, 1]")]), GraphValue::Double(-7.0));
+        assert_eq!(call("cosine", &[embedding.clone(), s("[6, 8]")]), GraphValue::Double(0.0));
+        assert_eq!(call("cosine", &[embedding.clone(), s("[0, 0]")]), GraphValue::Null);
+        assert_eq!(call("l2", &[embedding.clone(), GraphValue::Null]), GraphValue::Null);
+        assert!(function::getScalarFunction("l2").unwrap().call(&[embedding.clone(), s("[1, 2, 3]")]).is_err());
+        assert!(function::getScalarFunction("cast").unwrap().call(&[s("[1]"), s("vector(2)")]).is_err());
+
+        let tags = call("list", &[s("rust"), s("db")]);
+        assert_eq!(tags, GraphValue::List(vec![s("rust"), s("db")]));
+        assert_eq!(call("length", &[tags.clone()]), GraphValue::Integer(2));
+        assert_eq!(call("cast", &[tags.clone(), s("string")]), s(r#"["rust","db"]"#));
+        assert_eq!(call("cast", &[s("[1, 2]"), s("list<double>")]), GraphValue::List(vec![GraphValue::Double(1.0), GraphValue::Double(2.0)]));
+        assert_eq!(call("unnest", &[tags.clone()]), tags);
+        assert_eq!(call("unnest", &[GraphValue::Null]), GraphValue::Null);
+        assert!(function::getScalarFunction("unnest").unwrap().call(&[s("rust")]).is_err());
+        assert!(function::getScalarFunction("cast").unwrap().call(&[s("[1, null]"), s("list<integer>")]).is_err());
+
+        let store = call("point", &[GraphValue::Double(30.2741), GraphValue::Double(120.1551)]);
+        assert_eq!(call("cast", &[s("[30.2741, 120.1551]"), s("point")]), store);
+        assert_eq!(call("cast", &[store.clone(), s("string")]), s(r#"{"lat":30.2741,"lon":120.1551}"#));
+        assert!(matches!(call("distance", &[store.clone(), s("[31.2304, 121.4737]")]), GraphValue::Double(meters) if meters > 160_000.0 && meters < 170_000.0));
+        assert_eq!(call("within_radius", &[store.clone(), s("[30.28, 120.16]"), GraphValue::Integer(5000)]), GraphValue::Boolean(true));
+        assert_eq!(call("within_radius", &[store.clone(), s("[31.2304, 121.4737]"), GraphValue::Integer(5000)]), GraphValue::Boolean(false));
+        assert_eq!(call("within_box", &[store.clone(), s("[30, 120]"), s("[31, 121]")]), GraphValue::Boolean(true));
+        assert_eq!(call("within_box", &[store.clone(), s("[31, 120]"), s("[32, 121]")]), GraphValue::Boolean(false));
+        assert_eq!(call("within_radius", &[GraphValue::Null, store.clone(), GraphValue::Integer(1)]), GraphValue::Null);
+        assert!(function::getScalarFunction("point").unwrap().call(&[GraphValue::Integer(91), GraphValue::Integer(0)]).is_err());
+        assert!(function::getScalarFunction("within_radius").unwrap().call(&[store.clone(), store.clone(), GraphValue::Integer(-1)]).is_err());
+
+        assert!(function::getScalarFunction("unknown").is_err());
+        assert!(function::getScalarFunction("abs").unwrap().call(&[]).is_err());
+        assert!(function::getScalarFunction("cast").unwrap().call(&[s("a"), s("integer")]).is_err());
+    }
+
+    #[test]
+    pub fn testScalarFunctionsInSql() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table fnUser");
+        session.executeSql("create table fnUser (id integer, name string, score integer)").unwrap();
+        session.executeSql("create index fnUserName on fnUser[name]").unwrap();
+
+        // insert的values
+        session.executeSql("insert into fnUser values (1, lower('ABC'), abs(0 - 95))").unwrap();
+        session.executeSql("insert into fnUser values (2, concat('d', 'ef'), cast(round(61.6) as integer))").unwrap();
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        assert_eq!(rows("select fnUser[id, upper(name) as n, length(name) as l, score] order by id"),
+                   vec![json!({"id": 1, "n": "ABC", "l": 3, "score": 95}), json!({"id": 2, "n": "DEF", "l": 3, "score": 62})]);
+
+        // 函数的参数都是字面量的话 能够使用index
+        let explain = rows("explain select fnUser[id](name = lower('ABC'))");
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("index"));
+        assert_eq!(rows("select fnUser[id](name = lower('ABC'))"), vec![json!({"id": 1})]);
+
+        assert_eq!(rows("select fnUser[id](substr(name, 1, 1) = 'd')"), vec![json!({"id": 2})]);
+
+        // update的set
+        session.executeSql("update fnUser[name = upper(name)](id = 1)").unwrap();
+        assert_eq!(session.executeSql("select fnUser[name](id = 1)").unwrap().remove(0), vec![json!({"name": "ABC"})]);
+
+        session.executeSql("drop table fnUser").unwrap();
+    }
+}
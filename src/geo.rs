@@ -0,0 +1,337 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::graph_value::GraphValue;
+use crate::throwFormat;
+use anyhow::Result;
+
+// point column 落地的是 纬度(lat) 经度(lon) 两个f64 <br>
+// spatial index 的key是 geohash(u64) + dataKey, geohash是纬度和经度的cell编号的bit交错得到的 前缀相同的便在同1个大的cell里 <br>
+// 查找的时候用若干个cell覆盖要找的范围 得到的只是候选 还要使用原来的filter过滤
+
+/// 平均的地球半径 单位是米
+pub const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// 纬度和经度各自26位 总共52位 在赤道上的cell大约是0.6米
+pub const GEOHASH_BIT_COUNT_PER_AXIS: u32 = 26;
+
+/// point是null的话geohash是它 不会落在任何cell的范围里
+pub const GEOHASH_NULL: u64 = u64::MAX;
+
+/// 覆盖查找范围的cell的数量的上限 越多的话候选越精确 不过seek的次数也越多
+const COVER_CELL_COUNT_LIMIT: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    /// 纬度 -90到90
+    pub lat: f64,
+    /// 经度 -180到180
+    pub lon: f64,
+}
+
+impl Point {
+    pub fn new(lat: f64, lon: f64) -> Result<Point> {
+        if lat.is_finite() == false || lat < -90.0 || lat > 90.0 {
+            throwFormat!("latitude should be -90 to 90, but get {}", lat);
+        }
+
+        if lon.is_finite() == false || lon < -180.0 || lon > 180.0 {
+            throwFormat!("longitude should be -180 to 180, but get {}", lon);
+        }
+
+        Ok(Point { lat, lon })
+    }
+}
+
+/// '{"lat": 30.27, "lon": 120.15}' 或 '[30.27, 120.15]' 都是纬度在前
+pub fn parse(text: &str) -> Result<Point> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(json) => fromJson(&json),
+        Err(_) => throwFormat!(r#"invalid point:'{}', should be like '{{"lat": 30.27, "lon": 120.15}}' or '[30.27, 120.15]'"#, text),
+    }
+}
+
+pub fn fromJson(json: &Value) -> Result<Point> {
+    let (lat, lon) =
+        match json {
+            Value::Array(array) if array.len() == 2 => (array[0].as_f64(), array[1].as_f64()),
+            Value::Object(object) if object.len() == 2 => (object.get("lat").and_then(Value::as_f64), object.get("lon").and_then(Value::as_f64)),
+            _ => (None, None),
+        };
+
+    match (lat, lon) {
+        (Some(lat), Some(lon)) => Point::new(lat, lon),
+        _ => throwFormat!(r#"invalid point:{}, should be {{"lat": number, "lon": number}} or [lat, lon]"#, json),
+    }
+}
+
+/// distance within_radius within_box 的参数 可以是point column, '[30.27, 120.15]', 或是json
+pub fn fromGraphValue(value: &GraphValue) -> Result<Point> {
+    match value {
+        GraphValue::Point(point) => Ok(*point),
+        GraphValue::String(s) => parse(s),
+        GraphValue::Json(json) => fromJson(json),
+        _ => throwFormat!("{value:?} can not be used as point"),
+    }
+}
+
+/// {"lat":30.27,"lon":120.15}
+pub fn format(point: &Point) -> String {
+    format!(r#"{{"lat":{},"lon":{}}}"#, point.lat, point.lon)
+}
+
+/// 球面上的大圆距离(haversine) 单位是米
+pub fn distance(point: &Point, point0: &Point) -> f64 {
+    let latDelta = (point0.lat - point.lat).to_radians();
+    let lonDelta = (point0.lon - point.lon).to_radians();
+
+    let a = (latDelta / 2.0).sin().powi(2) + point.lat.to_radians().cos() * point0.lat.to_radians().cos() * (lonDelta / 2.0).sin().powi(2);
+
+    // 浮点的误差会使得a略微超出1
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().min(1.0).asin()
+}
+
+/// southWest的经度比northEast大的话说明跨越了180经线
+pub fn withinBox(point: &Point, southWest: &Point, northEast: &Point) -> bool {
+    if point.lat < southWest.lat || point.lat > northEast.lat {
+        return false;
+    }
+
+    if southWest.lon <= northEast.lon {
+        point.lon >= southWest.lon && point.lon <= northEast.lon
+    } else {
+        point.lon >= southWest.lon || point.lon <= northEast.lon
+    }
+}
+
+/// 经度上不会跨越180经线
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub minLat: f64,
+    pub maxLat: f64,
+    pub minLon: f64,
+    pub maxLon: f64,
+}
+
+/// 包含了圆形范围的矩形 跨越180经线的话拆成2个
+pub fn radiusBoundingBoxes(center: &Point, meters: f64) -> Vec<BoundingBox> {
+    let angularRadius = meters / EARTH_RADIUS_METERS;
+
+    let latDelta = angularRadius.to_degrees();
+    let minLat = center.lat - latDelta;
+    let maxLat = center.lat + latDelta;
+
+    // 包含了极点的话 经度是全部的
+    if minLat <= -90.0 || maxLat >= 90.0 {
+        return vec![BoundingBox { minLat: minLat.max(-90.0), maxLat: maxLat.min(90.0), minLon: -180.0, maxLon: 180.0 }];
+    }
+
+    // 圆和经线相切的地方 经度的跨度最大
+    let sinLonDelta = angularRadius.sin() / center.lat.to_radians().cos();
+    if sinLonDelta >= 1.0 {
+        return vec![BoundingBox { minLat, maxLat, minLon: -180.0, maxLon: 180.0 }];
+    }
+
+    let lonDelta = sinLonDelta.asin().to_degrees();
+
+    splitByAntimeridian(minLat, maxLat, center.lon - lonDelta, center.lon + lonDelta)
+}
+
+pub fn boxBoundingBoxes(southWest: &Point, northEast: &Point) -> Vec<BoundingBox> {
+    if southWest.lat > northEast.lat {
+        return Vec::new();
+    }
+
+    if southWest.lon <= northEast.lon {
+        vec![BoundingBox { minLat: southWest.lat, maxLat: northEast.lat, minLon: southWest.lon, maxLon: northEast.lon }]
+    } else {
+        splitByAntimeridian(southWest.lat, northEast.lat, southWest.lon, northEast.lon + 360.0)
+    }
+}
+
+fn splitByAntimeridian(minLat: f64, maxLat: f64, minLon: f64, maxLon: f64) -> Vec<BoundingBox> {
+    if minLon < -180.0 {
+        vec![
+            BoundingBox { minLat, maxLat, minLon: minLon + 360.0, maxLon: 180.0 },
+            BoundingBox { minLat, maxLat, minLon: -180.0, maxLon },
+        ]
+    } else if maxLon > 180.0 {
+        vec![
+            BoundingBox { minLat, maxLat, minLon, maxLon: 180.0 },
+            BoundingBox { minLat, maxLat, minLon: -180.0, maxLon: maxLon - 360.0 },
+        ]
+    } else {
+        vec![BoundingBox { minLat, maxLat, minLon, maxLon }]
+    }
+}
+
+/// value所在的cell的编号 共有 2^bitCount 个cell
+fn cellIndex(value: f64, min: f64, max: f64, bitCount: u32) -> u64 {
+    let cellCount = 1u64 << bitCount;
+    let index = ((value - min) / (max - min) * cellCount as f64).floor();
+
+    (index.max(0.0) as u64).min(cellCount - 1)
+}
+
+/// 由高到低 经度的bit和纬度的bit交错
+fn interleave(latCellIndex: u64, lonCellIndex: u64, bitCountPerAxis: u32) -> u64 {
+    let mut geohash = 0u64;
+
+    for bit in (0..bitCountPerAxis).rev() {
+        geohash = (geohash << 1) | ((lonCellIndex >> bit) & 1);
+        geohash = (geohash << 1) | ((latCellIndex >> bit) & 1);
+    }
+
+    geohash
+}
+
+pub fn geohash(point: &Point) -> u64 {
+    interleave(cellIndex(point.lat, -90.0, 90.0, GEOHASH_BIT_COUNT_PER_AXIS),
+               cellIndex(point.lon, -180.0, 180.0, GEOHASH_BIT_COUNT_PER_AXIS),
+               GEOHASH_BIT_COUNT_PER_AXIS)
+}
+
+/// 覆盖了各个boundingBox的geohash范围 [start, end) 已排序且合并了相连的 <br>
+/// 由细到粗 挑选cell数量不超过上限的最细的精度
+pub fn coverGeohashRanges(boundingBoxes: &[BoundingBox]) -> Vec<(u64, u64)> {
+    let mut bitCountPerAxis = GEOHASH_BIT_COUNT_PER_AXIS;
+
+    let cellIndexRanges =
+        loop {
+            let cellIndexRanges: Vec<(u64, u64, u64, u64)> =
+                boundingBoxes.iter().map(|boundingBox| {
+                    (cellIndex(boundingBox.minLat, -90.0, 90.0, bitCountPerAxis),
+                     cellIndex(boundingBox.maxLat, -90.0, 90.0, bitCountPerAxis),
+                     cellIndex(boundingBox.minLon, -180.0, 180.0, bitCountPerAxis),
+                     cellIndex(boundingBox.maxLon, -180.0, 180.0, bitCountPerAxis))
+                }).collect();
+
+            let cellCount: u64 =
+                cellIndexRanges.iter().map(|(minLatCellIndex, maxLatCellIndex, minLonCellIndex, maxLonCellIndex)| {
+                    (maxLatCellIndex - minLatCellIndex + 1) * (maxLonCellIndex - minLonCellIndex + 1)
+                }).sum();
+
+            if cellCount <= COVER_CELL_COUNT_LIMIT || bitCountPerAxis == 0 {
+                break cellIndexRanges;
+            }
+
+            bitCountPerAxis -= 1;
+        };
+
+    // 粗的cell对应的是完整的geohash的范围
+    let shift = 2 * (GEOHASH_BIT_COUNT_PER_AXIS - bitCountPerAxis);
+
+    let mut geohashRanges = Vec::new();
+
+    for (minLatCellIndex, maxLatCellIndex, minLonCellIndex, maxLonCellIndex) in cellIndexRanges {
+        for latCellIndex in minLatCellIndex..=maxLatCellIndex {
+            for lonCellIndex in minLonCellIndex..=maxLonCellIndex {
+                let cell = interleave(latCellIndex, lonCellIndex, bitCountPerAxis);
+                geohashRanges.push((cell << shift, (cell + 1) << shift));
+            }
+        }
+    }
+
+    geohashRanges.sort();
+
+    let mut mergedGeohashRanges: Vec<(u64, u64)> = Vec::with_capacity(geohashRanges.len());
+
+    for (start, end) in geohashRanges {
+        match mergedGeohashRanges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => mergedGeohashRanges.push((start, end)),
+        }
+    }
+
+    mergedGeohashRanges
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::{geo, meta};
+    use crate::geo::Point;
+    use crate::session::Session;
+
+    #[test]
+    pub fn testDistanceAndCover() {
+        let hangzhou = Point::new(30.2741, 120.1551).unwrap();
+        let shanghai = Point::new(31.2304, 121.4737).unwrap();
+
+        let meters = geo::distance(&hangzhou, &shanghai);
+        assert!(meters > 160_000.0 && meters < 170_000.0);
+        assert_eq!(geo::distance(&hangzhou, &hangzhou), 0.0);
+
+        assert_eq!(geo::parse("[30.2741, 120.1551]").unwrap(), hangzhou);
+        assert_eq!(geo::parse(&geo::format(&hangzhou)).unwrap(), hangzhou);
+        assert!(geo::parse("[91, 0]").is_err());
+        assert!(geo::parse(r#"{"lat": 1}"#).is_err());
+
+        // 跨越了180经线
+        let southWest = Point::new(-10.0, 170.0).unwrap();
+        let northEast = Point::new(10.0, -170.0).unwrap();
+        assert!(geo::withinBox(&Point::new(0.0, 179.0).unwrap(), &southWest, &northEast));
+        assert!(geo::withinBox(&Point::new(0.0, -175.0).unwrap(), &southWest, &northEast));
+        assert!(geo::withinBox(&Point::new(0.0, 0.0).unwrap(), &southWest, &northEast) == false);
+        assert_eq!(geo::boxBoundingBoxes(&southWest, &northEast).len(), 2);
+
+        // 圆形范围内的点的geohash都要被覆盖到
+        let boundingBoxes = geo::radiusBoundingBoxes(&hangzhou, 5000.0);
+        assert_eq!(boundingBoxes.len(), 1);
+
+        let geohashRanges = geo::coverGeohashRanges(&boundingBoxes);
+        assert!(geohashRanges.len() <= 16);
+
+        for (latDelta, lonDelta) in [(0.04, 0.0), (-0.04, 0.0), (0.0, 0.05), (0.0, -0.05), (0.03, 0.03)] {
+            let point = Point::new(hangzhou.lat + latDelta, hangzhou.lon + lonDelta).unwrap();
+            assert!(geo::distance(&hangzhou, &point) <= 5000.0);
+
+            let geohash = geo::geohash(&point);
+            assert!(geohashRanges.iter().any(|(start, end)| geohash >= *start && geohash < *end));
+        }
+
+        let geohash = geo::geohash(&shanghai);
+        assert!(geohashRanges.iter().any(|(start, end)| geohash >= *start && geohash < *end) == false);
+
+        assert!(geohashRanges.iter().all(|(_, end)| *end <= 1 << (2 * geo::GEOHASH_BIT_COUNT_PER_AXIS)));
+        assert_eq!(geo::radiusBoundingBoxes(&Point::new(0.0, 179.99).unwrap(), 5000.0).len(), 2);
+        assert_eq!(geo::radiusBoundingBoxes(&Point::new(89.99, 0.0).unwrap(), 5000.0)[0].minLon, -180.0);
+    }
+
+    #[test]
+    pub fn testPointColumn() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table geoShop");
+        session.executeSql("create table geoShop (id integer, location point)").unwrap();
+        session.executeSql("create index geoShopLocation on geoShop[location]").unwrap();
+        session.executeSql("insert into geoShop values (1, point(39.90, 116.39))").unwrap();
+        session.executeSql("insert into geoShop values (2, point(31.23, 121.47))").unwrap();
+        session.executeSql("insert into geoShop values (3, point(39.91, 116.40))").unwrap();
+        session.executeSql("insert into geoShop values (4, null)").unwrap();
+
+        let ids = |session: &mut Session, sql: &str| -> Vec<i64> {
+            let mut ids: Vec<i64> = session.executeSql(sql).unwrap().remove(0).iter().map(|row| row["id"].as_i64().unwrap()).collect();
+            ids.sort();
+            ids
+        };
+
+        // 落地后再读出来
+        assert_eq!(session.executeSql("select geoShop(id = 2 or id = 4) order by id").unwrap().remove(0),
+                   vec![json!({"id": 2, "location": {"lat": 31.23, "lon": 121.47}}), json!({"id": 4, "location": null})]);
+
+        let explain = session.executeSql("explain select geoShop[id](within_radius(location, point(39.90, 116.39), 5000))").unwrap().remove(0);
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("spatialIndex"));
+        assert_eq!(ids(&mut session, "select geoShop[id](within_radius(location, point(39.90, 116.39), 5000))"), vec![1, 3]);
+
+        let explain = session.executeSql("explain select geoShop[id](within_box(location, point(30, 120), point(32, 122)))").unwrap().remove(0);
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("spatialIndex"));
+        assert_eq!(ids(&mut session, "select geoShop[id](within_box(location, point(30, 120), point(32, 122)))"), vec![2]);
+
+        assert_eq!(session.executeSql("select geoShop[id](id < 4) order by distance(location, point(39.90, 116.39)) limit 2").unwrap().remove(0),
+                   vec![json!({"id": 1}), json!({"id": 3})]);
+
+        session.executeSql("drop table geoShop").unwrap();
+    }
+}
@@ -90,6 +90,10 @@ pub const DOT_STR: &str = ".";
 pub const EMPTY_BINARY: Vec<Byte> = vec![];
 
 pub const 百分号_CHAR: char = '%';
+
+pub const 问号_CHAR: char = '?';
+pub const DOLLAR_CHAR: char = '$';
+pub const AT_CHAR: char = '@';
 pub const 百分号_STR: &str = "%";
 
 // todo 20241127 如何对 DUMMY_ROW_DATA 实现 getRowSize() 是 0 需要有个标识来表明它是dummy的
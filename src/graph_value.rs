@@ -4,8 +4,10 @@ use std::mem;
 use serde::{Deserialize, Serialize, Serializer};
 use strum_macros::Display;
 use crate::graph_error::GraphError;
-use crate::{global, meta, throw, throwFormat, utils};
+use crate::{decimal, geo, global, json, list, meta, throw, throwFormat, utils, vector};
 use anyhow::Result;
+use rust_decimal::Decimal;
+use data_encoding::{BASE64, HEXLOWER};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use serde::ser::SerializeMap;
 use serde_json::Value;
@@ -14,6 +16,8 @@ use crate::executor::CommandExecutor;
 use crate::parser::element::Element;
 use crate::parser::op;
 use crate::parser::op::{LikePattern, LogicalOp, MathCalcOp, MathCmpOp, Op, SqlOp};
+use crate::temporal;
+use crate::temporal::Interval;
 use crate::types::{Byte, DataKey};
 
 #[derive(Deserialize, Debug, Clone)]
@@ -24,7 +28,24 @@ pub enum GraphValue {
     String(String),
     Boolean(bool),
     Integer(i64),
-    Decimal(f64),
+    /// 精确的定点数
+    Decimal(Decimal),
+    Double(f64),
+    /// UTC 微秒
+    Timestamp(i64),
+    /// 从1970-01-01开始的天数
+    Date(i64),
+    /// 只用在计算 不能落地保存
+    Interval(Interval),
+    Bytes(Vec<Byte>),
+    /// 落地的是序列化后的文本
+    Json(Value),
+    /// vector(n) column 的f32数组
+    Vector(Vec<f32>),
+    /// list<T> column 元素的type相同且不是null
+    List(Vec<GraphValue>),
+    /// point column 的纬度经度
+    Point(geo::Point),
     Null,
 
     IndexUseful {
@@ -37,7 +58,42 @@ pub enum GraphValue {
     IgnoreColumnActualValue,
 }
 
-/// type标识(u8) + 内容长度(u32,对应的是变长的 Pending String PoinstDesc) + 内容
+/// 最高位取反 binary(big endian)的大小顺序便和数值的顺序相同了 负数在前 <br>
+/// index的key是binary排序的 这样order by 可以直接利用index上的顺序
+#[inline]
+fn i64ToSortable(i: i64) -> u64 {
+    (i as u64) ^ (1 << 63)
+}
+
+#[inline]
+fn sortable2I64(u: u64) -> i64 {
+    (u ^ (1 << 63)) as i64
+}
+
+/// 正数最高位取反 负数全部取反
+#[inline]
+fn f64ToSortable(f: f64) -> u64 {
+    let bits = f.to_bits();
+
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits ^ (1 << 63)
+    }
+}
+
+#[inline]
+fn sortable2F64(u: u64) -> f64 {
+    if u >> 63 == 1 {
+        f64::from_bits(u ^ (1 << 63))
+    } else {
+        f64::from_bits(!u)
+    }
+}
+
+/// type标识(u8) + 内容长度(u32,对应的是变长的 Pending String Bytes Json PoinstDesc, vector是f32的数量, list是元素的数量) + 内容 <br>
+/// list的内容是各个元素依次的编码 <br>
+/// integer decimal double 的内容是 order preserving 的
 impl<'a> BinaryCodec<'a> for GraphValue {
     type OutputType = GraphValue;
 
@@ -52,13 +108,58 @@ impl<'a> BinaryCodec<'a> for GraphValue {
                 destByteSlice.put_u8(GraphValue::BOOLEAN);
                 destByteSlice.put_u8(if *s { 1 } else { 0 });
             }
+            GraphValue::Bytes(s) => {
+                destByteSlice.put_u8(GraphValue::BYTES);
+                destByteSlice.put_u32(s.len() as u32);
+                destByteSlice.put_slice(s);
+            }
+            GraphValue::Json(s) => {
+                let text = s.to_string();
+
+                destByteSlice.put_u8(GraphValue::JSON);
+                destByteSlice.put_u32(text.len() as u32);
+                destByteSlice.put_slice(text.as_bytes());
+            }
+            GraphValue::Vector(s) => {
+                destByteSlice.put_u8(GraphValue::VECTOR);
+                destByteSlice.put_u32(s.len() as u32);
+
+                for element in s {
+                    destByteSlice.put_f32(*element);
+                }
+            }
+            GraphValue::List(s) => {
+                destByteSlice.put_u8(GraphValue::LIST);
+                destByteSlice.put_u32(s.len() as u32);
+
+                for element in s {
+                    element.encode2ByteMut(destByteSlice)?;
+                }
+            }
+            GraphValue::Point(s) => {
+                destByteSlice.put_u8(GraphValue::POINT);
+                destByteSlice.put_f64(s.lat);
+                destByteSlice.put_f64(s.lon);
+            }
             GraphValue::Integer(s) => {
                 destByteSlice.put_u8(GraphValue::INTEGER);
-                destByteSlice.put_i64(*s);
+                destByteSlice.put_u64(i64ToSortable(*s));
             }
             GraphValue::Decimal(s) => {
                 destByteSlice.put_u8(GraphValue::DECIMAL);
-                destByteSlice.put_f64(*s);
+                destByteSlice.put_slice(&decimal::encode(s));
+            }
+            GraphValue::Double(s) => {
+                destByteSlice.put_u8(GraphValue::DOUBLE);
+                destByteSlice.put_u64(f64ToSortable(*s));
+            }
+            GraphValue::Timestamp(s) => {
+                destByteSlice.put_u8(GraphValue::TIMESTAMP);
+                destByteSlice.put_u64(i64ToSortable(*s));
+            }
+            GraphValue::Date(s) => {
+                destByteSlice.put_u8(GraphValue::DATE);
+                destByteSlice.put_u64(i64ToSortable(*s));
             }
             GraphValue::Null => destByteSlice.put_u8(GraphValue::NULL),
             _ => panic!("impossible")
@@ -73,7 +174,7 @@ impl<'a> BinaryCodec<'a> for GraphValue {
         let typeTag = srcSliceWrapper.get_u8();
 
         match typeTag {
-            GraphValue::PENDING | GraphValue::STRING => {
+            GraphValue::PENDING | GraphValue::STRING | GraphValue::BYTES | GraphValue::JSON => {
                 let contentLen = srcSliceWrapper.get_u32() as usize;
 
                 let slice = &srcSliceWrapper.slice[srcSliceWrapper.position..srcSliceWrapper.position + contentLen];
@@ -82,12 +183,49 @@ impl<'a> BinaryCodec<'a> for GraphValue {
                 match typeTag {
                     GraphValue::PENDING => Ok(GraphValue::Pending(String::from_utf8_lossy(slice).to_string())),
                     GraphValue::STRING => Ok(GraphValue::String(String::from_utf8_lossy(slice).to_string())),
+                    GraphValue::BYTES => Ok(GraphValue::Bytes(slice.to_vec())),
+                    GraphValue::JSON => Ok(GraphValue::Json(serde_json::from_slice(slice)?)),
                     _ => panic!("impossible")
                 }
             }
-            GraphValue::BOOLEAN => Ok(GraphValue::Boolean(srcSliceWrapper.get_u8() == 0)),
-            GraphValue::INTEGER => Ok(GraphValue::Integer(srcSliceWrapper.get_i64())),
-            GraphValue::DECIMAL => Ok(GraphValue::Decimal(srcSliceWrapper.get_f64())),
+            GraphValue::VECTOR => {
+                let count = srcSliceWrapper.get_u32() as usize;
+
+                let mut vector = Vec::with_capacity(count);
+                for _ in 0..count {
+                    vector.push(srcSliceWrapper.get_f32());
+                }
+
+                Ok(GraphValue::Vector(vector))
+            }
+            GraphValue::LIST => {
+                let count = srcSliceWrapper.get_u32() as usize;
+
+                let mut list = Vec::with_capacity(count);
+                for _ in 0..count {
+                    list.push(GraphValue::decodeFromSliceWrapper(srcSliceWrapper, None)?);
+                }
+
+                Ok(GraphValue::List(list))
+            }
+            GraphValue::POINT => {
+                let lat = srcSliceWrapper.get_f64();
+                let lon = srcSliceWrapper.get_f64();
+
+                Ok(GraphValue::Point(geo::Point { lat, lon }))
+            }
+            GraphValue::BOOLEAN => Ok(GraphValue::Boolean(srcSliceWrapper.get_u8() != 0)),
+            GraphValue::INTEGER => Ok(GraphValue::Integer(sortable2I64(srcSliceWrapper.get_u64()))),
+            GraphValue::DECIMAL => {
+                let slice = &srcSliceWrapper.slice[srcSliceWrapper.position..srcSliceWrapper.position + decimal::ENCODED_LEN];
+                let decimal = decimal::decode(slice);
+                srcSliceWrapper.advance(decimal::ENCODED_LEN);
+
+                Ok(GraphValue::Decimal(decimal))
+            }
+            GraphValue::DOUBLE => Ok(GraphValue::Double(sortable2F64(srcSliceWrapper.get_u64()))),
+            GraphValue::TIMESTAMP => Ok(GraphValue::Timestamp(sortable2I64(srcSliceWrapper.get_u64()))),
+            GraphValue::DATE => Ok(GraphValue::Date(sortable2I64(srcSliceWrapper.get_u64()))),
             GraphValue::NULL => Ok(GraphValue::Null),
             _ => throwFormat!("unknown type tag:{}",typeTag)
         }
@@ -111,17 +249,63 @@ impl<'a> BinaryCodec<'a> for GraphValue {
 
                 destByteSlice.put_u8(if *s { 1 } else { 0 });
             }
+            GraphValue::Bytes(s) => {
+                destByteSlice.put_u8(GraphValue::BYTES);
+                destByteSlice.put_u32(s.len() as u32);
+                destByteSlice.put_slice(s);
+            }
+            GraphValue::Json(s) => {
+                let text = s.to_string();
+
+                destByteSlice.put_u8(GraphValue::JSON);
+                destByteSlice.put_u32(text.len() as u32);
+                destByteSlice.put_slice(text.as_bytes());
+            }
+            GraphValue::Vector(s) => {
+                destByteSlice.put_u8(GraphValue::VECTOR);
+                destByteSlice.put_u32(s.len() as u32);
+
+                for element in s {
+                    destByteSlice.put_f32(*element);
+                }
+            }
+            GraphValue::List(s) => {
+                destByteSlice.put_u8(GraphValue::LIST);
+                destByteSlice.put_u32(s.len() as u32);
+
+                for element in s {
+                    let elementByteLen = element.encode2Slice(destByteSlice)?;
+                    destByteSlice = &mut mem::take(&mut destByteSlice)[elementByteLen..];
+                }
+            }
+            GraphValue::Point(s) => {
+                destByteSlice.put_u8(GraphValue::POINT);
+                destByteSlice.put_f64(s.lat);
+                destByteSlice.put_f64(s.lon);
+            }
             GraphValue::Integer(s) => {
                 destByteSlice.put_u8(GraphValue::INTEGER);
                 // destByteSlice = &mut destByteSlice[size_of::<Byte>()..];
 
-                destByteSlice.put_i64(*s);
+                destByteSlice.put_u64(i64ToSortable(*s));
             }
             GraphValue::Decimal(s) => {
                 destByteSlice.put_u8(GraphValue::DECIMAL);
+                destByteSlice.put_slice(&decimal::encode(s));
+            }
+            GraphValue::Double(s) => {
+                destByteSlice.put_u8(GraphValue::DOUBLE);
                 // destByteSlice = &mut destByteSlice[size_of::<Byte>()..];
 
-                destByteSlice.put_f64(*s);
+                destByteSlice.put_u64(f64ToSortable(*s));
+            }
+            GraphValue::Timestamp(s) => {
+                destByteSlice.put_u8(GraphValue::TIMESTAMP);
+                destByteSlice.put_u64(i64ToSortable(*s));
+            }
+            GraphValue::Date(s) => {
+                destByteSlice.put_u8(GraphValue::DATE);
+                destByteSlice.put_u64(i64ToSortable(*s));
             }
             GraphValue::Null => destByteSlice.put_u8(GraphValue::NULL),
             _ => panic!("impossible")
@@ -143,7 +327,18 @@ impl Serialize for GraphValue {
                 GraphValue::String(s) => s.serialize(serializer),
                 GraphValue::Boolean(s) => s.serialize(serializer),
                 GraphValue::Integer(s) => s.serialize(serializer),
-                GraphValue::Decimal(s) => s.serialize(serializer),
+                // 用string 避免json的number变为f64丢失精度
+                GraphValue::Decimal(s) => s.to_string().serialize(serializer),
+                GraphValue::Double(s) => s.serialize(serializer),
+                GraphValue::Timestamp(s) => temporal::formatTimestamp(*s).serialize(serializer),
+                GraphValue::Date(s) => temporal::formatDate(*s).serialize(serializer),
+                GraphValue::Interval(s) => s.to_string().serialize(serializer),
+                GraphValue::Bytes(s) => BASE64.encode(s).serialize(serializer),
+                // 原样嵌在结果里边 不是string
+                GraphValue::Json(s) => s.serialize(serializer),
+                GraphValue::Vector(s) => s.serialize(serializer),
+                GraphValue::List(s) => s.serialize(serializer),
+                GraphValue::Point(s) => s.serialize(serializer),
                 GraphValue::Null => serializer.serialize_none(),
                 _ => panic!("impossible")
             }
@@ -171,6 +366,42 @@ impl Serialize for GraphValue {
                     serialMap.serialize_key("Decimal")?;
                     serialMap.serialize_value(s)?;
                 }
+                GraphValue::Double(s) => {
+                    serialMap.serialize_key("Double")?;
+                    serialMap.serialize_value(s)?;
+                }
+                GraphValue::Timestamp(s) => {
+                    serialMap.serialize_key("Timestamp")?;
+                    serialMap.serialize_value(s)?;
+                }
+                GraphValue::Date(s) => {
+                    serialMap.serialize_key("Date")?;
+                    serialMap.serialize_value(s)?;
+                }
+                GraphValue::Interval(s) => {
+                    serialMap.serialize_key("Interval")?;
+                    serialMap.serialize_value(s)?;
+                }
+                GraphValue::Bytes(s) => {
+                    serialMap.serialize_key("Bytes")?;
+                    serialMap.serialize_value(s)?;
+                }
+                GraphValue::Json(s) => {
+                    serialMap.serialize_key("Json")?;
+                    serialMap.serialize_value(s)?;
+                }
+                GraphValue::Vector(s) => {
+                    serialMap.serialize_key("Vector")?;
+                    serialMap.serialize_value(s)?;
+                }
+                GraphValue::List(s) => {
+                    serialMap.serialize_key("List")?;
+                    serialMap.serialize_value(s)?;
+                }
+                GraphValue::Point(s) => {
+                    serialMap.serialize_key("Point")?;
+                    serialMap.serialize_value(s)?;
+                }
                 GraphValue::Null => {
                     serialMap.serialize_key("Null")?;
                     serialMap.serialize_value(&Value::Null)?;
@@ -190,6 +421,15 @@ impl Display for GraphValue {
             GraphValue::Boolean(s) => write!(f, "Boolean({})", s),
             GraphValue::Integer(s) => write!(f, "Integer({})", s),
             GraphValue::Decimal(s) => write!(f, "Decimal({})", s),
+            GraphValue::Double(s) => write!(f, "Double({})", s),
+            GraphValue::Timestamp(s) => write!(f, "Timestamp({})", temporal::formatTimestamp(*s)),
+            GraphValue::Date(s) => write!(f, "Date({})", temporal::formatDate(*s)),
+            GraphValue::Interval(s) => write!(f, "Interval({})", s),
+            GraphValue::Bytes(s) => write!(f, "Bytes({})", HEXLOWER.encode(s)),
+            GraphValue::Json(s) => write!(f, "Json({})", s),
+            GraphValue::Vector(s) => write!(f, "Vector({})", vector::format(s)),
+            GraphValue::List(s) => write!(f, "List({})", list::format(s)),
+            GraphValue::Point(s) => write!(f, "Point({})", geo::format(s)),
             _ => write!(f, "unknown({:?})", self),
         }
     }
@@ -205,6 +445,11 @@ impl TryFrom<&Element> for GraphValue {
             Element::Boolean(bool) => Ok(GraphValue::Boolean(*bool)),
             Element::IntegerLiteral(integer) => Ok(GraphValue::Integer(*integer)),
             Element::DecimalLiteral(decimal) => Ok(GraphValue::Decimal(*decimal)),
+            Element::DoubleLiteral(double) => Ok(GraphValue::Double(*double)),
+            Element::TimestampLiteral(timestamp) => Ok(GraphValue::Timestamp(*timestamp)),
+            Element::DateLiteral(date) => Ok(GraphValue::Date(*date)),
+            Element::IntervalLiteral(interval) => Ok(GraphValue::Interval(*interval)),
+            Element::BytesLiteral(bytes) => Ok(GraphValue::Bytes(bytes.clone())),
             Element::TextLiteral(columnName) => Ok(GraphValue::Pending(columnName.clone())),
             Element::Null => Ok(GraphValue::Null),
             _ => throwFormat!("element:{element:?} can not be transform to GraphValue"),
@@ -212,6 +457,64 @@ impl TryFrom<&Element> for GraphValue {
     }
 }
 
+/// 只能是json的基本类型 prepared statement 的参数 子查询的结果 都是这样的
+impl TryFrom<&Value> for GraphValue {
+    type Error = GraphError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Null => Ok(GraphValue::Null),
+            Value::Bool(bool) => Ok(GraphValue::Boolean(*bool)),
+            Value::Number(number) => {
+                if let Some(integer) = number.as_i64() {
+                    Ok(GraphValue::Integer(integer))
+                } else {
+                    // 小数优先当作decimal 超出了decimal的范围的话是double
+                    if let Ok(decimal) = decimal::parse(&number.to_string()) {
+                        return Ok(GraphValue::Decimal(decimal));
+                    }
+
+                    match number.as_f64() {
+                        Some(double) => Ok(GraphValue::Double(double)),
+                        None => throwFormat!("number:{} is not supported", number),
+                    }
+                }
+            }
+            Value::String(s) => Ok(GraphValue::String(s.clone())),
+            // 给json column的
+            Value::Array(_) | Value::Object(_) => Ok(GraphValue::Json(value.clone())),
+        }
+    }
+}
+
+/// prepared statement 的参数绑定到placeholder上
+impl TryFrom<&GraphValue> for Element {
+    type Error = GraphError;
+
+    fn try_from(graphValue: &GraphValue) -> Result<Self, Self::Error> {
+        match graphValue {
+            GraphValue::String(s) => Ok(Element::StringContent(s.clone())),
+            GraphValue::Boolean(bool) => Ok(Element::Boolean(*bool)),
+            GraphValue::Integer(integer) => Ok(Element::IntegerLiteral(*integer)),
+            GraphValue::Decimal(decimal) => Ok(Element::DecimalLiteral(*decimal)),
+            GraphValue::Double(double) => Ok(Element::DoubleLiteral(*double)),
+            GraphValue::Timestamp(timestamp) => Ok(Element::TimestampLiteral(*timestamp)),
+            GraphValue::Date(date) => Ok(Element::DateLiteral(*date)),
+            GraphValue::Interval(interval) => Ok(Element::IntervalLiteral(*interval)),
+            GraphValue::Bytes(bytes) => Ok(Element::BytesLiteral(bytes.clone())),
+            // json column 收到string的时候会parse
+            GraphValue::Json(json) => Ok(Element::StringContent(json.to_string())),
+            // vector column 收到string的时候也会parse
+            GraphValue::Vector(vector) => Ok(Element::StringContent(vector::format(vector))),
+            // list column 收到string的时候也会parse
+            GraphValue::List(list) => Ok(Element::StringContent(list::format(list))),
+            GraphValue::Point(point) => Ok(Element::StringContent(geo::format(point))),
+            GraphValue::Null => Ok(Element::Null),
+            _ => throwFormat!("graphValue:{graphValue:?} can not be used as parameter"),
+        }
+    }
+}
+
 pub type GraphValueType = Byte;
 
 impl GraphValue {
@@ -220,9 +523,17 @@ impl GraphValue {
     pub const STRING: GraphValueType = 1;
     pub const BOOLEAN: GraphValueType = 2;
     pub const INTEGER: GraphValueType = 3;
-    pub const DECIMAL: GraphValueType = 4;
+    pub const DOUBLE: GraphValueType = 4;
     pub const POINT_DESC: GraphValueType = 5;
     pub const NULL: GraphValueType = 6;
+    pub const TIMESTAMP: GraphValueType = 7;
+    pub const DATE: GraphValueType = 8;
+    pub const DECIMAL: GraphValueType = 9;
+    pub const BYTES: GraphValueType = 10;
+    pub const JSON: GraphValueType = 11;
+    pub const VECTOR: GraphValueType = 12;
+    pub const LIST: GraphValueType = 13;
+    pub const POINT: GraphValueType = 14;
 
     pub const GRAPH_VALUE_DUMMY: GraphValue = GraphValue::Null;
 
@@ -235,15 +546,25 @@ impl GraphValue {
             GraphValue::STRING => Ok(GraphValue::String(global::EMPTY_STR.to_owned())),
             GraphValue::BOOLEAN => Ok(GraphValue::Boolean(false)),
             GraphValue::INTEGER => Ok(GraphValue::Integer(0)),
-            GraphValue::DECIMAL => Ok(GraphValue::Decimal(0.0)),
+            GraphValue::DECIMAL => Ok(GraphValue::Decimal(Decimal::ZERO)),
+            GraphValue::DOUBLE => Ok(GraphValue::Double(0.0)),
+            GraphValue::BYTES => Ok(GraphValue::Bytes(Vec::new())),
+            GraphValue::JSON => Ok(GraphValue::Json(Value::Null)),
+            GraphValue::VECTOR => Ok(GraphValue::Vector(Vec::new())),
+            GraphValue::LIST => Ok(GraphValue::List(Vec::new())),
+            GraphValue::POINT => Ok(GraphValue::Point(geo::Point { lat: 0.0, lon: 0.0 })),
+            GraphValue::TIMESTAMP => Ok(GraphValue::Timestamp(0)),
+            GraphValue::DATE => Ok(GraphValue::Date(0)),
             _ => throwFormat!("unsupported graphValueType:{}", graphValueType)
         }
     }
 
-    /// 当前不支持 type的自动转换 两边的type要严格相同的
+    /// integer和decimal double之间会把integer提升成decimal double, decimal和double之间是double 别的type要严格相同的
     pub fn calc(&self, op: Op, rightValues: &[GraphValue]) -> Result<GraphValue> {
         if let Op::SqlOp(SqlOp::In) = op {
             self.calcIn(rightValues)
+        } else if op.isUnary() {
+            self.calcNot()
         } else {
             // 当前只允许in的时候有多个
             if rightValues.len() > 1 {
@@ -275,10 +596,9 @@ impl GraphValue {
                         })
                     }
                     GraphValue::IndexUseful { .. } | GraphValue::IndexUseless => Ok(GraphValue::IndexUseless),
-                    GraphValue::String(_) | GraphValue::Boolean(_) | GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Null => {
-                        self.calc(op, rightValues)
-                    }
-                    GraphValue::IgnoreColumnActualValue => panic!("impossible")
+                    GraphValue::IgnoreColumnActualValue => panic!("impossible"),
+                    // 常量
+                    _ => self.calc(op, rightValues),
                 }
             } else {
                 throw!("right values only can be multi when op is in")
@@ -287,8 +607,17 @@ impl GraphValue {
             let rightValue = &rightValues[0];
 
             match (self, rightValue) {
-                (GraphValue::Pending(columnName), GraphValue::String(_) | GraphValue::Boolean(_) | GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Null) => {
+                (GraphValue::Pending(columnName), rightValue) if rightValue.isConstant() => {
                     if op.permitByIndex() {
+                        // is null 变为 equal null
+                        if let Op::SqlOp(SqlOp::Is) = op {
+                            return Ok(GraphValue::IndexUseful {
+                                columnName: columnName.clone(),
+                                op: Op::MathCmpOp(MathCmpOp::Equal),
+                                values: vec![GraphValue::Null],
+                            });
+                        }
+
                         // like null, like 'a' 变为 equal
                         if let Op::SqlOp(SqlOp::Like) = op {
                             if let GraphValue::Null = rightValue {
@@ -319,12 +648,11 @@ impl GraphValue {
                         Ok(GraphValue::IndexUseless)
                     }
                 }
-                (GraphValue::String(_) | GraphValue::Boolean(_) | GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Null, GraphValue::Pending(_)) => {
+                (leftValue, GraphValue::Pending(_)) if leftValue.isConstant() => {
                     rightValue.calc0(op, &[self.clone()])
                 }
                 // 两边都是常量
-                (GraphValue::String(_) | GraphValue::Boolean(_) | GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Null,
-                    GraphValue::String(_) | GraphValue::Boolean(_) | GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Null) => {
+                (leftValue, rightValue) if leftValue.isConstant() && rightValue.isConstant() => {
                     self.calc(op, &[rightValue.clone()])
                 }
                 _ => Ok(GraphValue::IndexUseless)
@@ -336,15 +664,35 @@ impl GraphValue {
     pub fn calcOneToOne(&self, op: Op, rightValue: &GraphValue) -> Result<GraphValue> {
         match op {
             Op::MathCmpOp(mathCmpOp) => {
+                // timestamp date 之间比较 date当作当天的0点
+                if let (Some(micros), Some(micros0)) = (self.temporalMicros(), rightValue.temporalMicros()) {
+                    return GraphValue::Integer(micros).calcOneToOne(op, &GraphValue::Integer(micros0));
+                }
+
+                if let Some(ordering) = self.cmpDecimal(rightValue) {
+                    let result =
+                        match mathCmpOp {
+                            MathCmpOp::Equal => ordering.is_eq(),
+                            MathCmpOp::NotEqual => ordering.is_ne(),
+                            MathCmpOp::LessThan => ordering.is_lt(),
+                            MathCmpOp::LessEqual => ordering.is_le(),
+                            MathCmpOp::GreaterThan => ordering.is_gt(),
+                            MathCmpOp::GreaterEqual => ordering.is_ge(),
+                        };
+
+                    return Ok(GraphValue::Boolean(result));
+                }
+
                 match mathCmpOp {
                     MathCmpOp::LessEqual => {
                         match (self, rightValue) {
                             (GraphValue::String(s), GraphValue::String(s0)) => Ok(GraphValue::Boolean(s <= s0)),
+                            (GraphValue::Bytes(b), GraphValue::Bytes(b0)) => Ok(GraphValue::Boolean(b <= b0)),
                             (GraphValue::Boolean(b), GraphValue::Boolean(b0)) => Ok(GraphValue::Boolean(b <= b0)),
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Boolean(integer <= integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 <= &(*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Boolean(float <= float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Boolean(float64 <= &(*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 <= &(*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Boolean(float <= float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Boolean(&(*integer as f64) <= float64)),
                             (GraphValue::IgnoreColumnActualValue, _) | (_, GraphValue::IgnoreColumnActualValue) => Ok(GraphValue::Boolean(true)),
                             _ => Ok(GraphValue::Boolean(false)),
                         }
@@ -352,11 +700,20 @@ impl GraphValue {
                     MathCmpOp::Equal => {
                         match (self, rightValue) {
                             (GraphValue::String(s), GraphValue::String(s0)) => Ok(GraphValue::Boolean(s == s0)),
+                            (GraphValue::Bytes(b), GraphValue::Bytes(b0)) => Ok(GraphValue::Boolean(b == b0)),
+                            (GraphValue::Json(j), GraphValue::Json(j0)) => Ok(GraphValue::Boolean(j == j0)),
+                            // profile = '{"tier":"gold"}'
+                            (GraphValue::Json(j), GraphValue::String(s)) => Ok(GraphValue::Boolean(json::parse(s).is_ok_and(|j0| *j == j0))),
+                            (GraphValue::Vector(v), GraphValue::Vector(v0)) => Ok(GraphValue::Boolean(v == v0)),
+                            (GraphValue::Vector(v), GraphValue::String(s)) => Ok(GraphValue::Boolean(vector::parse(s).is_ok_and(|v0| *v == v0))),
+                            (GraphValue::List(l), GraphValue::List(l0)) => Ok(GraphValue::Boolean(l == l0)),
+                            (GraphValue::Point(p), GraphValue::Point(p0)) => Ok(GraphValue::Boolean(p == p0)),
+                            (GraphValue::Point(p), GraphValue::String(s)) => Ok(GraphValue::Boolean(geo::parse(s).is_ok_and(|p0| *p == p0))),
                             (GraphValue::Boolean(b), GraphValue::Boolean(b0)) => Ok(GraphValue::Boolean(b == b0)),
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Boolean(integer == integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 == &(*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Boolean(float == float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Boolean(float64 == &(*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 == &(*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Boolean(float == float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Boolean(&(*integer as f64) == float64)),
                             (GraphValue::Null, GraphValue::Null) => Ok(GraphValue::Boolean(true)),
                             (GraphValue::IgnoreColumnActualValue, _) | (_, GraphValue::IgnoreColumnActualValue) => Ok(GraphValue::Boolean(true)),
                             _ => Ok(GraphValue::Boolean(false)),
@@ -365,11 +722,12 @@ impl GraphValue {
                     MathCmpOp::LessThan => {
                         match (self, rightValue) {
                             (GraphValue::String(s), GraphValue::String(s0)) => Ok(GraphValue::Boolean(s < s0)),
+                            (GraphValue::Bytes(b), GraphValue::Bytes(b0)) => Ok(GraphValue::Boolean(b < b0)),
                             (GraphValue::Boolean(b), GraphValue::Boolean(b0)) => Ok(GraphValue::Boolean(b < b0)),
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Boolean(integer < integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 < &(*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Boolean(float < float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Boolean(float64 < &(*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 < &(*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Boolean(float < float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Boolean(&(*integer as f64) < float64)),
                             (GraphValue::IgnoreColumnActualValue, _) | (_, GraphValue::IgnoreColumnActualValue) => Ok(GraphValue::Boolean(true)),
                             _ => Ok(GraphValue::Boolean(false)),
                         }
@@ -377,11 +735,12 @@ impl GraphValue {
                     MathCmpOp::GreaterThan => {
                         match (self, rightValue) {
                             (GraphValue::String(s), GraphValue::String(s0)) => Ok(GraphValue::Boolean(s > s0)),
+                            (GraphValue::Bytes(b), GraphValue::Bytes(b0)) => Ok(GraphValue::Boolean(b > b0)),
                             (GraphValue::Boolean(b), GraphValue::Boolean(b0)) => Ok(GraphValue::Boolean(b > b0)),
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Boolean(integer > integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 > &(*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Boolean(float > float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Boolean(float64 > &(*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 > &(*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Boolean(float > float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Boolean(&(*integer as f64) > float64)),
                             (GraphValue::IgnoreColumnActualValue, _) | (_, GraphValue::IgnoreColumnActualValue) => Ok(GraphValue::Boolean(true)),
                             _ => Ok(GraphValue::Boolean(false)),
                         }
@@ -389,11 +748,12 @@ impl GraphValue {
                     MathCmpOp::GreaterEqual => {
                         match (self, rightValue) {
                             (GraphValue::String(s), GraphValue::String(s0)) => Ok(GraphValue::Boolean(s >= s0)),
+                            (GraphValue::Bytes(b), GraphValue::Bytes(b0)) => Ok(GraphValue::Boolean(b >= b0)),
                             (GraphValue::Boolean(b), GraphValue::Boolean(b0)) => Ok(GraphValue::Boolean(b >= b0)),
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Boolean(integer >= integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 >= &(*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Boolean(float >= float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Boolean(float64 >= &(*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 >= &(*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Boolean(float >= float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Boolean(&(*integer as f64) >= float64)),
                             (GraphValue::IgnoreColumnActualValue, _) | (_, GraphValue::IgnoreColumnActualValue) => Ok(GraphValue::Boolean(true)),
                             _ => Ok(GraphValue::Boolean(false)),
                         }
@@ -401,52 +761,73 @@ impl GraphValue {
                     MathCmpOp::NotEqual => {
                         match (self, rightValue) {
                             (GraphValue::String(s), GraphValue::String(s0)) => Ok(GraphValue::Boolean(s != s0)),
+                            (GraphValue::Bytes(b), GraphValue::Bytes(b0)) => Ok(GraphValue::Boolean(b != b0)),
+                            (GraphValue::Json(j), GraphValue::Json(j0)) => Ok(GraphValue::Boolean(j != j0)),
+                            (GraphValue::Json(j), GraphValue::String(s)) => Ok(GraphValue::Boolean(json::parse(s).is_ok_and(|j0| *j != j0))),
+                            (GraphValue::Vector(v), GraphValue::Vector(v0)) => Ok(GraphValue::Boolean(v != v0)),
+                            (GraphValue::Vector(v), GraphValue::String(s)) => Ok(GraphValue::Boolean(vector::parse(s).is_ok_and(|v0| *v != v0))),
+                            (GraphValue::List(l), GraphValue::List(l0)) => Ok(GraphValue::Boolean(l != l0)),
+                            (GraphValue::Point(p), GraphValue::Point(p0)) => Ok(GraphValue::Boolean(p != p0)),
+                            (GraphValue::Point(p), GraphValue::String(s)) => Ok(GraphValue::Boolean(geo::parse(s).is_ok_and(|p0| *p != p0))),
                             (GraphValue::Boolean(b), GraphValue::Boolean(b0)) => Ok(GraphValue::Boolean(b != b0)),
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Boolean(integer != integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 != &(*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Boolean(float != float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Boolean(float64 != &(*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Boolean(float64 != &(*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Boolean(float != float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Boolean(&(*integer as f64) != float64)),
                             _ => Ok(GraphValue::Boolean(false)),
                         }
                     }
                 }
             }
             Op::MathCalcOp(matchCalcOp) => {
+                // null 参与计算的结果是null
+                if let (GraphValue::Null, _) | (_, GraphValue::Null) = (self, rightValue) {
+                    return Ok(GraphValue::Null);
+                }
+
+                if let Some(result) = self.calcTemporal(matchCalcOp, rightValue)? {
+                    return Ok(result);
+                }
+
+                if let Some(result) = self.calcDecimal(matchCalcOp, rightValue)? {
+                    return Ok(result);
+                }
+
                 match matchCalcOp {
                     MathCalcOp::Plus => {
                         match (self, rightValue) {
                             (GraphValue::String(s), GraphValue::String(s0)) => Ok(GraphValue::String(format!("{s}{s0}"))),
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Integer(integer + integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Decimal(float64 + (*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Decimal(float + float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Decimal(float64 + (*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Double(float64 + (*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Double(float + float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Double((*integer as f64) + float64)),
                             _ => throwFormat!("can not use {op:?}, between {self:?} , {rightValue:?}"),
                         }
                     }
                     MathCalcOp::Divide => {
                         match (self, rightValue) {
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Integer(integer / integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Decimal(float64 / (*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Decimal(float / float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Decimal(float64 / (*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Double(float64 / (*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Double(float / float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Double((*integer as f64) / float64)),
                             _ => throwFormat!("can not use {op:?}, between {self:?} , {rightValue:?}"),
                         }
                     }
                     MathCalcOp::Multiply => {
                         match (self, rightValue) {
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Integer(integer * integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Decimal(float64 * (*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Decimal(float * float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Decimal(float64 * (*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Double(float64 * (*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Double(float * float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Double((*integer as f64) * float64)),
                             _ => throwFormat!("can not use {op:?}, between {self:?} , {rightValue:?}"),
                         }
                     }
                     MathCalcOp::Minus => {
                         match (self, rightValue) {
                             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Ok(GraphValue::Integer(integer - integer0)),
-                            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Decimal(float64 - (*integer as f64))),
-                            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Ok(GraphValue::Decimal(float - float0)),
-                            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Ok(GraphValue::Decimal(float64 - (*integer as f64))),
+                            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Ok(GraphValue::Double(float64 - (*integer as f64))),
+                            (GraphValue::Double(float), GraphValue::Double(float0)) => Ok(GraphValue::Double(float - float0)),
+                            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Ok(GraphValue::Double((*integer as f64) - float64)),
                             _ => throwFormat!("can not use {op:?}, between {self:?} , {rightValue:?}"),
                         }
                     }
@@ -466,6 +847,7 @@ impl GraphValue {
                             _ => throwFormat!("can not use {op:?}, between {self:?} , {rightValue:?}"),
                         }
                     }
+                    LogicalOp::Not => throw!("not has no right value"),
                 }
             }
             Op::SqlOp(SqlOp::Like) => { // todo 实现对like的计算 完成
@@ -486,24 +868,179 @@ impl GraphValue {
                     _ => throwFormat!("like can only used between strings")
                 }
             }
+            Op::SqlOp(SqlOp::Contain) => {
+                match (self, rightValue) {
+                    (GraphValue::Json(json), GraphValue::Json(json0)) => Ok(GraphValue::Boolean(json::contains(json, json0))),
+                    // profile @> '{"tier":"gold"}'
+                    (GraphValue::Json(json), GraphValue::String(s)) => Ok(GraphValue::Boolean(json::contains(json, &json::parse(s)?))),
+                    (GraphValue::Null, _) | (_, GraphValue::Null) => Ok(GraphValue::Boolean(false)),
+                    (GraphValue::IgnoreColumnActualValue, _) | (_, GraphValue::IgnoreColumnActualValue) => Ok(GraphValue::Boolean(true)),
+                    // tags contains 'rust', tags contains ['rust', 'db']
+                    (GraphValue::List(list), rightValue) => Ok(GraphValue::Boolean(list::contains(list, rightValue)?)),
+                    _ => throwFormat!("@> and contains can only used on json and list, but get {self:?} , {rightValue:?}"),
+                }
+            }
+            Op::SqlOp(SqlOp::Any) => {
+                match (self, rightValue) {
+                    (GraphValue::Null, _) | (_, GraphValue::Null) => Ok(GraphValue::Boolean(false)),
+                    (GraphValue::IgnoreColumnActualValue, _) | (_, GraphValue::IgnoreColumnActualValue) => Ok(GraphValue::Boolean(true)),
+                    // tags any ['rust', 'db']
+                    (GraphValue::List(list), rightValue) => Ok(GraphValue::Boolean(list::any(list, rightValue)?)),
+                    _ => throwFormat!("any can only used on list, but get {self:?} , {rightValue:?}"),
+                }
+            }
+            Op::SqlOp(SqlOp::Is | SqlOp::IsNot) => {
+                if let GraphValue::Null = rightValue {} else {
+                    throwFormat!("{op:?} can only be used with null, but get {rightValue:?}");
+                }
+
+                let isNull =
+                    match self {
+                        GraphValue::IgnoreColumnActualValue => return Ok(GraphValue::Boolean(true)),
+                        GraphValue::Null => true,
+                        _ => false,
+                    };
+
+                if let Op::SqlOp(SqlOp::Is) = op {
+                    Ok(GraphValue::Boolean(isNull))
+                } else {
+                    Ok(GraphValue::Boolean(isNull == false))
+                }
+            }
             _ => throwFormat!("can not use {op:?}, between {self:?} , {rightValue:?}"),
         }
     }
 
+    fn calcNot(&self) -> Result<GraphValue> {
+        match self {
+            GraphValue::Boolean(bool) => Ok(GraphValue::Boolean(*bool == false)),
+            _ => throwFormat!("can not use not on {self:?}"),
+        }
+    }
+
     fn calcIn(&self, rightValues: &[GraphValue]) -> Result<GraphValue> {
+        // 和其中的任意1个相等便可以
         for rightValue in rightValues {
             let calcResult = self.calcOneToOne(Op::MathCmpOp(MathCmpOp::Equal), rightValue)?;
-            if calcResult.asBoolean()? == false {
-                return Ok(GraphValue::Boolean(false));
+            if calcResult.asBoolean()? {
+                return Ok(GraphValue::Boolean(true));
             }
         }
 
-        Ok(GraphValue::Boolean(true))
+        Ok(GraphValue::Boolean(false))
+    }
+
+    /// timestamp 和 date 统一成微秒 date是当天的0点
+    pub fn temporalMicros(&self) -> Option<i64> {
+        match self {
+            GraphValue::Timestamp(micros) => Some(*micros),
+            GraphValue::Date(days) => Some(*days * temporal::MICROS_PER_DAY),
+            _ => None,
+        }
+    }
+
+    /// timestamp ± interval, date ± integer天数, timestamp - timestamp 得到 interval <br>
+    /// 不涉及timestamp date interval的话返回None 交给原来的数值计算
+    fn calcTemporal(&self, mathCalcOp: MathCalcOp, rightValue: &GraphValue) -> Result<Option<GraphValue>> {
+        let result =
+            match (self, mathCalcOp, rightValue) {
+                (GraphValue::Timestamp(_) | GraphValue::Date(_), MathCalcOp::Plus | MathCalcOp::Minus, GraphValue::Interval(interval)) => {
+                    let interval = if let MathCalcOp::Minus = mathCalcOp { interval.negate() } else { *interval };
+                    GraphValue::Timestamp(temporal::addInterval(self.temporalMicros().unwrap(), &interval)?)
+                }
+                (GraphValue::Interval(interval), MathCalcOp::Plus, GraphValue::Timestamp(_) | GraphValue::Date(_)) => {
+                    GraphValue::Timestamp(temporal::addInterval(rightValue.temporalMicros().unwrap(), interval)?)
+                }
+                (GraphValue::Date(days), MathCalcOp::Plus, GraphValue::Integer(days0)) |
+                (GraphValue::Integer(days0), MathCalcOp::Plus, GraphValue::Date(days)) => GraphValue::Date(days + days0),
+                (GraphValue::Date(days), MathCalcOp::Minus, GraphValue::Integer(days0)) => GraphValue::Date(days - days0),
+                (GraphValue::Date(days), MathCalcOp::Minus, GraphValue::Date(days0)) => GraphValue::Integer(days - days0),
+                (GraphValue::Timestamp(_) | GraphValue::Date(_), MathCalcOp::Minus, GraphValue::Timestamp(_) | GraphValue::Date(_)) => {
+                    GraphValue::Interval(Interval {
+                        months: 0,
+                        micros: self.temporalMicros().unwrap() - rightValue.temporalMicros().unwrap(),
+                    })
+                }
+                (GraphValue::Interval(interval), MathCalcOp::Plus | MathCalcOp::Minus, GraphValue::Interval(interval0)) => {
+                    let interval0 = if let MathCalcOp::Minus = mathCalcOp { interval0.negate() } else { *interval0 };
+                    GraphValue::Interval(Interval {
+                        months: interval.months + interval0.months,
+                        micros: interval.micros + interval0.micros,
+                    })
+                }
+                (GraphValue::Interval(interval), MathCalcOp::Multiply, GraphValue::Integer(integer)) |
+                (GraphValue::Integer(integer), MathCalcOp::Multiply, GraphValue::Interval(interval)) => {
+                    GraphValue::Interval(Interval {
+                        months: interval.months * integer,
+                        micros: interval.micros * integer,
+                    })
+                }
+                (GraphValue::Timestamp(_) | GraphValue::Date(_) | GraphValue::Interval(_), _, _) |
+                (_, _, GraphValue::Timestamp(_) | GraphValue::Date(_) | GraphValue::Interval(_)) => {
+                    throwFormat!("can not use {mathCalcOp:?}, between {self:?} , {rightValue:?}")
+                }
+                _ => return Ok(None),
+            };
+
+        Ok(Some(result))
+    }
+
+    /// decimal 和 integer 之间精确比较, 和 double 之间按照double比较 <br>
+    /// 不涉及decimal的话返回None
+    fn cmpDecimal(&self, rightValue: &GraphValue) -> Option<Ordering> {
+        match (self, rightValue) {
+            (GraphValue::Decimal(decimal), GraphValue::Decimal(decimal0)) => Some(decimal.cmp(decimal0)),
+            (GraphValue::Decimal(decimal), GraphValue::Integer(integer)) => Some(decimal.cmp(&Decimal::from(*integer))),
+            (GraphValue::Integer(integer), GraphValue::Decimal(decimal)) => Some(Decimal::from(*integer).cmp(decimal)),
+            (GraphValue::Decimal(decimal), GraphValue::Double(double)) => decimal::toF64(decimal).partial_cmp(double),
+            (GraphValue::Double(double), GraphValue::Decimal(decimal)) => double.partial_cmp(&decimal::toF64(decimal)),
+            _ => None,
+        }
+    }
+
+    /// decimal 和 integer 之间是精确的计算 溢出的话报错, 和 double 之间按照double计算 <br>
+    /// 不涉及decimal的话返回None
+    fn calcDecimal(&self, mathCalcOp: MathCalcOp, rightValue: &GraphValue) -> Result<Option<GraphValue>> {
+        let (decimal, decimal0) =
+            match (self, rightValue) {
+                (GraphValue::Decimal(decimal), GraphValue::Decimal(decimal0)) => (*decimal, *decimal0),
+                (GraphValue::Decimal(decimal), GraphValue::Integer(integer)) => (*decimal, Decimal::from(*integer)),
+                (GraphValue::Integer(integer), GraphValue::Decimal(decimal)) => (Decimal::from(*integer), *decimal),
+                (GraphValue::Decimal(decimal), GraphValue::Double(_)) => {
+                    return GraphValue::Double(decimal::toF64(decimal)).calcOneToOne(Op::MathCalcOp(mathCalcOp), rightValue).map(Some);
+                }
+                (GraphValue::Double(_), GraphValue::Decimal(decimal)) => {
+                    return self.calcOneToOne(Op::MathCalcOp(mathCalcOp), &GraphValue::Double(decimal::toF64(decimal))).map(Some);
+                }
+                _ => return Ok(None),
+            };
+
+        let result =
+            match mathCalcOp {
+                MathCalcOp::Plus => decimal.checked_add(decimal0),
+                MathCalcOp::Minus => decimal.checked_sub(decimal0),
+                MathCalcOp::Multiply => decimal.checked_mul(decimal0),
+                MathCalcOp::Divide => {
+                    if decimal0.is_zero() {
+                        throwFormat!("{} can not be divided by zero", decimal);
+                    }
+
+                    decimal.checked_div(decimal0)
+                }
+            };
+
+        match result {
+            Some(result) => Ok(Some(GraphValue::Decimal(result))),
+            None => throwFormat!("decimal overflow, {decimal} {mathCalcOp:?} {decimal0}"),
+        }
     }
 
     pub fn isConstant(&self) -> bool {
         match self {
-            GraphValue::String(_) | GraphValue::Boolean(_) | GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Null => true,
+            GraphValue::String(_) | GraphValue::Boolean(_) | GraphValue::Integer(_) | GraphValue::Decimal(_) | GraphValue::Double(_) | GraphValue::Null => true,
+            GraphValue::Timestamp(_) | GraphValue::Date(_) | GraphValue::Interval(_) | GraphValue::Bytes(_) | GraphValue::Json(_) | GraphValue::Vector(_) => true,
+            GraphValue::Point(_) => true,
+            GraphValue::List(list) => list.iter().all(|element| element.isConstant()),
             _ => false
         }
     }
@@ -512,9 +1049,23 @@ impl GraphValue {
     pub fn size(&self) -> Option<usize> {
         let selfByteLen = match self {
             GraphValue::String(s) => Self::LEN_BYTE_LEN + s.len(),
+            GraphValue::Bytes(s) => Self::LEN_BYTE_LEN + s.len(),
+            GraphValue::Json(s) => Self::LEN_BYTE_LEN + s.to_string().len(),
+            GraphValue::Vector(s) => Self::LEN_BYTE_LEN + s.len() * size_of::<f32>(),
+            GraphValue::List(s) => {
+                let mut elementsByteLen = 0;
+                for element in s {
+                    elementsByteLen += element.size()?;
+                }
+
+                Self::LEN_BYTE_LEN + elementsByteLen
+            }
             GraphValue::Boolean(_) => size_of::<Byte>(),
             GraphValue::Integer(_) => size_of::<i64>(),
-            GraphValue::Decimal(_) => size_of::<f64>(),
+            GraphValue::Decimal(_) => decimal::ENCODED_LEN,
+            GraphValue::Double(_) => size_of::<f64>(),
+            GraphValue::Point(_) => 2 * size_of::<f64>(),
+            GraphValue::Timestamp(_) | GraphValue::Date(_) => size_of::<i64>(),
             GraphValue::Null => 0,
             _ => return None
         };
@@ -561,6 +1112,14 @@ impl GraphValue {
             GraphValue::Boolean(_) => Self::BOOLEAN,
             GraphValue::Integer(_) => Self::INTEGER,
             GraphValue::Decimal(_) => Self::DECIMAL,
+            GraphValue::Double(_) => Self::DOUBLE,
+            GraphValue::Timestamp(_) => Self::TIMESTAMP,
+            GraphValue::Date(_) => Self::DATE,
+            GraphValue::Bytes(_) => Self::BYTES,
+            GraphValue::Json(_) => Self::JSON,
+            GraphValue::Vector(_) => Self::VECTOR,
+            GraphValue::List(_) => Self::LIST,
+            GraphValue::Point(_) => Self::POINT,
             GraphValue::Null => Self::NULL,
             _ => { panic!() }
         }
@@ -575,14 +1134,22 @@ impl PartialEq for GraphValue {
 
 impl PartialOrd for GraphValue {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if let Some(ordering) = self.cmpDecimal(other) {
+            return Some(ordering);
+        }
+
         match (self, other) {
             (GraphValue::String(s), GraphValue::String(s0)) => Some(s.cmp(s0)),
+            (GraphValue::Bytes(b), GraphValue::Bytes(b0)) => Some(b.cmp(b0)),
             (GraphValue::Boolean(b), GraphValue::Boolean(b0)) => Some(b.cmp(b0)),
             (GraphValue::Integer(integer), GraphValue::Integer(integer0)) => Some(integer.cmp(integer0)),
-            (GraphValue::Decimal(float64), GraphValue::Integer(integer)) => Some(float64.total_cmp(&(*integer as f64))),
-            (GraphValue::Decimal(float), GraphValue::Decimal(float0)) => Some(float.total_cmp(float0)),
-            (GraphValue::Integer(integer), GraphValue::Decimal(float64)) => Some(float64.total_cmp(&(*integer as f64))),
+            (GraphValue::Double(float64), GraphValue::Integer(integer)) => Some(float64.total_cmp(&(*integer as f64))),
+            (GraphValue::Double(float), GraphValue::Double(float0)) => Some(float.total_cmp(float0)),
+            (GraphValue::Integer(integer), GraphValue::Double(float64)) => Some((*integer as f64).total_cmp(float64)),
             (GraphValue::Null, GraphValue::Null) => Some(Ordering::Equal),
+            (GraphValue::Timestamp(_) | GraphValue::Date(_), GraphValue::Timestamp(_) | GraphValue::Date(_)) => {
+                Some(self.temporalMicros().unwrap().cmp(&other.temporalMicros().unwrap()))
+            }
             _ => None,
         }
     }
@@ -590,12 +1157,419 @@ impl PartialOrd for GraphValue {
 
 #[cfg(test)]
 mod test {
+    use bytes::BytesMut;
+    use serde_json::json;
+    use crate::codec::{BinaryCodec, SliceWrapper};
     use crate::global;
     use crate::graph_value::GraphValue;
+    use crate::meta;
+    use crate::meta::ColumnType;
+    use crate::parser::op::{MathCalcOp, MathCmpOp, Op, SqlOp};
+    use crate::{decimal, geo, json, temporal};
+    use crate::session::Session;
+    use crate::temporal::Interval;
     use crate::JSON_ENUM_UNTAGGED;
+    use rust_decimal::Decimal;
 
     #[test]
     pub fn testNull() {
         JSON_ENUM_UNTAGGED!(println!("{}", serde_json::to_string(&GraphValue::Null).unwrap()));
     }
-}
\ No newline at end of file
+
+    fn decimal(text: &str) -> Decimal {
+        decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    pub fn testEncodeOrderPreserving() {
+        let values = vec![
+            vec![GraphValue::Integer(i64::MIN), GraphValue::Integer(-7), GraphValue::Integer(0), GraphValue::Integer(3), GraphValue::Integer(i64::MAX)],
+            vec![GraphValue::Double(f64::MIN), GraphValue::Double(-1.5), GraphValue::Double(-0.0), GraphValue::Double(0.0), GraphValue::Double(2.25), GraphValue::Double(f64::MAX)],
+            vec![GraphValue::Decimal(decimal("-10.5")), GraphValue::Decimal(decimal("-0.01")), GraphValue::Decimal(decimal("0")), GraphValue::Decimal(decimal("0.1")), GraphValue::Decimal(decimal("2.00"))],
+            vec![GraphValue::Timestamp(-1), GraphValue::Timestamp(0), GraphValue::Timestamp(1_700_000_000_000_000)],
+            vec![GraphValue::Date(-365), GraphValue::Date(0), GraphValue::Date(19_000)],
+        ];
+
+        for values in values {
+            let mut prevBinary: Option<BytesMut> = None;
+
+            for value in values {
+                let mut binary = BytesMut::new();
+                value.encode2ByteMut(&mut binary).unwrap();
+
+                let mut sliceWrapper = SliceWrapper::new(binary.as_ref());
+                assert_eq!(GraphValue::decodeFromSliceWrapper(&mut sliceWrapper, None).unwrap(), value);
+
+                if let Some(prevBinary) = prevBinary {
+                    assert!(prevBinary < binary);
+                }
+
+                prevBinary = Some(binary);
+            }
+        }
+    }
+
+    #[test]
+    pub fn testBooleanRoundTrip() {
+        for bool in [true, false] {
+            let value = GraphValue::Boolean(bool);
+
+            let mut binary = BytesMut::new();
+            value.encode2ByteMut(&mut binary).unwrap();
+            assert_eq!(binary.len(), ColumnType::Boolean.graphValueSize().unwrap());
+
+            let mut sliceWrapper = SliceWrapper::new(binary.as_ref());
+            assert!(matches!(GraphValue::decodeFromSliceWrapper(&mut sliceWrapper, None).unwrap(), GraphValue::Boolean(b) if b == bool));
+
+            let mut slice = vec![0; value.size().unwrap()];
+            value.encode2Slice(slice.as_mut_slice()).unwrap();
+            assert_eq!(slice.as_slice(), binary.as_ref());
+        }
+
+        // index里边 false 在 true 前边
+        let mut falseBinary = BytesMut::new();
+        GraphValue::Boolean(false).encode2ByteMut(&mut falseBinary).unwrap();
+        let mut trueBinary = BytesMut::new();
+        GraphValue::Boolean(true).encode2ByteMut(&mut trueBinary).unwrap();
+        assert!(falseBinary < trueBinary);
+    }
+
+    #[test]
+    pub fn testBooleanColumn() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table boolUser");
+        session.executeSql("create table boolUser (id integer, active boolean)").unwrap();
+        session.executeSql("create index boolUserActive on boolUser[active]").unwrap();
+        session.executeSql("insert into boolUser values (1, true)").unwrap();
+        session.executeSql("insert into boolUser values (2, false)").unwrap();
+        session.executeSql("insert into boolUser values (3, null)").unwrap();
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        // 落地后再读出来
+        assert_eq!(rows("select boolUser order by id"),
+                   vec![json!({"id": 1, "active": true}), json!({"id": 2, "active": false}), json!({"id": 3, "active": null})]);
+
+        let explain = rows("explain select boolUser[id](active = true)");
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("index"));
+        assert_eq!(rows("select boolUser[id](active = true)"), vec![json!({"id": 1})]);
+        assert_eq!(rows("select boolUser[id](active = false)"), vec![json!({"id": 2})]);
+
+        session.executeSql("update boolUser[active = false](id = 1)").unwrap();
+        assert_eq!(session.executeSql("select boolUser[id](active = true)").unwrap().remove(0), Vec::<serde_json::Value>::new());
+
+        session.executeSql("drop table boolUser").unwrap();
+    }
+
+    #[test]
+    pub fn testBytes() {
+        let values = [GraphValue::Bytes(vec![]), GraphValue::Bytes(vec![0]), GraphValue::Bytes(vec![0, 0xff]), GraphValue::Bytes(vec![0xde, 0xad])];
+
+        for value in &values {
+            let mut binary = BytesMut::new();
+            value.encode2ByteMut(&mut binary).unwrap();
+            assert_eq!(binary.len(), value.size().unwrap());
+
+            let decoded = GraphValue::decodeFromSliceWrapper(&mut SliceWrapper::new(binary.as_ref()), None).unwrap();
+            assert_eq!(&decoded, value);
+
+            let mut slice = vec![0; value.size().unwrap()];
+            value.encode2Slice(slice.as_mut_slice()).unwrap();
+            assert_eq!(slice.as_slice(), binary.as_ref());
+        }
+
+        assert!(values[1] < values[2]);
+        assert!(values[2] != values[3]);
+        assert!(GraphValue::Bytes(vec![1]).calc(Op::SqlOp(SqlOp::In), &[GraphValue::Bytes(vec![0]), GraphValue::Bytes(vec![1])]).unwrap().asBoolean().unwrap());
+
+        // 给client的是base64 落地到undo log的要能原样还原
+        JSON_ENUM_UNTAGGED!(assert_eq!(serde_json::to_string(&values[3]).unwrap(), "\"3q0=\""));
+        let json = serde_json::to_string(&values[3]).unwrap();
+        assert_eq!(serde_json::from_str::<GraphValue>(&json).unwrap(), values[3]);
+    }
+
+    #[test]
+    pub fn testBytesColumn() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table bytesFile");
+        session.executeSql("create table bytesFile (id integer, digest bytes)").unwrap();
+        session.executeSql("create index bytesFileDigest on bytesFile[digest]").unwrap();
+        session.executeSql("insert into bytesFile values (1, x'dead')").unwrap();
+        session.executeSql("insert into bytesFile values (2, x'00ff')").unwrap();
+        session.executeSql("insert into bytesFile values (3, x'')").unwrap();
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        // 落地后再读出来 给client的是base64
+        assert_eq!(rows("select bytesFile order by id"),
+                   vec![json!({"id": 1, "digest": "3q0="}), json!({"id": 2, "digest": "AP8="}), json!({"id": 3, "digest": ""})]);
+
+        let explain = rows("explain select bytesFile[id](digest = x'00ff')");
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("index"));
+        assert_eq!(rows("select bytesFile[id](digest = x'00ff')"), vec![json!({"id": 2})]);
+        assert_eq!(rows("select bytesFile[id](digest = x'')"), vec![json!({"id": 3})]);
+
+        session.executeSql("drop table bytesFile").unwrap();
+    }
+
+    #[test]
+    pub fn testJson() {
+        let profile = GraphValue::Json(json::parse(r#"{"tier":"gold","tags":["a"]}"#).unwrap());
+
+        let mut binary = BytesMut::new();
+        profile.encode2ByteMut(&mut binary).unwrap();
+        assert_eq!(binary.len(), profile.size().unwrap());
+        assert_eq!(GraphValue::decodeFromSliceWrapper(&mut SliceWrapper::new(binary.as_ref()), None).unwrap(), profile);
+
+        // key的顺序不同的话也相等
+        assert!(profile == GraphValue::Json(json::parse(r#"{"tags":["a"],"tier":"gold"}"#).unwrap()));
+        assert!(profile == GraphValue::String(r#"{"tags":["a"], "tier":"gold"}"#.to_string()));
+        assert!(profile.calc(Op::SqlOp(SqlOp::Contain), &[GraphValue::String(r#"{"tags":["a"]}"#.to_string())]).unwrap().asBoolean().unwrap());
+        assert!(profile.calc(Op::SqlOp(SqlOp::Contain), &[GraphValue::String("{".to_string())]).is_err());
+        assert!(GraphValue::Null.calc(Op::SqlOp(SqlOp::Contain), &[profile.clone()]).unwrap().asBoolean().unwrap() == false);
+
+        let columnType = ColumnType::Json;
+        let mut value = GraphValue::String("[1, 2]".to_string());
+        assert!(columnType.compatibleWithValue(&value));
+        columnType.fitValue(&mut value).unwrap();
+        assert!(matches!(value, GraphValue::Json(ref json) if json.is_array()));
+        assert!(columnType.fitValue(&mut GraphValue::String("[1,".to_string())).is_err());
+
+        // 给client的是原样的json 不是string
+        JSON_ENUM_UNTAGGED!(assert_eq!(serde_json::to_string(&profile).unwrap(), r#"{"tags":["a"],"tier":"gold"}"#));
+        let json = serde_json::to_string(&profile).unwrap();
+        assert_eq!(serde_json::from_str::<GraphValue>(&json).unwrap(), profile);
+    }
+
+    #[test]
+    pub fn testVector() {
+        let embedding = GraphValue::Vector(vec![1.0, -2.5, 0.125]);
+
+        let mut binary = BytesMut::new();
+        embedding.encode2ByteMut(&mut binary).unwrap();
+        assert_eq!(binary.len(), embedding.size().unwrap());
+        assert_eq!(GraphValue::decodeFromSliceWrapper(&mut SliceWrapper::new(binary.as_ref()), None).unwrap(), embedding);
+
+        let mut slice = vec![0; embedding.size().unwrap()];
+        embedding.encode2Slice(slice.as_mut_slice()).unwrap();
+        assert_eq!(slice.as_slice(), binary.as_ref());
+
+        assert!(embedding == GraphValue::String("[1, -2.5, 0.125]".to_string()));
+        assert!(embedding != GraphValue::Vector(vec![1.0, -2.5]));
+
+        let columnType: ColumnType = "vector(3)".parse().unwrap();
+        assert_eq!(columnType.to_string(), "VECTOR(3)");
+
+        let mut value = GraphValue::String("[1, -2.5, 0.125]".to_string());
+        assert!(columnType.compatibleWithValue(&value));
+        columnType.fitValue(&mut value).unwrap();
+        assert_eq!(value, embedding);
+
+        let mut value = GraphValue::Json(json::parse("[1, 2, 3]").unwrap());
+        columnType.fitValue(&mut value).unwrap();
+        assert!(matches!(value, GraphValue::Vector(ref vector) if vector.len() == 3));
+
+        assert!(columnType.fitValue(&mut GraphValue::String("[1, 2]".to_string())).is_err());
+        assert!(columnType.fitValue(&mut GraphValue::String("[1, 2,".to_string())).is_err());
+        assert!("vector(0)".parse::<ColumnType>().is_err());
+
+        JSON_ENUM_UNTAGGED!(assert_eq!(serde_json::to_string(&embedding).unwrap(), "[1.0,-2.5,0.125]"));
+        let json = serde_json::to_string(&embedding).unwrap();
+        assert_eq!(serde_json::from_str::<GraphValue>(&json).unwrap(), embedding);
+    }
+
+    #[test]
+    pub fn testPoint() {
+        let store = GraphValue::Point(geo::Point::new(30.2741, 120.1551).unwrap());
+
+        let mut binary = BytesMut::new();
+        store.encode2ByteMut(&mut binary).unwrap();
+        assert_eq!(binary.len(), store.size().unwrap());
+        assert_eq!(GraphValue::decodeFromSliceWrapper(&mut SliceWrapper::new(binary.as_ref()), None).unwrap(), store);
+
+        let mut slice = vec![0; store.size().unwrap()];
+        store.encode2Slice(slice.as_mut_slice()).unwrap();
+        assert_eq!(slice.as_slice(), binary.as_ref());
+
+        assert!(store == GraphValue::String("[30.2741, 120.1551]".to_string()));
+        assert!(store != GraphValue::String(r#"{"lat": 30.2741, "lon": 120}"#.to_string()));
+
+        let columnType: ColumnType = "point".parse().unwrap();
+        assert_eq!(columnType.graphValueSize(), store.size());
+
+        let mut value = GraphValue::String(r#"{"lat": 30.2741, "lon": 120.1551}"#.to_string());
+        assert!(columnType.compatibleWithValue(&value));
+        columnType.fitValue(&mut value).unwrap();
+        assert_eq!(value, store);
+
+        assert!(columnType.fitValue(&mut GraphValue::String("[30.2741]".to_string())).is_err());
+        assert!(columnType.compatibleWithValue(&GraphValue::Integer(1)) == false);
+
+        JSON_ENUM_UNTAGGED!(assert_eq!(serde_json::to_string(&store).unwrap(), r#"{"lat":30.2741,"lon":120.1551}"#));
+        let json = serde_json::to_string(&store).unwrap();
+        assert_eq!(serde_json::from_str::<GraphValue>(&json).unwrap(), store);
+    }
+
+    #[test]
+    pub fn testList() {
+        let s = |s: &str| GraphValue::String(s.to_string());
+
+        let tags = GraphValue::List(vec![s("rust"), s("db")]);
+
+        let mut binary = BytesMut::new();
+        tags.encode2ByteMut(&mut binary).unwrap();
+        assert_eq!(binary.len(), tags.size().unwrap());
+        assert_eq!(GraphValue::decodeFromSliceWrapper(&mut SliceWrapper::new(binary.as_ref()), None).unwrap(), tags);
+
+        let mut slice = vec![0; tags.size().unwrap()];
+        tags.encode2Slice(slice.as_mut_slice()).unwrap();
+        assert_eq!(slice.as_slice(), binary.as_ref());
+
+        let contains = |right: GraphValue| tags.calc(Op::SqlOp(SqlOp::Contain), &[right]).unwrap().asBoolean().unwrap();
+        assert!(contains(s("rust")));
+        assert!(contains(GraphValue::List(vec![s("db"), s("rust")])));
+        assert!(contains(s("go")) == false);
+        assert!(contains(GraphValue::Null) == false);
+
+        let any = |right: GraphValue| tags.calc(Op::SqlOp(SqlOp::Any), &[right]).unwrap().asBoolean().unwrap();
+        assert!(any(GraphValue::List(vec![s("go"), s("db")])));
+        assert!(any(GraphValue::List(vec![s("go")])) == false);
+        assert!(GraphValue::Integer(1).calc(Op::SqlOp(SqlOp::Any), &[GraphValue::List(vec![])]).is_err());
+
+        let columnType: ColumnType = "list<string>".parse().unwrap();
+        assert_eq!(columnType.to_string(), "LIST<STRING>");
+
+        let mut value = s(r#"["rust", "db"]"#);
+        assert!(columnType.compatibleWithValue(&value));
+        columnType.fitValue(&mut value).unwrap();
+        assert_eq!(value, tags);
+
+        let columnType: ColumnType = "list<decimal(5, 1)>".parse().unwrap();
+        let mut value = GraphValue::List(vec![GraphValue::Integer(1), GraphValue::Decimal(decimal::parse("2.25").unwrap())]);
+        columnType.fitValue(&mut value).unwrap();
+        assert!(matches!(value, GraphValue::List(ref list) if list[0].to_string() == "Decimal(1.0)" && list[1].to_string() == "Decimal(2.3)"));
+
+        assert!(columnType.fitValue(&mut GraphValue::List(vec![s("a")])).is_err());
+        assert!(columnType.fitValue(&mut GraphValue::List(vec![GraphValue::Null])).is_err());
+        assert!("list<list<string>>".parse::<ColumnType>().is_err());
+        assert!("list<vector(3)>".parse::<ColumnType>().is_err());
+
+        JSON_ENUM_UNTAGGED!(assert_eq!(serde_json::to_string(&tags).unwrap(), r#"["rust","db"]"#));
+        let json = serde_json::to_string(&tags).unwrap();
+        assert_eq!(serde_json::from_str::<GraphValue>(&json).unwrap(), tags);
+    }
+
+    #[test]
+    pub fn testTemporalCalc() {
+        let day = GraphValue::Date(19_783);
+        let midnight = GraphValue::Timestamp(19_783 * temporal::MICROS_PER_DAY);
+        let noon = GraphValue::Timestamp(19_783 * temporal::MICROS_PER_DAY + 12 * temporal::MICROS_PER_HOUR);
+
+        // date 当作当天的0点
+        assert!(day == midnight);
+        assert!(day < noon);
+        assert!(noon.calc(Op::MathCmpOp(MathCmpOp::GreaterThan), &[day.clone()]).unwrap().asBoolean().unwrap());
+
+        let calc = |left: &GraphValue, op: MathCalcOp, right: GraphValue| left.calc(Op::MathCalcOp(op), &[right]).unwrap();
+
+        let halfDay = GraphValue::Interval(Interval::parse("12 hours").unwrap());
+        assert!(calc(&midnight, MathCalcOp::Plus, halfDay.clone()) == noon);
+        assert!(calc(&noon, MathCalcOp::Minus, halfDay.clone()) == midnight);
+        assert!(matches!(calc(&noon, MathCalcOp::Minus, midnight.clone()), GraphValue::Interval(Interval { months: 0, micros }) if micros == 12 * temporal::MICROS_PER_HOUR));
+        assert!(calc(&day, MathCalcOp::Plus, GraphValue::Integer(1)) == GraphValue::Date(19_784));
+        assert_eq!(calc(&GraphValue::Date(19_784), MathCalcOp::Minus, day.clone()), GraphValue::Integer(1));
+        assert!(matches!(calc(&day, MathCalcOp::Plus, GraphValue::Null), GraphValue::Null));
+
+        assert!(noon.calc(Op::MathCalcOp(MathCalcOp::Plus), &[GraphValue::Integer(1)]).is_err());
+        assert!(halfDay.calc(Op::MathCalcOp(MathCalcOp::Divide), &[GraphValue::Integer(2)]).is_err());
+    }
+
+    #[test]
+    pub fn testExactDecimal() {
+        let calc = |left: GraphValue, op: MathCalcOp, right: GraphValue| left.calc(Op::MathCalcOp(op), &[right]);
+
+        let sum = calc(GraphValue::Decimal(decimal("0.1")), MathCalcOp::Plus, GraphValue::Decimal(decimal("0.2"))).unwrap();
+        assert!(matches!(sum, GraphValue::Decimal(d) if d == decimal("0.3")));
+        assert!(sum == GraphValue::Decimal(decimal("0.30")));
+        assert!(sum == GraphValue::Double(0.3));
+
+        let product = calc(GraphValue::Integer(3), MathCalcOp::Multiply, GraphValue::Decimal(decimal("1.10"))).unwrap();
+        assert!(matches!(product, GraphValue::Decimal(d) if d.to_string() == "3.30"));
+        assert!(matches!(calc(GraphValue::Decimal(decimal("1.5")), MathCalcOp::Minus, GraphValue::Double(0.5)).unwrap(), GraphValue::Double(f) if f == 1.0));
+        assert!(calc(GraphValue::Decimal(decimal("1")), MathCalcOp::Divide, GraphValue::Integer(0)).is_err());
+        assert!(calc(GraphValue::Decimal(Decimal::MAX), MathCalcOp::Plus, GraphValue::Integer(1)).is_err());
+
+        assert!(GraphValue::Decimal(decimal("2.5")) > GraphValue::Integer(2));
+        assert!(GraphValue::Decimal(decimal("2.50")) == GraphValue::Decimal(decimal("2.5")));
+        assert!(GraphValue::Decimal(decimal("2.5")).calc(Op::SqlOp(SqlOp::In), &[GraphValue::Integer(2), GraphValue::Double(2.5)]).unwrap().asBoolean().unwrap());
+
+        let columnType: ColumnType = "decimal(5, 2)".parse().unwrap();
+
+        let mut value = GraphValue::Integer(7);
+        assert!(columnType.compatibleWithValue(&value));
+        columnType.fitValue(&mut value).unwrap();
+        assert!(matches!(value, GraphValue::Decimal(d) if d.to_string() == "7.00"));
+
+        let mut value = GraphValue::Decimal(decimal("1.005"));
+        columnType.fitValue(&mut value).unwrap();
+        assert!(matches!(value, GraphValue::Decimal(d) if d.to_string() == "1.01"));
+
+        assert!(columnType.fitValue(&mut GraphValue::Decimal(decimal("1000"))).is_err());
+        assert!(columnType.compatibleWithValue(&GraphValue::Double(1.0)) == false);
+
+        // index seek 的时候不能四舍五入
+        let mut value = GraphValue::Decimal(decimal("1.005"));
+        columnType.promoteValue(&mut value);
+        assert!(matches!(value, GraphValue::Decimal(d) if d.to_string() == "1.005"));
+
+        let mut binary = BytesMut::new();
+        GraphValue::Decimal(decimal("-12.30")).encode2ByteMut(&mut binary).unwrap();
+        assert_eq!(binary.len(), columnType.graphValueSize().unwrap());
+        let decoded = GraphValue::decodeFromSliceWrapper(&mut SliceWrapper::new(binary.as_ref()), None).unwrap();
+        assert!(matches!(decoded, GraphValue::Decimal(d) if d.to_string() == "-12.30"));
+
+        let mut slice = vec![0; decoded.size().unwrap()];
+        decoded.encode2Slice(slice.as_mut_slice()).unwrap();
+        assert_eq!(slice.as_slice(), binary.as_ref());
+    }
+
+    #[test]
+    pub fn testIntegerPromoteToDecimal() {
+        let cmp = |left: GraphValue, op: MathCmpOp, right: GraphValue| {
+            left.calc(Op::MathCmpOp(op), &[right]).unwrap().asBoolean().unwrap()
+        };
+
+        assert!(cmp(GraphValue::Double(3.5), MathCmpOp::GreaterThan, GraphValue::Integer(3)));
+        assert!(cmp(GraphValue::Integer(3), MathCmpOp::LessThan, GraphValue::Double(3.5)));
+        assert!(cmp(GraphValue::Integer(3), MathCmpOp::LessEqual, GraphValue::Double(3.0)));
+        assert!(cmp(GraphValue::Integer(3), MathCmpOp::Equal, GraphValue::Double(3.0)));
+        assert!(cmp(GraphValue::Integer(4), MathCmpOp::GreaterEqual, GraphValue::Double(3.5)));
+        assert!(cmp(GraphValue::Integer(4), MathCmpOp::NotEqual, GraphValue::Double(3.5)));
+        assert!(cmp(GraphValue::Integer(4), MathCmpOp::GreaterThan, GraphValue::Double(4.5)) == false);
+
+        let calc = |left: GraphValue, op: MathCalcOp, right: GraphValue| left.calc(Op::MathCalcOp(op), &[right]).unwrap();
+
+        assert_eq!(calc(GraphValue::Integer(10), MathCalcOp::Minus, GraphValue::Double(2.5)), GraphValue::Double(7.5));
+        assert_eq!(calc(GraphValue::Integer(5), MathCalcOp::Divide, GraphValue::Double(2.0)), GraphValue::Double(2.5));
+        assert_eq!(calc(GraphValue::Double(2.5), MathCalcOp::Plus, GraphValue::Integer(1)), GraphValue::Double(3.5));
+
+        // in 只要和其中1个相等
+        let inValues = [GraphValue::Integer(2), GraphValue::Double(3.0)];
+        assert!(GraphValue::Double(3.0).calc(Op::SqlOp(SqlOp::In), &inValues).unwrap().asBoolean().unwrap());
+        assert!(GraphValue::Integer(4).calc(Op::SqlOp(SqlOp::In), &inValues).unwrap().asBoolean().unwrap() == false);
+
+        assert!(GraphValue::Integer(3) < GraphValue::Double(3.5));
+
+        let mut value = GraphValue::Integer(7);
+        assert!(ColumnType::Double.compatibleWithValue(&value));
+        ColumnType::Double.promoteValue(&mut value);
+        assert!(matches!(value, GraphValue::Double(f) if f == 7.0));
+        assert!(ColumnType::Integer.compatibleWithValue(&GraphValue::Double(7.0)) == false);
+    }
+}
@@ -0,0 +1,127 @@
+use serde_json::Value;
+use crate::graph_value::GraphValue;
+use crate::throwFormat;
+use anyhow::Result;
+
+// json column 落地的是serde_json序列化后的文本 object的key是有序的 相同的内容得到相同的binary
+
+/// '{"tier":"gold"}'
+pub fn parse(text: &str) -> Result<Value> {
+    match serde_json::from_str(text) {
+        Ok(value) => Ok(value),
+        Err(e) => throwFormat!("invalid json:'{}', {}", text, e),
+    }
+}
+
+/// -> 'key' 是object的字段, -> 1 是array的下标 从0起, 不存在的话是None
+pub fn get<'a>(json: &'a Value, key: &GraphValue) -> Result<Option<&'a Value>> {
+    match key {
+        GraphValue::String(key) => Ok(json.as_object().and_then(|object| object.get(key))),
+        GraphValue::Integer(index) => {
+            if *index < 0 {
+                return Ok(None);
+            }
+
+            Ok(json.as_array().and_then(|array| array.get(*index as usize)))
+        }
+        _ => throwFormat!("json path key should be string or integer, but get {key:?}"),
+    }
+}
+
+/// ->> 使用 string的话去掉引号 json的null是sql的null
+pub fn toGraphValueText(json: &Value) -> GraphValue {
+    match json {
+        Value::Null => GraphValue::Null,
+        Value::String(s) => GraphValue::String(s.clone()),
+        _ => GraphValue::String(json.to_string()),
+    }
+}
+
+/// @> 和postgres的jsonb相同 <br>
+/// object: right的各个key在left都有且value包含, array: right的各个元素被left的某个元素包含, 别的要相等
+pub fn contains(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Object(left), Value::Object(right)) => {
+            right.iter().all(|(key, rightValue)| {
+                match left.get(key) {
+                    Some(leftValue) => contains(leftValue, rightValue),
+                    None => false,
+                }
+            })
+        }
+        (Value::Array(left), Value::Array(right)) => {
+            right.iter().all(|rightElement| left.iter().any(|leftElement| contains(leftElement, rightElement)))
+        }
+        // '["a", "b"]' @> '"a"'
+        (Value::Array(left), right) if right.is_object() == false => left.iter().any(|leftElement| leftElement == right),
+        (Value::Number(left), Value::Number(right)) => left.as_f64() == right.as_f64(),
+        (left, right) => left == right,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::graph_value::GraphValue;
+    use crate::session::Session;
+    use crate::{json, meta};
+
+    #[test]
+    pub fn testPathAndContains() {
+        let profile = json::parse(r#"{"tier":"gold","address":{"city":"hz","zip":310000},"tags":["a","b"],"note":null}"#).unwrap();
+
+        let address = json::get(&profile, &GraphValue::String("address".to_string())).unwrap().unwrap();
+        assert_eq!(json::toGraphValueText(json::get(address, &GraphValue::String("city".to_string())).unwrap().unwrap()), GraphValue::String("hz".to_string()));
+        assert_eq!(json::toGraphValueText(json::get(address, &GraphValue::String("zip".to_string())).unwrap().unwrap()), GraphValue::String("310000".to_string()));
+
+        let tags = json::get(&profile, &GraphValue::String("tags".to_string())).unwrap().unwrap();
+        assert_eq!(json::get(tags, &GraphValue::Integer(1)).unwrap().unwrap(), "b");
+        assert!(json::get(tags, &GraphValue::Integer(2)).unwrap().is_none());
+        assert!(json::get(&profile, &GraphValue::Integer(0)).unwrap().is_none());
+        assert!(matches!(json::toGraphValueText(json::get(&profile, &GraphValue::String("note".to_string())).unwrap().unwrap()), GraphValue::Null));
+        assert!(json::get(&profile, &GraphValue::Boolean(true)).is_err());
+
+        assert!(json::contains(&profile, &json::parse(r#"{"tier":"gold"}"#).unwrap()));
+        assert!(json::contains(&profile, &json::parse(r#"{"address":{"zip":310000.0},"tags":["b"]}"#).unwrap()));
+        assert!(json::contains(&profile, &json::parse(r#"{"tier":"silver"}"#).unwrap()) == false);
+        assert!(json::contains(&profile, &json::parse(r#"{"tags":["c"]}"#).unwrap()) == false);
+        assert!(json::contains(tags, &json::parse(r#""a""#).unwrap()));
+
+        assert!(json::parse("{tier}").is_err());
+    }
+
+    #[test]
+    pub fn testJsonColumn() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table jsonUser");
+        session.executeSql("create table jsonUser (id integer, tier string, profile json)").unwrap();
+        session.executeSql("create index jsonUserTier on jsonUser[tier]").unwrap();
+        session.executeSql("create index jsonUserProfile on jsonUser[profile]").unwrap();
+        session.executeSql(r#"insert into jsonUser values (1, 'gold', '{"tier":"gold","tags":["a","b"],"age":3}')"#).unwrap();
+        session.executeSql(r#"insert into jsonUser values (2, 'silver', '{"tier":"silver","tags":["b"]}')"#).unwrap();
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        // 落地后再读出来 是json本身不是string
+        assert_eq!(rows("select jsonUser(id = 1)"), vec![json!({"id": 1, "tier": "gold", "profile": {"tier": "gold", "tags": ["a", "b"], "age": 3}})]);
+        assert_eq!(rows("select jsonUser[id, profile->'tags' as tags, profile->>'tier' as t](id = 2)"), vec![json!({"id": 2, "tags": ["b"], "t": "silver"})]);
+
+        // @> 不能使用index 和它and的可以
+        let explain = rows(r#"explain select jsonUser[id](tier = 'gold' and profile @> '{"tags":["a"]}')"#);
+        assert_eq!(explain[0]["scans"][0]["chosenIndex"], json!("jsonUserTier"));
+        assert_eq!(rows(r#"select jsonUser[id](tier = 'gold' and profile @> '{"tags":["a"]}')"#), vec![json!({"id": 1})]);
+        assert_eq!(rows(r#"select jsonUser[id](tier = 'gold' and profile @> '{"tags":["c"]}')"#), Vec::<serde_json::Value>::new());
+
+        // json column上的index只用来等值查找
+        let explain = rows(r#"explain select jsonUser[id](profile = '{"tier":"silver","tags":["b"]}')"#);
+        assert_eq!(explain[0]["scans"][0]["chosenIndex"], json!("jsonUserProfile"));
+        assert_eq!(rows(r#"select jsonUser[id](profile = '{"tier":"silver","tags":["b"]}')"#), vec![json!({"id": 2})]);
+
+        assert_eq!(rows("select jsonUser[id](profile->>'tier' = 'silver')"), vec![json!({"id": 2})]);
+
+        session.executeSql("drop table jsonUser").unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use serde_json::Value;
+use crate::graph_value::GraphValue;
+use crate::parser::op::{MathCmpOp, Op};
+use crate::{temporal, throwFormat};
+use anyhow::Result;
+use data_encoding::HEXLOWER;
+
+// list<T> column 落地的是各个元素依次的编码 元素不能是null <br>
+// inverted index 对每个不同的元素各有1条key: 元素的编码 + dataKey
+
+/// '["a", "b"]' 或是prepared statement的参数 ["a", "b"] 是json数组
+pub fn fromJson(json: &Value) -> Result<Vec<GraphValue>> {
+    let array =
+        match json.as_array() {
+            Some(array) => array,
+            None => throwFormat!("invalid list:{}, should be array", json),
+        };
+
+    let mut list = Vec::with_capacity(array.len());
+
+    for element in array {
+        list.push(GraphValue::try_from(element)?);
+    }
+
+    Ok(list)
+}
+
+/// list column 可以收到 list, '["a", "b"]', 或是json数组
+pub fn fromGraphValue(value: &GraphValue) -> Result<Vec<GraphValue>> {
+    match value {
+        GraphValue::List(list) => Ok(list.clone()),
+        GraphValue::String(s) => {
+            match serde_json::from_str::<Value>(s) {
+                Ok(json) => fromJson(&json),
+                Err(_) => throwFormat!("invalid list:'{}', should be like '[\"a\", \"b\"]'", s),
+            }
+        }
+        GraphValue::Json(json) => fromJson(json),
+        _ => throwFormat!("{value:?} can not be used as list"),
+    }
+}
+
+/// 和json数组相同的文本 cast(tags as string) 和 prepared statement 的参数绑定使用
+pub fn format(list: &[GraphValue]) -> String {
+    let array: Vec<Value> =
+        list.iter().map(|element| {
+            match element {
+                GraphValue::String(s) => Value::String(s.clone()),
+                GraphValue::Boolean(bool) => Value::Bool(*bool),
+                GraphValue::Integer(integer) => Value::from(*integer),
+                GraphValue::Double(double) => Value::from(*double),
+                GraphValue::Decimal(decimal) => Value::String(decimal.to_string()),
+                GraphValue::Timestamp(timestamp) => Value::String(temporal::formatTimestamp(*timestamp)),
+                GraphValue::Date(date) => Value::String(temporal::formatDate(*date)),
+                GraphValue::Bytes(bytes) => Value::String(HEXLOWER.encode(bytes)),
+                GraphValue::Json(json) => json.clone(),
+                _ => Value::Null,
+            }
+        }).collect();
+
+    Value::Array(array).to_string()
+}
+
+/// 元素之间使用 = 比较 integer和double之类的也能相等
+pub fn containsElement(list: &[GraphValue], target: &GraphValue) -> Result<bool> {
+    for element in list {
+        if element.calcOneToOne(Op::MathCmpOp(MathCmpOp::Equal), target)?.asBoolean()? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// tags contains ['a', 'b'] 要全都有, tags contains 'a' 是其中之1
+pub fn contains(list: &[GraphValue], right: &GraphValue) -> Result<bool> {
+    match right {
+        GraphValue::List(list0) => {
+            for element in list0 {
+                if containsElement(list, element)? == false {
+                    return Ok(false);
+                }
+            }
+
+            Ok(true)
+        }
+        GraphValue::Json(json @ Value::Array(_)) => contains(list, &GraphValue::List(fromJson(json)?)),
+        _ => containsElement(list, right),
+    }
+}
+
+/// tags any ['a', 'b'] 有其中的任意1个便可以, tags any 'a' 和 contains 'a' 相同
+pub fn any(list: &[GraphValue], right: &GraphValue) -> Result<bool> {
+    match right {
+        GraphValue::List(list0) => {
+            for element in list0 {
+                if containsElement(list, element)? {
+                    return Ok(true);
+                }
+            }
+
+            Ok(false)
+        }
+        GraphValue::Json(json @ Value::Array(_)) => any(list, &GraphValue::List(fromJson(json)?)),
+        _ => containsElement(list, right),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::graph_value::GraphValue;
+    use crate::session::Session;
+    use crate::{list, meta};
+
+    #[test]
+    pub fn testContainsAny() {
+        let s = |s: &str| GraphValue::String(s.to_string());
+
+        let tags = list::fromGraphValue(&s(r#"["rust", "db"]"#)).unwrap();
+        assert_eq!(tags, vec![s("rust"), s("db")]);
+        assert_eq!(list::format(&tags), r#"["rust","db"]"#);
+
+        assert!(list::contains(&tags, &s("rust")).unwrap());
+        assert!(list::contains(&tags, &GraphValue::List(vec![s("db"), s("rust")])).unwrap());
+        assert!(list::contains(&tags, &GraphValue::List(vec![s("db"), s("go")])).unwrap() == false);
+        assert!(list::contains(&tags, &GraphValue::List(vec![])).unwrap());
+
+        assert!(list::any(&tags, &GraphValue::List(vec![s("go"), s("db")])).unwrap());
+        assert!(list::any(&tags, &GraphValue::List(vec![s("go")])).unwrap() == false);
+        assert!(list::any(&tags, &GraphValue::List(vec![])).unwrap() == false);
+
+        let numbers = vec![GraphValue::Integer(1), GraphValue::Integer(2)];
+        assert!(list::contains(&numbers, &GraphValue::Double(2.0)).unwrap());
+
+        assert!(list::fromGraphValue(&s("rust")).is_err());
+        assert!(list::fromGraphValue(&GraphValue::Integer(1)).is_err());
+    }
+
+    #[test]
+    pub fn testListColumn() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table listPost");
+        session.executeSql("create table listPost (id integer, tags list<string>, scores list<integer>)").unwrap();
+        session.executeSql("create index listPostTags on listPost[tags]").unwrap();
+        session.executeSql("insert into listPost values (1, ['a', 'b'], [1, 2])").unwrap();
+        session.executeSql("insert into listPost values (2, ['b', 'c'], [])").unwrap();
+        session.executeSql("insert into listPost values (3, [], null)").unwrap();
+
+        let ids = |session: &mut Session, sql: &str| -> Vec<i64> {
+            let mut ids: Vec<i64> = session.executeSql(sql).unwrap().remove(0).iter().map(|row| row["id"].as_i64().unwrap()).collect();
+            ids.sort();
+            ids
+        };
+
+        // 落地后再读出来 空的list和null是不同的
+        assert_eq!(session.executeSql("select listPost order by id").unwrap().remove(0),
+                   vec![json!({"id": 1, "tags": ["a", "b"], "scores": [1, 2]}), json!({"id": 2, "tags": ["b", "c"], "scores": []}), json!({"id": 3, "tags": [], "scores": null})]);
+
+        let explain = session.executeSql("explain select listPost[id](tags contains 'a')").unwrap().remove(0);
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("invertedIndex"));
+        assert_eq!(ids(&mut session, "select listPost[id](tags contains 'b')"), vec![1, 2]);
+        assert_eq!(ids(&mut session, "select listPost[id](tags any ['a', 'c'])"), vec![1, 2]);
+        assert_eq!(ids(&mut session, "select listPost[id](scores contains 2)"), vec![1]);
+
+        // update后 inverted index也要跟着变
+        session.executeSql("update listPost[tags = ['d']](id = 3)").unwrap();
+        assert_eq!(ids(&mut session, "select listPost[id](tags contains 'd')"), vec![3]);
+
+        session.executeSql("drop table listPost").unwrap();
+    }
+}
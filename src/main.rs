@@ -14,6 +14,13 @@ mod global;
 mod meta;
 mod expr;
 mod graph_value;
+mod function;
+mod temporal;
+mod decimal;
+mod json;
+mod vector;
+mod list;
+mod geo;
 mod session;
 mod codec;
 mod utils;
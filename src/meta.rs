@@ -8,8 +8,9 @@ use serde::{Deserialize, Serialize};
 use tokio::fs::{File, OpenOptions};
 use crate::graph_error::GraphError;
 use crate::{byte_slice_to_u64, file_goto_start, suffix_plus_plus, throw, throwFormat, u64ToByteArrRef};
-use crate::{global, meta};
+use crate::{decimal, geo, global, json, list, meta, temporal, vector};
 use anyhow::Result;
+use rust_decimal::Decimal;
 use tokio::fs;
 use std::path::Path;
 use std::sync::Arc;
@@ -18,7 +19,7 @@ use dashmap::DashMap;
 use lazy_static::lazy_static;
 use rocksdb::{BoundColumnFamily, ColumnFamilyDescriptor, DB, DBCommon};
 use rocksdb::{DBRawIteratorWithThreadMode, IteratorMode, MultiThreaded, OptimisticTransactionDB, Options};
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader};
 use crate::config::CONFIG;
 use crate::graph_value::GraphValue;
@@ -38,6 +39,8 @@ lazy_static! {
     /// db启动的时候设置的原先已使用的最大的txId
     pub static ref TX_ID_START_UP: TrickyContainer<TxId> = TrickyContainer::new();
     pub static ref TX_UNDERGOING_COUNT:AtomicU64 = AtomicU64::default();
+    /// 有unique index或primary key要校验的tx 它们的commit要串行 不然同时提交的相互看不到对方写入的
+    pub static ref COMMIT_LOCK: Mutex<()> = Mutex::new(());
 
     pub static ref DATA_KEY_PATTERN_VEC: Vec<Byte> = DATA_KEY_PATTERN.to_vec();
     pub static ref POINTER_KEY_PATTERN_VEC :Vec<Byte> = POINTER_KEY_PATTERN.to_vec();
@@ -150,6 +153,11 @@ pub const COLUMN_FAMILY_NAME_TX_ID: &str = "TX_ID";
 /// tableId(数字) -> dbObject的json文本
 pub const COLUMN_FAMILY_NAME_META: &str = "META";
 
+/// 落地数据的格式版本 保存在default columnFamily <br>
+/// 1: integer decimal 的binary改为了order preserving的 和之前的不兼容
+pub const STORE_FORMAT_VERSION: u64 = 1;
+pub const STORE_FORMAT_VERSION_KEY: &[Byte] = b"STORE_FORMAT_VERSION";
+
 pub fn isVisible(currentTxId: TxId, xmin: TxId, xmax: TxId) -> bool {
     // invisible
     if currentTxId >= xmax {
@@ -404,6 +412,12 @@ pub struct Table {
     #[serde(skip_serializing, skip_deserializing)]
     pub createIfNotExist: bool,
     pub indexNames: Vec<String>,
+    /// primary key (id) 涉及的column 没有primary key的话是empty
+    #[serde(default)]
+    pub primaryKeyColumnNames: Vec<String>,
+    /// primary key 对应的column family, key是各个primary key column的值 value是dataKey
+    #[serde(default)]
+    pub primaryKeyId: DBObjectId,
     #[serde(skip_serializing, skip_deserializing)]
     pub invalid: bool,
 }
@@ -423,6 +437,11 @@ impl Table {
     pub fn nextRowId(&self) -> RowId {
         self.rowIdCounter.fetch_add(1, Ordering::AcqRel)
     }
+
+    #[inline]
+    pub fn hasPrimaryKey(&self) -> bool {
+        self.primaryKeyColumnNames.is_empty() == false
+    }
 }
 
 impl Clone for Table {
@@ -434,6 +453,8 @@ impl Clone for Table {
             rowIdCounter: AtomicU64::new(self.rowIdCounter.load(Ordering::Acquire)),
             createIfNotExist: self.createIfNotExist,
             indexNames: self.indexNames.clone(),
+            primaryKeyColumnNames: self.primaryKeyColumnNames.clone(),
+            primaryKeyId: self.primaryKeyId,
             invalid: self.invalid,
         }
     }
@@ -486,12 +507,28 @@ impl PartialEq for Column {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Default, Copy)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Default)]
 pub enum ColumnType {
     #[default]
     String,
     Integer,
-    Decimal,
+    /// f64
+    Double,
+    Boolean,
+    Timestamp,
+    Date,
+    /// 精确的定点数 (precision, scale), None的话不限定
+    Decimal(Option<(u32, u32)>),
+    /// 变长的binary 只能用index做等值查找
+    Bytes,
+    /// 和bytes相同 只能用index做等值查找
+    Json,
+    /// 固定维度的f32数组 只能建ann index
+    Vector(u32),
+    /// 元素的type 不能是list和vector, 元素不能是null, 只能建inverted index
+    List(Box<ColumnType>),
+    /// 纬度经度 只能建spatial index
+    Point,
 }
 
 impl ColumnType {
@@ -499,12 +536,117 @@ impl ColumnType {
         match (self, columnValue) {
             (ColumnType::String, GraphValue::String(_)) => true,
             (ColumnType::Integer, GraphValue::Integer(_)) => true,
-            (ColumnType::Decimal, GraphValue::Decimal(_)) => true,
+            (ColumnType::Double, GraphValue::Double(_)) => true,
+            // integer decimal 会提升成 double
+            (ColumnType::Double, GraphValue::Integer(_) | GraphValue::Decimal(_)) => true,
+            // integer 会提升成 decimal
+            (ColumnType::Decimal(_), GraphValue::Decimal(_) | GraphValue::Integer(_)) => true,
+            (ColumnType::Boolean, GraphValue::Boolean(_)) => true,
+            (ColumnType::Timestamp, GraphValue::Timestamp(_)) => true,
+            // date 会提升成当天0点的 timestamp
+            (ColumnType::Timestamp, GraphValue::Date(_)) => true,
+            (ColumnType::Date, GraphValue::Date(_)) => true,
+            (ColumnType::Bytes, GraphValue::Bytes(_)) => true,
+            // string 会parse成 json
+            (ColumnType::Json, GraphValue::Json(_) | GraphValue::String(_)) => true,
+            // '[1, 2]' 和json数组 会parse成 vector
+            (ColumnType::Vector(_), GraphValue::Vector(_) | GraphValue::String(_) | GraphValue::Json(_)) => true,
+            // '["a", "b"]' 和json数组 会parse成 list
+            (ColumnType::List(_), GraphValue::List(_) | GraphValue::String(_) | GraphValue::Json(_)) => true,
+            // '[30.27, 120.15]' 和 '{"lat": 30.27, "lon": 120.15}' 会parse成 point
+            (ColumnType::Point, GraphValue::Point(_) | GraphValue::String(_) | GraphValue::Json(_)) => true,
             (_, GraphValue::Null) => true,
             _ => false
         }
     }
 
+    /// decimal的column收到了integer的话要转换成decimal 保证落地的data和index的type和column相同 <br>
+    /// decimal只会在不丢失精度的情况下变为column的scale index seek 直接使用
+    pub fn promoteValue(&self, columnValue: &mut GraphValue) {
+        match (self, &*columnValue) {
+            (ColumnType::Double, GraphValue::Integer(integer)) => *columnValue = GraphValue::Double(*integer as f64),
+            (ColumnType::Double, GraphValue::Decimal(decimal)) => *columnValue = GraphValue::Double(decimal::toF64(decimal)),
+            (ColumnType::Decimal(precisionScale), GraphValue::Integer(integer)) => {
+                *columnValue = GraphValue::Decimal(decimal::fitLossless(Decimal::from(*integer), *precisionScale))
+            }
+            (ColumnType::Decimal(precisionScale), GraphValue::Decimal(decimal)) => {
+                *columnValue = GraphValue::Decimal(decimal::fitLossless(*decimal, *precisionScale))
+            }
+            (ColumnType::Timestamp, GraphValue::Date(_)) => *columnValue = GraphValue::Timestamp(columnValue.temporalMicros().unwrap()),
+            (ColumnType::Json, GraphValue::String(s)) => {
+                if let Ok(json) = json::parse(s) {
+                    *columnValue = GraphValue::Json(json);
+                }
+            }
+            (ColumnType::Vector(_), GraphValue::String(_) | GraphValue::Json(_)) => {
+                if let Ok(vector) = vector::fromGraphValue(columnValue) {
+                    *columnValue = GraphValue::Vector(vector);
+                }
+            }
+            (ColumnType::List(_), GraphValue::String(_) | GraphValue::Json(_)) => {
+                if let Ok(list) = list::fromGraphValue(columnValue) {
+                    *columnValue = GraphValue::List(list);
+                }
+            }
+            (ColumnType::Point, GraphValue::String(_) | GraphValue::Json(_)) => {
+                if let Ok(point) = geo::fromGraphValue(columnValue) {
+                    *columnValue = GraphValue::Point(point);
+                }
+            }
+            _ => {}
+        }
+
+        // 各个元素也要按照元素的type
+        if let (ColumnType::List(elementType), GraphValue::List(list)) = (self, columnValue) {
+            for element in list {
+                elementType.promoteValue(element);
+            }
+        }
+    }
+
+    /// 落地之前 decimal还要按照column的scale四舍五入 超出了precision的话报错
+    pub fn fitValue(&self, columnValue: &mut GraphValue) -> Result<()> {
+        self.promoteValue(columnValue);
+
+        match (self, &*columnValue) {
+            (ColumnType::Decimal(precisionScale), GraphValue::Decimal(decimal)) => {
+                *columnValue = GraphValue::Decimal(decimal::fit(*decimal, *precisionScale)?)
+            }
+            // 不是合法的json
+            (ColumnType::Json, GraphValue::String(s)) => {
+                json::parse(s)?;
+            }
+            (ColumnType::Vector(_), GraphValue::String(_) | GraphValue::Json(_)) => {
+                vector::fromGraphValue(columnValue)?;
+            }
+            (ColumnType::Vector(dimension), GraphValue::Vector(vector)) => {
+                if vector.len() != *dimension as usize {
+                    throwFormat!("expected {} dimensions, but get {}", dimension, vector.len());
+                }
+            }
+            (ColumnType::List(_), GraphValue::String(_) | GraphValue::Json(_)) => {
+                list::fromGraphValue(columnValue)?;
+            }
+            (ColumnType::Point, GraphValue::String(_) | GraphValue::Json(_)) => {
+                geo::fromGraphValue(columnValue)?;
+            }
+            _ => {}
+        }
+
+        if let (ColumnType::List(elementType), GraphValue::List(list)) = (self, columnValue) {
+            for element in list {
+                if let GraphValue::Null = element {
+                    throw!("list element can not be null");
+                }
+
+                elementType.shouldCompatibleWithValue(element)?;
+                elementType.fitValue(element)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn shouldCompatibleWithValue(&self, columnValue: &GraphValue) -> Result<()> {
         if self.compatibleWithValue(columnValue) == false {
             throwFormat!("column type: {:?} and value: {:?} are not compatible", self, columnValue);
@@ -517,12 +659,55 @@ impl ColumnType {
         match (self, element) {
             (ColumnType::String, Element::StringContent(_)) => true,
             (ColumnType::Integer, Element::IntegerLiteral(_)) => true,
-            (ColumnType::Decimal, Element::DecimalLiteral(_)) => true,
+            (ColumnType::Double, Element::DoubleLiteral(_) | Element::DecimalLiteral(_) | Element::IntegerLiteral(_)) => true,
+            (ColumnType::Decimal(_), Element::DecimalLiteral(_) | Element::IntegerLiteral(_)) => true,
+            (ColumnType::Boolean, Element::Boolean(_)) => true,
+            (ColumnType::Timestamp, Element::TimestampLiteral(_) | Element::DateLiteral(_)) => true,
+            (ColumnType::Date, Element::DateLiteral(_)) => true,
+            (ColumnType::Bytes, Element::BytesLiteral(_)) => true,
+            (ColumnType::Json, Element::StringContent(_)) => true,
+            (ColumnType::Vector(_), Element::StringContent(_)) => true,
+            (ColumnType::List(_), Element::StringContent(_)) => true,
+            (ColumnType::Point, Element::StringContent(_)) => true,
             (_, Element::Null) => true,
             _ => false
         }
     }
 
+    /// 和fitValue相同 用在default value
+    pub fn promoteElement(&self, element: &mut Element) -> Result<()> {
+        match (self, &*element) {
+            (ColumnType::Double, Element::IntegerLiteral(integer)) => *element = Element::DoubleLiteral(*integer as f64),
+            (ColumnType::Double, Element::DecimalLiteral(decimal)) => *element = Element::DoubleLiteral(decimal::toF64(decimal)),
+            (ColumnType::Decimal(precisionScale), Element::IntegerLiteral(integer)) => {
+                *element = Element::DecimalLiteral(decimal::fit(Decimal::from(*integer), *precisionScale)?)
+            }
+            (ColumnType::Decimal(precisionScale), Element::DecimalLiteral(decimal)) => {
+                *element = Element::DecimalLiteral(decimal::fit(*decimal, *precisionScale)?)
+            }
+            (ColumnType::Timestamp, Element::DateLiteral(days)) => *element = Element::TimestampLiteral(*days * temporal::MICROS_PER_DAY),
+            // 还是string 落地的时候再parse
+            (ColumnType::Json, Element::StringContent(s)) => {
+                json::parse(s)?;
+            }
+            (ColumnType::Vector(dimension), Element::StringContent(s)) => {
+                let vector = vector::parse(s)?;
+                if vector.len() != *dimension as usize {
+                    throwFormat!("expected {} dimensions, but get {}", dimension, vector.len());
+                }
+            }
+            (ColumnType::List(_), Element::StringContent(s)) => {
+                self.fitValue(&mut GraphValue::String(s.clone()))?;
+            }
+            (ColumnType::Point, Element::StringContent(s)) => {
+                geo::parse(s)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     pub fn shouldCompatibleWithElement(&self, element: &Element) -> Result<()> {
         if self.compatibleWithElement(element) == false {
             throwFormat!("column type: {:?} and element: {:?} are not compatible", self, element);
@@ -533,9 +718,13 @@ impl ColumnType {
 
     pub fn graphValueSize(&self) -> Option<usize> {
         match self {
-            ColumnType::String => None,
+            ColumnType::String | ColumnType::Bytes | ColumnType::Json | ColumnType::Vector(_) | ColumnType::List(_) => None,
             ColumnType::Integer => Some(GraphValue::TYPE_BYTE_LEN + size_of::<i64>()),
-            ColumnType::Decimal => Some(GraphValue::TYPE_BYTE_LEN + size_of::<f64>())
+            ColumnType::Double => Some(GraphValue::TYPE_BYTE_LEN + size_of::<f64>()),
+            ColumnType::Point => Some(GraphValue::TYPE_BYTE_LEN + 2 * size_of::<f64>()),
+            ColumnType::Decimal(_) => Some(GraphValue::TYPE_BYTE_LEN + decimal::ENCODED_LEN),
+            ColumnType::Boolean => Some(GraphValue::TYPE_BYTE_LEN + size_of::<Byte>()),
+            ColumnType::Timestamp | ColumnType::Date => Some(GraphValue::TYPE_BYTE_LEN + size_of::<i64>()),
         }
     }
 }
@@ -544,10 +733,57 @@ impl FromStr for ColumnType {
     type Err = GraphError;
 
     fn from_str(str: &str) -> Result<Self, Self::Err> {
-        match str.to_lowercase().as_str() {
+        let lowercase: String = str.to_lowercase().split_whitespace().collect();
+
+        // decimal(10,2) decimal(10)
+        if let Some(precisionScale) = lowercase.strip_prefix("decimal(").and_then(|s| s.strip_suffix(')')) {
+            let numbers: Vec<Option<u32>> = precisionScale.split(',').map(|number| number.parse::<u32>().ok()).collect();
+
+            let (precision, scale) =
+                match numbers.as_slice() {
+                    [Some(precision)] => (*precision, 0),
+                    [Some(precision), Some(scale)] => (*precision, *scale),
+                    _ => throwFormat!("unknown type:{}", str),
+                };
+
+            if precision == 0 || precision > decimal::MAX_PRECISION || scale > precision {
+                throwFormat!("decimal precision should be 1 to {}, scale should be 0 to precision, but get {}", decimal::MAX_PRECISION, str);
+            }
+
+            return Ok(ColumnType::Decimal(Some((precision, scale))));
+        }
+
+        // vector(3)
+        if let Some(dimension) = lowercase.strip_prefix("vector(").and_then(|s| s.strip_suffix(')')) {
+            return match dimension.parse::<u32>() {
+                Ok(dimension) if dimension >= 1 && dimension <= vector::MAX_DIMENSION => Ok(ColumnType::Vector(dimension)),
+                _ => throwFormat!("vector dimension should be 1 to {}, but get {}", vector::MAX_DIMENSION, str),
+            };
+        }
+
+        // list<string>
+        if let Some(elementType) = lowercase.strip_prefix("list<").and_then(|s| s.strip_suffix('>')) {
+            let elementType: ColumnType = elementType.parse()?;
+
+            if let ColumnType::List(_) | ColumnType::Vector(_) = elementType {
+                throwFormat!("list element type can not be list or vector, but get {}", str);
+            }
+
+            return Ok(ColumnType::List(Box::new(elementType)));
+        }
+
+        match lowercase.as_str() {
             "string" => Ok(ColumnType::String),
             "integer" => Ok(ColumnType::Integer),
-            "decimal" => Ok(ColumnType::Decimal),
+            // 以前的decimal其实是f64 现在是精确的定点数 浮点数使用double
+            "decimal" => Ok(ColumnType::Decimal(None)),
+            "double" => Ok(ColumnType::Double),
+            "boolean" => Ok(ColumnType::Boolean),
+            "timestamp" => Ok(ColumnType::Timestamp),
+            "date" => Ok(ColumnType::Date),
+            "bytes" => Ok(ColumnType::Bytes),
+            "json" => Ok(ColumnType::Json),
+            "point" => Ok(ColumnType::Point),
             _ => throw!(&format!("unknown type:{}", str))
         }
     }
@@ -558,7 +794,17 @@ impl Display for ColumnType {
         match self {
             ColumnType::String => write!(f, "STRING"),
             ColumnType::Integer => write!(f, "INTEGER"),
-            ColumnType::Decimal => write!(f, "DECIMAL"),
+            ColumnType::Double => write!(f, "DOUBLE"),
+            ColumnType::Decimal(None) => write!(f, "DECIMAL"),
+            ColumnType::Decimal(Some((precision, scale))) => write!(f, "DECIMAL({}, {})", precision, scale),
+            ColumnType::Boolean => write!(f, "BOOLEAN"),
+            ColumnType::Timestamp => write!(f, "TIMESTAMP"),
+            ColumnType::Date => write!(f, "DATE"),
+            ColumnType::Bytes => write!(f, "BYTES"),
+            ColumnType::Json => write!(f, "JSON"),
+            ColumnType::Vector(dimension) => write!(f, "VECTOR({})", dimension),
+            ColumnType::List(elementType) => write!(f, "LIST<{}>", elementType),
+            ColumnType::Point => write!(f, "POINT"),
         }
     }
 }
@@ -572,10 +818,29 @@ pub struct Index {
     pub createIfNotExist: bool,
     pub tableName: String,
     pub columnNames: Vec<String>,
+    /// vector column 上的是ann index(ivf) 这是kmeans得到的各个list的中心 普通的index是None
+    #[serde(default)]
+    pub ivfCentroids: Option<Vec<Vec<f32>>>,
+    /// list column 上的是inverted index 每个不同的元素各有1条key
+    #[serde(default)]
+    pub inverted: bool,
+    /// point column 上的是spatial index key是 geohash(u64) + dataKey
+    #[serde(default)]
+    pub spatial: bool,
+    /// create unique index 建立的 insert和update时候不能有相同的(非null)值
+    #[serde(default)]
+    pub unique: bool,
     #[serde(skip_serializing, skip_deserializing)]
     pub invalid: bool,
 }
 
+impl Index {
+    /// ann index 的key是 listNo(u32) + dataKey 不能用在filter和普通的order by
+    pub fn isAnn(&self) -> bool {
+        self.ivfCentroids.is_some()
+    }
+}
+
 impl DBObjectTrait for Index {
     fn invalidate(&mut self) {
         self.invalid = true;
@@ -619,6 +884,8 @@ pub fn init() -> Result<()> {
 
         log::info!("column family: {COLUMN_FAMILY_NAME_META}, {COLUMN_FAMILY_NAME_TX_ID} created");
 
+        db.put(STORE_FORMAT_VERSION_KEY, u64ToByteArrRef!(STORE_FORMAT_VERSION))?;
+
         STORE.set(db);
 
         return Ok(());
@@ -638,6 +905,8 @@ pub fn init() -> Result<()> {
 
     let db = DB::open_cf_descriptors(&rocksDbOpts, dbDataDir, cfDescs)?;
 
+    checkStoreFormatVersion(&db, dbDataDir)?;
+
     // 遍历META
     let dbObjectVec = {
         let mut latestDBObjectId = DBObjectId::default();
@@ -658,10 +927,17 @@ pub fn init() -> Result<()> {
                 throw!("table记录的key和table中的tableId不同");
             }
 
-            dbObjectVec.push(dbObject);
-
             // key是以binary由大到小排序的 也便是table id由大到小排序
             latestDBObjectId = dbObjectId;
+
+            // index的trash 和 table的primary key 也占用了id 不过不是meta的key
+            match &dbObject {
+                DBObject::Index(index) => latestDBObjectId = latestDBObjectId.max(index.trashId),
+                DBObject::Table(table) => latestDBObjectId = latestDBObjectId.max(table.primaryKeyId),
+                _ => {}
+            }
+
+            dbObjectVec.push(dbObject);
         }
 
         DB_OBJECT_ID_COUNTER.store(latestDBObjectId + 1, Ordering::Release);
@@ -713,6 +989,46 @@ pub fn init() -> Result<()> {
     Ok(())
 }
 
+/// test都在同1个进程里边 store只能打开1趟 要执行sql的test都调用这个 <br>
+/// 同时undergoing的tx只能有TX_UNDERGOING_MAX_COUNT个 执行sql的test要串行 返回的guard要持有到test结束
+#[cfg(test)]
+pub fn initOnce() -> std::sync::MutexGuard<'static, ()> {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    static SQL_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    INIT.call_once(|| init().unwrap());
+
+    // 前边的test panic了不影响后边的
+    SQL_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 落地数据的格式和当前的不同的话不能打开 不然integer decimal会decode出错误的值 index也会seek到错误的位置 <br>
+/// 没有版本的是格式1之前的 要是还没有任何的table之类的可以直接升级
+fn checkStoreFormatVersion(db: &DB, dbDataDir: &str) -> Result<()> {
+    let storeFormatVersion =
+        match db.get(STORE_FORMAT_VERSION_KEY)? {
+            Some(storeFormatVersion) => byte_slice_to_u64!(storeFormatVersion.as_slice()),
+            None => {
+                let columnFamilyMeta = db.cf_handle(COLUMN_FAMILY_NAME_META).unwrap();
+
+                if db.iterator_cf(&columnFamilyMeta, IteratorMode::Start).next().is_none() {
+                    db.put(STORE_FORMAT_VERSION_KEY, u64ToByteArrRef!(STORE_FORMAT_VERSION))?;
+                    return Ok(());
+                }
+
+                0
+            }
+        };
+
+    if storeFormatVersion != STORE_FORMAT_VERSION {
+        throwFormat!("database directory {} has store format version {}, but current is {}, it can not be opened, \
+                     please export the data with the old version and import it into a new database directory",
+                     dbDataDir, storeFormatVersion, STORE_FORMAT_VERSION);
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use std::thread;
@@ -749,7 +1065,7 @@ mod test {
 
     #[test]
     pub fn manauallyExecuteSql() -> anyhow::Result<()> {
-        meta::init()?;
+        let _sqlTestGuard = meta::initOnce();
 
         let runtime = Builder::new_current_thread().enable_all().build()?;
         runtime.block_on(async {
@@ -799,6 +1115,39 @@ mod test {
         tx1.commit().unwrap();
     }
 
+    #[test]
+    pub fn testStoreFormatVersion() -> anyhow::Result<()> {
+        let dbDataDir = "test_store_format_version";
+        let _ = std::fs::remove_dir_all(dbDataDir);
+
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        {
+            let db = DB::open_cf(&options, dbDataDir, [meta::COLUMN_FAMILY_NAME_META])?;
+
+            // 空的老版本的直接升级
+            meta::checkStoreFormatVersion(&db, dbDataDir)?;
+            assert_eq!(db.get(meta::STORE_FORMAT_VERSION_KEY)?.unwrap(), meta::STORE_FORMAT_VERSION.to_be_bytes());
+
+            // 已有table的老版本的不能打开
+            db.delete(meta::STORE_FORMAT_VERSION_KEY)?;
+            db.put_cf(&db.cf_handle(meta::COLUMN_FAMILY_NAME_META).unwrap(), 1u64.to_be_bytes(), b"{}")?;
+            assert!(meta::checkStoreFormatVersion(&db, dbDataDir).is_err());
+
+            db.put(meta::STORE_FORMAT_VERSION_KEY, (meta::STORE_FORMAT_VERSION + 1).to_be_bytes())?;
+            assert!(meta::checkStoreFormatVersion(&db, dbDataDir).is_err());
+
+            db.put(meta::STORE_FORMAT_VERSION_KEY, meta::STORE_FORMAT_VERSION.to_be_bytes())?;
+            meta::checkStoreFormatVersion(&db, dbDataDir)?;
+        }
+
+        std::fs::remove_dir_all(dbDataDir)?;
+
+        Ok(())
+    }
+
     #[test]
     pub fn testWriteBatch() {
         let mut options = Options::default();
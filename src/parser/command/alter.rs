@@ -48,7 +48,7 @@ impl Parser {
                                 // alter table car add columns (id integer not null default 0,name string)
                                 "columns" => Alter::AlterTable(AlterTable::AddColumns {
                                     tableName,
-                                    columns2Add: self.parseColumnDefinitions()?,
+                                    columns2Add: self.parseColumnDefinitions(None)?,
                                 }),
                                 _ => self.throwSyntaxErrorDetail("not support")?
                             }
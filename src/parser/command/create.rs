@@ -1,7 +1,8 @@
 use crate::{global, throw, throwFormat, utils};
-use crate::meta::{Column, DBObject, Index, Table, TableType};
+use crate::meta::{Column, ColumnType, DBObject, Index, Table, TableType};
 use crate::parser::command::Command;
 use crate::parser::element::Element;
+use crate::parser::op::{MathCmpOp, Op};
 use crate::parser::Parser;
 use anyhow::Result;
 use crate::graph_value::GraphValue;
@@ -15,12 +16,20 @@ impl Parser {
 
         match dbObjectType.as_str() {
             DBObject::RELATION | DBObject::TABLE => self.parseCreateTable(dbObjectType.as_str()),
-            DBObject::INDEX => self.parseCreateIndex(),
+            DBObject::INDEX => self.parseCreateIndex(false),
+            // create unique index
+            "unique" => {
+                self.getCurrentElementAdvance()?
+                    .expectTextLiteralContentIgnoreCase(DBObject::INDEX, "unique should followed by index")?;
+
+                self.parseCreateIndex(true)
+            }
             _ => self.throwSyntaxErrorDetail(&format!("unknow database object {}", dbObjectType))?
         }
     }
 
     /// create table if not exist user (id integer not null default 0,name string) <br>
+    /// create table user (id integer, name string, primary key (id)) <br>
     /// 因为relation和table的结构是相同的 共用
     fn parseCreateTable(&mut self, dbObjectType: &str) -> Result<Command> {
         let mut table = Table::default();
@@ -48,7 +57,11 @@ impl Parser {
         // table名不能胡乱
         self.checkDbObjectName(&table.name)?;
 
-        table.columns = self.parseColumnDefinitions()?;
+        table.columns = self.parseColumnDefinitions(Some(&mut table.primaryKeyColumnNames))?;
+
+        if dbObjectType == DBObject::RELATION && table.primaryKeyColumnNames.is_empty() == false {
+            self.throwSyntaxErrorDetail("relation can not have primary key")?;
+        }
 
         if dbObjectType == DBObject::TABLE {
             Ok(Command::CreateTable(table))
@@ -57,7 +70,8 @@ impl Parser {
         }
     }
 
-    pub(super) fn parseColumnDefinitions(&mut self) -> Result<Vec<Column>> {
+    /// primaryKeyColumnNames 是none的话说明不能有 primary key (id)
+    pub(super) fn parseColumnDefinitions(&mut self, mut primaryKeyColumnNames: Option<&mut Vec<String>>) -> Result<Vec<Column>> {
         let mut columns = Vec::new();
 
         // 应该是"("
@@ -74,6 +88,9 @@ impl Parser {
         let mut readColumnState = ReadColumnState::ReadColumnName;
         let mut column = Column::default();
 
+        // 刚读取的是 primary key (id) 不是column
+        let mut readPrimaryKey = false;
+
         loop {
             let element = self.getCurrentElementAdvanceOption();
             if element.is_none() {
@@ -87,13 +104,102 @@ impl Parser {
 
                     match readColumnState {
                         ReadColumnState::ReadColumnName => {
+                            // primary key (id, name)
+                            if text.eq_ignore_ascii_case("primary") &&
+                                self.getCurrentElementOption().map_or(false, |element| element.expectTextLiteralContentIgnoreCaseBool("key")) {
+                                self.skipElement(1)?;
+
+                                let primaryKeyColumnNames =
+                                    match primaryKeyColumnNames.as_deref_mut() {
+                                        Some(primaryKeyColumnNames) => primaryKeyColumnNames,
+                                        None => self.throwSyntaxErrorDetail("primary key can not be defined here")?,
+                                    };
+
+                                if primaryKeyColumnNames.is_empty() == false {
+                                    self.throwSyntaxErrorDetail("primary key has already been defined")?;
+                                }
+
+                                // 读取(
+                                self.getCurrentElementAdvance()?
+                                    .expectTextLiteralContent(global::圆括号_STR)?;
+
+                                loop {
+                                    let columnName =
+                                        self.getCurrentElementAdvance()?
+                                            .expectTextLiteral(global::EMPTY_STR)?;
+
+                                    match columnName.as_str() {
+                                        global::逗号_STR => continue,
+                                        global::圆括号1_STR => break,
+                                        _ => {}
+                                    }
+
+                                    primaryKeyColumnNames.push(columnName);
+                                }
+
+                                if primaryKeyColumnNames.is_empty() {
+                                    self.throwSyntaxErrorDetail("primary key has no columns")?;
+                                }
+
+                                readPrimaryKey = true;
+                                readColumnState = ReadColumnState::ReadComplete;
+
+                                continue;
+                            }
+
                             self.checkDbObjectName(&text)?;
                             column.name = text;
                             readColumnState = ReadColumnState::ReadColumnType;
                         }
                         ReadColumnState::ReadColumnType => {
+                            let mut typeText = text;
+
+                            // decimal(10, 2) 要把括号里边的带上
+                            if let Some(element) = self.getCurrentElementOption() {
+                                if element.expectTextLiteralContentBool(global::圆括号_STR) {
+                                    loop {
+                                        match self.getCurrentElementAdvance()?.clone() {
+                                            Element::TextLiteral(text) => {
+                                                typeText.push_str(&text);
+
+                                                if text == global::圆括号1_STR {
+                                                    break;
+                                                }
+                                            }
+                                            Element::IntegerLiteral(integer) => typeText.push_str(&integer.to_string()),
+                                            _ => self.throwSyntaxError()?,
+                                        }
+                                    }
+                                }
+                            }
+
+                            // list<string> list<decimal(10, 2)> 要把尖括号里边的带上
+                            if let Some(Element::Op(Op::MathCmpOp(MathCmpOp::LessThan))) = self.getCurrentElementOption() {
+                                let mut 尖括号count = 0;
+
+                                loop {
+                                    match self.getCurrentElementAdvance()?.clone() {
+                                        Element::Op(Op::MathCmpOp(MathCmpOp::LessThan)) => {
+                                            typeText.push_str(global::小于_STR);
+                                            尖括号count += 1;
+                                        }
+                                        Element::Op(Op::MathCmpOp(MathCmpOp::GreaterThan)) => {
+                                            typeText.push_str(global::大于_STR);
+                                            尖括号count -= 1;
+
+                                            if 尖括号count == 0 {
+                                                break;
+                                            }
+                                        }
+                                        Element::TextLiteral(text) => typeText.push_str(&text),
+                                        Element::IntegerLiteral(integer) => typeText.push_str(&integer.to_string()),
+                                        _ => self.throwSyntaxError()?,
+                                    }
+                                }
+                            }
+
                             // 对应columnType的 from_str
-                            column.type0 = text.as_str().parse()?;
+                            column.type0 = typeText.as_str().parse()?;
 
                             // 应对跟在column type 后边的 not null 和 default value
 
@@ -148,9 +254,10 @@ impl Parser {
                                     }
                                     ReadColumnConstrainState::ReadDefault => {
                                         // column type 要和 default value 兼容
-                                        let element = self.getCurrentElementAdvance()?;
-                                        column.type0.shouldCompatibleWithElement(element)?;
-                                        column.defaultValue = Some(element.clone());
+                                        let mut element = self.getCurrentElementAdvance()?.clone();
+                                        column.type0.shouldCompatibleWithElement(&element)?;
+                                        column.type0.promoteElement(&mut element)?;
+                                        column.defaultValue = Some(element);
                                     }
                                 }
                             }
@@ -160,13 +267,20 @@ impl Parser {
                                 global::逗号_STR => {
                                     readColumnState = ReadColumnState::ReadColumnName;
 
-                                    columns.push(column);
-                                    column = Column::default();
+                                    if readPrimaryKey == false {
+                                        columns.push(column);
+                                        column = Column::default();
+                                    }
+
+                                    readPrimaryKey = false;
 
                                     continue;
                                 }
                                 global::圆括号1_STR => {
-                                    columns.push(column);
+                                    if readPrimaryKey == false {
+                                        columns.push(column);
+                                    }
+
                                     break;
                                 }
                                 _ => self.throwSyntaxError()?,
@@ -178,19 +292,50 @@ impl Parser {
             }
         }
 
-        if utils::hasDup(&mut columns,
-                         |prev, next| prev.name.cmp(&next.name),
-                         |prev, next| prev.name == next.name,
+        // hasDup会排序 不能打乱column原来的顺序
+        let mut columnNames: Vec<&String> = columns.iter().map(|column| &column.name).collect();
+
+        if utils::hasDup(&mut columnNames,
+                         |prev, next| prev.cmp(next),
+                         |prev, next| prev == next,
         ) {
             throw!("has duplicated column names");
         }
 
+        // primary key 涉及的column 要存在 且是 not null 的
+        if let Some(primaryKeyColumnNames) = primaryKeyColumnNames.filter(|primaryKeyColumnNames| primaryKeyColumnNames.is_empty() == false) {
+            let mut names: Vec<&String> = primaryKeyColumnNames.iter().collect();
+
+            if utils::hasDup(&mut names,
+                             |prev, next| prev.cmp(next),
+                             |prev, next| prev == next,
+            ) {
+                throw!("primary key has duplicated column names");
+            }
+
+            for primaryKeyColumnName in primaryKeyColumnNames.iter() {
+                let column =
+                    match columns.iter_mut().find(|column| &column.name == primaryKeyColumnName) {
+                        Some(column) => column,
+                        None => throwFormat!("primary key column: {} is not defined", primaryKeyColumnName),
+                    };
+
+                if matches!(column.type0, ColumnType::Vector(_) | ColumnType::List(_) | ColumnType::Json) {
+                    throwFormat!("column: {}, type: {} can not be used as primary key", column.name, column.type0);
+                }
+
+                column.nullable = false;
+            }
+        }
+
         Ok(columns)
     }
 
-    /// ```create index aaa on user[id, name] ```
-    fn parseCreateIndex(&mut self) -> Result<Command> {
+    /// ```create index aaa on user[id, name] ``` <br>
+    /// ```create unique index aaa on user[email] ```
+    fn parseCreateIndex(&mut self, unique: bool) -> Result<Command> {
         let mut index = Index::default();
+        index.unique = unique;
 
         index.name =
             self.getCurrentElementAdvance()?
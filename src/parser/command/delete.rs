@@ -3,7 +3,7 @@ use crate::expr::Expr;
 use crate::parser::command::Command;
 use crate::parser::Parser;
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct Delete {
     pub tableName: String,
     pub filterExpr: Option<Expr>,
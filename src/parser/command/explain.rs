@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use crate::parser::command::Command;
+use crate::parser::command::select::Select;
+use crate::parser::Parser;
+use anyhow::Result;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Explain {
+    /// explain analyze 会实际执行 另外记录各个stage的读取数量和耗时
+    pub analyze: bool,
+    pub select: Select,
+}
+
+impl Parser {
+    /// explain select user(id = 1) <br>
+    /// explain analyze select user(id = 1) -usage-> car
+    pub(in crate::parser) fn parseExplain(&mut self) -> Result<Command> {
+        let mut text =
+            self.getCurrentElementAdvance()?
+                .expectTextLiteral("explain should followed by select or analyze")?;
+
+        let analyze = text.eq_ignore_ascii_case("analyze");
+        if analyze {
+            text = self.getCurrentElementAdvance()?
+                .expectTextLiteral("explain analyze should followed by select")?;
+        }
+
+        if text.eq_ignore_ascii_case("select") == false {
+            return self.throwSyntaxErrorDetail("only select can be explained");
+        }
+
+        match self.parseSelect(true)? {
+            Command::Select(select) => Ok(Command::Explain(Explain { analyze, select })),
+            _ => panic!("impossible"),
+        }
+    }
+}
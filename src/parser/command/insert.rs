@@ -6,7 +6,7 @@ use crate::parser::Parser;
 use anyhow::Result;
 use crate::parser::element::Element;
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct Insert {
     pub tableName: String,
     /// insert into table (column) values ('a')
@@ -8,14 +8,14 @@ use crate::parser::Parser;
 use anyhow::Result;
 use crate::parser::command::select::SelectRel;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Link {
     LinkTo(LinkTo),
     LinkChain(Vec<SelectRel>),
 }
 
 /// link user(id = 1) to car(color = 'red') by usage(number = 2)
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct LinkTo {
     pub srcTableName: String,
     pub srcTableFilter: Option<Expr>,
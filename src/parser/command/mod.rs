@@ -1,11 +1,16 @@
 use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use crate::expr::Expr;
+use crate::graph_value::GraphValue;
+use crate::throw;
 use crate::meta::{DBObject, Index, Table};
 use crate::parser::command::alter::Alter;
 use crate::parser::command::delete::Delete;
+use crate::parser::command::explain::Explain;
 use crate::parser::command::insert::Insert;
-use crate::parser::command::link::Link;
+use crate::parser::command::link::{Link, LinkTo};
 use crate::parser::command::manage::Set;
-use crate::parser::command::select::Select;
+use crate::parser::command::select::{OrderBy, Projection, RelDesc, Select, SelectRel, SelectTable};
 use crate::parser::command::unlink::Unlink;
 use crate::parser::command::update::Update;
 
@@ -20,9 +25,10 @@ pub mod manage;
 mod drop;
 mod show;
 pub mod alter;
+pub mod explain;
 
 #[derive(Debug, Serialize, Deserialize)]
-pub enum Command { //  todo 实现 order by
+pub enum Command {
     CreateTable(Table),
     CreateIndex(Index),
     CreateRelation(Table),
@@ -42,6 +48,8 @@ pub enum Command { //  todo 实现 order by
 
     Select(Select),
 
+    Explain(Explain),
+
     Commit,
     Rollback,
 
@@ -56,7 +64,7 @@ pub enum Command { //  todo 实现 order by
 impl Command {
     pub fn needTx(&self) -> bool {
         match self {
-            Command::Select(_) => true,
+            Command::Select(_) | Command::Explain(_) => true,
             _ => self.isDml()
         }
     }
@@ -69,6 +77,67 @@ impl Command {
         }
     }
 
+    /// 只有select和dml能够prepare
+    pub fn isPreparable(&self) -> bool {
+        match self {
+            Command::Select(_) => true,
+            _ => self.isDml()
+        }
+    }
+
+    /// prepared statement 每趟执行都要从模板复制1份 table index 等不能clone的不会prepare
+    pub fn clonePrepared(&self) -> Result<Command> {
+        let command =
+            match self {
+                Command::Insert(insert) => Command::Insert(insert.clone()),
+                Command::Update(update) => Command::Update(update.clone()),
+                Command::Delete(delete) => Command::Delete(delete.clone()),
+                Command::Link(link) => Command::Link(link.clone()),
+                Command::Unlink(unlink) => Command::Unlink(unlink.clone()),
+                Command::Select(select) => Command::Select(select.clone()),
+                _ => throw!("only select and dml can be prepared"),
+            };
+
+        Ok(command)
+    }
+
+    /// 把command上各个expr的placeholder替换成实际参数
+    pub fn bindParams(&mut self, params: &[GraphValue]) -> Result<()> {
+        self.visitExprs(&mut |expr| expr.bindParams(params))
+    }
+
+    /// 遍历command上的各个最外层的expr 子查询内部的由visitor自己决定要不要下钻
+    pub fn visitExprs(&mut self, visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+        match self {
+            Command::Insert(insert) => {
+                for columnExprVec in &mut insert.columnExprVecVec {
+                    visit(columnExprVec.iter_mut(), visitor)?;
+                }
+            }
+            Command::Update(update) => {
+                visit(update.columnName_expr.values_mut(), visitor)?;
+                visit(update.filterExpr.iter_mut(), visitor)?;
+            }
+            Command::Delete(delete) => visit(delete.filterExpr.iter_mut(), visitor)?,
+            Command::Link(Link::LinkTo(linkTo)) => visitLinkTo(linkTo, visitor)?,
+            Command::Link(Link::LinkChain(selectRels)) => {
+                for selectRel in selectRels {
+                    visitSelectRel(selectRel, visitor)?;
+                }
+            }
+            Command::Unlink(Unlink::LinkToStyle(linkTo)) => visitLinkTo(linkTo, visitor)?,
+            Command::Unlink(Unlink::SelfStyle(unlinkSelfStyle)) => {
+                visit(unlinkSelfStyle.tableFilterExpr.iter_mut(), visitor)?;
+                visitRelDescs(&mut unlinkSelfStyle.relDescVec, visitor)?;
+            }
+            Command::Select(select) => visitSelectExprs(select, visitor)?,
+            Command::Explain(explain) => visitSelectExprs(&mut explain.select, visitor)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     pub fn isDdl(&self) -> bool {
         match self {
             Command::CreateTable(_) | Command::CreateIndex(_) | Command::CreateRelation(_) => true,
@@ -78,3 +147,66 @@ impl Command {
         }
     }
 }
+
+pub fn visitSelectExprs(select: &mut Select, visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    match select {
+        Select::SelectTable(selectTable) => visitSelectTable(selectTable, visitor),
+        Select::SelectRels(selectRels) => {
+            for selectRel in selectRels {
+                visitSelectRel(selectRel, visitor)?;
+            }
+
+            Ok(())
+        }
+        Select::SelectTableUnderRels(selectTableUnderRels) => {
+            visitSelectTable(&mut selectTableUnderRels.selectTable, visitor)?;
+            visitRelDescs(&mut selectTableUnderRels.relDescVec, visitor)
+        }
+    }
+}
+
+fn visit<'a>(exprs: impl Iterator<Item=&'a mut Expr>, visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    for expr in exprs {
+        visitor(expr)?;
+    }
+
+    Ok(())
+}
+
+fn visitProjections(projections: &mut Option<Vec<Projection>>, visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    visit(projections.iter_mut().flatten().map(|projection| &mut projection.expr), visitor)
+}
+
+fn visitOrderBys(orderBys: &mut Option<Vec<OrderBy>>, visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    visit(orderBys.iter_mut().flatten().filter_map(|orderBy| orderBy.expr.as_mut()), visitor)
+}
+
+fn visitSelectTable(selectTable: &mut SelectTable, visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    visit(selectTable.tableFilterExpr.iter_mut(), visitor)?;
+    visitProjections(&mut selectTable.projections, visitor)?;
+    visit(selectTable.having.iter_mut().map(|having| &mut having.expr), visitor)?;
+    visitOrderBys(&mut selectTable.orderBys, visitor)
+}
+
+fn visitSelectRel(selectRel: &mut SelectRel, visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    visit(selectRel.srcFilter.iter_mut(), visitor)?;
+    visitProjections(&mut selectRel.srcProjections, visitor)?;
+    visit(selectRel.srcHaving.iter_mut().map(|having| &mut having.expr), visitor)?;
+    visitOrderBys(&mut selectRel.srcOrderBys, visitor)?;
+    visit(selectRel.relationFilter.iter_mut(), visitor)?;
+    visit(selectRel.relationInsertColumnExprs.iter_mut().flatten(), visitor)?;
+    visit(selectRel.destFilter.iter_mut(), visitor)?;
+    visitProjections(&mut selectRel.destProjections, visitor)?;
+    visitOrderBys(&mut selectRel.destOrderBys, visitor)
+}
+
+fn visitLinkTo(linkTo: &mut LinkTo, visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    visit(linkTo.srcTableFilter.iter_mut(), visitor)?;
+    visit(linkTo.destTableFilter.iter_mut(), visitor)?;
+    visit(linkTo.relationColumnExprs.iter_mut(), visitor)?;
+    visit(linkTo.relationFilter.iter_mut(), visitor)
+}
+
+fn visitRelDescs(relDescs: &mut [RelDesc], visitor: &mut dyn FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    visit(relDescs.iter_mut().filter_map(|relDesc| relDesc.relationFliter.as_mut()), visitor)
+}
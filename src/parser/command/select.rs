@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use std::ops::Bound;
 use std::str::FromStr;
 use serde::{Deserialize, Serialize};
@@ -13,31 +14,41 @@ use crate::types::RelationDepth;
 use anyhow::Result;
 use crate::parser::command::link::Link;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Select {
     SelectTable(SelectTable),
     SelectRels(Vec<SelectRel>),
     SelectTableUnderRels(SelectTableUnderRels),
 }
 
-// todo select的时候column也有alias
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct SelectTable {
     pub tableName: String,
+    /// 未使用alias的单纯的column
     pub selectedColNames: Option<Vec<String>>,
+    pub projections: Option<Vec<Projection>>,
     pub tableFilterExpr: Option<Expr>,
     pub tableAlias: Option<String>,
+    pub aggregates: Option<Vec<Aggregate>>,
+    pub groupBys: Option<Vec<String>>,
+    pub having: Option<Having>,
+    pub orderBys: Option<Vec<OrderBy>>,
     pub limit: Option<usize>,
     /// concurrent scan 时候失效
     pub offset: Option<usize>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct SelectRel {
     pub srcTableName: String,
     pub srcColumnNames: Option<Vec<String>>,
+    pub srcProjections: Option<Vec<Projection>>,
     pub srcFilter: Option<Expr>,
     pub srcAlias: Option<String>,
+    pub srcAggregates: Option<Vec<Aggregate>>,
+    pub srcGroupBys: Option<Vec<String>>,
+    pub srcHaving: Option<Having>,
+    pub srcOrderBys: Option<Vec<OrderBy>>,
     pub srcLimit: Option<usize>,
     pub srcOffset: Option<usize>,
 
@@ -47,23 +58,170 @@ pub struct SelectRel {
     pub relationInsertColumnNames: Option<Vec<String>>,
     pub relationInsertColumnExprs: Option<Vec<Expr>>,
     pub relationDepth: Option<RelationDepth>,
+    /// -likes shortest recursive[1..10]->
+    pub relationPathSearch: Option<PathSearch>,
+    /// -likes-> <-likes- -likes-
+    pub relationDirection: RelationDirection,
+    /// -likes recursive[1..] trail->
+    pub relationPathSemantics: PathSemantics,
+    /// select user -likes recursive[2..4]-> user return paths
+    pub returnPaths: bool,
     pub relationAlias: Option<String>,
 
     pub destTableName: String,
     pub destColumnNames: Option<Vec<String>>,
+    pub destProjections: Option<Vec<Projection>>,
     pub destFilter: Option<Expr>,
     pub destAlias: Option<String>,
+    /// 以src的各条data分组 例如各个user使用的car的数量
+    pub destAggregates: Option<Vec<Aggregate>>,
+    pub destOrderBys: Option<Vec<OrderBy>>,
     pub destLimit: Option<usize>,
     pub destOffset: Option<usize>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+/// 在recursive的relation上寻找路径 而不是罗列全部可达的dest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PathSearch {
+    /// 广度优先 以src的全部node同时出发 到达满足destFilter的node便停下
+    Shortest,
+    /// 以relation上的column作为cost的dijkstra ```-road weighted by distance->```
+    Weighted {
+        columnName: String,
+    },
+}
+
+/// 沿着relation的方向
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RelationDirection {
+    /// ```-likes->``` 由relation的src到dest
+    #[default]
+    Forward,
+    /// ```<-likes-``` 由relation的dest到src
+    Reverse,
+    /// ```-likes-``` 两个方向都可以
+    Either,
+}
+
+/// recursive时候路径上能不能重复
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PathSemantics {
+    /// node和relation都能重复
+    #[default]
+    Walk,
+    /// relation不能重复
+    Trail,
+    /// node不能重复
+    Simple,
+}
+
+impl FromStr for PathSemantics {
+    type Err = GraphError;
+
+    fn from_str(str: &str) -> std::result::Result<Self, Self::Err> {
+        match str.to_lowercase().as_str() {
+            "walk" => Ok(PathSemantics::Walk),
+            "trail" => Ok(PathSemantics::Trail),
+            "simple" => Ok(PathSemantics::Simple),
+            _ => throw!("unknown path semantics"),
+        }
+    }
+}
+
+/// ```order by age desc, name``` ```order by cosine(embedding, $q) limit 10```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBy {
+    /// 是函数的话 排序前算出来以它为key放到rowData 排序后去掉
+    pub columnName: String,
+    pub asc: bool,
+    pub expr: Option<Expr>,
+}
+
+/// select list 中使用了alias的 ```name as userName``` ```age * 2 as doubleAge```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Projection {
+    pub expr: Expr,
+    pub alias: String,
+}
+
+/// ```count(*) count(distinct name) sum(age) as total```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub aggregateFunction: AggregateFunction,
+    /// count(*) 的时候是None
+    pub columnName: Option<String>,
+    pub distinct: bool,
+    pub alias: Option<String>,
+}
+
+impl Aggregate {
+    /// 用作结果rowData中的key 也是having order by 中引用它的方式
+    pub fn name(&self) -> String {
+        match (&self.columnName, self.distinct) {
+            (None, _) => format!("{}(*)", self.aggregateFunction),
+            (Some(columnName), true) => format!("{}(distinct {})", self.aggregateFunction, columnName),
+            (Some(columnName), false) => format!("{}({})", self.aggregateFunction, columnName),
+        }
+    }
+
+    /// 结果json中的key
+    pub fn displayName(&self) -> String {
+        self.alias.clone().unwrap_or_else(|| self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl FromStr for AggregateFunction {
+    type Err = GraphError;
+
+    fn from_str(str: &str) -> std::result::Result<Self, Self::Err> {
+        match str.to_lowercase().as_str() {
+            "count" => Ok(AggregateFunction::Count),
+            "sum" => Ok(AggregateFunction::Sum),
+            "avg" => Ok(AggregateFunction::Avg),
+            "min" => Ok(AggregateFunction::Min),
+            "max" => Ok(AggregateFunction::Max),
+            _ => throw!("unknown aggregate function"),
+        }
+    }
+}
+
+impl Display for AggregateFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AggregateFunction::Count => write!(f, "count"),
+            AggregateFunction::Sum => write!(f, "sum"),
+            AggregateFunction::Avg => write!(f, "avg"),
+            AggregateFunction::Min => write!(f, "min"),
+            AggregateFunction::Max => write!(f, "max"),
+        }
+    }
+}
+
+/// ```having count(*) > 1``` <br>
+/// expr中的聚合函数已被替换成了以它的name为内容的TextLiteral
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Having {
+    pub expr: Expr,
+    /// 可能有select list中没有的 也需要计算
+    pub aggregates: Vec<Aggregate>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct SelectTableUnderRels {
     pub selectTable: SelectTable,
     pub relDescVec: Vec<RelDesc>,
 }
 
-#[derive(Default, Serialize, Deserialize, Debug)]
+#[derive(Default, Clone, Serialize, Deserialize, Debug)]
 pub struct RelDesc {
     /// 该node处在rel的哪个位置上
     pub endPointType: EndPointType,
@@ -71,7 +229,7 @@ pub struct RelDesc {
     pub relationFliter: Option<Expr>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub enum EndPointType {
     Start,
     #[default]
@@ -104,6 +262,8 @@ impl Parser {
             ReadSrcColumnNames, // 可选
             ReadSrcFilterExpr, // 可选
             ReadSrcAlias, // 可选
+            ReadSrcGroupBy, // 可选
+            ReadSrcOrderBy, // 可选
             ReadSrcLimitOffset,
 
             ReadRelationName, // 可选
@@ -116,32 +276,181 @@ impl Parser {
             ReadDestColumnNames, // 可选
             ReadDestFilterExpr, // 可选
             ReadDestAlias, // 可选
+            ReadDestOrderBy, // 可选
             ReadDestLimitOffset,
 
             TryNextRound,
         }
 
-        fn parseSelectedColumnNames(parser: &mut Parser) -> Result<Vec<String>> {
+        // [id, name as userName, age * 2 as doubleAge, count(*) as total]
+        fn parseSelectedColumnNames(parser: &mut Parser) -> Result<(Vec<String>, Vec<Projection>, Vec<Aggregate>)> {
             let mut columnNames = Vec::default();
+            let mut projections = Vec::default();
+            let mut aggregates = Vec::default();
 
             loop {
-                let text =
-                    parser
-                        .getCurrentElementAdvance()?
-                        .expectTextLiteral(global::EMPTY_STR)?;
+                // 收集1项的各个element 到 "," 或 "]" 为止 括号和数组内部的","不算
+                let mut elementVec = Vec::new();
+                let mut 括号count = 0;
+
+                let reachEnd = loop {
+                    let element = parser.getCurrentElementAdvance()?.clone();
+
+                    if let Element::TextLiteral(ref text) = element {
+                        match text.as_str() {
+                            global::圆括号_STR | global::方括号_STR => 括号count += 1,
+                            global::逗号_STR if 括号count == 0 => break false,
+                            global::方括号1_STR if 括号count == 0 => break true,
+                            global::圆括号1_STR | global::方括号1_STR => 括号count -= 1,
+                            _ => {}
+                        }
+                    }
 
-                match text.as_str() {
-                    global::逗号_STR => continue,
-                    global::方括号1_STR => break,
-                    _ => columnNames.push(text),
+                    elementVec.push(element);
+                };
+
+                if elementVec.is_empty() == false {
+                    // 末尾的 as alias
+                    let mut alias = None;
+                    if elementVec.len() >= 3 && elementVec[elementVec.len() - 2].expectTextLiteralContentIgnoreCaseBool("as") {
+                        alias = Some(elementVec.pop().unwrap().expectTextLiteral("as should followed by column alias")?);
+                        elementVec.pop();
+                    }
+
+                    // count(*) 之类的聚合
+                    let aggregate =
+                        match elementVec.first() {
+                            Some(Element::TextLiteral(text)) if elementVec.len() > 1 => {
+                                let mut parserMini = Parser::default();
+                                parserMini.elementVecVec.push(elementVec.clone());
+                                parserMini.skipElement(1)?;
+
+                                let aggregate = parserMini.tryParseAggregate(text)?;
+                                if aggregate.is_some() && parserMini.hasRemainingElement() {
+                                    parser.throwSyntaxErrorDetail("aggregate can not be used in expression")?;
+                                }
+
+                                aggregate
+                            }
+                            _ => None,
+                        };
+
+                    if let [Element::TextLiteral(columnName)] = elementVec.as_slice() { // 单纯的column
+                        match alias {
+                            Some(alias) => projections.push(Projection { expr: Expr::Single(elementVec[0].clone()), alias }),
+                            None => columnNames.push(columnName.clone()),
+                        }
+                    } else if let Some(aggregate) = aggregate {
+                        aggregates.push(Aggregate { alias, ..aggregate });
+                    } else {
+                        let alias = match (alias, elementVec.as_slice()) {
+                            (Some(alias), _) => alias,
+                            // profile->'address'->>'city' 默认以末尾的key作为alias
+                            (None, [Element::TextLiteral(_), .., Element::Arrow2Right | Element::JsonTextArrow, Element::StringContent(key)]) => key.clone(),
+                            (None, _) => parser.throwSyntaxErrorDetail("computed column should have alias")?,
+                        };
+
+                        elementVec.insert(0, Element::TextLiteral(global::圆括号_STR.to_string()));
+                        elementVec.push(Element::TextLiteral(global::圆括号1_STR.to_string()));
+
+                        let mut parserMini = Parser::default();
+                        parserMini.elementVecVec.push(elementVec);
+
+                        projections.push(Projection { expr: parserMini.parseExpr(false)?, alias });
+                    }
+                }
+
+                if reachEnd {
+                    break;
                 }
             }
 
-            if columnNames.is_empty() {
+            if columnNames.is_empty() && projections.is_empty() && aggregates.is_empty() {
                 parser.throwSyntaxErrorDetail("no explicit column name")?;
             }
 
-            Ok(columnNames)
+            Ok((columnNames, projections, aggregates))
+        }
+
+        // group by age, name having count(*) > 1
+        fn parseGroupBys(parser: &mut Parser) -> Result<Vec<String>> {
+            parser.getCurrentElementAdvance()?.expectTextLiteralContentIgnoreCase("by", "group should followed by by")?;
+
+            let mut groupBys = Vec::new();
+
+            loop {
+                groupBys.push(parser.getCurrentElementAdvance()?.expectTextLiteral("expect column name after group by")?);
+
+                match parser.getCurrentElementAdvanceOption() {
+                    Some(element) => {
+                        if element.expectTextLiteralContentBool(global::逗号_STR) == false {
+                            parser.skipElement(-1)?;
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+
+            Ok(groupBys)
+        }
+
+        // order by age desc, name
+        fn parseOrderBys(parser: &mut Parser) -> Result<Vec<OrderBy>> {
+            parser.getCurrentElementAdvance()?.expectTextLiteralContentIgnoreCase("by", "order should followed by by")?;
+
+            let mut orderBys = Vec::new();
+
+            loop {
+                let element = parser.getCurrentElementAdvance()?.clone();
+                let mut columnName = element.expectTextLiteral("expect column name after order by")?;
+                let mut expr = None;
+
+                // order by count(*) desc
+                if let Some(aggregate) = parser.tryParseAggregate(&columnName)? {
+                    columnName = aggregate.name();
+                } else if let Some(functionExpr) = parser.tryParseFunction(&element)? {
+                    // order by l2(embedding, '[1, 2]') 不会和column的名字冲突
+                    columnName = format!("{}#{}", columnName.to_lowercase(), orderBys.len());
+                    expr = Some(functionExpr);
+                }
+
+                let mut orderBy = OrderBy {
+                    columnName,
+                    asc: true,
+                    expr,
+                };
+
+                // 尝试读取 asc desc ,
+                match parser.getCurrentElementAdvanceOption() {
+                    Some(element) => {
+                        if element.expectTextLiteralContentIgnoreCaseBool("asc") {
+                        } else if element.expectTextLiteralContentIgnoreCaseBool("desc") {
+                            orderBy.asc = false;
+                        } else {
+                            parser.skipElement(-1)?;
+                        }
+                    }
+                    None => {
+                        orderBys.push(orderBy);
+                        break;
+                    }
+                }
+
+                orderBys.push(orderBy);
+
+                match parser.getCurrentElementAdvanceOption() {
+                    Some(element) => {
+                        if element.expectTextLiteralContentBool(global::逗号_STR) == false {
+                            parser.skipElement(-1)?;
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+
+            Ok(orderBys)
         }
 
         fn parseLimitOffset(parser: &mut Parser, text: String, selectRel: &mut SelectRel, src: bool) -> Result<()> {
@@ -159,9 +468,20 @@ impl Parser {
                     }
 
                     // 尝试读取后边的offset
-                    if parser
-                        .getCurrentElementAdvance()?
-                        .expectTextLiteralContentIgnoreCaseBool("offset") {
+                    let hasOffset =
+                        match parser.getCurrentElementAdvanceOption() {
+                            Some(element) => {
+                                if element.expectTextLiteralContentIgnoreCaseBool("offset") {
+                                    true
+                                } else {
+                                    parser.skipElement(-1)?;
+                                    false
+                                }
+                            }
+                            None => false,
+                        };
+
+                    if hasOffset {
                         let offset = parser.getCurrentElementAdvance()?.expectIntegerLiteral()?;
                         if 0 > offset {
                             parser.throwSyntaxErrorDetail("offset should not be negtive")?;
@@ -186,7 +506,8 @@ impl Parser {
                         selectRel.destOffset = Some(offset as usize);
                     }
                 }
-                _ => parser.throwSyntaxError()?,
+                // 不是limit offset 例如 select user(id > 1) ,in usage 的逗号 还回去
+                _ => parser.skipElement(-1)?,
             }
 
             Result::<()>::Ok(())
@@ -220,7 +541,15 @@ impl Parser {
                 }
                 State::ReadSrcColumnNames => {
                     if currentElement.expectTextLiteralContentBool(global::方括号_STR) {
-                        selectRel.srcColumnNames = Some(parseSelectedColumnNames(self)?);
+                        let (columnNames, projections, aggregates) = parseSelectedColumnNames(self)?;
+
+                        selectRel.srcColumnNames = Some(columnNames);
+                        if projections.is_empty() == false {
+                            selectRel.srcProjections = Some(projections);
+                        }
+                        if aggregates.is_empty() == false {
+                            selectRel.srcAggregates = Some(aggregates);
+                        }
                     } else {
                         self.skipElement(-1)?;
                     }
@@ -250,6 +579,36 @@ impl Parser {
                         self.skipElement(-1)?;
                     }
 
+                    state = State::ReadSrcGroupBy;
+                    force = false;
+                }
+                State::ReadSrcGroupBy => {
+                    if currentElement.expectTextLiteralContentIgnoreCaseBool("group") {
+                        selectRel.srcGroupBys = Some(parseGroupBys(self)?);
+
+                        if let Some(element) = self.getCurrentElementAdvanceOption() {
+                            if element.expectTextLiteralContentIgnoreCaseBool("having") {
+                                selectRel.srcHaving = Some(self.parseHaving()?);
+                            } else {
+                                self.skipElement(-1)?;
+                            }
+                        }
+                    } else if currentElement.expectTextLiteralContentIgnoreCaseBool("having") {
+                        selectRel.srcHaving = Some(self.parseHaving()?);
+                    } else {
+                        self.skipElement(-1)?;
+                    }
+
+                    state = State::ReadSrcOrderBy;
+                    force = false;
+                }
+                State::ReadSrcOrderBy => {
+                    if currentElement.expectTextLiteralContentIgnoreCaseBool("order") {
+                        selectRel.srcOrderBys = Some(parseOrderBys(self)?);
+                    } else {
+                        self.skipElement(-1)?;
+                    }
+
                     state = State::ReadSrcLimitOffset;
                     force = false;
                 }
@@ -266,7 +625,17 @@ impl Parser {
                     force = false;
                 }
                 State::ReadRelationName => {
-                    if let Element::Op(Op::MathCalcOp(MathCalcOp::Minus)) = currentElement {
+                    let relationStart =
+                        match currentElement {
+                            Element::Op(Op::MathCalcOp(MathCalcOp::Minus)) => true,
+                            Element::Arrow2Left => {
+                                selectRel.relationDirection = RelationDirection::Reverse;
+                                true
+                            }
+                            _ => false,
+                        };
+
+                    if relationStart {
                         selectRel.relationName =
                             self
                                 .getCurrentElementAdvance()?
@@ -280,7 +649,12 @@ impl Parser {
                 }
                 State::ReadRelationColumnNames => {
                     if currentElement.expectTextLiteralContentBool(global::方括号_STR) {
-                        selectRel.relationColumnNames = Some(parseSelectedColumnNames(self)?);
+                        let (columnNames, projections, aggregates) = parseSelectedColumnNames(self)?;
+                        if projections.is_empty() == false || aggregates.is_empty() == false {
+                            self.throwSyntaxErrorDetail("alias and aggregate are not supported on relation")?;
+                        }
+
+                        selectRel.relationColumnNames = Some(columnNames);
                     } else {
                         self.skipElement(-1)?;
                     }
@@ -308,6 +682,31 @@ impl Parser {
                 }
                 //  todo 实现递归搜索的parse 完成
                 State::ReadRelationDepth => {
+                    // shortest 在 recursive 之前 读取后state不变
+                    if selectRel.relationPathSearch.is_none() && currentElement.expectTextLiteralContentIgnoreCaseBool("shortest") {
+                        selectRel.relationPathSearch = Some(PathSearch::Shortest);
+                        force = true;
+                        continue;
+                    }
+
+                    // weighted by distance 不能和recursive同用
+                    if selectRel.relationPathSearch.is_none() && currentElement.expectTextLiteralContentIgnoreCaseBool("weighted") {
+                        self.getCurrentElementAdvance()?.expectTextLiteralContentIgnoreCase("by", "weighted should followed by by")?;
+
+                        let columnName = self.getCurrentElementAdvance()?.expectTextLiteral("expect weight column name")?;
+                        selectRel.relationPathSearch = Some(PathSearch::Weighted { columnName });
+
+                        if self.getCurrentElement()?.expectTextLiteralContentIgnoreCaseBool("recursive") {
+                            self.throwSyntaxErrorDetail("weighted path search can not be used with recursive")?;
+                        }
+
+                        selectRel.relationDepth = Some((Bound::Included(1), Bound::Included(i64::MAX as usize)));
+
+                        state = State::ReadRelationAlias;
+                        force = false;
+                        continue;
+                    }
+
                     if currentElement.expectTextLiteralContentIgnoreCaseBool("recursive") {
                         // 使用独立的mini模式
                         let mut parseMini = Parser::default();
@@ -331,10 +730,28 @@ impl Parser {
                         parseMini.elementVecVec.push(elementVec);
 
                         selectRel.relationDepth = parseMini.parseRelationDepth()?;
+
+                        // recursive[1..] trail
+                        if let Some(text) = self.getCurrentElementOption().and_then(|element| element.expectTextLiteralOpt()) {
+                            if let Ok(pathSemantics) = PathSemantics::from_str(&text) {
+                                selectRel.relationPathSemantics = pathSemantics;
+                                self.skipElement(1)?;
+                            }
+                        }
+
+                        // recursive[1..1] 会得到None
+                        if selectRel.relationPathSearch.is_some() && selectRel.relationDepth.is_none() {
+                            selectRel.relationDepth = Some((Bound::Included(1), Bound::Included(1)));
+                        }
                     } else {
                         self.skipElement(-1)?;
                     }
 
+                    // 寻找路径的时候未写recursive的话深度不限
+                    if selectRel.relationPathSearch.is_some() && selectRel.relationDepth.is_none() {
+                        selectRel.relationDepth = Some((Bound::Included(1), Bound::Included(i64::MAX as usize)));
+                    }
+
                     state = State::ReadRelationAlias;
                     force = false;
                 }
@@ -352,7 +769,19 @@ impl Parser {
                     force = true;
                 }
                 State::ReadDestName => {
-                    if let Element::Arrow2Right = currentElement {
+                    let relationEnd =
+                        match (currentElement, selectRel.relationDirection) {
+                            (Element::Arrow2Right, RelationDirection::Forward) => true,
+                            (Element::Arrow2Right, _) => self.throwSyntaxErrorDetail("<-relation-> is not allowed")?,
+                            (Element::Op(Op::MathCalcOp(MathCalcOp::Minus)), RelationDirection::Forward) => {
+                                selectRel.relationDirection = RelationDirection::Either;
+                                true
+                            }
+                            (Element::Op(Op::MathCalcOp(MathCalcOp::Minus)), _) => true,
+                            _ => false,
+                        };
+
+                    if relationEnd {
                         selectRel.destTableName =
                             self.getCurrentElementAdvance()?
                                 .expectTextLiteral("expect a relation name")?;
@@ -364,7 +793,7 @@ impl Parser {
                             }
                         }
                     } else {
-                        break;
+                        self.throwSyntaxErrorDetail(&format!("expect -> or - after relation:{}", selectRel.relationName))?;
                     }
 
                     state = State::ReadDestColumnNames;
@@ -372,7 +801,22 @@ impl Parser {
                 }
                 State::ReadDestColumnNames => {
                     if currentElement.expectTextLiteralContentBool(global::方括号_STR) {
-                        selectRel.destColumnNames = Some(parseSelectedColumnNames(self)?);
+                        let (columnNames, projections, aggregates) = parseSelectedColumnNames(self)?;
+
+                        if aggregates.is_empty() == false {
+                            // 以src的data分组的 dest上不能有普通的column
+                            if let Some(columnName) = columnNames.first().or(projections.first().map(|projection| &projection.alias)) {
+                                self.throwSyntaxErrorDetail(&format!("dest column:{} can not be selected with aggregate", columnName))?;
+                            }
+
+                            selectRel.destAggregates = Some(aggregates);
+                        }
+
+                        if projections.is_empty() == false {
+                            selectRel.destProjections = Some(projections);
+                        }
+
+                        selectRel.destColumnNames = Some(columnNames);
                     } else {
                         self.skipElement(-1)?;
                     }
@@ -401,6 +845,16 @@ impl Parser {
                         self.skipElement(-1)?;
                     }
 
+                    state = State::ReadDestOrderBy;
+                    force = false;
+                }
+                State::ReadDestOrderBy => {
+                    if currentElement.expectTextLiteralContentIgnoreCaseBool("order") {
+                        selectRel.destOrderBys = Some(parseOrderBys(self)?);
+                    } else {
+                        self.skipElement(-1)?;
+                    }
+
                     state = State::ReadDestLimitOffset;
                     force = false;
                 }
@@ -414,8 +868,8 @@ impl Parser {
                     state = State::TryNextRound;
                     force = false;
                 }
-                State::TryNextRound => { // 尝试读取下个rel打头的 -rel-> 的 minus部分
-                    if let Element::Op(Op::MathCalcOp(MathCalcOp::Minus)) = currentElement {
+                State::TryNextRound => { // 尝试读取下个rel打头的 -rel-> 的 minus部分 或是 <-rel- 的 <-
+                    if let Element::Op(Op::MathCalcOp(MathCalcOp::Minus)) | Element::Arrow2Left = currentElement {
                         self.skipElement(-1)?;
 
                         // 到了下个的轮回了,上轮的dest变为下轮的src
@@ -424,8 +878,10 @@ impl Parser {
                         let selectRel0 = SelectRel {
                             srcTableName: selectRel.destTableName.clone(),
                             srcColumnNames: selectRel.destColumnNames.clone(),
+                            srcProjections: selectRel.destProjections.clone(),
                             srcFilter: selectRel.destFilter.clone(),
                             srcAlias: selectRel.destAlias.clone(),
+                            srcOrderBys: selectRel.destOrderBys.clone(),
                             srcLimit: selectRel.srcLimit.clone(),
                             srcOffset: selectRel.srcOffset.clone(),
                             ..Default::default()
@@ -437,6 +893,15 @@ impl Parser {
 
                         state = State::ReadRelationName;
                         force = true;
+                    } else if currentElement.expectTextLiteralContentIgnoreCaseBool("return") {
+                        self.getCurrentElementAdvance()?.expectTextLiteralContentIgnoreCase("paths", "return should followed by paths")?;
+
+                        if selectRel.relationDepth.is_none() {
+                            self.throwSyntaxErrorDetail("return paths needs recursive relation")?;
+                        }
+
+                        selectRel.returnPaths = true;
+                        break;
                     } else {
                         break;
                     }
@@ -450,16 +915,27 @@ impl Parser {
             let selectTable = SelectTable {
                 tableName: selectRel.srcTableName,
                 selectedColNames: selectRel.srcColumnNames,
+                projections: selectRel.srcProjections,
                 tableFilterExpr: selectRel.srcFilter,
                 tableAlias: selectRel.srcAlias,
+                aggregates: selectRel.srcAggregates,
+                groupBys: selectRel.srcGroupBys,
+                having: selectRel.srcHaving,
+                orderBys: selectRel.srcOrderBys,
                 limit: selectRel.srcLimit,
                 offset: selectRel.srcOffset,
                 ..Default::default()
             };
 
+            self.checkSelectTableAggregate(&selectTable)?;
+
             // select user limit 1 offset 0 跳出了上边的loop也到这也满足state == State::ReadRelationName
             // 需要区分要看后边还有没有了 要是有的话当成selectTableUnderRels 要没有的话便是selectTable
             if state == State::ReadRelationName && self.getCurrentElementOption().is_some() {
+                if selectTable.aggregates.is_some() || selectTable.groupBys.is_some() || selectTable.having.is_some() {
+                    self.throwSyntaxErrorDetail("aggregate is not supported in select table under relations")?;
+                }
+
                 // 复用成果 因为前部分都是select 1个 表
                 self.skipElement(-1)?;
                 return self.parseSelectTableUnderRels(selectTable);
@@ -486,6 +962,34 @@ impl Parser {
                 Result::<(), anyhow::Error>::Ok(())
             };
 
+        for (index, selectRel) in selectRelVec.iter().enumerate() {
+            if regardRelPartAsFilter == false && selectRel.relationDirection != RelationDirection::Forward {
+                self.throwSyntaxErrorDetail("link only supports -relation->")?;
+            }
+
+            if selectRel.srcAggregates.is_some() || selectRel.srcGroupBys.is_some() || selectRel.srcHaving.is_some() {
+                self.throwSyntaxErrorDetail("relation select only supports aggregate on dest")?;
+            }
+
+            if selectRel.destAggregates.is_some() && index != selectRelVec.len() - 1 {
+                self.throwSyntaxErrorDetail("aggregate on dest only supported on the last relation")?;
+            }
+
+            if selectRel.relationPathSearch.is_some() || selectRel.returnPaths {
+                if regardRelPartAsFilter == false {
+                    self.throwSyntaxErrorDetail("path is not supported in link")?;
+                }
+
+                if selectRelVec.len() > 1 {
+                    self.throwSyntaxErrorDetail("path only supported on single relation")?;
+                }
+
+                if selectRel.destAggregates.is_some() {
+                    self.throwSyntaxErrorDetail("aggregate is not supported with path")?;
+                }
+            }
+        }
+
         for selectRel in &selectRelVec {
             testDuplicatedAlias(selectRel.srcAlias.as_ref())?;
             testDuplicatedAlias(selectRel.relationAlias.as_ref())?;
@@ -499,6 +1003,146 @@ impl Parser {
         }
     }
 
+    /// text是聚合函数名 且后边紧跟着"(" 的话读取聚合函数 不然返回None
+    fn tryParseAggregate(&mut self, text: &str) -> Result<Option<Aggregate>> {
+        let aggregateFunction = match AggregateFunction::from_str(text) {
+            Ok(aggregateFunction) => aggregateFunction,
+            Err(_) => return Ok(None),
+        };
+
+        match self.getCurrentElementOption() {
+            Some(element) if element.expectTextLiteralContentBool(global::圆括号_STR) => self.skipElement(1)?,
+            _ => return Ok(None),
+        }
+
+        let mut aggregate = Aggregate {
+            aggregateFunction,
+            columnName: None,
+            distinct: false,
+            alias: None,
+        };
+
+        match self.getCurrentElementAdvance()?.clone() {
+            Element::Op(Op::MathCalcOp(MathCalcOp::Multiply)) => {
+                if aggregateFunction != AggregateFunction::Count {
+                    self.throwSyntaxErrorDetail("only count supports *")?;
+                }
+            }
+            Element::TextLiteral(text) => {
+                if text.eq_ignore_ascii_case("distinct") {
+                    aggregate.distinct = true;
+                    aggregate.columnName = Some(self.getCurrentElementAdvance()?.expectTextLiteral("distinct should followed by column name")?);
+                } else {
+                    aggregate.columnName = Some(text);
+                }
+            }
+            _ => self.throwSyntaxErrorDetail("expect column name or * in aggregate function")?,
+        }
+
+        self.getCurrentElementAdvance()?.expectTextLiteralContent(global::圆括号1_STR)?;
+
+        Ok(Some(aggregate))
+    }
+
+    /// ```having count(*) > 1 and sum(age) > 10``` <br>
+    /// 收集到 order limit offset 为止 把聚合函数替换为TextLiteral后以mini parser解析expr
+    fn parseHaving(&mut self) -> Result<Having> {
+        let mut aggregates: Vec<Aggregate> = Vec::new();
+
+        let mut elementVec = vec![Element::TextLiteral(global::圆括号_STR.to_string())];
+
+        loop {
+            let element = match self.getCurrentElementAdvanceOption() {
+                Some(element) => element.clone(),
+                None => break,
+            };
+
+            if let Element::TextLiteral(ref text) = element {
+                match text.to_lowercase().as_str() {
+                    "order" | "limit" | "offset" => {
+                        self.skipElement(-1)?;
+                        break;
+                    }
+                    _ => {}
+                }
+
+                if let Some(aggregate) = self.tryParseAggregate(text)? {
+                    let name = aggregate.name();
+
+                    if aggregates.iter().any(|aggregate| aggregate.name() == name) == false {
+                        aggregates.push(aggregate);
+                    }
+
+                    elementVec.push(Element::TextLiteral(name));
+                    continue;
+                }
+            }
+
+            elementVec.push(element);
+        }
+
+        if elementVec.len() == 1 {
+            self.throwSyntaxErrorDetail("having should followed by condition")?;
+        }
+
+        elementVec.push(Element::TextLiteral(global::圆括号1_STR.to_string()));
+
+        let mut parserMini = Parser::default();
+        parserMini.elementVecVec.push(elementVec);
+
+        Ok(Having {
+            expr: parserMini.parseExpr(false)?,
+            aggregates,
+        })
+    }
+
+    /// 普通的column要在group by中 order by 要能在聚合后的结果中找到
+    fn checkSelectTableAggregate(&self, selectTable: &SelectTable) -> Result<()> {
+        if selectTable.aggregates.is_none() && selectTable.groupBys.is_none() && selectTable.having.is_none() {
+            return Ok(());
+        }
+
+        let groupBys = selectTable.groupBys.clone().unwrap_or_default();
+
+        let mut columnNames: hashbrown::HashSet<String> = hashbrown::HashSet::new();
+
+        if let Some(ref selectedColNames) = selectTable.selectedColNames {
+            columnNames.extend(selectedColNames.iter().cloned());
+        }
+
+        // projection 只能用到 group by 的column
+        for projection in selectTable.projections.iter().flatten() {
+            projection.expr.extractColumnNames(&mut columnNames)?;
+        }
+
+        for columnName in &columnNames {
+            if groupBys.contains(columnName) == false {
+                self.throwSyntaxErrorDetail(&format!("column:{} should be in group by", columnName))?;
+            }
+        }
+
+        if let Some(ref orderBys) = selectTable.orderBys {
+            let aggregateNames: Vec<String> =
+                selectTable.aggregates.iter().flatten()
+                    .chain(selectTable.having.iter().flat_map(|having| having.aggregates.iter()))
+                    .flat_map(|aggregate| [Some(aggregate.name()), aggregate.alias.clone()])
+                    .flatten()
+                    .collect();
+
+            for orderBy in orderBys {
+                if orderBy.expr.is_some() {
+                    self.throwSyntaxErrorDetail("function in order by can not be used with aggregate")?;
+                }
+
+                if groupBys.contains(&orderBy.columnName) == false && aggregateNames.contains(&orderBy.columnName) == false {
+                    self.throwSyntaxErrorDetail(&format!("order by column:{} should be in group by or aggregate", orderBy.columnName))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// ```select user(id >1 ) as user0 ,in usage (number = 7) ,as end in own(number =7)```
     fn parseSelectTableUnderRels(&mut self, selectTable: SelectTable) -> Result<Command> {
         let mut selectTableUnderRels = SelectTableUnderRels::default();
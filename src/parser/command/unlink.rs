@@ -12,13 +12,13 @@ use anyhow::Result;
 
 pub type UnlinkLinkToStyle = LinkTo;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Unlink {
     LinkToStyle(UnlinkLinkToStyle),
     SelfStyle(UnlinkSelfStyle),
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct UnlinkSelfStyle {
     pub tableName: String,
     pub tableFilterExpr: Option<Expr>,
@@ -7,7 +7,7 @@ use crate::parser::element::Element;
 use crate::parser::op::{MathCmpOp, Op};
 use crate::parser::Parser;
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct Update {
     pub tableName: String,
     // todo insert的values的expr要能支持含column name的
@@ -76,18 +76,29 @@ impl Parser {
 
                         self.skipElement(-1)?;
 
+                        // 括号里边的","不算 例如 name = substr(name, 1, 2), tags = ['a', 'b']
+                        let mut 括号count = 0;
+
                         'innerLoop:
                         loop {
                             let currentElement = self.getCurrentElementAdvance()?;
 
-                            if currentElement.expectTextLiteralContentBool(global::逗号_STR) {
+                            if 括号count == 0 && currentElement.expectTextLiteralContentBool(global::方括号1_STR) {
                                 getPair!();
-                                break 'innerLoop;
+                                break 'outerLoop;
                             }
 
-                            if currentElement.expectTextLiteralContentBool(global::方括号1_STR) {
+                            if currentElement.expectTextLiteralContentBool(global::圆括号_STR) ||
+                                currentElement.expectTextLiteralContentBool(global::方括号_STR) {
+                                括号count += 1;
+                            } else if currentElement.expectTextLiteralContentBool(global::圆括号1_STR) ||
+                                currentElement.expectTextLiteralContentBool(global::方括号1_STR) {
+                                括号count -= 1;
+                            }
+
+                            if 括号count == 0 && currentElement.expectTextLiteralContentBool(global::逗号_STR) {
                                 getPair!();
-                                break 'outerLoop;
+                                break 'innerLoop;
                             }
 
                             elementVec.push(currentElement.clone());
@@ -1,11 +1,15 @@
 use std::fmt::{Debug, Display, Formatter};
 use serde::{Deserialize, Serialize};
-use crate::{global, suffix_plus_plus, throw};
+use crate::{decimal, global, suffix_plus_plus, throw, throwFormat};
 use crate::parser::op::{LogicalOp, MathCalcOp, Op, SqlOp};
 use crate::parser::Parser;
 use anyhow::Result;
+use rust_decimal::Decimal;
+use data_encoding::{HEXLOWER, HEXLOWER_PERMISSIVE};
 use strum_macros::Display as StrumDisplay;
-use crate::types::ElementType;
+use crate::temporal;
+use crate::temporal::Interval;
+use crate::types::{Byte, ElementType};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub enum Element {
@@ -14,11 +18,27 @@ pub enum Element {
     /// 对应''包括起来的内容
     StringContent(String),
     IntegerLiteral(i64),
-    DecimalLiteral(f64),
+    /// 带小数点的数字是精确的decimal
+    DecimalLiteral(Decimal),
+    /// decimal表示不了的 比如超过了28位有效数字
+    DoubleLiteral(f64),
+    BytesLiteral(Vec<Byte>),
+    /// timestamp '2024-01-02 03:04:05' 对应的UTC微秒
+    TimestampLiteral(i64),
+    /// date '2024-01-02' 对应的天数
+    DateLiteral(i64),
+    /// interval '1 day'
+    IntervalLiteral(Interval),
     Op(Op),
     Boolean(bool),
     /// 对应"->"
     Arrow2Right,
+    /// 对应"<-"
+    Arrow2Left,
+    /// 对应"->>" 取json的字段得到text, "->" 在expr里边是取json的字段
+    JsonTextArrow,
+    /// prepared statement 的 ? 或 $1 从0起
+    Placeholder(usize),
     Null,
     Not,
     Default,
@@ -35,6 +55,14 @@ impl Element {
     pub const NULL: ElementType = 7;
     pub const NOT: ElementType = 8;
     pub const DEFAULT: ElementType = 9;
+    pub const ARROW_2_LEFT: ElementType = 10;
+    pub const PLACEHOLDER: ElementType = 11;
+    pub const TIMESTAMP_LITERAL: ElementType = 12;
+    pub const DATE_LITERAL: ElementType = 13;
+    pub const INTERVAL_LITERAL: ElementType = 14;
+    pub const DOUBLE_LITERAL: ElementType = 15;
+    pub const BYTES_LITERAL: ElementType = 16;
+    pub const JSON_TEXT_ARROW: ElementType = 17;
 
     pub(super) fn getType(&self) -> ElementType {
         match self {
@@ -42,9 +70,17 @@ impl Element {
             Element::StringContent(_) => Self::STRING_CONTENT,
             Element::IntegerLiteral(_) => Self::INTEGER_LITERAL,
             Element::DecimalLiteral(_) => Self::DECIMAL_LITERAL,
+            Element::DoubleLiteral(_) => Self::DOUBLE_LITERAL,
+            Element::BytesLiteral(_) => Self::BYTES_LITERAL,
+            Element::TimestampLiteral(_) => Self::TIMESTAMP_LITERAL,
+            Element::DateLiteral(_) => Self::DATE_LITERAL,
+            Element::IntervalLiteral(_) => Self::INTERVAL_LITERAL,
             Element::Op(_) => Self::OP,
             Element::Boolean(_) => Self::BOOLEAN,
             Element::Arrow2Right => Self::ARROW_2_RIGHT,
+            Element::Arrow2Left => Self::ARROW_2_LEFT,
+            Element::JsonTextArrow => Self::JSON_TEXT_ARROW,
+            Element::Placeholder(_) => Self::PLACEHOLDER,
             Element::Null => Self::NULL,
             Element::Not => Self::NOT,
             Element::Default => Self::DEFAULT,
@@ -137,9 +173,17 @@ impl Display for Element {
             Element::StringContent(s) => write!(f, "StringContent({})", s),
             Element::IntegerLiteral(s) => write!(f, "IntegerLiteral({})", s),
             Element::DecimalLiteral(s) => write!(f, "DecimalLiteral({})", s),
+            Element::DoubleLiteral(s) => write!(f, "DoubleLiteral({})", s),
+            Element::BytesLiteral(s) => write!(f, "BytesLiteral(x'{}')", HEXLOWER.encode(s)),
+            Element::TimestampLiteral(s) => write!(f, "TimestampLiteral({})", temporal::formatTimestamp(*s)),
+            Element::DateLiteral(s) => write!(f, "DateLiteral({})", temporal::formatDate(*s)),
+            Element::IntervalLiteral(s) => write!(f, "IntervalLiteral({})", s),
             Element::Boolean(bool) => write!(f, "Boolean({})", bool),
             Element::Op(op) => write!(f, "Op({})", op),
             Element::Arrow2Right => write!(f, "To"),
+            Element::Arrow2Left => write!(f, "From"),
+            Element::JsonTextArrow => write!(f, "JsonTextArrow"),
+            Element::Placeholder(index) => write!(f, "Placeholder({})", index),
             Element::Null => write!(f, "Null"),
             Element::Not => write!(f, "Not"),
             Element::Default => write!(f, "Default")
@@ -225,6 +269,16 @@ impl Parser {
                         }
                     }
                 }
+                global::问号_CHAR => {
+                    if self.whetherIn单引号() {
+                        self.pendingChars.push(currentChar);
+                    } else {
+                        self.collectPendingChars(&mut currentElementVec);
+
+                        currentElementVec.push(Element::Placeholder(self.placeholderCount));
+                        self.placeholderCount += 1;
+                    }
+                }
                 global::分号_CHAR | global::换行_CHAR => { // 要是写了多个sql的话 以";" 和 换行分割
                     // 单纯的是文本内容
                     if self.whetherIn单引号() {
@@ -243,6 +297,12 @@ impl Parser {
                     // 单纯currentCharIndex的是文本内容
                     if self.whetherIn单引号() {
                         self.pendingChars.push(currentChar);
+                    } else if currentChar == global::小于_CHAR && Some(global::减号_CHAR) == self.peekNextChar() {
+                        // 应对<-
+                        advanceCount = 2;
+
+                        self.collectPendingChars(&mut currentElementVec);
+                        currentElementVec.push(Element::Arrow2Left);
                     } else {
                         let operatorString: String =
                             // 应对  "!=" ">=" "<=" 两个char的 目前的不容许有空格的
@@ -273,10 +333,15 @@ impl Parser {
                     if self.whetherIn单引号() {
                         self.pendingChars.push(currentChar);
                     } else {
-                        // 应对->
+                        // 应对-> ->>
                         let element = if currentChar == global::减号_CHAR && Some(global::大于_CHAR) == self.peekNextChar() {
-                            advanceCount = 2;
-                            Element::Arrow2Right
+                            if Some(&global::大于_CHAR) == self.chars.get(self.currentCharIndex + 2) {
+                                advanceCount = 3;
+                                Element::JsonTextArrow
+                            } else {
+                                advanceCount = 2;
+                                Element::Arrow2Right
+                            }
                         } else {
                             let mathCalcOp = MathCalcOp::fromChar(currentChar)?;
                             Element::Op(Op::MathCalcOp(mathCalcOp))
@@ -287,19 +352,35 @@ impl Parser {
                         currentElementVec.push(element);
                     }
                 }
+                // json的 @>
+                global::AT_CHAR => {
+                    if self.whetherIn单引号() == false && Some(global::大于_CHAR) == self.peekNextChar() {
+                        advanceCount = 2;
+
+                        self.collectPendingChars(&mut currentElementVec);
+                        currentElementVec.push(Element::Op(Op::SqlOp(SqlOp::Contain)));
+                    } else {
+                        self.pendingChars.push(currentChar);
+                    }
+                }
                 'n' | 'N' => { // 应对null, not
+                    // 不能continue 那样会跳过下边的advanceChar
                     if self.whetherIn单引号() {
                         self.pendingChars.push(currentChar);
-                        continue;
-                    }
-
-                    if self.tryPrefecthIgnoreCase(&vec!['U', 'L', 'L']) {
+                    } else if self.tryPrefecthIgnoreCase(&vec!['U', 'L', 'L']) {
                         // 需要了断 pendingChars
                         self.collectPendingChars(&mut currentElementVec);
                         currentElementVec.push(Element::Null);
                     } else if self.tryPrefecthIgnoreCase(&vec!['o', 't']) {
                         self.collectPendingChars(&mut currentElementVec);
-                        currentElementVec.push(Element::Not);
+
+                        // is not 合并成1个op
+                        if let Some(Element::Op(Op::SqlOp(SqlOp::Is))) = currentElementVec.last() {
+                            currentElementVec.pop();
+                            currentElementVec.push(Element::Op(Op::SqlOp(SqlOp::IsNot)));
+                        } else {
+                            currentElementVec.push(Element::Not);
+                        }
                     } else {
                         self.pendingChars.push(currentChar);
                     }
@@ -307,10 +388,7 @@ impl Parser {
                 'd' | 'D' => { // 应对default
                     if self.whetherIn单引号() {
                         self.pendingChars.push(currentChar);
-                        continue;
-                    }
-
-                    if self.tryPrefecthIgnoreCase(&vec!['e', 'f', 'a', 'u', 'l', 't']) {
+                    } else if self.tryPrefecthIgnoreCase(&vec!['e', 'f', 'a', 'u', 'l', 't']) {
                         // 需要了断 pendingChars
                         self.collectPendingChars(&mut currentElementVec);
                         currentElementVec.push(Element::Default);
@@ -321,10 +399,7 @@ impl Parser {
                 'l' | 'L' => {
                     if self.whetherIn单引号() {
                         self.pendingChars.push(currentChar);
-                        continue;
-                    }
-
-                    if self.tryPrefecthIgnoreCase(&vec!['i', 'k', 'e']) {
+                    } else if self.tryPrefecthIgnoreCase(&vec!['i', 'k', 'e']) {
                         // 需要了断 pendingChars
                         self.collectPendingChars(&mut currentElementVec);
                         currentElementVec.push(Element::Op(Op::SqlOp(SqlOp::Like)));
@@ -361,6 +436,46 @@ impl Parser {
             self.throwSyntaxErrorDetail("the sql is empty string")?;
         }
 
+        for elementVec in &mut self.elementVecVec {
+            Self::mergeTypedLiterals(elementVec)?;
+        }
+
+        Ok(())
+    }
+
+    /// timestamp '2024-01-02 03:04:05', date '2024-01-02', interval '1 day', x'deadbeef' 合并成1个element <br>
+    /// 后边不是紧跟着string的话 还是当作普通的text 例如表的字段名是date
+    fn mergeTypedLiterals(elementVec: &mut Vec<Element>) -> Result<()> {
+        let mut index = 0;
+
+        while index + 1 < elementVec.len() {
+            let merged =
+                match (&elementVec[index], &elementVec[index + 1]) {
+                    (Element::TextLiteral(text), Element::StringContent(content)) => {
+                        match text.to_lowercase().as_str() {
+                            "timestamp" => Some(Element::TimestampLiteral(temporal::parseTimestamp(content)?)),
+                            "date" => Some(Element::DateLiteral(temporal::parseDate(content)?)),
+                            "interval" => Some(Element::IntervalLiteral(Interval::parse(content)?)),
+                            "x" => {
+                                match HEXLOWER_PERMISSIVE.decode(content.as_bytes()) {
+                                    Ok(bytes) => Some(Element::BytesLiteral(bytes)),
+                                    Err(_) => throwFormat!("invalid hex literal:x'{}'", content),
+                                }
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+
+            if let Some(merged) = merged {
+                elementVec[index] = merged;
+                elementVec.remove(index + 1);
+            }
+
+            index += 1;
+        }
+
         Ok(())
     }
 
@@ -465,7 +580,10 @@ impl Parser {
                     Element::StringContent(text)
                 } else {
                     if isDecimal {
-                        Element::DecimalLiteral(text.parse::<f64>().unwrap())
+                        match decimal::parse(&text) {
+                            Ok(decimal) => Element::DecimalLiteral(decimal),
+                            Err(_) => Element::DoubleLiteral(text.parse::<f64>().unwrap()),
+                        }
                     } else {
                         Element::IntegerLiteral(text.parse::<i64>().unwrap())
                     }
@@ -483,9 +601,17 @@ impl Parser {
                         "OR" => Element::Op(Op::LogicalOp(LogicalOp::Or)),
                         "AND" => Element::Op(Op::LogicalOp(LogicalOp::And)),
                         "IN" => Element::Op(Op::SqlOp(SqlOp::In)),
+                        "IS" => Element::Op(Op::SqlOp(SqlOp::Is)),
+                        // tags contains 'rust', tags any ['rust', 'db']
+                        "CONTAINS" => Element::Op(Op::SqlOp(SqlOp::Contain)),
+                        "ANY" => Element::Op(Op::SqlOp(SqlOp::Any)),
                         // todo 应对 recursive 查询
                         _ => {
-                            Element::TextLiteral(text)
+                            // $1 这样的placeholder
+                            match text.strip_prefix(global::DOLLAR_CHAR).map(|index| index.parse::<usize>()) {
+                                Some(Ok(index)) if index > 0 => Element::Placeholder(index - 1),
+                                _ => Element::TextLiteral(text),
+                            }
                         }
                     }
                 }
@@ -561,11 +687,11 @@ impl Parser {
     }
     /// getCurrentElementAdvance, 得到current element 然后 advance
     pub(super) fn getCurrentElementAdvance(&mut self) -> Result<&Element> {
-        if let Some(element) = self.getCurrentElementOption() {
-            Ok(element)
-        } else {
+        if self.hasRemainingElement() == false {
             self.throwSyntaxErrorDetail("unexpected end of sql")?
         }
+
+        Ok(self.getCurrentElementAdvanceOption().unwrap())
     }
 
     pub(super) fn getCurrentElementOption(&self) -> Option<&Element> {
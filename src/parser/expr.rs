@@ -1,7 +1,9 @@
 use crate::expr::Expr;
-use crate::{global, prefix_plus_plus, suffix_plus_plus};
+use std::mem;
+use crate::{function, global, prefix_minus_minus, prefix_plus_plus, suffix_plus_plus, throwFormat};
+use crate::parser::command::Command;
 use crate::parser::element::Element;
-use crate::parser::op::{Op, SqlOp};
+use crate::parser::op::{LogicalOp, MathCmpOp, Op, SqlOp};
 use crate::parser::Parser;
 use anyhow::Result;
 
@@ -29,6 +31,9 @@ impl Parser {
 
         let mut parseCondState = ParseCondState::ParsingLeft;
 
+        // 右边的expr可能是 lower('a') 这样的占了多个element 碰到后边的+时候要回到它的打头
+        let mut rightExprStartIndex = None;
+
         loop {
             let currentElement = match self.getCurrentElementAdvanceOption() {
                 None => break,
@@ -37,18 +42,64 @@ impl Parser {
 
             match parseCondState {
                 ParseCondState::ParsingLeft => {
+                    // not (...) 只能是跟着括号
+                    if let Element::Not = currentElement {
+                        if self.getCurrentElement()?.expectTextLiteralContentBool(global::圆括号_STR) == false {
+                            self.throwSyntaxErrorDetail("not should be followed by (")?;
+                        }
+
+                        expr = Expr::BiDirection {
+                            leftExpr: Box::new(self.parseExpr(false)?),
+                            op: Op::LogicalOp(LogicalOp::Not),
+                            rightExprs: Default::default(),
+                        };
+
+                        // 和 a = 1 相同 后边可以跟着 and or
+                        parseCondState = ParseCondState::ParseRightComplete;
+                        continue;
+                    }
+
                     if currentElement.expectTextLiteralContentBool(global::圆括号_STR) {
                         self.skipElement(-1)?;
+
+                        // (select ...) > 1
+                        if let Some(subquery) = self.parseSubquery()? {
+                            expr = subquery;
+                            parseCondState = ParseCondState::ParsingOp;
+                            continue;
+                        }
+
                         expr = self.parseExpr(false)?;
                         parseCondState = ParseCondState::ParsingOp;
                         continue;
                     }
 
+                    // lower(name) = 'a', case when ... end = 'a'
+                    if let Some(compound) = self.tryParseCaseOrFunction(&currentElement)? {
+                        expr = compound;
+                        parseCondState = ParseCondState::ParsingOp;
+                        continue;
+                    }
+
                     expr = Expr::Single(currentElement);
 
                     parseCondState = ParseCondState::ParsingOp;
                 }
                 ParseCondState::ParsingOp => {
+                    // profile->'address'->>'city' = 'hz' 的左边
+                    if let Element::Arrow2Right | Element::JsonTextArrow = currentElement {
+                        expr = self.parseJsonPath(&currentElement, expr)?;
+                        continue;
+                    }
+
+                    // within_radius(location, '[30.27, 120.15]', 5000) and id > 0 左边自身已是完整的
+                    // 交给ParseRightComplete 不然会成为 (... and id) > 0
+                    if let Element::Op(Op::LogicalOp(_)) = currentElement {
+                        parseCondState = ParseCondState::ParseRightComplete;
+                        self.skipElement(-1)?;
+                        continue;
+                    }
+
                     if let Element::Op(op) = currentElement {
                         expr = Expr::BiDirection {
                             leftExpr: Box::new(expr),
@@ -70,10 +121,37 @@ impl Parser {
                     parseCondState = ParseCondState::ParsingRight;
                 }
                 ParseCondState::ParsingRight => {
+                    rightExprStartIndex = Some(self.currentElementIndex - 1);
+
+                    // is null, is not null
+                    if let Expr::BiDirection { op: Op::SqlOp(SqlOp::Is | SqlOp::IsNot), .. } = expr {
+                        if let Element::Null = currentElement {} else {
+                            self.throwSyntaxErrorDetail("is and is not can only be followed by null")?;
+                        }
+                    }
+
                     match currentElement {
                         Element::TextLiteral(ref text) => {
                             // 后续要支持 a in ('a') 和 a = (0+1)
                             if text == global::圆括号_STR {
+                                // a in (select ...) 和 a > (select ...)
+                                self.skipElement(-1)?;
+                                if let Some(subquery) = self.parseSubquery()? {
+                                    if let Expr::BiDirection { leftExpr: left, op, .. } = expr {
+                                        expr = Expr::BiDirection {
+                                            leftExpr: left,
+                                            op,
+                                            rightExprs: vec![Box::new(subquery)],
+                                        }
+                                    } else {
+                                        self.throwSyntaxError()?;
+                                    }
+
+                                    parseCondState = ParseCondState::ParseRightComplete;
+                                    continue;
+                                }
+                                self.skipElement(1)?;
+
                                 // 要应对 a in ('a'),那么碰到"("的话需要去看看前边的是不是 in
 
                                 // 需要先回过去然后回过来,不然prevElement还是currentElement
@@ -127,11 +205,18 @@ impl Parser {
                         _ => {}
                     }
 
+                    // a = lower('A'), a = case when ... end
+                    let rightExpr =
+                        match self.tryParseCaseOrFunction(&currentElement)? {
+                            Some(compound) => compound,
+                            None => Expr::Single(currentElement),
+                        };
+
                     if let Expr::BiDirection { leftExpr: left, op, .. } = expr {
                         expr = Expr::BiDirection {
                             leftExpr: left,
                             op,
-                            rightExprs: vec![Box::new(Expr::Single(currentElement))],
+                            rightExprs: vec![Box::new(rightExpr)],
                         }
                     } else {
                         self.throwSyntaxError()?;
@@ -140,6 +225,19 @@ impl Parser {
                     parseCondState = ParseCondState::ParseRightComplete;
                 }
                 ParseCondState::ParseRightComplete => {
+                    // tier = profile->>'tier' 的右边
+                    if let Element::Arrow2Right | Element::JsonTextArrow = currentElement {
+                        match expr {
+                            Expr::BiDirection { ref mut rightExprs, .. } if rightExprs.is_empty() == false => {
+                                let rightExpr = rightExprs.pop().unwrap();
+                                rightExprs.push(Box::new(self.parseJsonPath(&currentElement, *rightExpr)?));
+                            }
+                            _ => expr = self.parseJsonPath(&currentElement, expr)?,
+                        }
+
+                        continue;
+                    }
+
                     match currentElement {
                         Element::TextLiteral(text) => {
                             // 要是不是以(打头的话,那么到这没有必要继续了
@@ -187,7 +285,10 @@ impl Parser {
                                 Op::MathCalcOp(_) => {
                                     if let Expr::BiDirection { leftExpr: left, op, .. } = expr {
                                         // 需要先回到0+6的起始index
-                                        self.skipElement(-2)?;
+                                        match rightExprStartIndex {
+                                            Some(rightExprStartIndex) => self.currentElementIndex = rightExprStartIndex,
+                                            None => self.throwSyntaxError()?,
+                                        }
 
                                         expr = Expr::BiDirection {
                                             leftExpr: left,
@@ -226,6 +327,310 @@ impl Parser {
         Ok(expr)
     }
 
+    /// 已经读取了的element是 -> 或 ->> 后边跟着的是字段名或数组的下标 成为 json_get json_get_text 函数
+    fn parseJsonPath(&mut self, arrow: &Element, jsonExpr: Expr) -> Result<Expr> {
+        let key = self.getCurrentElementAdvance()?.clone();
+
+        if let Element::StringContent(_) | Element::IntegerLiteral(_) | Element::Placeholder(_) = key {} else {
+            self.throwSyntaxErrorDetail("-> and ->> should be followed by 'key' or array index")?;
+        }
+
+        let functionName =
+            match arrow {
+                Element::JsonTextArrow => "json_get_text",
+                _ => "json_get",
+            };
+
+        Ok(Expr::Function {
+            functionName: functionName.to_string(),
+            args: vec![Box::new(jsonExpr), Box::new(Expr::Single(key))],
+        })
+    }
+
+    /// 当前的element是"(" 要是后边跟着的是select 那么到对应的")"为止都是子查询 返回后index在")"的后边 <br>
+    /// 不是的话返回None 且index不变
+    fn parseSubquery(&mut self) -> Result<Option<Expr>> {
+        self.getCurrentElement()?.expectTextLiteralContent(global::圆括号_STR)?;
+
+        match self.peekNextElementOpt() {
+            Some(element) if element.expectTextLiteralContentIgnoreCaseBool("select") => {}
+            _ => return Ok(None),
+        }
+
+        // 跳过 "(" 和 "select"
+        self.skipElement(2)?;
+
+        let mut 括号count = 1;
+        let mut elementVec = Vec::new();
+
+        loop {
+            let currentElement = self.getCurrentElementAdvance()?.clone();
+
+            if currentElement.expectTextLiteralContentBool(global::圆括号_STR) {
+                suffix_plus_plus!(括号count);
+            } else if currentElement.expectTextLiteralContentBool(global::圆括号1_STR) {
+                if prefix_minus_minus!(括号count) == 0 {
+                    break;
+                }
+            }
+
+            elementVec.push(currentElement);
+        }
+
+        // 和parseInExprs相同 单独生成小的parser
+        let mut selectParser = Parser::default();
+        selectParser.sql = self.sql.clone();
+        selectParser.elementVecVec.push(elementVec);
+
+        match selectParser.parseSelect(true)? {
+            Command::Select(select) => Ok(Some(Expr::Subquery(Box::new(select)))),
+            _ => panic!("impossible"),
+        }
+    }
+
+    fn tryParseCaseOrFunction(&mut self, element: &Element) -> Result<Option<Expr>> {
+        if element.expectTextLiteralContentIgnoreCaseBool("case") {
+            return Ok(Some(self.parseCase()?));
+        }
+
+        // ['rust', 'db']
+        if element.expectTextLiteralContentBool(global::方括号_STR) {
+            return Ok(Some(self.parseArrayLiteral()?));
+        }
+
+        self.tryParseFunction(element)
+    }
+
+    /// 已经读取了的element是"[" 到对应的"]"为止 各个元素以","分隔 成为 list 函数 返回后index在"]"的后边
+    fn parseArrayLiteral(&mut self) -> Result<Expr> {
+        let mut elementVecs = Vec::new();
+        let mut elementVec = Vec::new();
+        let mut 括号count = 0;
+
+        loop {
+            let element =
+                match self.getCurrentElementAdvanceOption() {
+                    Some(element) => element.clone(),
+                    None => self.throwSyntaxErrorDetail("[ should be closed by ]")?,
+                };
+
+            if let Element::TextLiteral(ref text) = element {
+                match text.as_str() {
+                    global::圆括号_STR | global::方括号_STR => 括号count += 1,
+                    global::方括号1_STR if 括号count == 0 => break,
+                    global::圆括号1_STR | global::方括号1_STR => 括号count -= 1,
+                    global::逗号_STR if 括号count == 0 => {
+                        elementVecs.push(mem::take(&mut elementVec));
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+
+            elementVec.push(element);
+        }
+
+        // 不是 [] 这样的空的
+        if elementVecs.is_empty() == false || elementVec.is_empty() == false {
+            elementVecs.push(elementVec);
+        }
+
+        let mut args = Vec::with_capacity(elementVecs.len());
+        for elementVec in elementVecs {
+            args.push(Box::new(self.parseExprFromElements(elementVec)?));
+        }
+
+        Ok(Expr::Function {
+            functionName: "list".to_string(),
+            args,
+        })
+    }
+
+    /// 已经读取了的element是case 到对应的end为止 返回后index在end的后边 <br>
+    /// case when score > 90 then 'gold' when score > 60 then 'silver' else 'none' end <br>
+    /// case level when 'gold' then 1 else 0 end
+    fn parseCase(&mut self) -> Result<Expr> {
+        // 各个部分的打头的keyword和它后边的element
+        let mut keyword_elementVecs: Vec<(String, Vec<Element>)> = Vec::new();
+
+        let mut keyword = "case".to_string();
+        let mut elementVec = Vec::new();
+
+        // 内部嵌套的case
+        let mut caseCount = 0;
+
+        loop {
+            let element =
+                match self.getCurrentElementAdvanceOption() {
+                    Some(element) => element.clone(),
+                    None => self.throwSyntaxErrorDetail("case should be closed by end")?,
+                };
+
+            if let Element::TextLiteral(ref text) = element {
+                let text = text.to_lowercase();
+
+                match text.as_str() {
+                    "case" => caseCount += 1,
+                    "end" if caseCount == 0 => {
+                        keyword_elementVecs.push((keyword, elementVec));
+                        break;
+                    }
+                    "end" => caseCount -= 1,
+                    "when" | "then" | "else" if caseCount == 0 => {
+                        keyword_elementVecs.push((mem::replace(&mut keyword, text), mem::take(&mut elementVec)));
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+
+            elementVec.push(element);
+        }
+
+        let mut keyword_elementVecs = keyword_elementVecs.into_iter();
+
+        // case 和 第1个when之间的 是simple case
+        let operand =
+            match keyword_elementVecs.next() {
+                Some((_, elementVec)) if elementVec.is_empty() => None,
+                Some((_, elementVec)) => Some(Box::new(self.parseExprFromElements(elementVec)?)),
+                None => panic!("impossible"),
+            };
+
+        let mut whenThens = Vec::new();
+        let mut elseExpr = None;
+
+        loop {
+            match (keyword_elementVecs.next(), keyword_elementVecs.next()) {
+                (Some((when, whenElementVec)), Some((then, thenElementVec))) if when == "when" && then == "then" => {
+                    if elseExpr.is_some() {
+                        self.throwSyntaxErrorDetail("else should be the last of case")?;
+                    }
+
+                    whenThens.push((self.parseExprFromElements(whenElementVec)?, self.parseExprFromElements(thenElementVec)?));
+                }
+                (Some((else0, elseElementVec)), None) if else0 == "else" => {
+                    elseExpr = Some(Box::new(self.parseExprFromElements(elseElementVec)?));
+                }
+                (None, None) => break,
+                _ => self.throwSyntaxErrorDetail("case should be like case [expr] when expr then expr [else expr] end")?,
+            }
+        }
+
+        if whenThens.is_empty() {
+            self.throwSyntaxErrorDetail("case should have at least one when")?;
+        }
+
+        Ok(Expr::Case {
+            operand,
+            whenThens,
+            elseExpr,
+        })
+    }
+
+    /// 单独生成小的parser来解析 element要被全部用掉
+    fn parseExprFromElements(&self, elementVec: Vec<Element>) -> Result<Expr> {
+        if elementVec.is_empty() {
+            self.throwSyntaxErrorDetail("expect expr")?;
+        }
+
+        let mut parserMini = Parser::default();
+        parserMini.sql = self.sql.clone();
+        parserMini.elementVecVec.push(elementVec);
+
+        let expr = parserMini.parseExpr(false)?;
+
+        if parserMini.hasRemainingElement() {
+            self.throwSyntaxErrorDetail("unexpected element after expr")?;
+        }
+
+        Ok(expr)
+    }
+
+    /// 已经读取了的element是函数名 且当前的element是"(" 那么是函数调用 到对应的")"为止 返回后index在")"的后边 <br>
+    /// 各个参数以","分隔 cast(x as integer) 的 as integer 成为末尾的string参数
+    pub(in crate::parser) fn tryParseFunction(&mut self, element: &Element) -> Result<Option<Expr>> {
+        let functionName =
+            match element {
+                Element::TextLiteral(text) if text != global::圆括号_STR && text != global::圆括号1_STR => text,
+                _ => return Ok(None),
+            };
+
+        match self.getCurrentElementOption() {
+            Some(element) if element.expectTextLiteralContentBool(global::圆括号_STR) => self.skipElement(1)?,
+            _ => return Ok(None),
+        }
+
+        let scalarFunction = function::getScalarFunction(functionName)?;
+
+        let mut argElementVecs = Vec::new();
+        let mut elementVec = Vec::new();
+        let mut 括号count = 0;
+
+        loop {
+            let element = self.getCurrentElementAdvance()?.clone();
+
+            if let Element::TextLiteral(ref text) = element {
+                match text.as_str() {
+                    global::圆括号_STR | global::方括号_STR => 括号count += 1,
+                    global::圆括号1_STR if 括号count == 0 => break,
+                    global::圆括号1_STR | global::方括号1_STR => 括号count -= 1,
+                    global::逗号_STR if 括号count == 0 => {
+                        argElementVecs.push(mem::take(&mut elementVec));
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+
+            elementVec.push(element);
+        }
+
+        // 不是 f() 这样的没有参数的
+        if argElementVecs.is_empty() == false || elementVec.is_empty() == false {
+            argElementVecs.push(elementVec);
+        }
+
+        let mut args = Vec::with_capacity(argElementVecs.len());
+
+        for mut argElementVec in argElementVecs {
+            // cast(x as integer) cast(x as decimal(10, 2))
+            let mut typeName = None;
+            if let Some(asIndex) = argElementVec.iter().rposition(|element| element.expectTextLiteralContentIgnoreCaseBool("as")) {
+                if asIndex >= 1 && asIndex + 1 < argElementVec.len() {
+                    let mut typeText = String::new();
+
+                    for element in argElementVec.split_off(asIndex + 1) {
+                        match element {
+                            Element::TextLiteral(text) => typeText.push_str(&text),
+                            Element::IntegerLiteral(integer) => typeText.push_str(&integer.to_string()),
+                            // cast(x as list<string>)
+                            Element::Op(Op::MathCmpOp(MathCmpOp::LessThan)) => typeText.push_str(global::小于_STR),
+                            Element::Op(Op::MathCmpOp(MathCmpOp::GreaterThan)) => typeText.push_str(global::大于_STR),
+                            _ => throwFormat!("as should followed by type name, but get {}", element),
+                        }
+                    }
+
+                    argElementVec.pop();
+                    typeName = Some(typeText);
+                }
+            }
+
+            args.push(Box::new(self.parseExprFromElements(argElementVec)?));
+
+            if let Some(typeName) = typeName {
+                args.push(Box::new(Expr::Single(Element::StringContent(typeName.to_lowercase()))));
+            }
+        }
+
+        scalarFunction.checkArgCount(args.len())?;
+
+        Ok(Some(Expr::Function {
+            functionName: scalarFunction.name.to_string(),
+            args,
+        }))
+    }
+
     /// 单独的函数解析 a in (0,0+6,0+(a+1),)的in后边的括号包含的用","分隔的多个expr
     // 单独的生成小的parser,element只包含expr的
     pub(super) fn parseInExprs(&mut self) -> Result<Vec<Expr>> {
@@ -234,6 +639,8 @@ impl Parser {
 
         let mut 括号count = 0;
         let mut 括号1count = 0;
+        // in (['a', 'b']) 的数组里边的","不算
+        let mut 方括号count = 0;
 
         let mut pendingElementVec = Vec::new();
         let mut exprParserVec = Vec::new();
@@ -268,6 +675,16 @@ impl Parser {
                                 pendingElementVec.push(currentElement.clone());
                             }
                         }
+                        global::方括号_STR => {
+                            pendingElementVec.push(currentElement.clone());
+                            suffix_plus_plus!(方括号count);
+                        }
+                        global::方括号1_STR => {
+                            pendingElementVec.push(currentElement.clone());
+                            prefix_minus_minus!(方括号count);
+                        }
+                        // 内部的括号里边的","不算 例如 in (substr(a, 1, 2))
+                        global::逗号_STR if 括号count - 括号1count > 1 || 方括号count > 0 => pendingElementVec.push(currentElement.clone()),
                         global::逗号_STR => {
                             if pendingElementVec.len() == 0 {
                                 continue;
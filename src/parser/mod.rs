@@ -2,6 +2,7 @@ use crate::{global, prefix_plus_plus, throw, throwFormat};
 use crate::parser::command::Command;
 use crate::parser::element::Element;
 use anyhow::Result;
+use std::cmp;
 
 pub mod element;
 pub mod command;
@@ -23,6 +24,9 @@ pub struct Parser {
     currentElementVecIndex: usize,
     /// ```Vec<Element>的index```
     currentElementIndex: usize,
+
+    /// 读到的 ? 的数量 用来给它们编号
+    placeholderCount: usize,
 }
 
 pub fn parse(sql: &str) -> Result<Vec<Command>> {
@@ -45,6 +49,32 @@ pub fn parse(sql: &str) -> Result<Vec<Command>> {
     parser.parse()
 }
 
+/// prepared statement 使用 返回的还有placeholder的数量 ? 和 $1 可以混用
+pub fn parsePrepared(sql: &str) -> Result<(Vec<Command>, usize)> {
+    let mut parser = Parser::new(sql);
+
+    parser.parseElement()?;
+
+    let mut paramCount = 0;
+    for elementVec in &parser.elementVecVec {
+        for element in elementVec {
+            if let Element::Placeholder(index) = element {
+                paramCount = cmp::max(paramCount, index + 1);
+            }
+        }
+    }
+
+    let commands = parser.parse()?;
+
+    for command in &commands {
+        if command.isPreparable() == false {
+            throw!("only select and dml can be prepared");
+        }
+    }
+
+    Ok((commands, paramCount))
+}
+
 impl Parser {
     pub fn new(sql: &str) -> Self {
         let mut parser = Parser::default();
@@ -76,6 +106,7 @@ impl Parser {
                     "set" => self.parseSet()?,
                     "show" => self.parseShow()?,
                     "alter" => self.parseAlter()?,
+                    "explain" => self.parseExplain()?,
                     _ => self.throwSyntaxError()?,
                 };
 
@@ -119,16 +150,80 @@ impl Parser {
 #[cfg(test)]
 mod test {
     use std::fmt::Debug;
+    use crate::expr::Expr;
+    use crate::graph_value::GraphValue;
     use crate::parser;
+    use crate::meta::ColumnType;
+    use crate::parser::command::Command;
+    use crate::parser::command::select::Select;
+    use crate::parser::element::Element;
+    use crate::parser::op::{LogicalOp, MathCmpOp, Op, SqlOp};
 
     #[test]
     pub fn testParseCreateTable() {
         parser::parse("create table if not exist user (id integer not null default 0,name string)").unwrap();
+        parser::parse("create table account (id integer, active boolean not null default true)").unwrap();
+
+        assert!(parser::parse("create table account (active boolean default 1)").is_err());
+
+        match parser::parse("create table account (balance decimal(10, 2) not null default 1.005, rate double default 1, amount decimal)").unwrap().remove(0) {
+            Command::CreateTable(table) => {
+                assert_eq!(table.columns[0].type0.to_string(), "DECIMAL(10, 2)");
+                assert!(matches!(table.columns[0].defaultValue, Some(Element::DecimalLiteral(d)) if d.to_string() == "1.01"));
+                assert!(matches!(table.columns[1].defaultValue, Some(Element::DoubleLiteral(f)) if f == 1.0));
+                assert_eq!(table.columns[2].type0.to_string(), "DECIMAL");
+            }
+            _ => panic!(),
+        }
+
+        parser::parse("select account[cast(balance as decimal(5,1)) as b](balance > 0.1 + 0.2)").unwrap();
+
+        assert!(parser::parse("create table account (balance decimal(29, 2))").is_err());
+        assert!(parser::parse("create table account (balance decimal(2, 3))").is_err());
+        assert!(parser::parse("create table account (balance decimal(3, 2) default 10)").is_err());
     }
 
     #[test]
     pub fn testParseCreateIndex() {
         parser::parse("create index aaa on user[name,id]").unwrap();
+
+        match parser::parse("create unique index userEmail on user[email]").unwrap().remove(0) {
+            Command::CreateIndex(index) => {
+                assert!(index.unique);
+                assert_eq!(index.columnNames, vec!["email".to_string()]);
+            }
+            _ => panic!(),
+        }
+
+        match parser::parse("create index aaa on user[name]").unwrap().remove(0) {
+            Command::CreateIndex(index) => assert!(index.unique == false),
+            _ => panic!(),
+        }
+
+        assert!(parser::parse("create unique table user (id integer)").is_err());
+    }
+
+    #[test]
+    pub fn testParsePrimaryKey() {
+        match parser::parse("create table user (id integer, name string, primary key (id, name), age integer)").unwrap().remove(0) {
+            Command::CreateTable(table) => {
+                assert_eq!(table.primaryKeyColumnNames, vec!["id".to_string(), "name".to_string()]);
+                assert_eq!(table.columns.len(), 3);
+                assert!(table.columns.iter().all(|column| column.nullable == (column.name == "age")));
+            }
+            _ => panic!(),
+        }
+
+        match parser::parse("create table user (id integer, name string)").unwrap().remove(0) {
+            Command::CreateTable(table) => assert!(table.primaryKeyColumnNames.is_empty()),
+            _ => panic!(),
+        }
+
+        assert!(parser::parse("create table user (id integer, primary key (name))").is_err());
+        assert!(parser::parse("create table user (id integer, primary key (id, id))").is_err());
+        assert!(parser::parse("create table user (id integer, primary key (id), primary key (id))").is_err());
+        assert!(parser::parse("create table user (embedding vector(3), primary key (embedding))").is_err());
+        assert!(parser::parse("create relation own (number integer, primary key (number))").is_err());
     }
 
     #[test]
@@ -145,6 +240,472 @@ mod test {
         parser::parse("select user[id,name](id=1 and 0=6 and name like '%a')").unwrap();
     }
 
+    #[test]
+    pub fn testParseSelectOrderBy() {
+        parser::parse("select user(age > 3) order by age desc, name limit 10 offset 1").unwrap();
+        parser::parse("select user -likes-> user order by age limit 3").unwrap();
+
+        let (mut commands, paramCount) = parser::parsePrepared("select product order by cosine(embedding, $1) limit 10").unwrap();
+        assert_eq!(paramCount, 1);
+
+        let mut command = commands.remove(0);
+        command.bindParams(&[GraphValue::String("[1, 0]".to_string())]).unwrap();
+        match command {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                let orderBy = &selectTable.orderBys.unwrap()[0];
+                assert_eq!(orderBy.columnName, "cosine#0");
+                assert!(matches!(&orderBy.expr, Some(Expr::Function { args, .. }) if matches!(args[1].as_ref(), Expr::Single(Element::StringContent(s)) if s == "[1, 0]")));
+            }
+            _ => panic!(),
+        }
+
+        parser::parse("select user(id = 1) -bought-> product order by l2(embedding, '[1, 0]') limit 10").unwrap();
+        parser::parse("create table product (id integer, embedding vector(3))").unwrap();
+
+        assert!(parser::parse("select product[count(*)] group by id order by cosine(embedding, '[1, 0]')").is_err());
+        assert!(parser::parse("create table product (embedding vector(0))").is_err());
+    }
+
+    #[test]
+    pub fn testParseSelectAggregate() {
+        parser::parse("select user[count(*), count(distinct age), sum(age)](age > 3)").unwrap();
+        parser::parse("select user[city, avg(age)] group by city having count(*) > 1 and max(age) < 60 order by count(*) desc limit 3").unwrap();
+        parser::parse("select user -usage-> car[count(*), min(price)]").unwrap();
+
+        assert!(parser::parse("select user[name, count(*)]").is_err());
+        assert!(parser::parse("select user[sum(*)]").is_err());
+        assert!(parser::parse("select user -usage-> car[id, count(*)]").is_err());
+    }
+
+    #[test]
+    pub fn testParseSelectAlias() {
+        parser::parse("select user[id, name as userName, age * 2 as doubleAge](id > 1)").unwrap();
+        parser::parse("select user[city as c, count(*) as total] group by city order by total desc").unwrap();
+        parser::parse("select user[name as owner] -usage-> car[id, price / 100 as hundreds]").unwrap();
+
+        assert!(parser::parse("select user[age * 2]").is_err());
+        assert!(parser::parse("select user[name as n, count(*)] group by city").is_err());
+    }
+
+    #[test]
+    pub fn testParseSelectShortest() {
+        parser::parse("select user(id=1) -likes shortest recursive[1..10]-> user(id=7)").unwrap();
+        parser::parse("select user(id=1) -likes(w > 0) shortest as l-> user(id=7)").unwrap();
+
+        assert!(parser::parse("select user(id=1) -likes shortest-> car(id=7)").is_err());
+        assert!(parser::parse("select user(id=1) -likes shortest-> user -own-> car").is_err());
+    }
+
+    #[test]
+    pub fn testParseSelectReturnPaths() {
+        parser::parse("select user -likes recursive[2..4]-> user return paths").unwrap();
+        parser::parse("select user(id=1) -likes recursive[1..3]-> user(id=7) order by id limit 1 return paths").unwrap();
+
+        assert!(parser::parse("select user -likes-> user return paths").is_err());
+        assert!(parser::parse("select user -likes recursive[2..4]-> user return").is_err());
+    }
+
+    #[test]
+    pub fn testParseSelectPathSemantics() {
+        parser::parse("select user -likes recursive[1..] trail-> user").unwrap();
+        parser::parse("select user -likes recursive(1..3] simple as l-> user return paths").unwrap();
+        parser::parse("select user -likes recursive[1..3] walk-> user").unwrap();
+
+        assert!(parser::parse("select user -likes recursive[1..3] circle-> user").is_err());
+    }
+
+    #[test]
+    pub fn testParseSelectDirection() {
+        parser::parse("select user(id=2) <-friend- user").unwrap();
+        parser::parse("select user(id=2) -friend(w > 1) as f- user").unwrap();
+        parser::parse("select user(id=2) <-friend- user -usage-> car").unwrap();
+        parser::parse("select user(id=1) -friend recursive[1..3] simple- user(id=4) return paths").unwrap();
+
+        assert!(parser::parse("select user <-friend-> user").is_err());
+        assert!(parser::parse("link user(id=1) <-friend- user(id=2)").is_err());
+    }
+
+    #[test]
+    pub fn testParseExplain() {
+        match parser::parse("explain select user(id > 1 and id < 4)").unwrap().remove(0) {
+            Command::Explain(explain) => assert!(explain.analyze == false),
+            _ => panic!(),
+        }
+
+        match parser::parse("explain analyze select user(id = 1) -usage-> car").unwrap().remove(0) {
+            Command::Explain(explain) => assert!(explain.analyze),
+            _ => panic!(),
+        }
+
+        assert!(parser::parse("explain delete from user(id = 1)").is_err());
+        assert!(parser::parse("explain analyze").is_err());
+    }
+
+    #[test]
+    pub fn testParsePrepared() {
+        let (commands, paramCount) = parser::parsePrepared("select user(id = ? and name = ?)").unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(paramCount, 2);
+
+        let (_, paramCount) = parser::parsePrepared("update user[name = $2] (id = $1)").unwrap();
+        assert_eq!(paramCount, 2);
+
+        // 单引号里的 ? 是文本
+        let (_, paramCount) = parser::parsePrepared("insert into user values (?, 'a?')").unwrap();
+        assert_eq!(paramCount, 1);
+
+        let (mut commands, _) = parser::parsePrepared("delete from user(name = ?)").unwrap();
+        let mut command = commands.remove(0);
+        assert!(command.clonePrepared().unwrap().bindParams(&[]).is_err());
+        command.bindParams(&[GraphValue::String("' or 1 = 1 --".to_string())]).unwrap();
+        match command {
+            Command::Delete(delete) => {
+                match delete.filterExpr.unwrap() {
+                    Expr::BiDirection { rightExprs, .. } => {
+                        assert!(matches!(rightExprs[0].as_ref(), Expr::Single(Element::StringContent(s)) if s == "' or 1 = 1 --"));
+                    }
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        assert!(parser::parsePrepared("create table user (id integer)").is_err());
+    }
+
+    #[test]
+    pub fn testParseSubquery() {
+        let filterExpr =
+            |sql: &str| {
+                match parser::parse(sql).unwrap().remove(0) {
+                    Command::Select(Select::SelectTable(selectTable)) => selectTable.tableFilterExpr.unwrap(),
+                    _ => panic!(),
+                }
+            };
+
+        match filterExpr("select user(age > (select user[avg(age)]))") {
+            Expr::BiDirection { rightExprs, .. } => assert!(matches!(rightExprs[0].as_ref(), Expr::Subquery(_))),
+            _ => panic!(),
+        }
+
+        match filterExpr("select car(id in (select user(id = 1) -usage-> car[id]))") {
+            Expr::BiDirection { rightExprs, .. } => {
+                assert_eq!(rightExprs.len(), 1);
+                assert!(matches!(rightExprs[0].as_ref(), Expr::Subquery(select) if matches!(**select, Select::SelectRels(_))));
+            }
+            _ => panic!(),
+        }
+
+        match filterExpr("select user((select user[age](id = 2)) = age and name = 'a')") {
+            Expr::BiDirection { leftExpr, .. } => {
+                match *leftExpr {
+                    Expr::BiDirection { leftExpr, .. } => assert!(matches!(*leftExpr, Expr::Subquery(_))),
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        // 子查询里边的placeholder也要算上
+        let (_, paramCount) = parser::parsePrepared("select user(id in (select user[id](age > ?)) and name = ?)").unwrap();
+        assert_eq!(paramCount, 2);
+    }
+
+    #[test]
+    pub fn testParseNotIsNull() {
+        let filterExpr =
+            |sql: &str| {
+                match parser::parse(sql).unwrap().remove(0) {
+                    Command::Select(Select::SelectTable(selectTable)) => selectTable.tableFilterExpr.unwrap(),
+                    _ => panic!(),
+                }
+            };
+
+        match filterExpr("select user(name is null)") {
+            Expr::BiDirection { op: Op::SqlOp(SqlOp::Is), rightExprs, .. } => assert!(matches!(rightExprs[0].as_ref(), Expr::Single(Element::Null))),
+            _ => panic!(),
+        }
+
+        assert!(matches!(filterExpr("select user(name IS NOT NULL)"), Expr::BiDirection { op: Op::SqlOp(SqlOp::IsNot), .. }));
+
+        // not (...) and ...
+        match filterExpr("select user(not (id = 1 or id = 2) and name is not null)") {
+            Expr::BiDirection { leftExpr, op: Op::LogicalOp(LogicalOp::And), .. } => {
+                match *leftExpr {
+                    Expr::BiDirection { op: Op::LogicalOp(LogicalOp::Not), rightExprs, .. } => assert!(rightExprs.is_empty()),
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        assert!(matches!(filterExpr("select user(id = 1 or not (age > 1))"), Expr::BiDirection { op: Op::LogicalOp(LogicalOp::Or), .. }));
+
+        assert!(parser::parse("select user(name is 'a')").is_err());
+        assert!(parser::parse("select user(not id = 1)").is_err());
+    }
+
+    #[test]
+    pub fn testParseFunction() {
+        match parser::parse("select user(lower(name) = 'a' and length(trim(name)) > 1)").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                match selectTable.tableFilterExpr.unwrap() {
+                    Expr::BiDirection { leftExpr, .. } => {
+                        match *leftExpr {
+                            Expr::BiDirection { leftExpr, .. } => {
+                                assert!(matches!(*leftExpr, Expr::Function { ref functionName, ref args } if functionName == "lower" && args.len() == 1));
+                            }
+                            _ => panic!(),
+                        }
+                    }
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        // cast(x as integer) 的类型成为第2个参数
+        match parser::parse("select user[CAST(age AS string) as a, substr(name, 1, 2) as s]").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                let projections = selectTable.projections.unwrap();
+                match &projections[0].expr {
+                    Expr::Function { functionName, args } => {
+                        assert_eq!(functionName, "cast");
+                        assert!(matches!(args[1].as_ref(), Expr::Single(Element::StringContent(s)) if s == "string"));
+                    }
+                    _ => panic!(),
+                }
+                assert!(matches!(&projections[1].expr, Expr::Function { args, .. } if args.len() == 3));
+            }
+            _ => panic!(),
+        }
+
+        parser::parse("update user[name = concat(substr(name, 1, 1), '!'), age = abs(age)](id = 1)").unwrap();
+        parser::parse("insert into user values (1, upper('a'))").unwrap();
+        parser::parse("select user(id in (abs(1), round(2.5, 0)))").unwrap();
+
+        assert!(parser::parse("select user(unknown(name) = 1)").is_err());
+        assert!(parser::parse("select user(substr(name) = 'a')").is_err());
+
+        // 右边的计算出错要返回err 不能panic
+        match parser::parse("select user(1 = cast('x' as integer))").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => assert!(selectTable.tableFilterExpr.unwrap().calc(None).is_err()),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    pub fn testParseTemporalLiteral() {
+        match parser::parse("select usage(since >= TIMESTAMP '2024-01-02 03:04:05' and until < date '2024-02-01' + interval '1 day')").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                match selectTable.tableFilterExpr.unwrap() {
+                    Expr::BiDirection { leftExpr, rightExprs, .. } => {
+                        assert!(matches!(*leftExpr, Expr::BiDirection { ref rightExprs, .. } if matches!(rightExprs[0].as_ref(), Expr::Single(Element::TimestampLiteral(_)))));
+                        assert!(matches!(rightExprs[0].as_ref(), Expr::BiDirection { rightExprs, .. }
+                            if matches!(rightExprs[0].as_ref(), Expr::BiDirection { leftExpr, .. } if matches!(leftExpr.as_ref(), Expr::Single(Element::DateLiteral(_))))));
+                    }
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        // 后边不是string的话 date还是普通的text
+        parser::parse("create table event (date date, at timestamp default date '2024-01-01')").unwrap();
+        parser::parse("select event[date_trunc('month', at) as m](date = date '2024-01-01' and at > now() - interval '2 hours')").unwrap();
+
+        assert!(parser::parse("select event(at > timestamp '2024-13-01')").is_err());
+        assert!(parser::parse("select event(at > now() - interval '2 fortnights')").is_err());
+        assert!(parser::parse("create table event (d date default timestamp '2024-01-01 00:00:00')").is_err());
+    }
+
+    #[test]
+    pub fn testParseBytesLiteral() {
+        match parser::parse("create table file (x integer, content bytes default X'DEADbeef')").unwrap().remove(0) {
+            Command::CreateTable(table) => {
+                assert_eq!(table.columns[1].type0.to_string(), "BYTES");
+                assert!(matches!(&table.columns[1].defaultValue, Some(Element::BytesLiteral(b)) if b.as_slice() == [0xde, 0xad, 0xbe, 0xef]));
+            }
+            _ => panic!(),
+        }
+
+        // 后边不是string的话 x还是普通的text
+        parser::parse("select file[x](x = 1 and content in (x'', x'00ff'))").unwrap();
+
+        assert!(parser::parse("select file(content = x'abc')").is_err());
+        assert!(parser::parse("select file(content = x'0g')").is_err());
+        assert!(parser::parse("create table file (content bytes default 'abc')").is_err());
+    }
+
+    #[test]
+    pub fn testParseJsonPath() {
+        match parser::parse("select user[profile->'address'->>'city', profile->'tags'->0 as t](profile->>'tier' = 'gold' and 'a' = profile->'x'->>'y' and profile @> '{\"v\":1}')").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                let projections = selectTable.projections.unwrap();
+
+                // 没有alias的话是末尾的key
+                assert_eq!(projections[0].alias, "city");
+                match &projections[0].expr {
+                    Expr::Function { functionName, args } => {
+                        assert_eq!(functionName, "json_get_text");
+                        assert!(matches!(args[0].as_ref(), Expr::Function { functionName, .. } if functionName == "json_get"));
+                        assert!(matches!(args[1].as_ref(), Expr::Single(Element::StringContent(s)) if s == "city"));
+                    }
+                    _ => panic!(),
+                }
+                assert!(matches!(&projections[1].expr, Expr::Function { args, .. } if matches!(args[1].as_ref(), Expr::Single(Element::IntegerLiteral(0)))));
+
+                // ((profile->>'tier' = 'gold' and 'a' = profile->'x'->>'y') and profile @> '{"v":1}')
+                match selectTable.tableFilterExpr.unwrap() {
+                    Expr::BiDirection { leftExpr, rightExprs, .. } => {
+                        match *leftExpr {
+                            Expr::BiDirection { leftExpr, rightExprs, .. } => {
+                                assert!(matches!(*leftExpr, Expr::BiDirection { ref leftExpr, .. } if matches!(leftExpr.as_ref(), Expr::Function { functionName, .. } if functionName == "json_get_text")));
+                                assert!(matches!(rightExprs[0].as_ref(), Expr::BiDirection { rightExprs, .. } if matches!(rightExprs[0].as_ref(), Expr::Function { functionName, .. } if functionName == "json_get_text")));
+                            }
+                            _ => panic!(),
+                        }
+
+                        assert!(matches!(rightExprs[0].as_ref(), Expr::BiDirection { op: Op::SqlOp(SqlOp::Contain), .. }));
+                    }
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        // 不是在expr里边的 -> 还是relation
+        parser::parse("select user(id = 1) -usage(number > 9) as usage0-> car").unwrap();
+        parser::parse("create table user (id integer, profile json default '{}')").unwrap();
+
+        assert!(parser::parse("select user(profile->tier = 'gold')").is_err());
+        assert!(parser::parse("select user[profile->>'tier' = 'a']").is_err());
+        assert!(parser::parse("create table user (profile json default '{tier}')").is_err());
+    }
+
+    #[test]
+    pub fn testParseList() {
+        match parser::parse("create table post (id integer, tags list<string>, prices list<decimal(5, 1)> default '[]')").unwrap().remove(0) {
+            Command::CreateTable(table) => {
+                assert_eq!(table.columns[1].type0, ColumnType::List(Box::new(ColumnType::String)));
+                assert_eq!(table.columns[2].type0.to_string(), "LIST<DECIMAL(5, 1)>");
+            }
+            _ => panic!(),
+        }
+
+        match parser::parse("insert into post values (1, ['rust', 'db'], [])").unwrap().remove(0) {
+            Command::Insert(insert) => {
+                assert!(matches!(&insert.columnExprVecVec[0][1], Expr::Function { functionName, args } if functionName == "list" && args.len() == 2));
+                assert!(matches!(&insert.columnExprVecVec[0][2], Expr::Function { functionName, args } if functionName == "list" && args.is_empty()));
+            }
+            _ => panic!(),
+        }
+
+        match parser::parse("select post[id, unnest(tags) as tag](tags contains 'rust' and tags any ['db', concat('a', 'i')])").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                let projections = selectTable.projections.unwrap();
+                assert!(matches!(&projections[0].expr, Expr::Function { functionName, .. } if functionName == "unnest"));
+                assert_eq!(projections[0].alias, "tag");
+
+                match selectTable.tableFilterExpr.unwrap() {
+                    Expr::BiDirection { leftExpr, op: Op::LogicalOp(LogicalOp::And), rightExprs } => {
+                        assert!(matches!(*leftExpr, Expr::BiDirection { op: Op::SqlOp(SqlOp::Contain), .. }));
+                        assert!(matches!(rightExprs[0].as_ref(), Expr::BiDirection { op: Op::SqlOp(SqlOp::Any), .. }));
+                    }
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        parser::parse("update post[tags = ['a', 'b'], id = 2](tags contains 'rust')").unwrap();
+        parser::parse("select post(id in ([1, 2], 3))").unwrap();
+        parser::parse("select post[cast(tags as list<string>) as t]").unwrap();
+
+        assert!(parser::parse("create table post (tags list<list<string>>)").is_err());
+        assert!(parser::parse("create table post (tags list<string)").is_err());
+        assert!(parser::parse("insert into post values (['a', 'b')").is_err());
+    }
+
+    #[test]
+    pub fn testParsePoint() {
+        match parser::parse("create table store (id integer, location point default '[30.27, 120.15]')").unwrap().remove(0) {
+            Command::CreateTable(table) => assert_eq!(table.columns[1].type0, ColumnType::Point),
+            _ => panic!(),
+        }
+
+        // 左边是函数的话 and 后边的 id > 0 是完整的1个部分
+        match parser::parse("select store(within_radius(location, point(30.27, 120.15), 5000) and id > 0)").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                match selectTable.tableFilterExpr.unwrap() {
+                    Expr::BiDirection { leftExpr, op: Op::LogicalOp(LogicalOp::And), rightExprs } => {
+                        assert!(matches!(*leftExpr, Expr::Function { ref functionName, ref args } if functionName == "within_radius" && args.len() == 3));
+                        assert!(matches!(rightExprs[0].as_ref(), Expr::BiDirection { op: Op::MathCmpOp(MathCmpOp::GreaterThan), .. }));
+                    }
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        parser::parse("select store(id = 1) -near-> driver(distance(location, '[30.27, 120.15]') < 1000)").unwrap();
+        parser::parse("select store[name] order by distance(location, point(30.27, 120.15)) limit 3").unwrap();
+
+        assert!(parser::parse("create table store (location point default '[91, 0]')").is_err());
+        assert!(parser::parse("select store(within_box(location, '[30, 120]'))").is_err());
+    }
+
+    #[test]
+    pub fn testParseCase() {
+        match parser::parse("select user[case when score > 90 then 'gold' when score > 60 then 'silver' else 'none' end as level]").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                let projections = selectTable.projections.unwrap();
+                assert!(matches!(&projections[0].expr, Expr::Case { operand: None, whenThens, elseExpr: Some(_) } if whenThens.len() == 2));
+            }
+            _ => panic!(),
+        }
+
+        // simple case 嵌套在 searched case 里边
+        match parser::parse("select user(case when case level when 'gold' then true end then 1 end = 1)").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => {
+                match selectTable.tableFilterExpr.unwrap() {
+                    Expr::BiDirection { leftExpr, .. } => {
+                        match *leftExpr {
+                            Expr::Case { operand: None, whenThens, elseExpr: None } => {
+                                assert!(matches!(&whenThens[0].0, Expr::Case { operand: Some(_), .. }));
+                            }
+                            _ => panic!(),
+                        }
+                    }
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+
+        parser::parse("update user[level = case when score > 90 then 'gold' when score > 60 then 'silver' else 'none' end](id = 1)").unwrap();
+        parser::parse("select user(id = case name when 'a' then 1 else 2 end)").unwrap();
+
+        assert!(parser::parse("select user(case when score > 1 then 1 = 1)").is_err());
+        assert!(parser::parse("select user(case else 1 end = 1)").is_err());
+        assert!(parser::parse("select user(case when score > 1 else 1 end = 1)").is_err());
+
+        // 右边的case的分支计算出错要返回err 不能panic
+        match parser::parse("select user(1 = case when 1 = 1 then cast('x' as integer) end)").unwrap().remove(0) {
+            Command::Select(Select::SelectTable(selectTable)) => assert!(selectTable.tableFilterExpr.unwrap().calc(None).is_err()),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    pub fn testParseSelectWeighted() {
+        parser::parse("select city(name='a') -road weighted by distance-> city(name='b')").unwrap();
+        parser::parse("select city(name='a') -road(distance < 100) weighted by distance as r-> city(name='b')").unwrap();
+
+        assert!(parser::parse("select city(name='a') -road weighted distance-> city(name='b')").is_err());
+        assert!(parser::parse("select city(name='a') -road weighted by distance recursive[1..3]-> city(name='b')").is_err());
+    }
+
     #[test]
     pub fn testParseLink() {
         // parser::parse("link user(id > 1 and ( name = 'a' or code = (1 + 0) and true))").unwrap();
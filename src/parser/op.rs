@@ -26,6 +26,15 @@ impl Display for Op {
 }
 
 impl Op {
+    /// 只有leftExpr
+    pub fn isUnary(&self) -> bool {
+        if let Op::LogicalOp(LogicalOp::Not) = self {
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn permitByIndex(&self) -> bool {
         match self {
             Op::MathCmpOp(mathCmpOp) => {
@@ -35,7 +44,16 @@ impl Op {
                     true
                 }
             }
-            Op::SqlOp(_) => true,
+            Op::SqlOp(sqlOp) => {
+                match sqlOp {
+                    // 和NotEqual相同
+                    SqlOp::IsNot => false,
+                    // index上是整个json的binary, list的话要使用inverted index
+                    SqlOp::Contain => false,
+                    SqlOp::Any => false,
+                    _ => true,
+                }
+            }
             Op::LogicalOp(_) => false,
             Op::MathCalcOp(_) => false,
         }
@@ -75,6 +93,8 @@ impl FromStr for MathCmpOp {
 pub enum LogicalOp {
     And,
     Or,
+    /// not (...) 是1元的 没有rightExprs
+    Not,
 }
 
 #[derive(DisplayStrum, Clone, Debug, Copy, Serialize, Deserialize)]
@@ -82,6 +102,14 @@ pub enum SqlOp {
     In,
     /// like 'a' 会在calc0的时候被消化掉变为 ='a'
     Like,
+    /// is null 右边只能是null 会在calc0的时候被消化掉变为 =null
+    Is,
+    /// is not null 右边只能是null 和NotEqual相同不能使用index
+    IsNot,
+    /// json的 @> 和 list的 contains 不能使用普通的index
+    Contain,
+    /// list的 any 有其中的任意1个 和contains相同
+    Any,
 }
 
 #[derive(DisplayStrum, Clone, Debug, Copy, Serialize, Deserialize)]
@@ -25,6 +25,7 @@ use tokio::io::AsyncWriteExt;
 use graph_independent::AllocatorExt;
 use crate::config::{Config, CONFIG};
 use crate::executor::CommandExecutor;
+use crate::graph_value::GraphValue;
 use crate::meta::{DBObject, DBObjectTrait};
 use crate::parser::command::Command;
 use crate::types::{Byte, ColumnFamily, DBObjectId, DBRawIterator, KV, SelectResultToFront, SessionHashMap, SessionHashSet, SessionVec, Snapshot, TableMutations, TxId, DataKey};
@@ -35,6 +36,8 @@ pub struct Session {
 
     txId: Option<TxId>,
     pub dbObjectId_mutations: RwLock<HashMap<DBObjectId, TableMutations>>,
+    /// commit时候要对最新落地的再校验的
+    pub commitChecks: RwLock<Vec<CommitCheck>>,
     snapshot: Option<Snapshot<'static>>,
 
     pub bump: Bump,
@@ -45,11 +48,24 @@ pub struct Session {
     /// in byte
     pub workingMemorySize: usize,
 
+    /// recursive时候展开的node或路径的数量上限 超过的话报错
+    pub recursiveMaxCount: usize,
+
     /// 不是stream的话,select时候会将全部的data全部返回的
     pub streamMode: bool,
     /// 上1趟读到的最后的key
     /// 当streamMode时候,如果是none说明读取应结束了
     pub lastDataKey: Option<DataKey>,
+
+    /// 当前session prepare过的 statementId -> 解析好的command
+    preparedStatements: HashMap<u64, PreparedStatement>,
+    preparedStatementIdCounter: u64,
+}
+
+/// 解析结果缓存起来 执行时只是复制后替换placeholder 不会再解析sql
+struct PreparedStatement {
+    commands: Vec<Command>,
+    paramCount: usize,
 }
 
 impl Session {
@@ -79,8 +95,32 @@ impl Session {
              self.generateTx()?;
          }*/
 
-        // todo 要是执行的过程有报错 是不是应该rollback
-        let selectResultToFront = CommandExecutor::new(self).execute(&mut commands)?;
+        self.executeCommands(&mut commands)
+    }
+
+    /// 释放prepare过的statement 不然会一直留在session里
+    pub fn closePrepared(&mut self, statementId: u64) -> Result<()> {
+        if self.preparedStatements.remove(&statementId).is_none() {
+            throwFormat!("prepared statement:{} not exist", statementId);
+        }
+
+        Ok(())
+    }
+
+    fn executeCommands(&mut self, commands: &mut [Command]) -> Result<SelectResultToFront> {
+        // todo 要是执行的过程有报错 是不是应该rollback 完成
+        // autoCommit的话报错时要rollback 不然例如违反了unique index的insert已写入的部分会在后边的commit时落地
+        let selectResultToFront =
+            match CommandExecutor::new(self).execute(commands) {
+                Ok(selectResultToFront) => selectResultToFront,
+                Err(e) => {
+                    if self.autoCommit {
+                        self.rollback()?;
+                    }
+
+                    return Err(e);
+                }
+            };
 
         // todo sql中执行了commit rollback使得当前tx提交后,当前不是inTx了,要是后边还有不是set的sql需要再重起1个tx
         if self.autoCommit {
@@ -90,6 +130,42 @@ impl Session {
         Ok(selectResultToFront)
     }
 
+    /// 返回statementId 供executePrepared使用
+    pub fn prepare(&mut self, sql: &str) -> Result<u64> {
+        let (commands, paramCount) = parser::parsePrepared(sql)?;
+
+        self.preparedStatementIdCounter += 1;
+        let statementId = self.preparedStatementIdCounter;
+
+        self.preparedStatements.insert(statementId, PreparedStatement { commands, paramCount });
+
+        Ok(statementId)
+    }
+
+    pub fn executePrepared(&mut self, statementId: u64, params: &[GraphValue]) -> Result<SelectResultToFront> {
+        let mut commands = {
+            let preparedStatement = match self.preparedStatements.get(&statementId) {
+                Some(preparedStatement) => preparedStatement,
+                None => throwFormat!("prepared statement:{} not exist", statementId),
+            };
+
+            if params.len() != preparedStatement.paramCount {
+                throwFormat!("prepared statement:{} needs {} parameters, but got {}", statementId, preparedStatement.paramCount, params.len());
+            }
+
+            let mut commands = Vec::with_capacity(preparedStatement.commands.len());
+            for command in &preparedStatement.commands {
+                let mut command = command.clonePrepared()?;
+                command.bindParams(params)?;
+                commands.push(command);
+            }
+
+            commands
+        };
+
+        self.executeCommands(&mut commands)
+    }
+
     /// 提交之后 在到下个执行sql前 session都是 not in tx 的
     pub fn commit(&mut self) -> Result<()> {
         // todo sql中执行了commit导致当前tx提交后,当前不是inTx了,调用commit报错,需要commit()不要限制inTx 完成
@@ -111,7 +187,11 @@ impl Session {
             let currentTxId = self.txId.unwrap();
 
             // cf需要现用现取 内部使用的是read 而create cf会用到write
-            let cf = Session::getColumnFamily(meta::COLUMN_FAMILY_NAME_TX_ID.parse::<DBObjectId>()?)?;
+            // "TX_ID"不是数字id 不能走getColumnFamily
+            let cf = match meta::STORE.cf_handle(meta::COLUMN_FAMILY_NAME_TX_ID) {
+                Some(cf) => cf,
+                None => throwFormat!("column family:{} not exist", meta::COLUMN_FAMILY_NAME_TX_ID)
+            };
 
             let txUndergoingMaxCount = CONFIG.flyingTxMaxCount.load(Ordering::Acquire) as u64;
 
@@ -131,8 +211,25 @@ impl Session {
             batch.put_cf(&cf, u64ToByteArrRef!(currentTxId), global::EMPTY_BINARY);
         }
 
+        // 别的tx可能同时写入了相同的unique index的值或primary key 要在commit的锁里边对最新落地的再校验1趟
+        let commitLockGuard =
+            if self.commitChecks.read().unwrap().is_empty() {
+                None
+            } else {
+                Some(meta::COMMIT_LOCK.lock().unwrap())
+            };
+
+        if commitLockGuard.is_some() {
+            if let Err(e) = CommandExecutor::new(self).validateCommitChecks(&mut batch) {
+                self.rollback()?;
+                return Err(e);
+            }
+        }
+
         self.db.write(batch)?;
 
+        drop(commitLockGuard);
+
         meta::TX_UNDERGOING_COUNT.fetch_sub(1, Ordering::AcqRel);
 
         Ok(self.clean())
@@ -142,6 +239,7 @@ impl Session {
     pub fn rollback(&mut self) -> Result<()> {
         if self.inTx() {
             self.clean();
+            meta::TX_UNDERGOING_COUNT.fetch_sub(1, Ordering::AcqRel);
         }
 
         Ok(())
@@ -151,6 +249,7 @@ impl Session {
         self.txId = None;
         self.snapshot = None;
         self.dbObjectId_mutations.write().unwrap().clear();
+        self.commitChecks.write().unwrap().clear();
         self.bump.reset();
     }
 
@@ -170,6 +269,7 @@ impl Session {
         self.txId = Some(meta::TX_ID_COUNTER.fetch_add(1, Ordering::AcqRel));
         self.snapshot = Some(self.db.snapshot());
         self.dbObjectId_mutations.write().unwrap().clear();
+        self.commitChecks.write().unwrap().clear();
 
         Ok(())
     }
@@ -261,6 +361,12 @@ impl Session {
         Ok(self.db.delete_cf(&columnFamilyMeta, key)?)
     }
 
+    /// 直接上手datastore 读到的是最新落地的
+    #[inline]
+    pub fn getWithoutSnapshot(&self, key: &[Byte], columnFamily: &ColumnFamily) -> Result<Option<Vec<Byte>>> {
+        Ok(self.db.get_cf(columnFamily, key)?)
+    }
+
     /// 直接上手datastore
     #[inline]
     pub fn deleteWithoutSnapshot(&self, key: &[Byte], columnFamily: &ColumnFamily) -> Result<()> {
@@ -384,6 +490,16 @@ impl Session {
         self.writeMutation(dbObjectId, Mutation::AddIndex { data })
     }
 
+    #[inline]
+    pub fn writeAddPrimaryKeyMutation(&self, dbObjectId: DBObjectId, data: KV) {
+        self.writeMutation(dbObjectId, Mutation::AddPrimaryKey { data })
+    }
+
+    #[inline]
+    pub fn writeCommitCheck(&self, commitCheck: CommitCheck) {
+        self.commitChecks.write().unwrap().push(commitCheck);
+    }
+
     pub fn writeMutation(&self, dbObjectId: DBObjectId, mutation: Mutation) {
         let mut dbObjectId_mutations = self.dbObjectId_mutations.write().unwrap();
         let tableMutations = dbObjectId_mutations.getMutWithDefault(&dbObjectId);
@@ -415,6 +531,9 @@ impl Session {
             Mutation::AddIndex { data } => {
                 tableMutations.insert(data.0, data.1);
             }
+            Mutation::AddPrimaryKey { data } => {
+                tableMutations.insert(data.0, data.1);
+            }
         };
     }
 
@@ -449,9 +568,13 @@ impl Default for Session {
             txId: None,
             snapshot: None,
             dbObjectId_mutations: Default::default(),
+            commitChecks: Default::default(),
             workingMemorySize: Config::DEFAULT_WORKING_MEMORY_SIZE,
+            recursiveMaxCount: Config::DEFAULT_RECURSIVE_MAX_COUNT,
             streamMode: false,
             lastDataKey: None,
+            preparedStatements: Default::default(),
+            preparedStatementIdCounter: 0,
         }
     }
 }
@@ -470,6 +593,10 @@ pub enum Mutation {
     AddIndex {
         data: KV
     },
+    /// primary key的值 -> dataKey
+    AddPrimaryKey {
+        data: KV
+    },
     UpdateData {
         oldXmax: KV,
         newData: KV,
@@ -486,11 +613,40 @@ pub enum Mutation {
 
 }
 
+/// 执行时候是以snapshot校验的 同时进行的别的tx可能写入了相同的值并先提交了 commit时候要对最新落地的再校验1趟
+pub enum CommitCheck {
+    UniqueIndex {
+        tableId: DBObjectId,
+        indexId: DBObjectId,
+        indexName: String,
+        /// 各个column的值encode后的 不含末尾的dataKey
+        indexValue: Vec<Byte>,
+        dataKey: DataKey,
+    },
+    PrimaryKey {
+        tableId: DBObjectId,
+        primaryKeyId: DBObjectId,
+        tableName: String,
+        primaryKey: Vec<Byte>,
+        dataKey: DataKey,
+    },
+    /// 删掉的data 要是最新落地的primary key还指向它 commit时候把primary key删掉
+    PrimaryKeyDeleted {
+        primaryKeyId: DBObjectId,
+        primaryKey: Vec<Byte>,
+        dataKey: DataKey,
+    },
+}
+
 #[cfg(test)]
 mod test {
     use bumpalo::Bump;
     use bytes::BytesMut;
     use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use crate::graph_value::GraphValue;
+    use crate::meta;
+    use crate::session::Session;
 
     #[test]
     pub fn testSerialBox() {
@@ -541,4 +697,26 @@ mod test {
         bump.alloc(1u8);
         println!("{}", bump.allocated_bytes());*/
     }
+
+    #[test]
+    pub fn testClosePrepared() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table preparedUser");
+        session.executeSql("create table preparedUser (id integer, name string)").unwrap();
+        session.executeSql("insert into preparedUser values (1, 'a')").unwrap();
+
+        let statementId = session.prepare("select preparedUser[name](id = ?)").unwrap();
+        assert_eq!(session.executePrepared(statementId, &[GraphValue::Integer(1)]).unwrap().remove(0), vec![json!({"name": "a"})]);
+
+        session.closePrepared(statementId).unwrap();
+
+        // close之后就不能再执行 也不能再close
+        assert!(session.executePrepared(statementId, &[GraphValue::Integer(1)]).is_err());
+        assert!(session.closePrepared(statementId).is_err());
+
+        session.executeSql("drop table preparedUser").unwrap();
+    }
 }
\ No newline at end of file
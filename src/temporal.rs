@@ -0,0 +1,311 @@
+use std::fmt::{Display, Formatter};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use crate::{throw, throwFormat};
+use anyhow::Result;
+
+// timestamp 是UTC的从1970-01-01开始的微秒数, date 是从1970-01-01开始的天数
+
+pub const MICROS_PER_SECOND: i64 = 1_000_000;
+pub const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+pub const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+pub const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+
+/// 月的天数不固定 要和微秒分开记录
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Interval {
+    pub months: i64,
+    pub micros: i64,
+}
+
+impl Interval {
+    /// '1 day', '2 hours 30 minutes', '-1 year 2 months'
+    pub fn parse(text: &str) -> Result<Interval> {
+        let mut interval = Interval::default();
+
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        if parts.is_empty() || parts.len() % 2 != 0 {
+            throwFormat!("invalid interval:'{}', should be like '1 day 2 hours'", text);
+        }
+
+        for pair in parts.chunks(2) {
+            let amount =
+                match pair[0].parse::<i64>() {
+                    Ok(amount) => amount,
+                    Err(_) => throwFormat!("invalid interval:'{}', {} is not integer", text, pair[0]),
+                };
+
+            let unit = pair[1].to_lowercase();
+
+            match unit.trim_end_matches('s') {
+                "year" => interval.months += amount * 12,
+                "month" => interval.months += amount,
+                "week" => interval.micros += amount * 7 * MICROS_PER_DAY,
+                "day" => interval.micros += amount * MICROS_PER_DAY,
+                "hour" => interval.micros += amount * MICROS_PER_HOUR,
+                "minute" => interval.micros += amount * MICROS_PER_MINUTE,
+                "second" => interval.micros += amount * MICROS_PER_SECOND,
+                "millisecond" => interval.micros += amount * 1000,
+                "microsecond" => interval.micros += amount,
+                _ => throwFormat!("invalid interval:'{}', unknown unit:{}", text, pair[1]),
+            }
+        }
+
+        Ok(interval)
+    }
+
+    pub fn negate(&self) -> Interval {
+        Interval {
+            months: -self.months,
+            micros: -self.micros,
+        }
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+
+        if self.months != 0 {
+            parts.push(format!("{} months", self.months));
+        }
+
+        let days = self.micros / MICROS_PER_DAY;
+        if days != 0 {
+            parts.push(format!("{} days", days));
+        }
+
+        let micros = self.micros % MICROS_PER_DAY;
+        if micros != 0 || parts.is_empty() {
+            let sign = if micros < 0 { "-" } else { "" };
+            let micros = micros.abs();
+
+            let mut time = format!("{}{:02}:{:02}:{:02}",
+                                   sign,
+                                   micros / MICROS_PER_HOUR,
+                                   micros % MICROS_PER_HOUR / MICROS_PER_MINUTE,
+                                   micros % MICROS_PER_MINUTE / MICROS_PER_SECOND);
+
+            if micros % MICROS_PER_SECOND != 0 {
+                time.push_str(&format!(".{:06}", micros % MICROS_PER_SECOND));
+            }
+
+            parts.push(time);
+        }
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// '2024-01-02', '2024-01-02 03:04:05.123456', '2024-01-02T03:04:05Z', '2024-01-02 03:04:05+08:00'
+pub fn parseTimestamp(text: &str) -> Result<i64> {
+    let text = text.trim();
+
+    if let Ok(dateTime) = DateTime::parse_from_rfc3339(text) {
+        return Ok(dateTime.timestamp_micros());
+    }
+
+    if let Ok(dateTime) = DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%:z") {
+        return Ok(dateTime.timestamp_micros());
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"] {
+        if let Ok(naiveDateTime) = NaiveDateTime::parse_from_str(text, format) {
+            return Ok(naiveDateTime.and_utc().timestamp_micros());
+        }
+    }
+
+    match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        Ok(naiveDate) => Ok(naiveDate2Days(naiveDate) * MICROS_PER_DAY),
+        Err(_) => throwFormat!("invalid timestamp:'{}'", text),
+    }
+}
+
+/// '2024-01-02'
+pub fn parseDate(text: &str) -> Result<i64> {
+    match NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d") {
+        Ok(naiveDate) => Ok(naiveDate2Days(naiveDate)),
+        Err(_) => throwFormat!("invalid date:'{}'", text),
+    }
+}
+
+pub fn formatTimestamp(micros: i64) -> String {
+    match DateTime::from_timestamp_micros(micros) {
+        Some(dateTime) => dateTime.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        None => format!("invalid timestamp:{}", micros),
+    }
+}
+
+pub fn formatDate(days: i64) -> String {
+    match days2NaiveDate(days) {
+        Some(naiveDate) => naiveDate.format("%Y-%m-%d").to_string(),
+        None => format!("invalid date:{}", days),
+    }
+}
+
+pub fn now() -> i64 {
+    Utc::now().timestamp_micros()
+}
+
+pub fn addInterval(micros: i64, interval: &Interval) -> Result<i64> {
+    let mut dateTime =
+        match DateTime::from_timestamp_micros(micros) {
+            Some(dateTime) => dateTime,
+            None => throwFormat!("invalid timestamp:{}", micros),
+        };
+
+    // 1月31日加1个月 得到2月的最后1天
+    if interval.months != 0 {
+        let months = Months::new(interval.months.unsigned_abs() as u32);
+
+        let added =
+            if interval.months > 0 {
+                dateTime.checked_add_months(months)
+            } else {
+                dateTime.checked_sub_months(months)
+            };
+
+        dateTime =
+            match added {
+                Some(added) => added,
+                None => throw!("timestamp out of range"),
+            };
+    }
+
+    match dateTime.checked_add_signed(Duration::microseconds(interval.micros)) {
+        Some(dateTime) => Ok(dateTime.timestamp_micros()),
+        None => throw!("timestamp out of range"),
+    }
+}
+
+/// date_trunc 使用 week是以周1打头的
+pub fn truncateTimestamp(unit: &str, micros: i64) -> Result<i64> {
+    let unit = unit.to_lowercase();
+
+    match unit.as_str() {
+        "microsecond" => return Ok(micros),
+        "millisecond" => return Ok(micros.div_euclid(1000) * 1000),
+        "second" => return Ok(micros.div_euclid(MICROS_PER_SECOND) * MICROS_PER_SECOND),
+        "minute" => return Ok(micros.div_euclid(MICROS_PER_MINUTE) * MICROS_PER_MINUTE),
+        "hour" => return Ok(micros.div_euclid(MICROS_PER_HOUR) * MICROS_PER_HOUR),
+        _ => {}
+    }
+
+    let days = truncateDate(unit.as_str(), micros.div_euclid(MICROS_PER_DAY))?;
+
+    Ok(days * MICROS_PER_DAY)
+}
+
+pub fn truncateDate(unit: &str, days: i64) -> Result<i64> {
+    let naiveDate =
+        match days2NaiveDate(days) {
+            Some(naiveDate) => naiveDate,
+            None => throwFormat!("invalid date:{}", days),
+        };
+
+    let truncated =
+        match unit.to_lowercase().as_str() {
+            "day" => Some(naiveDate),
+            "week" => Some(naiveDate - Duration::days(naiveDate.weekday().num_days_from_monday() as i64)),
+            "month" => naiveDate.with_day(1),
+            "quarter" => naiveDate.with_day(1).and_then(|naiveDate| naiveDate.with_month(naiveDate.month0() / 3 * 3 + 1)),
+            "year" => naiveDate.with_day(1).and_then(|naiveDate| naiveDate.with_month(1)),
+            _ => throwFormat!("unknown date_trunc unit:{}", unit),
+        };
+
+    Ok(naiveDate2Days(truncated.unwrap()))
+}
+
+fn naiveDate2Days(naiveDate: NaiveDate) -> i64 {
+    naiveDate.signed_duration_since(DateTime::UNIX_EPOCH.date_naive()).num_days()
+}
+
+fn days2NaiveDate(days: i64) -> Option<NaiveDate> {
+    DateTime::UNIX_EPOCH.date_naive().checked_add_signed(Duration::try_days(days)?)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::{meta, temporal};
+    use crate::session::Session;
+    use crate::temporal::Interval;
+
+    #[test]
+    pub fn testParseAndFormat() {
+        let micros = temporal::parseTimestamp("2024-02-29 13:14:15.123456").unwrap();
+        assert_eq!(temporal::formatTimestamp(micros), "2024-02-29T13:14:15.123456Z");
+        assert_eq!(temporal::parseTimestamp("2024-02-29T21:14:15.123456+08:00").unwrap(), micros);
+        assert_eq!(temporal::parseTimestamp("1970-01-02").unwrap(), temporal::MICROS_PER_DAY);
+        assert_eq!(temporal::parseTimestamp("1969-12-31 23:59:59").unwrap(), -temporal::MICROS_PER_SECOND);
+
+        assert_eq!(temporal::parseDate("1970-01-11").unwrap(), 10);
+        assert_eq!(temporal::formatDate(-1), "1969-12-31");
+
+        assert!(temporal::parseTimestamp("2024-13-01").is_err());
+        assert!(temporal::parseDate("yesterday").is_err());
+
+        let interval = Interval::parse("1 year 2 months 3 days 4 hours 5 seconds").unwrap();
+        assert_eq!(interval.months, 14);
+        assert_eq!(interval.to_string(), "14 months 3 days 04:00:05");
+
+        assert!(Interval::parse("1 fortnight").is_err());
+        assert!(Interval::parse("day").is_err());
+    }
+
+    #[test]
+    pub fn testArithmeticAndTruncate() {
+        let jan31 = temporal::parseTimestamp("2024-01-31 10:00:00").unwrap();
+
+        let added = temporal::addInterval(jan31, &Interval::parse("1 month 1 hour").unwrap()).unwrap();
+        assert_eq!(temporal::formatTimestamp(added), "2024-02-29T11:00:00Z");
+
+        let subtracted = temporal::addInterval(jan31, &Interval::parse("1 month").unwrap().negate()).unwrap();
+        assert_eq!(temporal::formatTimestamp(subtracted), "2023-12-31T10:00:00Z");
+
+        let micros = temporal::parseTimestamp("2024-05-16 13:14:15").unwrap();
+        assert_eq!(temporal::formatTimestamp(temporal::truncateTimestamp("hour", micros).unwrap()), "2024-05-16T13:00:00Z");
+        assert_eq!(temporal::formatTimestamp(temporal::truncateTimestamp("week", micros).unwrap()), "2024-05-13T00:00:00Z");
+        assert_eq!(temporal::formatTimestamp(temporal::truncateTimestamp("quarter", micros).unwrap()), "2024-04-01T00:00:00Z");
+        assert_eq!(temporal::formatTimestamp(temporal::truncateTimestamp("YEAR", micros).unwrap()), "2024-01-01T00:00:00Z");
+
+        // 1970年以前的也要向下取
+        let before1970 = temporal::parseTimestamp("1969-12-31 23:30:00").unwrap();
+        assert_eq!(temporal::formatTimestamp(temporal::truncateTimestamp("day", before1970).unwrap()), "1969-12-31T00:00:00Z");
+
+        assert!(temporal::truncateTimestamp("decade", micros).is_err());
+    }
+
+    #[test]
+    pub fn testTemporalColumns() {
+        let _sqlTestGuard = meta::initOnce();
+
+        let mut session = Session::new();
+
+        let _ = session.executeSql("drop table timeEvent");
+        session.executeSql("create table timeEvent (id integer, at timestamp, day date)").unwrap();
+        session.executeSql("create index timeEventAt on timeEvent[at]").unwrap();
+        session.executeSql("create index timeEventDay on timeEvent[day]").unwrap();
+        session.executeSql("insert into timeEvent values (1, timestamp '2024-01-02 03:04:05', date '2024-01-01')").unwrap();
+        session.executeSql("insert into timeEvent values (2, timestamp '2024-02-02 03:04:05.123', date '2024-02-01')").unwrap();
+        session.executeSql("insert into timeEvent values (3, timestamp '2024-03-02 03:04:05', date '2024-03-01')").unwrap();
+
+        let mut rows = |sql: &str| session.executeSql(sql).unwrap().remove(0);
+
+        // 落地后再读出来 毫秒也要保留
+        assert_eq!(rows("select timeEvent(id = 2)"), vec![json!({"id": 2, "at": "2024-02-02T03:04:05.123Z", "day": "2024-02-01"})]);
+
+        let explain = rows("explain select timeEvent[id](at > timestamp '2024-01-15 00:00:00')");
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("index"));
+
+        let mut ids: Vec<i64> = rows("select timeEvent[id](at > timestamp '2024-01-15 00:00:00')").iter().map(|row| row["id"].as_i64().unwrap()).collect();
+        ids.sort();
+        assert_eq!(ids, vec![2, 3]);
+
+        let explain = rows("explain select timeEvent[id](day = date '2024-02-01')");
+        assert_eq!(explain[0]["scans"][0]["accessPath"], json!("index"));
+        assert_eq!(rows("select timeEvent[id](day = date '2024-02-01')"), vec![json!({"id": 2})]);
+
+        session.executeSql("drop table timeEvent").unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+use serde_json::Value;
+use crate::graph_value::GraphValue;
+use crate::{throw, throwFormat};
+use anyhow::Result;
+
+// vector(n) column 落地的是n个f32 <br>
+// ann index 是ivf: kmeans得到若干list的中心 各个vector归到离它最近的中心的list 查找的时候只看离query最近的若干list
+
+/// vector(n) 的n的上限
+pub const MAX_DIMENSION: u32 = 16000;
+
+/// ivf的list数量的上限
+pub const IVF_MAX_LIST_COUNT: usize = 1024;
+
+/// kmeans训练最多使用这么多的vector 均匀间隔的挑选
+pub const IVF_TRAINING_SAMPLE_LIMIT: usize = 65536;
+
+/// 表上的vector少于这些的话不能建立ann index 训练不出有意义的中心 查找会成为全表扫描
+pub const IVF_MIN_TRAINING_VECTOR_COUNT: usize = 64;
+
+const KMEANS_ITERATION_COUNT: usize = 10;
+
+/// order by 中可以使用ann index的distance函数 都是越小越相似
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistanceMetric {
+    /// 1 - cosine similarity
+    Cosine,
+    /// euclidean distance
+    L2,
+    /// 内积的相反数
+    Dot,
+}
+
+impl DistanceMetric {
+    pub fn fromFunctionName(functionName: &str) -> Option<DistanceMetric> {
+        match functionName {
+            "cosine" => Some(DistanceMetric::Cosine),
+            "l2" => Some(DistanceMetric::L2),
+            "dot" => Some(DistanceMetric::Dot),
+            _ => None,
+        }
+    }
+
+    /// cosine 碰到长度是0的vector的话是None
+    pub fn distance(&self, vector: &[f32], vector0: &[f32]) -> Result<Option<f64>> {
+        if vector.len() != vector0.len() {
+            throwFormat!("different vector dimensions {} and {}", vector.len(), vector0.len());
+        }
+
+        let pairs = vector.iter().zip(vector0).map(|(a, b)| (*a as f64, *b as f64));
+
+        match self {
+            DistanceMetric::Cosine => {
+                let (mut dot, mut norm, mut norm0) = (0f64, 0f64, 0f64);
+
+                for (a, b) in pairs {
+                    dot += a * b;
+                    norm += a * a;
+                    norm0 += b * b;
+                }
+
+                if norm == 0.0 || norm0 == 0.0 {
+                    return Ok(None);
+                }
+
+                // 浮点的误差会使得相同方向的 similarity 略大于1
+                Ok(Some(1.0 - (dot / (norm.sqrt() * norm0.sqrt())).clamp(-1.0, 1.0)))
+            }
+            DistanceMetric::L2 => Ok(Some(pairs.map(|(a, b)| (a - b) * (a - b)).sum::<f64>().sqrt())),
+            DistanceMetric::Dot => Ok(Some(-pairs.map(|(a, b)| a * b).sum::<f64>())),
+        }
+    }
+}
+
+/// '[1, 2.5, 3]'
+pub fn parse(text: &str) -> Result<Vec<f32>> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(json) => fromJson(&json),
+        Err(_) => throwFormat!("invalid vector:'{}', should be like '[1, 2.5, 3]'", text),
+    }
+}
+
+/// prepared statement 的参数 [1, 2.5, 3] 是json数组
+pub fn fromJson(json: &Value) -> Result<Vec<f32>> {
+    let array =
+        match json.as_array() {
+            Some(array) => array,
+            None => throwFormat!("invalid vector:{}, should be array of number", json),
+        };
+
+    let mut vector = Vec::with_capacity(array.len());
+
+    for element in array {
+        match element.as_f64() {
+            Some(number) if (number as f32).is_finite() => vector.push(number as f32),
+            _ => throwFormat!("invalid vector:{}, element:{} is not finite number", json, element),
+        }
+    }
+
+    if vector.is_empty() {
+        throw!("vector must have at least 1 dimension");
+    }
+
+    Ok(vector)
+}
+
+/// distance函数的参数 可以是vector column, '[1, 2]', 或是json数组
+pub fn fromGraphValue(value: &GraphValue) -> Result<Vec<f32>> {
+    match value {
+        GraphValue::Vector(vector) => Ok(vector.clone()),
+        GraphValue::String(s) => parse(s),
+        GraphValue::Json(json) => fromJson(json),
+        _ => throwFormat!("{value:?} can not be used as vector"),
+    }
+}
+
+/// [1,2.5,3]
+pub fn format(vector: &[f32]) -> String {
+    let elements: Vec<String> = vector.iter().map(|element| element.to_string()).collect();
+    format!("[{}]", elements.join(","))
+}
+
+/// 行数的平方根 没有行的话是0 这时全部的vector都在0号list
+pub fn ivfListCount(vectorCount: usize) -> usize {
+    ((vectorCount as f64).sqrt() as usize).min(IVF_MAX_LIST_COUNT)
+}
+
+/// 查找的时候先看离query最近的这么多的list 不够limit的话再看后边的
+pub fn ivfProbeCount(listCount: usize) -> usize {
+    ((listCount as f64).sqrt().ceil() as usize).max(1)
+}
+
+/// lloyd迭代 初始的中心是均匀间隔挑选的 相同的数据得到相同的中心
+pub fn kmeans(vectors: &[Vec<f32>], listCount: usize) -> Vec<Vec<f32>> {
+    if vectors.is_empty() || listCount == 0 {
+        return Vec::new();
+    }
+
+    let listCount = listCount.min(vectors.len());
+    let dimension = vectors[0].len();
+
+    let mut centroids: Vec<Vec<f32>> =
+        (0..listCount).map(|index| vectors[index * vectors.len() / listCount].clone()).collect();
+
+    for _ in 0..KMEANS_ITERATION_COUNT {
+        let mut sums = vec![vec![0f64; dimension]; listCount];
+        let mut counts = vec![0usize; listCount];
+
+        for vector in vectors {
+            let listNo = nearestList(&centroids, vector) as usize;
+
+            for (sum, element) in sums[listNo].iter_mut().zip(vector) {
+                *sum += *element as f64;
+            }
+
+            counts[listNo] += 1;
+        }
+
+        let mut changed = false;
+
+        // 空的list保留原来的中心
+        for ((centroid, sum), count) in centroids.iter_mut().zip(sums).zip(counts) {
+            if count == 0 {
+                continue;
+            }
+
+            let newCentroid: Vec<f32> = sum.iter().map(|sum| (sum / count as f64) as f32).collect();
+
+            if newCentroid != *centroid {
+                *centroid = newCentroid;
+                changed = true;
+            }
+        }
+
+        if changed == false {
+            break;
+        }
+    }
+
+    centroids
+}
+
+/// 离vector最近(l2)的中心的下标 没有中心的话是0
+pub fn nearestList(centroids: &[Vec<f32>], vector: &[f32]) -> u32 {
+    let mut nearest = (0u32, f64::MAX);
+
+    for (listNo, centroid) in centroids.iter().enumerate() {
+        let distance: f64 = centroid.iter().zip(vector).map(|(a, b)| (*a as f64 - *b as f64).powi(2)).sum();
+
+        if distance < nearest.1 {
+            nearest = (listNo as u32, distance);
+        }
+    }
+
+    nearest.0
+}
+
+/// 以query和各个中心的distance排序的list 没有中心的话只有0号list
+pub fn rankLists(centroids: &[Vec<f32>], query: &[f32], distanceMetric: DistanceMetric) -> Result<Vec<u32>> {
+    if centroids.is_empty() {
+        return Ok(vec![0]);
+    }
+
+    let mut listNo_distance = Vec::with_capacity(centroids.len());
+
+    for (listNo, centroid) in centroids.iter().enumerate() {
+        // cosine 碰到长度是0的中心 排在最后
+        let distance = distanceMetric.distance(centroid, query)?.unwrap_or(f64::MAX);
+        listNo_distance.push((listNo as u32, distance));
+    }
+
+    listNo_distance.sort_by(|(_, distance), (_, distance0)| distance.total_cmp(distance0));
+
+    Ok(listNo_distance.into_iter().map(|(listNo, _)| listNo).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vector;
+    use crate::vector::DistanceMetric;
+
+    #[test]
+    pub fn testDistance() {
+        let a = vector::parse("[1, 0, 0]").unwrap();
+        let b = vector::parse("[0, 2, 0]").unwrap();
+        let c = vector::parse("[2, 0, 0]").unwrap();
+
+        assert_eq!(DistanceMetric::Cosine.distance(&a, &b).unwrap(), Some(1.0));
+        assert_eq!(DistanceMetric::Cosine.distance(&a, &c).unwrap(), Some(0.0));
+        assert_eq!(DistanceMetric::Cosine.distance(&a, &[0.0, 0.0, 0.0]).unwrap(), None);
+        assert_eq!(DistanceMetric::L2.distance(&a, &c).unwrap(), Some(1.0));
+        assert_eq!(DistanceMetric::Dot.distance(&a, &c).unwrap(), Some(-2.0));
+        assert!(DistanceMetric::L2.distance(&a, &[1.0]).is_err());
+
+        assert_eq!(vector::format(&vector::parse("[1.5, -2]").unwrap()), "[1.5,-2]");
+        assert!(vector::parse("[]").is_err());
+        assert!(vector::parse("[1, \"a\"]").is_err());
+        assert!(vector::parse("1, 2").is_err());
+    }
+
+    #[test]
+    pub fn testKmeans() {
+        let mut vectors = Vec::new();
+        for index in 0..50 {
+            let offset = index as f32 / 100.0;
+            vectors.push(vec![offset, offset]);
+            vectors.push(vec![10.0 + offset, 10.0 + offset]);
+        }
+
+        assert_eq!(vector::ivfListCount(vectors.len()), 10);
+        assert_eq!(vector::ivfListCount(0), 0);
+        assert_eq!(vector::ivfProbeCount(10), 4);
+
+        let centroids = vector::kmeans(&vectors, 2);
+        assert_eq!(centroids.len(), 2);
+
+        // 两簇分到了不同的list
+        let listNo = vector::nearestList(&centroids, &[0.1, 0.1]);
+        let listNo0 = vector::nearestList(&centroids, &[10.1, 10.1]);
+        assert_ne!(listNo, listNo0);
+
+        assert_eq!(vector::rankLists(&centroids, &[9.0, 9.0], DistanceMetric::L2).unwrap(), vec![listNo0, listNo]);
+        assert_eq!(vector::rankLists(&[], &[9.0, 9.0], DistanceMetric::L2).unwrap(), vec![0]);
+        assert!(vector::kmeans(&[], 2).is_empty());
+    }
+}
@@ -21,6 +21,10 @@ use crate::types::SelectResultToFront;
 pub struct GraphWsRequest {
     pub requestType: RequestType,
     pub sql: Option<String>,
+    /// ExecutePrepared ClosePrepared 使用 是Prepare返回的
+    pub statementId: Option<u64>,
+    /// ExecutePrepared 使用 依次对应 ? 或 $1 $2
+    pub params: Option<Vec<Value>>,
 }
 
 impl Default for GraphWsRequest {
@@ -28,6 +32,8 @@ impl Default for GraphWsRequest {
         GraphWsRequest {
             requestType: RequestType::None,
             sql: None,
+            statementId: None,
+            params: None,
         }
     }
 }
@@ -37,6 +43,9 @@ impl Default for GraphWsRequest {
 pub enum RequestType {
     ExecuteSql,
     TestParser,
+    Prepare,
+    ExecutePrepared,
+    ClosePrepared,
     None,
 }
 
@@ -45,6 +54,8 @@ pub struct GraphWsResponse {
     success: bool,
     errorMsg: Option<String>,
     data: Option<SelectResultToFront>,
+    /// Prepare 时候返回
+    statementId: Option<u64>,
 }
 
 impl GraphWsResponse {
@@ -67,6 +78,15 @@ impl GraphWsResponse {
             success: true,
             errorMsg: None,
             data: Some(data),
+            statementId: None,
+        }
+    }
+
+    pub fn successWithStatementId(statementId: u64) -> GraphWsResponse {
+        GraphWsResponse {
+            success: true,
+            statementId: Some(statementId),
+            ..Default::default()
         }
     }
 }
@@ -178,6 +198,41 @@ async fn processGraphWsRequest(writeStream: &mut SplitSink<WebSocketStream<TcpSt
                 None => return Ok(()),
             }
         }
+        RequestType::Prepare => {
+            let sql = match graphWsRequest.sql {
+                Some(sql) => sql,
+                None => throw!("prepare request needs sql"),
+            };
+
+            let statementId = session.prepare(&sql)?;
+
+            writeStream.send(Message::Text(GraphWsResponse::successWithStatementId(statementId).to_string())).await?;
+
+            return Ok(());
+        }
+        RequestType::ExecutePrepared => {
+            let statementId = match graphWsRequest.statementId {
+                Some(statementId) => statementId,
+                None => throw!("execute prepared request needs statementId"),
+            };
+
+            let mut params = Vec::new();
+            for param in graphWsRequest.params.iter().flatten() {
+                params.push(GraphValue::try_from(param)?);
+            }
+
+            selectResultToFront.replace(
+                tokio::task::block_in_place(|| session.executePrepared(statementId, &params))?
+            );
+        }
+        RequestType::ClosePrepared => {
+            let statementId = match graphWsRequest.statementId {
+                Some(statementId) => statementId,
+                None => throw!("close prepared request needs statementId"),
+            };
+
+            session.closePrepared(statementId)?;
+        }
         RequestType::TestParser => {
             if remoteAddr.ip().is_loopback() == false {
                 throw!("test parser request can only be from localhost");
@@ -217,6 +272,7 @@ mod test {
         println!("{}", serde_json::to_string(&GraphWsRequest {
             requestType: RequestType::ExecuteSql,
             sql: Some("aaaa".to_string()),
+            ..Default::default()
         }).unwrap());
     }
 }
\ No newline at end of file